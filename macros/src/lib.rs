@@ -1,51 +1,379 @@
 use proc_macro::TokenStream;
+use proc_macro2::Span;
 use quote::quote;
-use syn::{DeriveInput, Meta, parse_macro_input};
+use syn::{
+    Expr, ExprArray, ExprLit, ImplItem, ItemImpl, Lit, LitStr, Meta, Path, Token,
+    parse_macro_input, parse_quote, punctuated::Punctuated, spanned::Spanned,
+};
 
-#[proc_macro_derive(Slash)]
-pub fn derive_slash(input: TokenStream) -> TokenStream {
-    let ast = parse_macro_input!(input as syn::DeriveInput);
-    let name = &ast.ident;
+// Discord's limits for application commands.
+const MAX_NAME_LENGTH: usize = 32;
+const MAX_DESCRIPTION_LENGTH: usize = 100;
 
-    let expanded = quote! {
-        impl Slash for #name {
-            // fn is_slash(&self) -> bool {
-            //     true
-            // }
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    ChatInput,
+    User,
+    Message,
+}
+
+struct CommandAttributes {
+    name: LitStr,
+    description: Option<LitStr>,
+    kind: Kind,
+    permissions: Vec<Path>,
+    aliases: Vec<LitStr>,
+    options: Option<Expr>,
+    // Seconds between uses, shared by the `bucket` (`User` by default).
+    cooldown: Option<Expr>,
+    bucket: Option<Path>,
+    slash: bool,
+    legacy: bool,
+    autocomplete: bool,
+    // Handlers of subcommands are registered through their parent, so no `command()`.
+    subcommand: bool,
+}
+
+// Placed on `impl CommandTrait for Command`, fills in the command flags and generates the
+// `command()` constructor, e.g.
+// `#[command(name = "snipe", description = "...", aliases = ["s"], cooldown = 5, slash, legacy)]`
+// With `subcommand` a `node()` is generated instead, for `ICommand::subcommand`.
+#[proc_macro_attribute]
+pub fn command(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let metas = parse_macro_input!(attr with Punctuated::<Meta, Token![,]>::parse_terminated);
+    let mut item = parse_macro_input!(item as ItemImpl);
+
+    let attributes = match parse_attributes(metas) {
+        Ok(attributes) => attributes,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    if let Err(e) = validate(&attributes) {
+        return e.to_compile_error().into();
+    }
+
+    // Context menus are only ever application commands.
+    let flags = [
+        (
+            "is_slash",
+            attributes.slash || attributes.kind != Kind::ChatInput,
+        ),
+        ("is_legacy", attributes.legacy),
+        ("supports_autocomplete", attributes.autocomplete),
+    ];
+    for (flag, enabled) in flags {
+        let defined = item
+            .items
+            .iter()
+            .any(|i| matches!(i, ImplItem::Fn(f) if f.sig.ident == flag));
+        if enabled && !defined {
+            let ident = syn::Ident::new(flag, Span::call_site());
+            item.items.push(parse_quote! {
+                fn #ident(&self) -> bool {
+                    true
+                }
+            });
         }
+    }
+
+    if let Some(seconds) = &attributes.cooldown {
+        let bucket = match &attributes.bucket {
+            Some(bucket) => quote!(::utils::CooldownBucket::#bucket),
+            None => quote!(::utils::CooldownBucket::User),
+        };
+        item.items.push(parse_quote! {
+            fn cooldown(&self) -> ::std::option::Option<::utils::Cooldown> {
+                ::std::option::Option::Some(::utils::Cooldown::new(#bucket, #seconds))
+            }
+        });
+    }
+
+    let self_ty = &item.self_ty;
+    let name = &attributes.name;
+    let description = attributes
+        .description
+        .clone()
+        .unwrap_or_else(|| LitStr::new("", Span::call_site()));
+    let kind = match attributes.kind {
+        Kind::ChatInput => quote!(::serenity::all::CommandType::ChatInput),
+        Kind::User => quote!(::serenity::all::CommandType::User),
+        Kind::Message => quote!(::serenity::all::CommandType::Message),
     };
+    let permissions = attributes
+        .permissions
+        .iter()
+        .map(|p| quote!(::utils::BotPermission::#p));
+    let options = match &attributes.options {
+        Some(options) => quote!(#options),
+        None => quote!(::std::vec::Vec::new()),
+    };
+    let aliases = &attributes.aliases;
 
-    TokenStream::from(expanded)
-}
+    if attributes.subcommand {
+        let options = match &attributes.options {
+            Some(options) => quote!(#options),
+            None => quote!(::std::vec::Vec::new()),
+        };
+        return TokenStream::from(quote! {
+            impl #self_ty {
+                pub const NAME: &'static str = #name;
+                pub const DESCRIPTION: &'static str = #description;
+
+                pub fn node() -> ::utils::SubcommandNode {
+                    ::utils::SubcommandNode::command(
+                        #name,
+                        #description,
+                        #options,
+                        ::std::sync::Arc::new(#self_ty),
+                    )
+                }
+            }
 
-#[proc_macro_derive(Legacy)]
-pub fn derive_legacy(input: TokenStream) -> TokenStream {
-    let ast = parse_macro_input!(input as syn::DeriveInput);
-    let name = &ast.ident;
+            #item
+        });
+    }
 
     let expanded = quote! {
-        impl Legacy for #name {
-            // fn is_legacy(&self) -> bool {
-            //     true
-            // }
+        impl #self_ty {
+            pub const NAME: &'static str = #name;
+            pub const DESCRIPTION: &'static str = #description;
         }
-    };
 
+        pub fn command() -> ::utils::CommandTemplate {
+            (
+                ::utils::ICommand::new(
+                    #name.to_string(),
+                    #description.to_string(),
+                    #kind,
+                    #options,
+                    vec![#(#permissions),*],
+                )
+                .aliases(&[#(#aliases),*]),
+                ::std::sync::Arc::new(#self_ty),
+            )
+        }
+
+        #item
+    };
     TokenStream::from(expanded)
 }
 
-#[proc_macro_derive(Autocomplete)]
-pub fn derive_autocomplete(input: TokenStream) -> TokenStream {
-    let ast = parse_macro_input!(input as syn::DeriveInput);
-    let name = &ast.ident;
+fn parse_attributes(metas: Punctuated<Meta, Token![,]>) -> syn::Result<CommandAttributes> {
+    let mut name = None;
+    let mut description = None;
+    let mut kind = Kind::ChatInput;
+    let mut permissions = vec![];
+    let mut aliases = vec![];
+    let mut options = None;
+    let (mut cooldown, mut bucket) = (None, None);
+    let (mut slash, mut legacy, mut autocomplete) = (false, false, false);
+    let mut subcommand = false;
 
-    let expanded = quote! {
-        impl Autocomplete for #name {
-            // fn is_autocomplete(&self) -> bool {
-            //     true
-            // }
+    for meta in metas {
+        match &meta {
+            Meta::Path(path) if path.is_ident("slash") => slash = true,
+            Meta::Path(path) if path.is_ident("legacy") => legacy = true,
+            Meta::Path(path) if path.is_ident("autocomplete") => autocomplete = true,
+            Meta::Path(path) if path.is_ident("subcommand") => subcommand = true,
+            Meta::NameValue(nv) if nv.path.is_ident("name") => name = Some(lit_str(&nv.value)?),
+            Meta::NameValue(nv) if nv.path.is_ident("description") => {
+                description = Some(lit_str(&nv.value)?)
+            }
+            Meta::NameValue(nv) if nv.path.is_ident("kind") => {
+                kind = match &nv.value {
+                    Expr::Path(p) if p.path.is_ident("ChatInput") => Kind::ChatInput,
+                    Expr::Path(p) if p.path.is_ident("User") => Kind::User,
+                    Expr::Path(p) if p.path.is_ident("Message") => Kind::Message,
+                    value => {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "expected `ChatInput`, `User` or `Message`",
+                        ));
+                    }
+                }
+            }
+            Meta::NameValue(nv) if nv.path.is_ident("permissions") => {
+                let Expr::Array(ExprArray { elems, .. }) = &nv.value else {
+                    return Err(syn::Error::new(
+                        nv.value.span(),
+                        "expected a list like `[ManageMessages]`",
+                    ));
+                };
+                for elem in elems {
+                    let Expr::Path(p) = elem else {
+                        return Err(syn::Error::new(elem.span(), "expected a permission name"));
+                    };
+                    permissions.push(p.path.clone());
+                }
+            }
+            Meta::NameValue(nv) if nv.path.is_ident("aliases") => {
+                let Expr::Array(ExprArray { elems, .. }) = &nv.value else {
+                    return Err(syn::Error::new(
+                        nv.value.span(),
+                        "expected a list like `[\"s\"]`",
+                    ));
+                };
+                for elem in elems {
+                    aliases.push(lit_str(elem)?);
+                }
+            }
+            Meta::NameValue(nv) if nv.path.is_ident("options") => options = Some(nv.value.clone()),
+            Meta::NameValue(nv) if nv.path.is_ident("cooldown") => {
+                cooldown = Some(nv.value.clone())
+            }
+            Meta::NameValue(nv) if nv.path.is_ident("bucket") => {
+                bucket = match &nv.value {
+                    Expr::Path(p)
+                        if ["User", "Channel", "Guild"]
+                            .iter()
+                            .any(|b| p.path.is_ident(b)) =>
+                    {
+                        Some(p.path.clone())
+                    }
+                    value => {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "expected `User`, `Channel` or `Guild`",
+                        ));
+                    }
+                }
+            }
+            // Shorthand for the options of a typed argument struct.
+            Meta::NameValue(nv) if nv.path.is_ident("arguments") => {
+                let arguments = &nv.value;
+                options = Some(parse_quote!(<#arguments as ::utils::Arguments>::options()));
+            }
+            _ => return Err(syn::Error::new(meta.span(), "unknown command attribute")),
         }
+    }
+
+    let Some(name) = name else {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "missing `name = \"...\"`",
+        ));
     };
+    Ok(CommandAttributes {
+        name,
+        description,
+        kind,
+        permissions,
+        aliases,
+        options,
+        cooldown,
+        bucket,
+        slash,
+        legacy,
+        autocomplete,
+        subcommand,
+    })
+}
 
-    TokenStream::from(expanded)
+fn lit_str(expr: &Expr) -> syn::Result<LitStr> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(lit), ..
+        }) => Ok(lit.clone()),
+        _ => Err(syn::Error::new(expr.span(), "expected a string literal")),
+    }
+}
+
+// The same limits `ICommand::new` checks at startup, caught while compiling instead.
+fn validate(attributes: &CommandAttributes) -> syn::Result<()> {
+    let name = attributes.name.value();
+    let length = name.chars().count();
+    if length == 0 || length > MAX_NAME_LENGTH {
+        return Err(syn::Error::new(
+            attributes.name.span(),
+            format!("command names must be 1 to {} characters", MAX_NAME_LENGTH),
+        ));
+    }
+    if attributes.kind == Kind::ChatInput
+        && !name
+            .chars()
+            .all(|c| c == '-' || c == '_' || (c.is_alphanumeric() && !c.is_uppercase()))
+    {
+        return Err(syn::Error::new(
+            attributes.name.span(),
+            "slash command names must be lowercase without spaces",
+        ));
+    }
+
+    match (&attributes.description, attributes.kind) {
+        (Some(description), Kind::ChatInput) => {
+            let length = description.value().chars().count();
+            if length == 0 || length > MAX_DESCRIPTION_LENGTH {
+                return Err(syn::Error::new(
+                    description.span(),
+                    format!(
+                        "command descriptions must be 1 to {} characters",
+                        MAX_DESCRIPTION_LENGTH
+                    ),
+                ));
+            }
+        }
+        (None, Kind::ChatInput) => {
+            return Err(syn::Error::new(
+                attributes.name.span(),
+                "missing `description = \"...\"`",
+            ));
+        }
+        (Some(description), _) => {
+            return Err(syn::Error::new(
+                description.span(),
+                "context menu commands don't have a description",
+            ));
+        }
+        (None, _) => {}
+    }
+
+    if attributes.subcommand
+        && (attributes.kind != Kind::ChatInput || !attributes.permissions.is_empty())
+    {
+        return Err(syn::Error::new(
+            attributes.name.span(),
+            "subcommands use the kind and permissions of their parent command",
+        ));
+    }
+    if attributes.kind != Kind::ChatInput
+        && (attributes.legacy || attributes.autocomplete || attributes.options.is_some())
+    {
+        return Err(syn::Error::new(
+            attributes.name.span(),
+            "context menu commands can't be legacy or take options",
+        ));
+    }
+    for alias in &attributes.aliases {
+        let value = alias.value();
+        if value.is_empty() || value.chars().any(char::is_whitespace) {
+            return Err(syn::Error::new(
+                alias.span(),
+                "aliases must be a single word",
+            ));
+        }
+    }
+    if !attributes.aliases.is_empty() && (attributes.subcommand || !attributes.legacy) {
+        return Err(syn::Error::new(
+            attributes.name.span(),
+            "aliases are only available for legacy commands",
+        ));
+    }
+    if attributes.subcommand && attributes.cooldown.is_some() {
+        return Err(syn::Error::new(
+            attributes.name.span(),
+            "cooldowns are checked for the whole command, not its subcommands",
+        ));
+    }
+    if attributes.bucket.is_some() && attributes.cooldown.is_none() {
+        return Err(syn::Error::new(
+            attributes.name.span(),
+            "`bucket` needs a `cooldown = seconds`",
+        ));
+    }
+    if attributes.autocomplete && !attributes.slash {
+        return Err(syn::Error::new(
+            attributes.name.span(),
+            "autocomplete is only available for slash commands",
+        ));
+    }
+    Ok(())
 }
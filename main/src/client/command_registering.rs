@@ -1,28 +1,339 @@
 use std::sync::Arc;
 
-use serenity::all::{CreateCommand, GuildId, Http, UserId};
-use utils::{error, info};
+use serde_json::{Value, json};
+use serenity::all::{Command, CreateCommand, GuildId, Http};
+use utils::{Data, error, info, warning};
 
-use crate::ElapsedTime;
+use crate::{ApplicationCommand, ApplicationCommands, CommandSettings, ElapsedTime};
 
-pub async fn run(http: Arc<Http>, commands: Vec<CreateCommand>) {
+// Where commands are registered, from `COMMAND_REGISTRATION`: `global` (the default), `dev` for
+// the comma separated ids in `COMMAND_DEV_GUILDS`, or `guild` to register the enabled commands of
+// every guild as it's received from the gateway. Disabled commands are only hidden in `guild` mode.
+pub enum RegistrationMode {
+    Global,
+    Dev(Vec<GuildId>),
+    Guild,
+}
+
+impl RegistrationMode {
+    pub fn from_env() -> Self {
+        let dev_guilds = std::env::var("COMMAND_DEV_GUILDS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|id| id.trim().parse::<u64>().ok())
+            .filter(|id| *id != 0)
+            .map(GuildId::new)
+            .collect();
+        let mode = std::env::var("COMMAND_REGISTRATION").unwrap_or_default();
+        match mode.to_lowercase().as_str() {
+            "" | "global" => Self::Global,
+            "dev" => Self::Dev(dev_guilds),
+            "guild" => Self::Guild,
+            _ => {
+                warning!(
+                    "Unknown COMMAND_REGISTRATION '{}', registering globally.",
+                    mode
+                );
+                Self::Global
+            }
+        }
+    }
+}
+
+pub async fn run(http: Arc<Http>, data: Data, commands: Vec<ApplicationCommand>) {
     let timer = ElapsedTime::new();
-    info!("Registering commands...");
-
-    // match http.create_global_commands(&commands).await {
-    //     Ok(_) => info!(
-    //         "Commands successfully registered! ({}ms)",
-    //         timer.elapsed_ms()
-    //     ),
-    //     Err(why) => error!("Failed to register commands: {:?}", why),
-    // }
-
-    let dev_guild = GuildId::from(851102546470371338);
-    match dev_guild.set_commands(http, commands).await {
-        Ok(_) => info!(
-            "Commands successfully registered! ({}ms)",
-            timer.elapsed_ms()
-        ),
-        Err(why) => error!("Failed to register commands: {:?}", why),
+    let commands = Arc::new(commands);
+    data.write()
+        .await
+        .insert::<ApplicationCommands>(commands.clone());
+
+    let builders = commands
+        .iter()
+        .map(|c| c.command.clone())
+        .collect::<Vec<_>>();
+    match RegistrationMode::from_env() {
+        RegistrationMode::Global => {
+            info!("Registering commands globally...");
+            sync(&http, None, &builders).await;
+        }
+        RegistrationMode::Dev(guilds) => {
+            info!("Registering commands for {} dev guilds...", guilds.len());
+            if guilds.is_empty() {
+                warning!("COMMAND_REGISTRATION is dev but COMMAND_DEV_GUILDS has no guilds.");
+            }
+            for guild_id in guilds {
+                sync(&http, Some(guild_id), &builders).await;
+            }
+            warn_global(&http).await;
+        }
+        RegistrationMode::Guild => {
+            info!("Commands will be registered per guild");
+            warn_global(&http).await;
+            return;
+        }
+    }
+    info!("Commands registered ({}ms)", timer.elapsed_ms());
+}
+
+// Registers the commands a guild has enabled, only used in `guild` mode.
+pub async fn sync_guild(http: &Http, data: &Data, guild_id: GuildId) {
+    if !matches!(RegistrationMode::from_env(), RegistrationMode::Guild) {
+        return;
+    }
+    let (commands, settings) = {
+        let data = data.read().await;
+        (
+            data.get::<ApplicationCommands>().cloned(),
+            data.get::<CommandSettings>()
+                .and_then(|s| s.get(&guild_id).map(|s| s.clone()))
+                .unwrap_or_default(),
+        )
+    };
+    let Some(commands) = commands else {
+        return;
+    };
+    let builders = commands
+        .iter()
+        .filter(|c| settings.command_enabled(&c.name, c.module))
+        .map(|c| c.command.clone())
+        .collect::<Vec<_>>();
+    sync(http, Some(guild_id), &builders).await;
+}
+
+// Only uploads commands that are new or differ from the registered ones, and deletes the
+// registered commands that no longer exist.
+async fn sync(http: &Http, guild_id: Option<GuildId>, commands: &[CreateCommand]) {
+    let scope = match guild_id {
+        Some(guild_id) => format!("guild {}", guild_id),
+        None => "global".to_string(),
+    };
+    let registered = match guild_id {
+        Some(guild_id) => guild_id.get_commands(http).await,
+        None => Command::get_global_commands(http).await,
+    };
+    let registered = match registered {
+        Ok(registered) => registered
+            .into_iter()
+            .filter_map(|c| Some((c.id, canonical(&serde_json::to_value(&c).ok()?))))
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            error!("Failed to fetch {} commands: {:?}", scope, e);
+            return;
+        }
+    };
+
+    let mut wanted = vec![];
+    let (mut updated, mut unchanged) = (0, 0);
+    for command in commands {
+        let Ok(value) = serde_json::to_value(command) else {
+            continue;
+        };
+        let value = canonical(&value);
+        let key = command_key(&value);
+        if registered.iter().any(|(_, r)| *r == value) {
+            unchanged += 1;
+            wanted.push(key);
+            continue;
+        }
+        let result = match guild_id {
+            Some(guild_id) => guild_id.create_command(http, command.clone()).await,
+            None => Command::create_global_command(http, command.clone()).await,
+        };
+        match result {
+            Ok(_) => updated += 1,
+            Err(e) => error!("Failed to register {} command {}: {:?}", scope, key.0, e),
+        }
+        wanted.push(key);
+    }
+
+    let mut deleted = 0;
+    for (id, value) in &registered {
+        if wanted.contains(&command_key(value)) {
+            continue;
+        }
+        let result = match guild_id {
+            Some(guild_id) => guild_id.delete_command(http, *id).await,
+            None => Command::delete_global_command(http, *id).await,
+        };
+        match result {
+            Ok(_) => deleted += 1,
+            Err(e) => error!("Failed to delete {} command {}: {:?}", scope, id, e),
+        }
+    }
+
+    if updated > 0 || deleted > 0 {
+        info!(
+            "Updated {} and deleted {} {} commands, {} unchanged",
+            updated, deleted, scope, unchanged
+        );
+    }
+}
+
+// Global commands would show up next to the guild ones, they're left alone since the same
+// application may be running elsewhere.
+async fn warn_global(http: &Http) {
+    if let Ok(global) = Command::get_global_commands(http).await
+        && !global.is_empty()
+    {
+        warning!(
+            "{} global commands are registered and will show next to the guild commands",
+            global.len()
+        );
+    }
+}
+
+// Commands are identified by name and type, a user command can share the name of a slash one.
+fn command_key(value: &Value) -> (String, u64) {
+    (
+        value["name"].as_str().unwrap_or_default().to_string(),
+        value["type"].as_u64().unwrap_or(1),
+    )
+}
+
+// The fields Discord keeps for a command, with its defaults filled in, so a builder can be
+// compared with a registered command.
+fn canonical(value: &Value) -> Value {
+    json!({
+        "name": value["name"],
+        "type": value["type"].as_u64().unwrap_or(1),
+        "description": value["description"].as_str().unwrap_or_default(),
+        "nsfw": value["nsfw"].as_bool().unwrap_or(false),
+        "default_member_permissions": value["default_member_permissions"],
+        "options": canonical_options(&value["options"]),
+    })
+}
+
+fn canonical_options(options: &Value) -> Value {
+    let options = options
+        .as_array()
+        .map(|options| {
+            options
+                .iter()
+                .map(|option| {
+                    let choices = option["choices"]
+                        .as_array()
+                        .map(|choices| {
+                            choices
+                                .iter()
+                                .map(|c| json!({ "name": c["name"], "value": c["value"] }))
+                                .collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default();
+                    json!({
+                        "type": option["type"],
+                        "name": option["name"],
+                        "description": option["description"],
+                        "required": option["required"].as_bool().unwrap_or(false),
+                        "autocomplete": option["autocomplete"].as_bool().unwrap_or(false),
+                        "choices": choices,
+                        "channel_types": option["channel_types"].as_array().cloned().unwrap_or_default(),
+                        "min_value": option["min_value"].as_f64(),
+                        "max_value": option["max_value"].as_f64(),
+                        "min_length": option["min_length"].as_u64(),
+                        "max_length": option["max_length"].as_u64(),
+                        "options": canonical_options(&option["options"]),
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    Value::Array(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::all::{CommandOptionType, CommandType, CreateCommandOption, Permissions};
+
+    use super::*;
+
+    fn builder() -> CreateCommand {
+        CreateCommand::new("feed")
+            .description("Manage feeds")
+            .default_member_permissions(Permissions::MANAGE_GUILD)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Add a feed")
+                    .add_sub_option(
+                        CreateCommandOption::new(CommandOptionType::Integer, "limit", "The limit")
+                            .min_int_value(1)
+                            .max_int_value(10),
+                    )
+                    .add_sub_option(
+                        CreateCommandOption::new(CommandOptionType::String, "order", "The order")
+                            .required(true)
+                            .add_string_choice("New", "new"),
+                    ),
+            )
+    }
+
+    // The same command as Discord returns it, with ids and defaults filled in.
+    fn registered() -> Value {
+        json!({
+            "id": "1",
+            "application_id": "2",
+            "version": "3",
+            "type": 1,
+            "name": "feed",
+            "name_localizations": null,
+            "description": "Manage feeds",
+            "description_localizations": null,
+            "default_member_permissions": "32",
+            "dm_permission": true,
+            "nsfw": false,
+            "integration_types": [0],
+            "contexts": null,
+            "options": [{
+                "type": 1,
+                "name": "add",
+                "description": "Add a feed",
+                "options": [
+                    {
+                        "type": 4,
+                        "name": "limit",
+                        "description": "The limit",
+                        "min_value": 1,
+                        "max_value": 10,
+                    },
+                    {
+                        "type": 3,
+                        "name": "order",
+                        "description": "The order",
+                        "required": true,
+                        "choices": [{ "name": "New", "name_localizations": null, "value": "new" }],
+                    },
+                ],
+            }],
+        })
+    }
+
+    fn canonical_builder(command: &CreateCommand) -> Value {
+        canonical(&serde_json::to_value(command).unwrap())
+    }
+
+    #[test]
+    fn matches_the_registered_command() {
+        assert_eq!(canonical_builder(&builder()), canonical(&registered()));
+    }
+
+    #[test]
+    fn notices_changes() {
+        let registered = canonical(&registered());
+        let renamed = builder().description("Manage subreddit feeds");
+        assert_ne!(canonical_builder(&renamed), registered);
+        let option = builder().add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "List the feeds",
+        ));
+        assert_ne!(canonical_builder(&option), registered);
+        let permissions = builder().default_member_permissions(Permissions::ADMINISTRATOR);
+        assert_ne!(canonical_builder(&permissions), registered);
+    }
+
+    #[test]
+    fn identifies_commands_by_name_and_type() {
+        let slash = canonical_builder(&builder());
+        let user = canonical_builder(&CreateCommand::new("feed").kind(CommandType::User));
+        assert_eq!(command_key(&slash), ("feed".to_string(), 1));
+        assert_eq!(command_key(&user), ("feed".to_string(), 2));
     }
 }
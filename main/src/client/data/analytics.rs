@@ -0,0 +1,290 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{ChannelId, GuildId, UserId},
+    prelude::TypeMapKey,
+};
+
+use crate::storage;
+
+const ANALYTICS_STORAGE: &str = "analytics";
+
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+pub const MAX_RETENTION_DAYS: u32 = 90;
+pub const HOUR: i64 = 60 * 60; // seconds
+
+pub struct ServerAnalytics;
+pub type ServerAnalyticsMap = DashMap<GuildId, GuildAnalytics>;
+impl TypeMapKey for ServerAnalytics {
+    type Value = Arc<ServerAnalyticsMap>;
+}
+
+// Open voice sessions, only kept in memory and rebuilt from the guild's voice states on startup.
+pub struct VoiceSessions;
+pub type VoiceSessionsMap = DashMap<(GuildId, UserId), DateTime<Utc>>;
+impl TypeMapKey for VoiceSessions {
+    type Value = Arc<VoiceSessionsMap>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildAnalytics {
+    #[serde(default = "default_retention")]
+    pub retention_days: u32,
+    // Keyed by the hour since the unix epoch.
+    #[serde(default)]
+    pub hours: BTreeMap<i64, HourBucket>,
+}
+
+fn default_retention() -> u32 {
+    DEFAULT_RETENTION_DAYS
+}
+
+impl Default for GuildAnalytics {
+    fn default() -> Self {
+        Self {
+            retention_days: DEFAULT_RETENTION_DAYS,
+            hours: BTreeMap::new(),
+        }
+    }
+}
+
+impl GuildAnalytics {
+    pub fn bucket(&mut self, at: DateTime<Utc>) -> &mut HourBucket {
+        self.hours.entry(hour_of(at)).or_default()
+    }
+
+    // Drops buckets older than the retention period.
+    pub fn prune(&mut self, now: DateTime<Utc>) {
+        let oldest = hour_of(now) - self.retention_days as i64 * 24;
+        self.hours = self.hours.split_off(&oldest);
+    }
+
+    pub fn summarize(&self, since: DateTime<Utc>) -> AnalyticsSummary {
+        let mut summary = AnalyticsSummary::default();
+        for (hour, bucket) in self.hours.range(hour_of(since)..) {
+            let messages = bucket.messages.values().sum::<u64>();
+            summary.messages += messages;
+            *summary.messages_by_hour.entry(*hour).or_default() += messages;
+            for (channel, count) in &bucket.messages {
+                *summary.channels.entry(*channel).or_default() += count;
+            }
+            for (user, count) in &bucket.users {
+                *summary.users.entry(*user).or_default() += count;
+            }
+            for (user, seconds) in &bucket.voice {
+                *summary.voice.entry(*user).or_default() += seconds;
+            }
+            for (command, count) in &bucket.commands {
+                *summary.commands.entry(command.clone()).or_default() += count;
+            }
+            summary.joins += bucket.joins;
+            summary.leaves += bucket.leaves;
+        }
+        summary
+    }
+
+    // One point per `hours_per_point` hours from `since` until now, including empty periods.
+    pub fn series(
+        &self,
+        since: DateTime<Utc>,
+        hours_per_point: i64,
+        value: impl Fn(&HourBucket) -> u64,
+    ) -> Vec<(i64, u64)> {
+        let (start, end) = (hour_of(since), hour_of(Utc::now()));
+        (start..=end)
+            .step_by(hours_per_point.max(1) as usize)
+            .map(|from| {
+                let total = self
+                    .hours
+                    .range(from..from + hours_per_point)
+                    .map(|(_, bucket)| value(bucket))
+                    .sum();
+                (from, total)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HourBucket {
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub messages: HashMap<ChannelId, u64>,
+    // Messages sent per user, which also gives the active users of the hour.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub users: HashMap<UserId, u64>,
+    // Seconds spent in voice per user.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub voice: HashMap<UserId, u64>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub commands: HashMap<String, u64>,
+    #[serde(default)]
+    pub joins: u64,
+    #[serde(default)]
+    pub leaves: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AnalyticsSummary {
+    pub messages: u64,
+    pub messages_by_hour: BTreeMap<i64, u64>,
+    pub channels: HashMap<ChannelId, u64>,
+    pub users: HashMap<UserId, u64>,
+    pub voice: HashMap<UserId, u64>,
+    pub commands: HashMap<String, u64>,
+    pub joins: u64,
+    pub leaves: u64,
+}
+
+impl AnalyticsSummary {
+    pub fn voice_minutes(&self) -> u64 {
+        self.voice.values().sum::<u64>() / 60
+    }
+
+    pub fn top<K: Clone>(map: &HashMap<K, u64>, count: usize) -> Vec<(K, u64)> {
+        let mut entries = map.iter().map(|(k, v)| (k.clone(), *v)).collect::<Vec<_>>();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.1));
+        entries.truncate(count);
+        entries
+    }
+}
+
+pub fn hour_of(at: DateTime<Utc>) -> i64 {
+    at.timestamp().div_euclid(HOUR)
+}
+
+pub fn load_analytics() -> ServerAnalyticsMap {
+    storage::load(ANALYTICS_STORAGE)
+}
+
+pub fn save_analytics(repo: &ServerAnalyticsMap) {
+    storage::save(ANALYTICS_STORAGE, repo);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn channel(id: u64) -> ChannelId {
+        ChannelId::new(id)
+    }
+
+    #[test]
+    fn buckets_by_hour() {
+        let start = hour_of(at("2024-01-01T10:00:00Z"));
+        assert_eq!(hour_of(at("2024-01-01T10:59:59Z")), start);
+        assert_eq!(hour_of(at("2024-01-01T11:00:00Z")), start + 1);
+        assert_eq!(hour_of(at("1970-01-01T00:59:59Z")), 0);
+        assert_eq!(hour_of(at("1969-12-31T23:59:59Z")), -1);
+
+        let mut analytics = GuildAnalytics::default();
+        *analytics
+            .bucket(at("2024-01-01T10:05:00Z"))
+            .messages
+            .entry(channel(1))
+            .or_default() += 1;
+        *analytics
+            .bucket(at("2024-01-01T10:55:00Z"))
+            .messages
+            .entry(channel(1))
+            .or_default() += 1;
+        analytics.bucket(at("2024-01-01T11:00:00Z")).joins += 1;
+
+        assert_eq!(analytics.hours.len(), 2);
+        assert_eq!(analytics.hours[&start].messages[&channel(1)], 2);
+        assert_eq!(analytics.hours[&(start + 1)].joins, 1);
+    }
+
+    #[test]
+    fn prunes_buckets_past_the_retention() {
+        let now = at("2024-02-01T12:30:00Z");
+        let mut analytics = GuildAnalytics {
+            retention_days: 7,
+            ..Default::default()
+        };
+        analytics.bucket(at("2024-01-25T11:59:59Z")).joins += 1;
+        analytics.bucket(at("2024-01-25T12:00:00Z")).joins += 1;
+        analytics.bucket(now).joins += 1;
+
+        analytics.prune(now);
+        assert_eq!(
+            analytics.hours.keys().copied().collect::<Vec<_>>(),
+            [hour_of(at("2024-01-25T12:00:00Z")), hour_of(now)]
+        );
+    }
+
+    #[test]
+    fn summarizes_from_the_given_hour() {
+        let mut analytics = GuildAnalytics::default();
+        let old = analytics.bucket(at("2024-01-01T09:00:00Z"));
+        old.messages.insert(channel(1), 100);
+        old.joins = 5;
+        let bucket = analytics.bucket(at("2024-01-01T10:00:00Z"));
+        bucket.messages.insert(channel(1), 3);
+        bucket.messages.insert(channel(2), 4);
+        bucket.users.insert(UserId::new(1), 7);
+        bucket.voice.insert(UserId::new(1), 90);
+        bucket.commands.insert("help".to_string(), 2);
+        bucket.joins = 1;
+        let bucket = analytics.bucket(at("2024-01-01T11:00:00Z"));
+        bucket.messages.insert(channel(2), 1);
+        bucket.voice.insert(UserId::new(1), 60);
+        bucket.leaves = 2;
+
+        let summary = analytics.summarize(at("2024-01-01T10:30:00Z"));
+        assert_eq!(summary.messages, 8);
+        assert_eq!(
+            summary
+                .messages_by_hour
+                .values()
+                .copied()
+                .collect::<Vec<_>>(),
+            [7, 1]
+        );
+        assert_eq!(summary.channels[&channel(1)], 3);
+        assert_eq!(summary.channels[&channel(2)], 5);
+        assert_eq!(summary.users[&UserId::new(1)], 7);
+        assert_eq!(summary.voice_minutes(), 2);
+        assert_eq!(summary.commands["help"], 2);
+        assert_eq!((summary.joins, summary.leaves), (1, 2));
+    }
+
+    #[test]
+    fn ranks_the_top_entries() {
+        let counts = HashMap::from([("a", 3), ("b", 10), ("c", 1), ("d", 7)]);
+
+        assert_eq!(
+            AnalyticsSummary::top(&counts, 3),
+            [("b", 10), ("d", 7), ("a", 3)]
+        );
+        assert_eq!(AnalyticsSummary::top(&counts, 10).len(), 4);
+        assert!(AnalyticsSummary::top(&counts, 0).is_empty());
+    }
+
+    #[test]
+    fn series_include_empty_periods() {
+        let now = Utc::now();
+        let mut analytics = GuildAnalytics::default();
+        analytics.bucket(now - chrono::Duration::hours(5)).joins = 2;
+        analytics.bucket(now - chrono::Duration::hours(4)).joins = 3;
+        analytics.bucket(now).joins = 1;
+
+        let series = analytics.series(now - chrono::Duration::hours(5), 2, |b| b.joins);
+        assert_eq!(
+            series.iter().map(|(_, joins)| *joins).collect::<Vec<_>>(),
+            [5, 0, 1]
+        );
+        assert_eq!(series[0].0, hour_of(now) - 5);
+    }
+}
@@ -0,0 +1,265 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{ChannelId, GuildId, RoleId, UserId},
+    prelude::TypeMapKey,
+};
+use tokio::sync::RwLock;
+
+use crate::storage;
+
+const BIRTHDAYS_STORAGE: &str = "birthdays";
+const BIRTHDAY_CONFIG_STORAGE: &str = "birthday_config";
+const CELEBRATIONS_STORAGE: &str = "birthday_celebrations";
+
+// Fits in an embed field, and in a reply with some text around it.
+pub const MAX_BIRTHDAY_TEMPLATE_LENGTH: usize = 1000;
+pub const DEFAULT_BIRTHDAY_TEMPLATE: &str = "🎉 Happy birthday {user}! Have a wonderful day!";
+pub const BIRTHDAY_ROLE_DURATION: i64 = 24; // hours
+
+pub struct UserBirthdays;
+pub type UserBirthdaysRepo = DashMap<UserId, Birthday>;
+impl TypeMapKey for UserBirthdays {
+    type Value = Arc<UserBirthdaysRepo>;
+}
+
+pub struct ServerBirthdayConfigRepo;
+impl TypeMapKey for ServerBirthdayConfigRepo {
+    type Value = Arc<DashMap<GuildId, BirthdayConfig>>;
+}
+
+pub struct BirthdayCelebrations;
+impl TypeMapKey for BirthdayCelebrations {
+    type Value = Arc<RwLock<Vec<BirthdayCelebration>>>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Birthday {
+    pub month: u32,
+    pub day: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<i32>,
+    pub timezone: Tz,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BirthdayConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<ChannelId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<RoleId>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BirthdayCelebration {
+    pub guild_id: GuildId,
+    pub user_id: UserId,
+    pub date: NaiveDate,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<RoleId>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Birthday {
+    pub fn new(month: u32, day: u32, year: Option<i32>, timezone: Tz) -> Option<Self> {
+        // 2000 is a leap year, so February 29th is accepted when no year is given.
+        NaiveDate::from_ymd_opt(year.unwrap_or(2000), month, day)?;
+        Some(Self {
+            month,
+            day,
+            year,
+            timezone,
+        })
+    }
+
+    /// Parses `MM-DD` or `YYYY-MM-DD`.
+    pub fn parse_date(input: &str) -> Option<(u32, u32, Option<i32>)> {
+        let parts = input
+            .split(['-', '/'])
+            .map(|p| p.parse::<i32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        match parts.as_slice() {
+            [month, day] => Some((*month as u32, *day as u32, None)),
+            [year, month, day] if *year > 1900 => Some((*month as u32, *day as u32, Some(*year))),
+            _ => None,
+        }
+    }
+
+    pub fn local_today(&self, now: DateTime<Utc>) -> NaiveDate {
+        now.with_timezone(&self.timezone).date_naive()
+    }
+
+    pub fn is_today(&self, now: DateTime<Utc>) -> bool {
+        let today = self.local_today(now);
+        self.occurrence_in(today.year()) == Some(today)
+    }
+
+    /// Leap day birthdays are celebrated on February 28th in non-leap years.
+    pub fn occurrence_in(&self, year: i32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, self.month, self.day)
+            .or_else(|| NaiveDate::from_ymd_opt(year, self.month, self.day - 1))
+    }
+
+    pub fn next_occurrence(&self, now: DateTime<Utc>) -> Option<NaiveDate> {
+        let today = self.local_today(now);
+        match self.occurrence_in(today.year()) {
+            Some(date) if date >= today => Some(date),
+            _ => self.occurrence_in(today.year() + 1),
+        }
+    }
+
+    pub fn age_on(&self, date: NaiveDate) -> Option<i32> {
+        self.year.map(|year| date.year() - year)
+    }
+
+    pub fn date_str(&self) -> String {
+        let date = NaiveDate::from_ymd_opt(self.year.unwrap_or(2000), self.month, self.day);
+        match (date, self.year) {
+            (Some(date), Some(_)) => date.format("%B %-d, %Y").to_string(),
+            (Some(date), None) => date.format("%B %-d").to_string(),
+            (None, _) => format!("{:02}-{:02}", self.month, self.day),
+        }
+    }
+}
+
+impl BirthdayCelebration {
+    pub fn new(guild_id: GuildId, user_id: UserId, date: NaiveDate, role: Option<RoleId>) -> Self {
+        Self {
+            guild_id,
+            user_id,
+            date,
+            role,
+            expires_at: Utc::now() + Duration::hours(BIRTHDAY_ROLE_DURATION),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Utc::now() >= self.expires_at
+    }
+
+    /// Celebrations are kept for a day after they expire so the same birthday is not announced
+    /// twice when the local day is longer than the role duration.
+    pub fn is_stale(&self) -> bool {
+        self.role.is_none() && Utc::now() >= self.expires_at + Duration::days(1)
+    }
+}
+
+pub fn load_birthdays() -> UserBirthdaysRepo {
+    storage::load(BIRTHDAYS_STORAGE)
+}
+
+pub fn save_birthdays(repo: &UserBirthdaysRepo) {
+    storage::save(BIRTHDAYS_STORAGE, repo);
+}
+
+pub fn load_birthday_configs() -> DashMap<GuildId, BirthdayConfig> {
+    storage::load(BIRTHDAY_CONFIG_STORAGE)
+}
+
+pub fn save_birthday_configs(repo: &DashMap<GuildId, BirthdayConfig>) {
+    storage::save(BIRTHDAY_CONFIG_STORAGE, repo);
+}
+
+pub fn load_celebrations() -> Vec<BirthdayCelebration> {
+    storage::load(CELEBRATIONS_STORAGE)
+}
+
+pub fn save_celebrations(celebrations: &[BirthdayCelebration]) {
+    storage::save(CELEBRATIONS_STORAGE, celebrations);
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap()
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(Birthday::parse_date("03-14"), Some((3, 14, None)));
+        assert_eq!(
+            Birthday::parse_date("1995/03/14"),
+            Some((3, 14, Some(1995)))
+        );
+        assert_eq!(Birthday::parse_date("14"), None);
+        assert_eq!(Birthday::parse_date("03-xx"), None);
+        assert_eq!(Birthday::parse_date("95-03-14"), None);
+        assert_eq!(Birthday::parse_date("1995-03-14-01"), None);
+    }
+
+    #[test]
+    fn validates_dates() {
+        assert!(Birthday::new(2, 29, None, Tz::UTC).is_some());
+        assert!(Birthday::new(2, 29, Some(2004), Tz::UTC).is_some());
+        assert!(Birthday::new(2, 29, Some(2001), Tz::UTC).is_none());
+        assert!(Birthday::new(2, 30, None, Tz::UTC).is_none());
+        assert!(Birthday::new(13, 1, None, Tz::UTC).is_none());
+        assert!(Birthday::new(4, 0, None, Tz::UTC).is_none());
+    }
+
+    #[test]
+    fn leap_days_move_to_february_28th() {
+        let birthday = Birthday::new(2, 29, Some(2000), Tz::UTC).unwrap();
+        assert_eq!(birthday.occurrence_in(2024), Some(date(2024, 2, 29)));
+        assert_eq!(birthday.occurrence_in(2023), Some(date(2023, 2, 28)));
+        assert!(birthday.is_today(at(2023, 2, 28, 12)));
+        assert!(!birthday.is_today(at(2023, 3, 1, 12)));
+        assert_eq!(
+            birthday.next_occurrence(at(2023, 3, 1, 12)),
+            Some(date(2024, 2, 29))
+        );
+        assert_eq!(birthday.age_on(date(2024, 2, 29)), Some(24));
+    }
+
+    #[test]
+    fn next_occurrence_rolls_over() {
+        let birthday = Birthday::new(6, 15, None, Tz::UTC).unwrap();
+        assert_eq!(
+            birthday.next_occurrence(at(2025, 6, 15, 23)),
+            Some(date(2025, 6, 15))
+        );
+        assert_eq!(
+            birthday.next_occurrence(at(2025, 6, 16, 0)),
+            Some(date(2026, 6, 15))
+        );
+        assert_eq!(
+            birthday.next_occurrence(at(2025, 1, 1, 0)),
+            Some(date(2025, 6, 15))
+        );
+        assert_eq!(birthday.age_on(date(2025, 6, 15)), None);
+    }
+
+    #[test]
+    fn days_follow_the_timezone() {
+        let tokyo = Birthday::new(6, 15, None, chrono_tz::Asia::Tokyo).unwrap();
+        let los_angeles = Birthday::new(6, 15, None, chrono_tz::America::Los_Angeles).unwrap();
+        // 16:00 UTC is already the 16th in Tokyo but still the 15th in Los Angeles.
+        let now = at(2025, 6, 15, 16);
+        assert!(!tokyo.is_today(now));
+        assert!(los_angeles.is_today(now));
+        assert_eq!(tokyo.next_occurrence(now), Some(date(2026, 6, 15)));
+        assert_eq!(los_angeles.next_occurrence(now), Some(date(2025, 6, 15)));
+    }
+
+    #[test]
+    fn formats_dates() {
+        let with_year = Birthday::new(3, 14, Some(1995), Tz::UTC).unwrap();
+        let without_year = Birthday::new(3, 14, None, Tz::UTC).unwrap();
+        assert_eq!(with_year.date_str(), "March 14, 1995");
+        assert_eq!(without_year.date_str(), "March 14");
+    }
+}
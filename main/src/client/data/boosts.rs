@@ -0,0 +1,185 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{ChannelId, GuildId, RoleId, UserId},
+    prelude::TypeMapKey,
+};
+
+use crate::storage;
+
+const BOOST_CONFIG_STORAGE: &str = "boost_config";
+const BOOSTERS_STORAGE: &str = "boosters";
+
+// Fits in an embed field, and in a reply with some text around it.
+pub const MAX_BOOST_TEMPLATE_LENGTH: usize = 1000;
+pub const DEFAULT_BOOST_TEMPLATE: &str =
+    "💎 Thank you {user} for boosting **{guild.name}**! We now have {guild.boost_count} boosts.";
+
+pub struct ServerBoostConfigRepo;
+pub type ServerBoostConfigMap = DashMap<GuildId, BoostConfig>;
+impl TypeMapKey for ServerBoostConfigRepo {
+    type Value = Arc<ServerBoostConfigMap>;
+}
+
+pub struct Boosters;
+pub type BoostersMap = DashMap<GuildId, HashMap<UserId, BoosterRecord>>;
+impl TypeMapKey for Boosters {
+    type Value = Arc<BoostersMap>;
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BoostConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<ChannelId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub custom_roles: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoosterRecord {
+    pub since: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<RoleId>,
+}
+
+impl BoosterRecord {
+    pub fn new(since: DateTime<Utc>) -> Self {
+        Self { since, role: None }
+    }
+}
+
+// Records the members that boost and aren't recorded yet. The recorded members that stopped
+// boosting are returned, their records are removed by `remove_booster` with their roles.
+pub fn reconcile_boosters(
+    records: &mut HashMap<UserId, BoosterRecord>,
+    members: impl IntoIterator<Item = (UserId, Option<DateTime<Utc>>)>,
+) -> Vec<UserId> {
+    let mut ended = vec![];
+    for (user_id, since) in members {
+        match (since, records.contains_key(&user_id)) {
+            (Some(since), false) => {
+                records.insert(user_id, BoosterRecord::new(since));
+            }
+            (None, true) => ended.push(user_id),
+            _ => {}
+        }
+    }
+    ended
+}
+
+pub fn record_boost(repo: &BoostersMap, guild_id: GuildId, user_id: UserId, since: DateTime<Utc>) {
+    repo.entry(guild_id)
+        .or_default()
+        .insert(user_id, BoosterRecord::new(since));
+}
+
+pub fn remove_booster(
+    repo: &BoostersMap,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Option<BoosterRecord> {
+    repo.get_mut(&guild_id)
+        .and_then(|mut records| records.remove(&user_id))
+}
+
+// Keeps the boost start of an existing record, members that boosted while the bot was offline
+// are recorded with the given one.
+pub fn set_booster_role(
+    repo: &BoostersMap,
+    guild_id: GuildId,
+    user_id: UserId,
+    since: DateTime<Utc>,
+    role: Option<RoleId>,
+) {
+    repo.entry(guild_id)
+        .or_default()
+        .entry(user_id)
+        .or_insert_with(|| BoosterRecord::new(since))
+        .role = role;
+}
+
+pub fn load_boost_configs() -> ServerBoostConfigMap {
+    storage::load(BOOST_CONFIG_STORAGE)
+}
+
+pub fn save_boost_configs(repo: &ServerBoostConfigMap) {
+    storage::save(BOOST_CONFIG_STORAGE, repo);
+}
+
+pub fn load_boosters() -> BoostersMap {
+    storage::load(BOOSTERS_STORAGE)
+}
+
+pub fn save_boosters(repo: &BoostersMap) {
+    storage::save(BOOSTERS_STORAGE, repo);
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    const GUILD: GuildId = GuildId::new(1);
+
+    #[test]
+    fn reconciles_boosters() {
+        let now = Utc::now();
+        let mut records = HashMap::from([
+            (UserId::new(1), BoosterRecord::new(now - Duration::days(30))),
+            (UserId::new(2), BoosterRecord::new(now - Duration::days(10))),
+        ]);
+        let ended = reconcile_boosters(
+            &mut records,
+            [
+                (UserId::new(1), Some(now)),
+                (UserId::new(2), None),
+                (UserId::new(3), Some(now - Duration::days(1))),
+                (UserId::new(4), None),
+            ],
+        );
+        assert_eq!(ended, [UserId::new(2)]);
+        // Existing records keep when the boost started, new ones use the member's.
+        assert_eq!(records[&UserId::new(1)].since, now - Duration::days(30));
+        assert_eq!(records[&UserId::new(3)].since, now - Duration::days(1));
+        assert!(!records.contains_key(&UserId::new(4)));
+    }
+
+    #[test]
+    fn records_and_removes_boosters() {
+        let repo = BoostersMap::new();
+        let now = Utc::now();
+        record_boost(&repo, GUILD, UserId::new(1), now);
+        set_booster_role(
+            &repo,
+            GUILD,
+            UserId::new(1),
+            now + Duration::days(1),
+            Some(RoleId::new(5)),
+        );
+        let record = repo.get(&GUILD).unwrap()[&UserId::new(1)].clone();
+        assert_eq!(record.since, now);
+        assert_eq!(record.role, Some(RoleId::new(5)));
+
+        let removed = remove_booster(&repo, GUILD, UserId::new(1)).unwrap();
+        assert_eq!(removed.role, Some(RoleId::new(5)));
+        assert!(remove_booster(&repo, GUILD, UserId::new(1)).is_none());
+        assert!(remove_booster(&repo, GuildId::new(2), UserId::new(1)).is_none());
+    }
+
+    #[test]
+    fn roles_of_unrecorded_boosters_start_a_record() {
+        let repo = BoostersMap::new();
+        let since = Utc::now() - Duration::days(3);
+        set_booster_role(&repo, GUILD, UserId::new(1), since, Some(RoleId::new(5)));
+        set_booster_role(&repo, GUILD, UserId::new(1), Utc::now(), None);
+        let record = repo.get(&GUILD).unwrap()[&UserId::new(1)].clone();
+        assert_eq!(record.since, since);
+        assert_eq!(record.role, None);
+    }
+}
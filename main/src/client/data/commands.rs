@@ -1,46 +1,483 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, process, sync::Arc};
 
-use serenity::{all::CreateCommand, prelude::TypeMapKey};
-use utils::{BotPermission, CommandTrait, info};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{ChannelId, CommandType, CreateCommand, GuildChannel, GuildId, RoleId},
+    prelude::TypeMapKey,
+};
+use utils::{BotPermission, CommandTrait, error, info};
 
-use crate::commands::{configuration, fun, integration, security, server, utilities};
+use crate::{
+    commands::{configuration, fun, integration, security, server, utilities},
+    storage,
+};
+
+const COMMAND_SETTINGS_STORAGE: &str = "command_settings";
 
 pub struct Commands;
-pub type CommandsMap = HashMap<String, (Arc<dyn CommandTrait>, Vec<BotPermission>)>;
+// Handler, permissions and the module of every command by name.
+pub type CommandsMap = HashMap<String, (Arc<dyn CommandTrait>, Vec<BotPermission>, &'static str)>;
 impl TypeMapKey for Commands {
     type Value = CommandsMap;
 }
 
-fn commands() -> Vec<utils::CommandTemplate> {
-    let mut commands = Vec::new();
+// Lowercase aliases declared by commands, and the name of the command they stand for.
+pub struct CommandAliases;
+pub type CommandAliasesMap = HashMap<String, String>;
+impl TypeMapKey for CommandAliases {
+    type Value = Arc<CommandAliasesMap>;
+}
+
+// What `help` shows about a command, collected while loading since the templates aren't kept.
+#[derive(Debug, Clone)]
+pub struct HelpEntry {
+    pub name: String,
+    pub description: String,
+    pub kind: CommandType,
+    pub aliases: Vec<String>,
+    // `name sub <required> [optional]` and what it does, for each syntax the command supports.
+    pub legacy: Vec<(String, String)>,
+    pub slash: Vec<(String, String)>,
+}
+
+pub struct CommandHelp;
+pub type CommandHelpMap = HashMap<String, HelpEntry>;
+impl TypeMapKey for CommandHelp {
+    type Value = Arc<CommandHelpMap>;
+}
+
+// A slash or context menu command with the module it was loaded from.
+#[derive(Debug, Clone)]
+pub struct ApplicationCommand {
+    pub name: String,
+    pub module: &'static str,
+    pub command: CreateCommand,
+}
+
+// Kept so commands can be registered again for guilds joined later.
+pub struct ApplicationCommands;
+impl TypeMapKey for ApplicationCommands {
+    type Value = Arc<Vec<ApplicationCommand>>;
+}
+
+pub struct CommandSettings;
+pub type CommandSettingsMap = DashMap<GuildId, GuildCommandSettings>;
+impl TypeMapKey for CommandSettings {
+    type Value = Arc<CommandSettingsMap>;
+}
+
+// Commands that can't be disabled or restricted, so a server can't lock itself out.
+pub const ALWAYS_ENABLED: [&str; 2] = ["commands", "help"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GuildCommandSettings {
+    #[serde(default)]
+    pub disabled_modules: Vec<String>,
+    #[serde(default)]
+    pub disabled_commands: Vec<String>,
+    #[serde(default)]
+    pub restrictions: HashMap<String, CommandRestriction>,
+    // Lowercase aliases added by the server, and the name of the command they stand for.
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+}
 
-    commands.extend(server::get_modules());
-    commands.extend(security::get_modules());
-    commands.extend(integration::get_modules());
-    commands.extend(fun::get_modules());
-    commands.extend(configuration::get_modules());
-    commands.extend(utilities::get_modules());
+// Where and by whom a command can be used, empty lists don't restrict anything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandRestriction {
+    #[serde(default)]
+    pub channels: Vec<ChannelId>,
+    #[serde(default)]
+    pub roles: Vec<RoleId>,
+}
 
-    commands
+impl CommandRestriction {
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty() && self.roles.is_empty()
+    }
 }
 
-pub fn load_commands() -> (Vec<CreateCommand>, CommandsMap) {
+impl GuildCommandSettings {
+    pub fn module_enabled(&self, module: &str) -> bool {
+        !self
+            .disabled_modules
+            .iter()
+            .any(|m| m.eq_ignore_ascii_case(module))
+    }
+
+    pub fn command_enabled(&self, command: &str, module: &str) -> bool {
+        ALWAYS_ENABLED.contains(&command)
+            || (self.module_enabled(module) && !self.disabled_commands.iter().any(|c| c == command))
+    }
+
+    // Why the command can't be used here, `None` when it can. Channels also match their
+    // category, or the parent channel of a thread.
+    pub fn blocked(
+        &self,
+        command: &str,
+        module: &str,
+        channel: Option<&GuildChannel>,
+        roles: &[RoleId],
+    ) -> Option<&'static str> {
+        if !self.command_enabled(command, module) {
+            return Some("This command is disabled in this server.");
+        }
+        if ALWAYS_ENABLED.contains(&command) {
+            return None;
+        }
+        let restriction = self.restrictions.get(command)?;
+        if !restriction.channels.is_empty()
+            && !channel.is_some_and(|channel| {
+                restriction.channels.contains(&channel.id)
+                    || channel
+                        .parent_id
+                        .is_some_and(|parent| restriction.channels.contains(&parent))
+            })
+        {
+            return Some("This command can't be used in this channel.");
+        }
+        if !restriction.roles.is_empty() && !roles.iter().any(|r| restriction.roles.contains(r)) {
+            return Some("You don't have a role that can use this command.");
+        }
+        None
+    }
+}
+
+pub const MODULES: [&str; 6] = [
+    "server",
+    "security",
+    "integration",
+    "fun",
+    "configuration",
+    "utilities",
+];
+
+fn commands() -> Vec<(&'static str, utils::CommandTemplate)> {
+    let modules = [
+        server::get_modules(),
+        security::get_modules(),
+        integration::get_modules(),
+        fun::get_modules(),
+        configuration::get_modules(),
+        utilities::get_modules(),
+    ];
+
+    MODULES
+        .into_iter()
+        .zip(modules)
+        .flat_map(|(module, commands)| commands.into_iter().map(move |c| (module, c)))
+        .collect()
+}
+
+// The command a legacy message means by `word`, its name ignoring case, one of its aliases, or
+// an alias the server added.
+pub fn find_command(
+    commands: &CommandsMap,
+    aliases: &CommandAliasesMap,
+    settings: Option<&GuildCommandSettings>,
+    word: &str,
+) -> Option<String> {
+    if commands.contains_key(word) {
+        return Some(word.to_string());
+    }
+    let word = word.to_lowercase();
+    if commands.contains_key(&word) {
+        return Some(word);
+    }
+    aliases
+        .get(&word)
+        .or_else(|| settings.and_then(|s| s.aliases.get(&word)))
+        .filter(|name| commands.contains_key(*name))
+        .cloned()
+}
+
+pub fn load_commands() -> (
+    Vec<ApplicationCommand>,
+    CommandsMap,
+    CommandAliasesMap,
+    CommandHelpMap,
+) {
     info!("Loading commands...");
     let mut output_commands = Vec::new();
     let mut commands_map = CommandsMap::new();
+    let mut aliases_map = CommandAliasesMap::new();
+    let mut help_map = CommandHelpMap::new();
 
-    for (command, function) in commands().iter() {
+    for (module, (command, function)) in commands() {
         let func = function.clone();
         if func.is_slash() {
-            output_commands.push(command.get_command());
+            output_commands.push(ApplicationCommand {
+                name: command.get_name(),
+                module,
+                command: command.get_command(),
+            });
+        }
+        let name = command.get_name();
+        if commands_map
+            .keys()
+            .any(|other| other.eq_ignore_ascii_case(&name))
+        {
+            error!("Command {} is defined more than once", name);
+            process::exit(1);
+        }
+        for alias in command.get_aliases() {
+            if let Some(other) = aliases_map.insert(alias.clone(), name.clone()) {
+                error!(
+                    "Alias {} of command {} is already used by {}",
+                    alias, name, other
+                );
+                process::exit(1);
+            }
         }
-        commands_map.insert(
-            command.get_name().to_string(),
-            (func, command.get_permissions()),
+        let chat_input = command.get_kind() == CommandType::ChatInput;
+        help_map.insert(
+            name.clone(),
+            HelpEntry {
+                name: name.clone(),
+                description: command.get_description(),
+                kind: command.get_kind(),
+                aliases: command.get_aliases().to_vec(),
+                legacy: if func.is_legacy() {
+                    command.usage(true)
+                } else {
+                    vec![]
+                },
+                slash: if func.is_slash() && chat_input {
+                    command.usage(false)
+                } else {
+                    vec![]
+                },
+            },
         );
-        info!("Loaded command: {}", command.get_name());
+        commands_map.insert(name.clone(), (func, command.get_permissions(), module));
+        info!("Loaded command: {}", name);
+    }
+
+    // Checked once everything is loaded, the command could come after the alias.
+    for (alias, name) in &aliases_map {
+        if let Some(other) = commands_map
+            .keys()
+            .find(|other| other.eq_ignore_ascii_case(alias))
+        {
+            error!(
+                "Alias {} of command {} is the name of command {}",
+                alias, name, other
+            );
+            process::exit(1);
+        }
     }
 
     info!("Loaded {} commands", output_commands.len());
-    (output_commands, commands_map)
+    (output_commands, commands_map, aliases_map, help_map)
+}
+
+pub fn load_command_settings() -> CommandSettingsMap {
+    storage::load(COMMAND_SETTINGS_STORAGE)
+}
+
+pub fn save_command_settings(repo: &CommandSettingsMap) {
+    storage::save(COMMAND_SETTINGS_STORAGE, repo);
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::{
+        all::{Context, Guild},
+        async_trait,
+    };
+    use utils::{CommandArguments, CommandResponse, UserType};
+
+    use super::*;
+
+    struct Noop;
+
+    #[async_trait]
+    impl CommandTrait for Noop {
+        async fn execute<'a>(
+            &self,
+            _: &'a Context,
+            _: UserType,
+            _: Option<(Guild, GuildChannel)>,
+            _: CommandArguments<'a>,
+        ) -> Result<Option<CommandResponse>, String> {
+            Ok(None)
+        }
+    }
+
+    fn commands_map(names: &[&str]) -> CommandsMap {
+        names
+            .iter()
+            .map(|name| {
+                let handler: Arc<dyn CommandTrait> = Arc::new(Noop);
+                (name.to_string(), (handler, vec![], "utilities"))
+            })
+            .collect()
+    }
+
+    fn aliases_map(aliases: &[(&str, &str)]) -> HashMap<String, String> {
+        aliases
+            .iter()
+            .map(|(alias, name)| (alias.to_string(), name.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn finds_commands_ignoring_case() {
+        let commands = commands_map(&["ping", "View avatar"]);
+        let aliases = CommandAliasesMap::new();
+        let find = |word| find_command(&commands, &aliases, None, word);
+        assert_eq!(find("ping").as_deref(), Some("ping"));
+        assert_eq!(find("PiNg").as_deref(), Some("ping"));
+        assert_eq!(find("View avatar").as_deref(), Some("View avatar"));
+        assert_eq!(find("pong"), None);
+    }
+
+    #[test]
+    fn finds_commands_by_declared_aliases() {
+        let commands = commands_map(&["ping", "help"]);
+        let aliases = aliases_map(&[("p", "ping"), ("help", "ping")]);
+        let find = |word| find_command(&commands, &aliases, None, word);
+        assert_eq!(find("p").as_deref(), Some("ping"));
+        assert_eq!(find("P").as_deref(), Some("ping"));
+        // A command's own name wins over an alias.
+        assert_eq!(find("help").as_deref(), Some("help"));
+    }
+
+    #[test]
+    fn finds_commands_by_server_aliases() {
+        let commands = commands_map(&["ping", "snipe"]);
+        let aliases = aliases_map(&[("p", "ping")]);
+        let settings = GuildCommandSettings {
+            aliases: aliases_map(&[("s", "snipe"), ("p", "snipe")]),
+            ..Default::default()
+        };
+        let find = |word| find_command(&commands, &aliases, Some(&settings), word);
+        assert_eq!(find("s").as_deref(), Some("snipe"));
+        assert_eq!(find("S").as_deref(), Some("snipe"));
+        // Declared aliases are checked before the server's.
+        assert_eq!(find("p").as_deref(), Some("ping"));
+        assert_eq!(find_command(&commands, &aliases, None, "s"), None);
+    }
+
+    #[test]
+    fn ignores_aliases_of_missing_commands() {
+        let commands = commands_map(&["ping"]);
+        let aliases = aliases_map(&[("old", "removed")]);
+        let settings = GuildCommandSettings {
+            aliases: aliases_map(&[("gone", "deleted")]),
+            ..Default::default()
+        };
+        assert_eq!(
+            find_command(&commands, &aliases, Some(&settings), "old"),
+            None
+        );
+        assert_eq!(
+            find_command(&commands, &aliases, Some(&settings), "gone"),
+            None
+        );
+    }
+
+    fn channel(id: u64, parent: Option<u64>) -> GuildChannel {
+        let mut channel = GuildChannel::default();
+        channel.id = ChannelId::new(id);
+        channel.parent_id = parent.map(ChannelId::new);
+        channel
+    }
+
+    // Subcommands declared as raw options would skip the router and its typed arguments.
+    #[test]
+    fn subcommands_are_routed() {
+        for (_, (command, _)) in commands() {
+            for option in command.get_options() {
+                let kind = serenity::json::to_value(option).unwrap()["type"].as_u64();
+                assert!(
+                    !matches!(kind, Some(1 | 2)),
+                    "{} declares subcommands without routing them",
+                    command.get_name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn disabled_modules_and_commands() {
+        let settings = GuildCommandSettings {
+            disabled_modules: vec!["Fun".to_string()],
+            disabled_commands: vec!["ping".to_string()],
+            ..Default::default()
+        };
+        assert!(!settings.command_enabled("birthday", "fun"));
+        assert!(!settings.command_enabled("ping", "utilities"));
+        assert!(settings.command_enabled("avatar", "utilities"));
+    }
+
+    #[test]
+    fn some_commands_are_always_enabled() {
+        let settings = GuildCommandSettings {
+            disabled_modules: vec!["server".to_string(), "utilities".to_string()],
+            disabled_commands: vec!["commands".to_string(), "help".to_string()],
+            ..Default::default()
+        };
+        assert!(settings.command_enabled("commands", "server"));
+        assert!(settings.command_enabled("help", "utilities"));
+    }
+
+    #[test]
+    fn restricts_to_channels_and_their_children() {
+        let mut settings = GuildCommandSettings::default();
+        settings.restrictions.insert(
+            "snipe".to_string(),
+            CommandRestriction {
+                channels: vec![ChannelId::new(1)],
+                roles: vec![],
+            },
+        );
+        let blocked =
+            |channel: Option<&GuildChannel>| settings.blocked("snipe", "server", channel, &[]);
+        assert_eq!(blocked(Some(&channel(1, None))), None);
+        assert_eq!(blocked(Some(&channel(2, Some(1)))), None);
+        assert!(blocked(Some(&channel(2, Some(3)))).is_some());
+        assert!(blocked(None).is_some());
+        assert_eq!(settings.blocked("avatar", "utilities", None, &[]), None);
+    }
+
+    #[test]
+    fn restricts_to_roles() {
+        let mut settings = GuildCommandSettings::default();
+        settings.restrictions.insert(
+            "snipe".to_string(),
+            CommandRestriction {
+                channels: vec![],
+                roles: vec![RoleId::new(5)],
+            },
+        );
+        let here = channel(1, None);
+        assert!(
+            settings
+                .blocked("snipe", "server", Some(&here), &[RoleId::new(6)])
+                .is_some()
+        );
+        assert_eq!(
+            settings.blocked(
+                "snipe",
+                "server",
+                Some(&here),
+                &[RoleId::new(6), RoleId::new(5)]
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn disabled_commands_are_blocked_first() {
+        let settings = GuildCommandSettings {
+            disabled_commands: vec!["snipe".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            settings.blocked("snipe", "server", None, &[]),
+            Some("This command is disabled in this server.")
+        );
+    }
 }
@@ -0,0 +1,35 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
+use serenity::{all::UserId, prelude::TypeMapKey};
+use utils::CooldownBucket;
+
+// Commands a user can run within the window, whatever the commands are.
+pub const RATE_LIMIT_COMMANDS: usize = 5;
+pub const RATE_LIMIT_WINDOW: i64 = 10; // seconds
+
+// When the cooldown of a command ends, by command name and the id of its bucket.
+pub struct Cooldowns;
+pub type CooldownsMap = DashMap<(String, CooldownBucket, u64), DateTime<Utc>>;
+impl TypeMapKey for Cooldowns {
+    type Value = Arc<CooldownsMap>;
+}
+
+// Times of the recent commands of a user, oldest first, and when they were last told to slow
+// down so it's only said once per window.
+#[derive(Default)]
+pub struct RateLimit {
+    pub uses: VecDeque<DateTime<Utc>>,
+    pub notified: Option<DateTime<Utc>>,
+}
+
+pub struct CommandRateLimits;
+pub type CommandRateLimitsMap = DashMap<UserId, RateLimit>;
+impl TypeMapKey for CommandRateLimits {
+    type Value = Arc<CommandRateLimitsMap>;
+}
+
+pub fn rate_limit_window() -> Duration {
+    Duration::seconds(RATE_LIMIT_WINDOW)
+}
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serenity::{
+    all::{Attachment, ChannelId, GuildId, Message, MessageId, Timestamp, User},
+    prelude::TypeMapKey,
+};
+
+use crate::storage;
+
+const MESSAGE_LOG_STORAGE: &str = "message_log";
+
+pub const MESSAGE_STORE_TTL: i64 = 6 * 60 * 60; // seconds
+pub const MAX_STORED_MESSAGES: usize = 50_000;
+
+// Recent messages kept by the bot itself, so deletions can still be sniped and logged once
+// serenity's cache no longer has them.
+pub struct MessageStore;
+pub type MessageStoreMap = DashMap<MessageId, StoredMessage>;
+impl TypeMapKey for MessageStore {
+    type Value = Arc<MessageStoreMap>;
+}
+
+pub struct MessageLogChannels;
+pub type MessageLogChannelMap = DashMap<GuildId, ChannelId>;
+impl TypeMapKey for MessageLogChannels {
+    type Value = Arc<MessageLogChannelMap>;
+}
+
+#[derive(Debug, Clone)]
+pub struct StoredMessage {
+    pub id: MessageId,
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    pub author: User,
+    pub content: String,
+    pub attachments: Vec<Attachment>,
+    pub timestamp: Timestamp,
+    pub stored_at: DateTime<Utc>,
+}
+
+impl StoredMessage {
+    pub fn new(message: &Message, guild_id: GuildId) -> Self {
+        Self {
+            id: message.id,
+            guild_id,
+            channel_id: message.channel_id,
+            author: message.author.clone(),
+            content: message.content.clone(),
+            attachments: message.attachments.clone(),
+            timestamp: message.timestamp,
+            stored_at: Utc::now(),
+        }
+    }
+
+    // Rebuilds a partial message with the fields snipes and logs use.
+    pub fn to_message(&self) -> Message {
+        let mut message = Message::default();
+        message.id = self.id;
+        message.guild_id = Some(self.guild_id);
+        message.channel_id = self.channel_id;
+        message.author = self.author.clone();
+        message.content = self.content.clone();
+        message.attachments = self.attachments.clone();
+        message.timestamp = self.timestamp;
+        message
+    }
+}
+
+pub fn load_message_log_channels() -> MessageLogChannelMap {
+    storage::load(MESSAGE_LOG_STORAGE)
+}
+
+pub fn save_message_log_channels(repo: &MessageLogChannelMap) {
+    storage::save(MESSAGE_LOG_STORAGE, repo);
+}
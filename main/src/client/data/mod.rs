@@ -1,12 +1,23 @@
+mod analytics;
+mod birthdays;
+mod boosts;
 pub mod commands;
+mod cooldowns;
 mod environment;
 mod extras;
+mod message_store;
 mod pagination;
 mod permissions;
 mod prefixes;
+mod reactions;
+mod reddit;
+mod roleplay;
 mod snipes;
+pub mod storage;
+mod twitch;
 mod user_afk;
 mod voice_master;
+mod youtube;
 
 use std::sync::Arc;
 
@@ -15,13 +26,24 @@ use utils::{Data, UserConfigHash};
 
 use serenity::{Client, all::ShardManager, prelude::TypeMap};
 
-pub fn initialize_type_map(env: Env, commands_map: CommandsMap) -> TypeMap {
+pub fn initialize_type_map(
+    env: Env,
+    commands_map: CommandsMap,
+    aliases_map: CommandAliasesMap,
+    help_map: CommandHelpMap,
+) -> TypeMap {
     let mut data = TypeMap::new();
 
     data.insert::<Environment>(env);
     data.insert::<Commands>(commands_map);
+    data.insert::<CommandAliases>(aliases_map.into());
+    data.insert::<CommandHelp>(help_map.into());
+    data.insert::<CommandSettings>(load_command_settings().into());
+    data.insert::<Cooldowns>(DashMap::new().into());
+    data.insert::<CommandRateLimits>(DashMap::new().into());
 
-    data.insert::<ServerPrefixes>(prefixes::setup().into());
+    data.insert::<ServerPrefixes>(load_server_prefixes().into());
+    data.insert::<UserPrefixes>(load_user_prefixes().into());
     data.insert::<VoiceHub>(test_voice_hub().into());
     data.insert::<UserAFK>(UserConfigHash::new().into());
     data.insert::<UserVoiceConfigRepo>(UserConfigHash::new().into());
@@ -29,7 +51,43 @@ pub fn initialize_type_map(env: Env, commands_map: CommandsMap) -> TypeMap {
     data.insert::<Snipes>(DashMap::new().into());
     data.insert::<EditSnipes>(DashMap::new().into());
     data.insert::<ReactionSnipes>(DashMap::new().into());
-    data.insert::<BlacklistedSnipes>(DashMap::new().into());
+    data.insert::<ReactionHistory>(DashMap::new().into());
+    data.insert::<CachedAttachments>(DashMap::new().into());
+    data.insert::<BlacklistedSnipes>(load_snipe_blacklist().into());
+    data.insert::<SnipeOptOuts>(load_snipe_opt_outs().into());
+    data.insert::<ServerSnipeConfigs>(load_snipe_configs().into());
+    data.insert::<MessageStore>(DashMap::new().into());
+    data.insert::<MessageLogChannels>(load_message_log_channels().into());
+
+    data.insert::<UserBirthdays>(load_birthdays().into());
+    data.insert::<ServerBirthdayConfigRepo>(load_birthday_configs().into());
+    data.insert::<BirthdayCelebrations>(Arc::new(load_celebrations().into()));
+
+    data.insert::<ServerBoostConfigRepo>(load_boost_configs().into());
+    data.insert::<Boosters>(load_boosters().into());
+
+    data.insert::<AutoReactions>(load_auto_reactions().into());
+    data.insert::<ReactionRoles>(load_reaction_roles().into());
+
+    data.insert::<RoleplayCounters>(load_roleplay_counters().into());
+    data.insert::<RoleplayMedia>(crate::roleplay::default_providers().into());
+
+    data.insert::<RedditSubscriptions>(load_reddit_subscriptions().into());
+    data.insert::<RedditSeenPosts>(load_reddit_seen().into());
+    data.insert::<RedditFeed>(crate::reddit::default_provider());
+
+    data.insert::<TwitchSubscriptions>(load_twitch_subscriptions().into());
+    data.insert::<TwitchLiveStreams>(load_twitch_live().into());
+    if let Some(twitch) = crate::twitch::from_env() {
+        data.insert::<Twitch>(twitch.into());
+    }
+
+    data.insert::<YoutubeSubscriptions>(load_youtube_subscriptions().into());
+    data.insert::<YoutubeSeenVideos>(load_youtube_seen().into());
+    data.insert::<YoutubeFeed>(crate::youtube::default_provider());
+
+    data.insert::<ServerAnalytics>(load_analytics().into());
+    data.insert::<VoiceSessions>(DashMap::new().into());
 
     data.insert::<Paginations>(PaginationsMap::new());
     data
@@ -40,13 +98,23 @@ pub async fn build_dynamic_data(data: Data, manager: Arc<ShardManager>) {
     data.insert::<ShardManagerContainer>(manager);
 }
 
+pub use analytics::*;
+pub use birthdays::*;
+pub use boosts::*;
 pub use commands::*;
+pub use cooldowns::*;
 pub use environment::*;
 pub use extras::*;
+pub use message_store::*;
 pub use pagination::*;
 pub use prefixes::*;
+pub use reactions::*;
+pub use reddit::*;
+pub use roleplay::*;
 pub use snipes::*;
+pub use twitch::*;
 pub use user_afk::*;
 pub use voice_master::*;
+pub use youtube::*;
 
 use crate::client::data;
@@ -1,8 +1,21 @@
 use std::{collections::HashMap, sync::Arc};
 
 use dashmap::DashMap;
-use serenity::{all::GuildId, prelude::TypeMapKey};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{GuildId, UserId},
+    prelude::TypeMapKey,
+};
 use tokio::sync::RwLock;
+use utils::{UserConfigHash, UserGlobalType};
+
+use super::storage;
+
+const SERVER_PREFIXES_STORAGE: &str = "server_prefixes";
+const USER_PREFIXES_STORAGE: &str = "user_prefixes";
+
+pub const MAX_PREFIXES: usize = 5;
+pub const MAX_PREFIX_LENGTH: usize = 10;
 
 #[derive(Debug, Hash, Eq, PartialEq)]
 pub enum ServerPrefix {
@@ -11,13 +24,173 @@ pub enum ServerPrefix {
 }
 
 pub struct ServerPrefixes;
-pub type ServerPrefixesMap = DashMap<ServerPrefix, String>;
+pub type ServerPrefixesMap = DashMap<ServerPrefix, Vec<String>>;
 impl TypeMapKey for ServerPrefixes {
     type Value = Arc<ServerPrefixesMap>;
 }
 
+// Personal prefixes, for one server or for everywhere, used next to the server's prefixes.
+pub struct UserPrefixes;
+impl TypeMapKey for UserPrefixes {
+    type Value = Arc<UserConfigHash<String>>;
+}
+
+// Personal prefixes as they're stored, the keys of `UserConfigHash` can't be JSON keys.
+#[derive(Default, Serialize, Deserialize)]
+struct StoredUserPrefixes {
+    everywhere: HashMap<UserId, String>,
+    servers: HashMap<GuildId, HashMap<UserId, String>>,
+}
+
 pub fn setup() -> ServerPrefixesMap {
     let mut prefixes = ServerPrefixesMap::new();
-    prefixes.insert(ServerPrefix::Default, "!".to_string());
+    prefixes.insert(ServerPrefix::Default, vec!["!".to_string()]);
     prefixes
 }
+
+// Only the prefixes servers changed are stored, the default ones come from `setup`.
+pub fn load_server_prefixes() -> ServerPrefixesMap {
+    let prefixes = setup();
+    let stored: HashMap<GuildId, Vec<String>> = storage::load(SERVER_PREFIXES_STORAGE);
+    for (guild_id, value) in stored {
+        prefixes.insert(ServerPrefix::Guild(guild_id), value);
+    }
+    prefixes
+}
+
+pub fn save_server_prefixes(repo: &ServerPrefixesMap) {
+    let stored = repo
+        .iter()
+        .filter_map(|entry| match entry.key() {
+            ServerPrefix::Guild(guild_id) => Some((*guild_id, entry.value().clone())),
+            ServerPrefix::Default => None,
+        })
+        .collect::<HashMap<_, _>>();
+    storage::save(SERVER_PREFIXES_STORAGE, &stored);
+}
+
+pub fn load_user_prefixes() -> UserConfigHash<String> {
+    let stored: StoredUserPrefixes = storage::load(USER_PREFIXES_STORAGE);
+    let prefixes = UserConfigHash::new();
+    for (user_id, value) in stored.everywhere {
+        prefixes.insert(UserGlobalType::User(user_id), value);
+    }
+    for (guild_id, users) in stored.servers {
+        for (user_id, value) in users {
+            prefixes.insert(UserGlobalType::Guild(guild_id, user_id), value);
+        }
+    }
+    prefixes
+}
+
+pub fn save_user_prefixes(repo: &UserConfigHash<String>) {
+    let mut stored = StoredUserPrefixes::default();
+    for entry in repo.iter() {
+        match entry.key() {
+            UserGlobalType::User(user_id) => {
+                stored.everywhere.insert(*user_id, entry.value().clone());
+            }
+            UserGlobalType::Guild(guild_id, user_id) => {
+                stored
+                    .servers
+                    .entry(*guild_id)
+                    .or_default()
+                    .insert(*user_id, entry.value().clone());
+            }
+        }
+    }
+    storage::save(USER_PREFIXES_STORAGE, &stored);
+}
+
+// The prefixes of a server, the default ones when it has none or outside of servers.
+pub fn server_prefixes(repo: &ServerPrefixesMap, guild_id: Option<GuildId>) -> Vec<String> {
+    guild_id
+        .and_then(|guild_id| repo.get(&ServerPrefix::Guild(guild_id)))
+        .or(repo.get(&ServerPrefix::Default))
+        .map(|prefixes| prefixes.clone())
+        .expect("Default prefix must be set")
+}
+
+// The personal prefix of a user, the one for the server before the global one.
+pub fn user_prefix(
+    repo: &UserConfigHash<String>,
+    guild_id: Option<GuildId>,
+    user_id: UserId,
+) -> Option<String> {
+    match guild_id {
+        Some(guild_id) => repo.get(&guild_id, &user_id).map(|p| p.clone()),
+        None => repo
+            .get_exact(&UserGlobalType::User(user_id))
+            .map(|p| p.clone()),
+    }
+}
+
+// The longest match wins so `!!` isn't read as `!` followed by `!`.
+pub fn longest_prefix(content: &str, candidates: Vec<String>) -> Option<String> {
+    candidates
+        .into_iter()
+        .filter(|prefix| content.starts_with(prefix.as_str()))
+        .max_by_key(|prefix| prefix.len())
+}
+
+pub fn user_prefix_key(guild_id: Option<GuildId>, user_id: UserId) -> UserGlobalType {
+    match guild_id {
+        Some(guild_id) => UserGlobalType::Guild(guild_id, user_id),
+        None => UserGlobalType::User(user_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUILD: GuildId = GuildId::new(1);
+    const OTHER_GUILD: GuildId = GuildId::new(2);
+    const USER: UserId = UserId::new(3);
+
+    fn prefixes(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn servers_without_prefixes_use_the_default() {
+        let repo = setup();
+        repo.insert(ServerPrefix::Guild(GUILD), prefixes(&["?", "$"]));
+        assert_eq!(server_prefixes(&repo, Some(GUILD)), prefixes(&["?", "$"]));
+        assert_eq!(server_prefixes(&repo, Some(OTHER_GUILD)), prefixes(&["!"]));
+        assert_eq!(server_prefixes(&repo, None), prefixes(&["!"]));
+    }
+
+    #[test]
+    fn personal_prefixes_prefer_the_server_one() {
+        let repo = UserConfigHash::new();
+        assert_eq!(user_prefix(&repo, Some(GUILD), USER), None);
+
+        repo.insert(user_prefix_key(None, USER), "..".to_string());
+        assert_eq!(user_prefix(&repo, Some(GUILD), USER).as_deref(), Some(".."));
+        assert_eq!(user_prefix(&repo, None, USER).as_deref(), Some(".."));
+
+        repo.insert(user_prefix_key(Some(GUILD), USER), ">>".to_string());
+        assert_eq!(user_prefix(&repo, Some(GUILD), USER).as_deref(), Some(">>"));
+        assert_eq!(
+            user_prefix(&repo, Some(OTHER_GUILD), USER).as_deref(),
+            Some("..")
+        );
+        // Outside of servers only the global prefix applies.
+        assert_eq!(user_prefix(&repo, None, USER).as_deref(), Some(".."));
+    }
+
+    #[test]
+    fn the_longest_matching_prefix_wins() {
+        let candidates = prefixes(&["!", "!!", "?"]);
+        assert_eq!(
+            longest_prefix("!!ping", candidates.clone()).as_deref(),
+            Some("!!")
+        );
+        assert_eq!(
+            longest_prefix("!ping", candidates.clone()).as_deref(),
+            Some("!")
+        );
+        assert_eq!(longest_prefix("ping", candidates), None);
+    }
+}
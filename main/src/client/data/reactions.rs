@@ -0,0 +1,290 @@
+use std::sync::{Arc, OnceLock};
+
+use dashmap::DashMap;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{ChannelId, GuildId, Message, MessageId, ReactionType, RoleId},
+    prelude::TypeMapKey,
+};
+
+use utils::truncate;
+
+use crate::storage;
+
+const AUTO_REACTIONS_STORAGE: &str = "auto_reactions";
+const REACTION_ROLES_STORAGE: &str = "reaction_roles";
+
+pub const MAX_AUTO_REACTIONS: usize = 25;
+pub const MAX_REACTION_EMOJIS: usize = 10;
+pub const MAX_REACTION_ROLES: usize = 20;
+pub const MAX_REACTION_KEYWORD_LENGTH: usize = 100;
+pub const MAX_REACTION_PATTERN_LENGTH: usize = 200;
+// Bounds the memory a compiled pattern can use, patterns like `\w{1000}{1000}` are rejected.
+const REACTION_REGEX_SIZE_LIMIT: usize = 1 << 16;
+
+pub struct AutoReactions;
+pub type AutoReactionsMap = DashMap<GuildId, Vec<AutoReaction>>;
+impl TypeMapKey for AutoReactions {
+    type Value = Arc<AutoReactionsMap>;
+}
+
+pub struct ReactionRoles;
+pub type ReactionRolesMap = DashMap<MessageId, ReactionRoleMessage>;
+impl TypeMapKey for ReactionRoles {
+    type Value = Arc<ReactionRolesMap>;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum ReactionTrigger {
+    Keyword(String),
+    Regex(String),
+    Channel(ChannelId),
+}
+
+impl std::fmt::Display for ReactionTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReactionTrigger::Keyword(keyword) => write!(
+                f,
+                "keyword `{}`",
+                truncate(keyword, MAX_REACTION_KEYWORD_LENGTH)
+            ),
+            ReactionTrigger::Regex(pattern) => write!(
+                f,
+                "regex `{}`",
+                truncate(pattern, MAX_REACTION_PATTERN_LENGTH)
+            ),
+            ReactionTrigger::Channel(channel_id) => write!(f, "channel <#{}>", channel_id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoReaction {
+    pub trigger: ReactionTrigger,
+    pub emojis: Vec<ReactionType>,
+    #[serde(skip)]
+    regex: OnceLock<Option<Regex>>,
+}
+
+impl AutoReaction {
+    pub fn new(trigger: ReactionTrigger, emojis: Vec<ReactionType>) -> Self {
+        Self {
+            trigger,
+            emojis,
+            regex: OnceLock::new(),
+        }
+    }
+
+    pub fn matches(&self, message: &Message) -> bool {
+        match &self.trigger {
+            ReactionTrigger::Keyword(keyword) => message
+                .content
+                .to_lowercase()
+                .contains(&keyword.to_lowercase()),
+            ReactionTrigger::Regex(pattern) => self
+                .regex
+                .get_or_init(|| reaction_regex(pattern).ok())
+                .as_ref()
+                .is_some_and(|regex| regex.is_match(&message.content)),
+            ReactionTrigger::Channel(channel_id) => message.channel_id == *channel_id,
+        }
+    }
+}
+
+pub fn reaction_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern)
+        .size_limit(REACTION_REGEX_SIZE_LIMIT)
+        .build()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReactionRoleMode {
+    #[default]
+    Toggle,
+    Unique,
+    Verify,
+}
+
+impl ReactionRoleMode {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.to_lowercase().as_str() {
+            "toggle" => Some(ReactionRoleMode::Toggle),
+            "unique" => Some(ReactionRoleMode::Unique),
+            "verify" => Some(ReactionRoleMode::Verify),
+            _ => None,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ReactionRoleMode::Toggle => "Reacting gives the role, unreacting removes it",
+            ReactionRoleMode::Unique => "Members can only pick one of the roles",
+            ReactionRoleMode::Verify => "Reacting gives the role, unreacting keeps it",
+        }
+    }
+}
+
+impl std::fmt::Display for ReactionRoleMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ReactionRoleMode::Toggle => "toggle",
+            ReactionRoleMode::Unique => "unique",
+            ReactionRoleMode::Verify => "verify",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactionRole {
+    pub emoji: ReactionType,
+    pub role: RoleId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactionRoleMessage {
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    #[serde(default)]
+    pub mode: ReactionRoleMode,
+    pub roles: Vec<ReactionRole>,
+}
+
+impl ReactionRoleMessage {
+    pub fn new(guild_id: GuildId, channel_id: ChannelId, mode: ReactionRoleMode) -> Self {
+        Self {
+            guild_id,
+            channel_id,
+            mode,
+            roles: vec![],
+        }
+    }
+
+    pub fn role_for(&self, emoji: &ReactionType) -> Option<RoleId> {
+        self.roles
+            .iter()
+            .find(|r| same_emoji(&r.emoji, emoji))
+            .map(|r| r.role)
+    }
+}
+
+// Custom emojis are compared by ID since the name and animated flag can change.
+pub fn same_emoji(a: &ReactionType, b: &ReactionType) -> bool {
+    match (a, b) {
+        (ReactionType::Custom { id: a, .. }, ReactionType::Custom { id: b, .. }) => a == b,
+        (ReactionType::Unicode(a), ReactionType::Unicode(b)) => {
+            a.trim_end_matches('\u{fe0f}') == b.trim_end_matches('\u{fe0f}')
+        }
+        _ => false,
+    }
+}
+
+pub fn load_auto_reactions() -> AutoReactionsMap {
+    storage::load(AUTO_REACTIONS_STORAGE)
+}
+
+pub fn save_auto_reactions(repo: &AutoReactionsMap) {
+    storage::save(AUTO_REACTIONS_STORAGE, repo);
+}
+
+pub fn load_reaction_roles() -> ReactionRolesMap {
+    storage::load(REACTION_ROLES_STORAGE)
+}
+
+pub fn save_reaction_roles(repo: &ReactionRolesMap) {
+    storage::save(REACTION_ROLES_STORAGE, repo);
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::all::EmojiId;
+
+    use super::*;
+
+    fn custom(id: u64, name: &str, animated: bool) -> ReactionType {
+        ReactionType::Custom {
+            animated,
+            id: EmojiId::new(id),
+            name: Some(name.to_string()),
+        }
+    }
+
+    fn message(content: &str, channel_id: u64) -> Message {
+        let mut message = Message::default();
+        message.content = content.to_string();
+        message.channel_id = ChannelId::new(channel_id);
+        message
+    }
+
+    #[test]
+    fn compares_emojis() {
+        assert!(same_emoji(
+            &custom(1, "cat", false),
+            &custom(1, "dog", true)
+        ));
+        assert!(!same_emoji(
+            &custom(1, "cat", false),
+            &custom(2, "cat", false)
+        ));
+        let heart = ReactionType::Unicode("❤".to_string());
+        assert!(same_emoji(
+            &heart,
+            &ReactionType::Unicode("❤\u{fe0f}".to_string())
+        ));
+        assert!(!same_emoji(
+            &heart,
+            &ReactionType::Unicode("💙".to_string())
+        ));
+        assert!(!same_emoji(&heart, &custom(1, "❤", false)));
+    }
+
+    #[test]
+    fn keywords_match_ignoring_case() {
+        let reaction = AutoReaction::new(ReactionTrigger::Keyword("Hello".to_string()), vec![]);
+        assert!(reaction.matches(&message("well HELLO there", 1)));
+        assert!(!reaction.matches(&message("hi there", 1)));
+    }
+
+    #[test]
+    fn patterns_match_messages() {
+        let reaction = AutoReaction::new(ReactionTrigger::Regex(r"^\d{3}$".to_string()), vec![]);
+        assert!(reaction.matches(&message("123", 1)));
+        assert!(!reaction.matches(&message("1234", 1)));
+
+        // Patterns that don't compile, or compile too large, never match.
+        let invalid = AutoReaction::new(ReactionTrigger::Regex("(".to_string()), vec![]);
+        assert!(!invalid.matches(&message("(", 1)));
+        assert!(reaction_regex(r"\w{1000}{1000}").is_err());
+    }
+
+    #[test]
+    fn channels_match_their_messages() {
+        let reaction = AutoReaction::new(ReactionTrigger::Channel(ChannelId::new(5)), vec![]);
+        assert!(reaction.matches(&message("anything", 5)));
+        assert!(!reaction.matches(&message("anything", 6)));
+    }
+
+    #[test]
+    fn finds_roles_by_emoji() {
+        let mut config =
+            ReactionRoleMessage::new(GuildId::new(1), ChannelId::new(2), ReactionRoleMode::Toggle);
+        config.roles.push(ReactionRole {
+            emoji: custom(1, "cat", false),
+            role: RoleId::new(10),
+        });
+        assert_eq!(
+            config.role_for(&custom(1, "renamed", false)),
+            Some(RoleId::new(10))
+        );
+        assert_eq!(config.role_for(&custom(2, "cat", false)), None);
+        assert_eq!(
+            ReactionRoleMode::parse("UNIQUE"),
+            Some(ReactionRoleMode::Unique)
+        );
+        assert_eq!(ReactionRoleMode::parse("other"), None);
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{ChannelId, GuildId},
+    prelude::TypeMapKey,
+};
+
+use crate::{reddit::RedditProvider, storage};
+
+const REDDIT_SUBSCRIPTIONS_STORAGE: &str = "reddit_subscriptions";
+const REDDIT_SEEN_STORAGE: &str = "reddit_seen";
+
+pub const MAX_REDDIT_SUBSCRIPTIONS: usize = 10;
+// Reddit's own limits for subreddit names and flair texts.
+pub const MAX_SUBREDDIT_LENGTH: usize = 21;
+pub const MAX_FLAIR_LENGTH: usize = 64;
+// Enough to cover a few pages of `/new` without the file growing unbounded.
+pub const MAX_REDDIT_SEEN: usize = 300;
+
+pub struct RedditSubscriptions;
+pub type RedditSubscriptionsMap = DashMap<GuildId, Vec<RedditSubscription>>;
+impl TypeMapKey for RedditSubscriptions {
+    type Value = Arc<RedditSubscriptionsMap>;
+}
+
+// Post IDs already handled, keyed by `RedditSubscription::key`.
+pub struct RedditSeenPosts;
+pub type RedditSeenPostsMap = DashMap<String, Vec<String>>;
+impl TypeMapKey for RedditSeenPosts {
+    type Value = Arc<RedditSeenPostsMap>;
+}
+
+pub struct RedditFeed;
+impl TypeMapKey for RedditFeed {
+    type Value = Arc<dyn RedditProvider>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedditSubscription {
+    pub subreddit: String,
+    pub channel: ChannelId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flair: Option<String>,
+    #[serde(default)]
+    pub allow_nsfw: bool,
+    #[serde(default)]
+    pub min_score: i64,
+}
+
+impl RedditSubscription {
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.channel, self.subreddit.to_lowercase())
+    }
+
+    // Checks the NSFW and flair filters. The score is checked separately since it can still
+    // change after the post was first seen.
+    pub fn accepts(&self, post: &RedditPost, nsfw_channel: bool) -> bool {
+        if post.over_18 && !(self.allow_nsfw && nsfw_channel) {
+            return false;
+        }
+        match &self.flair {
+            Some(flair) => post
+                .link_flair_text
+                .as_ref()
+                .is_some_and(|f| f.eq_ignore_ascii_case(flair)),
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedditPost {
+    pub id: String,
+    pub title: String,
+    pub author: String,
+    pub subreddit: String,
+    pub permalink: String,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub selftext: String,
+    #[serde(default)]
+    pub score: i64,
+    #[serde(default)]
+    pub num_comments: u64,
+    #[serde(default)]
+    pub over_18: bool,
+    #[serde(default)]
+    pub link_flair_text: Option<String>,
+    #[serde(default)]
+    pub post_hint: Option<String>,
+    pub created_utc: f64,
+}
+
+pub fn load_reddit_subscriptions() -> RedditSubscriptionsMap {
+    storage::load(REDDIT_SUBSCRIPTIONS_STORAGE)
+}
+
+pub fn save_reddit_subscriptions(repo: &RedditSubscriptionsMap) {
+    storage::save(REDDIT_SUBSCRIPTIONS_STORAGE, repo);
+}
+
+pub fn load_reddit_seen() -> RedditSeenPostsMap {
+    storage::load(REDDIT_SEEN_STORAGE)
+}
+
+pub fn save_reddit_seen(repo: &RedditSeenPostsMap) {
+    storage::save(REDDIT_SEEN_STORAGE, repo);
+}
@@ -0,0 +1,114 @@
+use std::{collections::HashMap, sync::Arc};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serenity::{all::UserId, prelude::TypeMapKey};
+
+use crate::{roleplay::MediaProvider, storage};
+
+const ROLEPLAY_COUNTERS_STORAGE: &str = "roleplay_counters";
+
+pub struct RoleplayCounters;
+pub type RoleplayCountersMap = DashMap<UserId, HashMap<RoleplayAction, HashMap<UserId, u64>>>;
+impl TypeMapKey for RoleplayCounters {
+    type Value = Arc<RoleplayCountersMap>;
+}
+
+pub struct RoleplayMedia;
+impl TypeMapKey for RoleplayMedia {
+    type Value = Arc<Vec<Arc<dyn MediaProvider>>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoleplayAction {
+    Hug,
+    Pat,
+    Slap,
+    Kiss,
+    Cuddle,
+    Poke,
+    Bite,
+    Tickle,
+    Punch,
+    Highfive,
+    Handhold,
+    Feed,
+}
+
+impl RoleplayAction {
+    pub const ALL: [RoleplayAction; 12] = [
+        RoleplayAction::Hug,
+        RoleplayAction::Pat,
+        RoleplayAction::Slap,
+        RoleplayAction::Kiss,
+        RoleplayAction::Cuddle,
+        RoleplayAction::Poke,
+        RoleplayAction::Bite,
+        RoleplayAction::Tickle,
+        RoleplayAction::Punch,
+        RoleplayAction::Highfive,
+        RoleplayAction::Handhold,
+        RoleplayAction::Feed,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RoleplayAction::Hug => "hug",
+            RoleplayAction::Pat => "pat",
+            RoleplayAction::Slap => "slap",
+            RoleplayAction::Kiss => "kiss",
+            RoleplayAction::Cuddle => "cuddle",
+            RoleplayAction::Poke => "poke",
+            RoleplayAction::Bite => "bite",
+            RoleplayAction::Tickle => "tickle",
+            RoleplayAction::Punch => "punch",
+            RoleplayAction::Highfive => "highfive",
+            RoleplayAction::Handhold => "handhold",
+            RoleplayAction::Feed => "feed",
+        }
+    }
+
+    // Present tense used in the embed, e.g. "**A** hugs **B**".
+    pub fn verb(&self) -> &'static str {
+        match self {
+            RoleplayAction::Hug => "hugs",
+            RoleplayAction::Pat => "pats",
+            RoleplayAction::Slap => "slaps",
+            RoleplayAction::Kiss => "kisses",
+            RoleplayAction::Cuddle => "cuddles",
+            RoleplayAction::Poke => "pokes",
+            RoleplayAction::Bite => "bites",
+            RoleplayAction::Tickle => "tickles",
+            RoleplayAction::Punch => "punches",
+            RoleplayAction::Highfive => "high-fives",
+            RoleplayAction::Handhold => "holds hands with",
+            RoleplayAction::Feed => "feeds",
+        }
+    }
+
+    pub fn past(&self) -> &'static str {
+        match self {
+            RoleplayAction::Hug => "hugged",
+            RoleplayAction::Pat => "patted",
+            RoleplayAction::Slap => "slapped",
+            RoleplayAction::Kiss => "kissed",
+            RoleplayAction::Cuddle => "cuddled",
+            RoleplayAction::Poke => "poked",
+            RoleplayAction::Bite => "bitten",
+            RoleplayAction::Tickle => "tickled",
+            RoleplayAction::Punch => "punched",
+            RoleplayAction::Highfive => "high-fived",
+            RoleplayAction::Handhold => "held hands with",
+            RoleplayAction::Feed => "fed",
+        }
+    }
+}
+
+pub fn load_roleplay_counters() -> RoleplayCountersMap {
+    storage::load(ROLEPLAY_COUNTERS_STORAGE)
+}
+
+pub fn save_roleplay_counters(repo: &RoleplayCountersMap) {
+    storage::save(ROLEPLAY_COUNTERS_STORAGE, repo);
+}
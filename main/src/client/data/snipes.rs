@@ -1,27 +1,382 @@
-use std::sync::Arc;
+use std::{collections::VecDeque, path::PathBuf, sync::Arc};
 
+use chrono::{DateTime, Duration, Utc};
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
 use serenity::{
-    all::{ChannelId, GuildId, Message, Reaction},
+    all::{
+        ChannelId, GuildId, Message, MessageId, Reaction, ReactionType, Timestamp, User, UserId,
+    },
     prelude::TypeMapKey,
 };
 
+use crate::storage;
+
+const SNIPE_CONFIG_STORAGE: &str = "snipe_config";
+const SNIPE_BLACKLIST_STORAGE: &str = "snipe_blacklist";
+const SNIPE_OPT_OUT_STORAGE: &str = "snipe_opt_outs";
+
+pub const DEFAULT_SNIPE_CAPACITY: usize = 10;
+pub const MAX_SNIPE_CAPACITY: usize = 50;
+pub const DEFAULT_SNIPE_TTL: u64 = 60 * 60; // seconds
+pub const MAX_SNIPE_TTL: u64 = 24 * 60 * 60; // seconds
+pub const REACTION_HISTORY_TTL: i64 = 24 * 60 * 60; // seconds since the last reaction
+pub const MAX_REACTION_EVENTS: usize = 500; // per message
+pub const MAX_REACTION_HISTORIES: usize = 10_000;
+pub const DEFAULT_ATTACHMENT_CACHE_SIZE: u64 = 8 * 1024 * 1024; // bytes
+pub const MAX_ATTACHMENT_CACHE_SIZE: u64 = 10 * 1024 * 1024; // bytes, the upload limit for bots
+pub const MAX_ATTACHMENT_CACHE_TYPES: usize = 10;
+
 pub struct Snipes;
 impl TypeMapKey for Snipes {
-    type Value = Arc<DashMap<ChannelId, Vec<Message>>>;
+    type Value = Arc<DashMap<ChannelId, SnipeBuffer<Message>>>;
 }
 
 pub struct EditSnipes;
 impl TypeMapKey for EditSnipes {
-    type Value = Arc<DashMap<ChannelId, Vec<Message>>>;
+    type Value = Arc<DashMap<ChannelId, SnipeBuffer<EditSnipe>>>;
 }
 
 pub struct ReactionSnipes;
 impl TypeMapKey for ReactionSnipes {
-    type Value = Arc<DashMap<ChannelId, Vec<Reaction>>>;
+    type Value = Arc<DashMap<ChannelId, SnipeBuffer<Reaction>>>;
+}
+
+pub struct ReactionHistory;
+pub type ReactionHistoryMap = DashMap<MessageId, MessageReactionHistory>;
+impl TypeMapKey for ReactionHistory {
+    type Value = Arc<ReactionHistoryMap>;
+}
+
+#[derive(Debug, Clone)]
+pub struct MessageReactionHistory {
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    pub events: Vec<ReactionEvent>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReactionEvent {
+    pub user_id: UserId,
+    pub emoji: ReactionType,
+    pub added: bool,
+    pub at: DateTime<Utc>,
+}
+
+impl MessageReactionHistory {
+    pub fn last_activity(&self) -> Option<DateTime<Utc>> {
+        self.events.last().map(|event| event.at)
+    }
 }
 
+// Channels and categories that are never sniped.
 pub struct BlacklistedSnipes;
+pub type BlacklistedSnipesMap = DashMap<GuildId, Vec<ChannelId>>;
 impl TypeMapKey for BlacklistedSnipes {
-    type Value = Arc<DashMap<GuildId, Vec<ChannelId>>>;
+    type Value = Arc<BlacklistedSnipesMap>;
+}
+
+// Users whose messages and reactions are never stored, with the time they opted out.
+pub struct SnipeOptOuts;
+pub type SnipeOptOutsMap = DashMap<UserId, DateTime<Utc>>;
+impl TypeMapKey for SnipeOptOuts {
+    type Value = Arc<SnipeOptOutsMap>;
+}
+
+// Files of deleted messages downloaded before Discord removes them, newest message last.
+pub struct CachedAttachments;
+pub type CachedAttachmentsMap = DashMap<MessageId, Vec<CachedAttachment>>;
+impl TypeMapKey for CachedAttachments {
+    type Value = Arc<CachedAttachmentsMap>;
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedAttachment {
+    pub guild_id: GuildId,
+    pub author_id: UserId,
+    // Unique across messages, used as the `attachment://` name when re-uploading.
+    pub filename: String,
+    pub content_type: Option<String>,
+    pub path: PathBuf,
+    pub cached_at: DateTime<Utc>,
+}
+
+impl CachedAttachment {
+    pub fn is_image(&self) -> bool {
+        self.content_type
+            .as_ref()
+            .is_some_and(|t| t.starts_with("image/"))
+    }
+}
+
+pub struct ServerSnipeConfigs;
+pub type ServerSnipeConfigMap = DashMap<GuildId, SnipeConfig>;
+impl TypeMapKey for ServerSnipeConfigs {
+    type Value = Arc<ServerSnipeConfigMap>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnipeConfig {
+    // Entries kept per channel, the oldest are dropped first.
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+    // Seconds an entry stays snipeable.
+    #[serde(default = "default_ttl")]
+    pub ttl: u64,
+    #[serde(default)]
+    pub attachments: AttachmentCacheConfig,
+}
+
+// Which attachments of deleted messages are downloaded so they can be re-uploaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentCacheConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_attachment_size")]
+    pub max_size: u64,
+    // Content types like `image/png`, or a whole family like `image`.
+    #[serde(default = "default_attachment_types")]
+    pub types: Vec<String>,
+}
+
+impl AttachmentCacheConfig {
+    pub fn allows(&self, size: u32, content_type: Option<&str>) -> bool {
+        let Some(content_type) = content_type else {
+            return false;
+        };
+        let content_type = content_type.split(';').next().unwrap_or_default().trim();
+        self.enabled
+            && size as u64 <= self.max_size
+            && self.types.iter().any(|t| {
+                content_type.eq_ignore_ascii_case(t)
+                    || content_type
+                        .split_once('/')
+                        .is_some_and(|(family, _)| family.eq_ignore_ascii_case(t))
+            })
+    }
+}
+
+impl Default for AttachmentCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_size: DEFAULT_ATTACHMENT_CACHE_SIZE,
+            types: default_attachment_types(),
+        }
+    }
+}
+
+fn default_attachment_size() -> u64 {
+    DEFAULT_ATTACHMENT_CACHE_SIZE
+}
+
+fn default_attachment_types() -> Vec<String> {
+    vec!["image".to_string()]
+}
+
+fn default_capacity() -> usize {
+    DEFAULT_SNIPE_CAPACITY
+}
+
+fn default_ttl() -> u64 {
+    DEFAULT_SNIPE_TTL
+}
+
+impl Default for SnipeConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_SNIPE_CAPACITY,
+            ttl: DEFAULT_SNIPE_TTL,
+            attachments: AttachmentCacheConfig::default(),
+        }
+    }
+}
+
+// Every known revision of an edited message, oldest first.
+#[derive(Debug, Clone)]
+pub struct EditSnipe {
+    pub message_id: MessageId,
+    pub author: User,
+    pub revisions: Vec<Revision>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Revision {
+    pub content: String,
+    pub at: Timestamp,
+}
+
+impl EditSnipe {
+    // The revisions of the most recent edit.
+    pub fn last_edit(&self) -> Option<(&Revision, &Revision)> {
+        match self.revisions.as_slice() {
+            [.., before, after] => Some((before, after)),
+            _ => None,
+        }
+    }
+}
+
+// Fixed size history of a channel, oldest entries first.
+#[derive(Debug, Clone)]
+pub struct SnipeBuffer<T> {
+    pub guild_id: GuildId,
+    entries: VecDeque<(DateTime<Utc>, T)>,
+}
+
+impl<T> SnipeBuffer<T> {
+    pub fn new(guild_id: GuildId) -> Self {
+        Self {
+            guild_id,
+            entries: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, item: T, config: &SnipeConfig) {
+        self.entries.push_back((Utc::now(), item));
+        self.enforce(config, Utc::now());
+    }
+
+    // Drops entries past the TTL and over the capacity.
+    pub fn enforce(&mut self, config: &SnipeConfig, now: DateTime<Utc>) {
+        let oldest = now - Duration::seconds(config.ttl as i64);
+        while self.entries.front().is_some_and(|(at, _)| *at < oldest)
+            || self.entries.len() > config.capacity
+        {
+            self.entries.pop_front();
+        }
+    }
+
+    // Takes an entry out, so it can be pushed back as the newest one.
+    pub fn take(&mut self, predicate: impl Fn(&T) -> bool) -> Option<T> {
+        let index = self.entries.iter().position(|(_, item)| predicate(item))?;
+        self.entries.remove(index).map(|(_, item)| item)
+    }
+
+    pub fn retain(&mut self, predicate: impl Fn(&T) -> bool) {
+        self.entries.retain(|(_, item)| predicate(item));
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        self.entries.iter().map(|(_, item)| item)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+pub fn load_snipe_configs() -> ServerSnipeConfigMap {
+    storage::load(SNIPE_CONFIG_STORAGE)
+}
+
+pub fn save_snipe_configs(repo: &ServerSnipeConfigMap) {
+    storage::save(SNIPE_CONFIG_STORAGE, repo);
+}
+
+pub fn load_snipe_blacklist() -> BlacklistedSnipesMap {
+    storage::load(SNIPE_BLACKLIST_STORAGE)
+}
+
+pub fn save_snipe_blacklist(repo: &BlacklistedSnipesMap) {
+    storage::save(SNIPE_BLACKLIST_STORAGE, repo);
+}
+
+pub fn load_snipe_opt_outs() -> SnipeOptOutsMap {
+    storage::load(SNIPE_OPT_OUT_STORAGE)
+}
+
+pub fn save_snipe_opt_outs(repo: &SnipeOptOutsMap) {
+    storage::save(SNIPE_OPT_OUT_STORAGE, repo);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(capacity: usize, ttl: u64) -> SnipeConfig {
+        SnipeConfig {
+            capacity,
+            ttl,
+            ..Default::default()
+        }
+    }
+
+    fn items<T: Copy>(buffer: &SnipeBuffer<T>) -> Vec<T> {
+        buffer.iter().copied().collect()
+    }
+
+    #[test]
+    fn evicts_the_oldest_entries() {
+        let config = config(3, DEFAULT_SNIPE_TTL);
+        let mut buffer = SnipeBuffer::new(GuildId::new(1));
+        for item in 1..=5 {
+            buffer.push(item, &config);
+        }
+
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(items(&buffer), [3, 4, 5]);
+    }
+
+    #[test]
+    fn shrinks_to_a_lower_capacity() {
+        let mut buffer = SnipeBuffer::new(GuildId::new(1));
+        for item in 1..=5 {
+            buffer.push(item, &SnipeConfig::default());
+        }
+
+        buffer.enforce(&config(2, DEFAULT_SNIPE_TTL), Utc::now());
+        assert_eq!(items(&buffer), [4, 5]);
+    }
+
+    #[test]
+    fn drops_expired_entries() {
+        let config = config(DEFAULT_SNIPE_CAPACITY, 60);
+        let mut buffer = SnipeBuffer::new(GuildId::new(1));
+        buffer.push(1, &config);
+        buffer.push(2, &config);
+
+        buffer.enforce(&config, Utc::now() + Duration::seconds(30));
+        assert_eq!(buffer.len(), 2);
+        buffer.enforce(&config, Utc::now() + Duration::seconds(61));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn takes_entries_out() {
+        let config = SnipeConfig::default();
+        let mut buffer = SnipeBuffer::new(GuildId::new(1));
+        for item in 1..=3 {
+            buffer.push(item, &config);
+        }
+
+        assert_eq!(buffer.take(|i| *i == 2), Some(2));
+        assert_eq!(buffer.take(|i| *i == 2), None);
+        buffer.push(2, &config);
+        assert_eq!(items(&buffer), [1, 3, 2]);
+
+        buffer.retain(|i| *i != 1);
+        assert_eq!(items(&buffer), [3, 2]);
+    }
+
+    #[test]
+    fn attachments_match_their_types() {
+        let config = AttachmentCacheConfig {
+            enabled: true,
+            types: vec!["image".to_string(), "video/mp4".to_string()],
+            ..Default::default()
+        };
+
+        assert!(config.allows(100, Some("image/png")));
+        assert!(config.allows(100, Some("IMAGE/PNG; charset=binary")));
+        assert!(config.allows(100, Some("video/mp4")));
+        assert!(!config.allows(100, Some("video/webm")));
+        assert!(!config.allows(100, None));
+        assert!(!config.allows(DEFAULT_ATTACHMENT_CACHE_SIZE as u32 + 1, Some("image/png")));
+        let disabled = AttachmentCacheConfig::default();
+        assert!(!disabled.allows(100, Some("image/png")));
+    }
 }
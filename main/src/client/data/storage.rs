@@ -0,0 +1,68 @@
+use std::{fs, path::PathBuf};
+
+use serde::{Serialize, de::DeserializeOwned};
+use utils::error;
+
+const STORAGE_DIR: &str = "data";
+
+fn storage_path(name: &str) -> PathBuf {
+    let current_dir = std::env::current_dir().expect("Failed to get current directory");
+    current_dir.join(STORAGE_DIR).join(format!("{}.json", name))
+}
+
+// Directory for data that isn't kept as JSON, like cached files.
+pub fn directory(name: &str) -> PathBuf {
+    let current_dir = std::env::current_dir().expect("Failed to get current directory");
+    current_dir.join(STORAGE_DIR).join(name)
+}
+
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    let path = storage_path(name);
+    let Ok(content) = fs::read_to_string(&path) else {
+        return T::default();
+    };
+    match serde_json::from_str(&content) {
+        Ok(value) => value,
+        Err(e) => {
+            // The file is moved aside so the next save can't overwrite what's left of it.
+            let backup = path.with_extension("json.bak");
+            error!(
+                "Failed to parse stored {} data, moving it to {}: {}",
+                name,
+                backup.display(),
+                e
+            );
+            if let Err(e) = fs::rename(&path, &backup) {
+                error!("Failed to move {} data aside: {}", name, e);
+            }
+            T::default()
+        }
+    }
+}
+
+// Written to a temporary file first and renamed over the old one, so a crash while writing
+// can't leave a half written file behind.
+pub fn save<T: Serialize + ?Sized>(name: &str, value: &T) {
+    let path = storage_path(name);
+    if let Some(parent) = path.parent()
+        && let Err(e) = fs::create_dir_all(parent)
+    {
+        error!("Failed to create storage directory: {}", e);
+        return;
+    }
+    let content = match serde_json::to_string(value) {
+        Ok(content) => content,
+        Err(e) => {
+            error!("Failed to serialize {} data: {}", name, e);
+            return;
+        }
+    };
+    let temporary = path.with_extension("json.tmp");
+    if let Err(e) = fs::write(&temporary, content) {
+        error!("Failed to write {} data: {}", name, e);
+        return;
+    }
+    if let Err(e) = fs::rename(&temporary, &path) {
+        error!("Failed to replace {} data: {}", name, e);
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{ChannelId, GuildId, MessageId, RoleId},
+    prelude::TypeMapKey,
+};
+
+use crate::{
+    storage,
+    twitch::{StreamWatcher, TwitchApi},
+};
+
+const TWITCH_SUBSCRIPTIONS_STORAGE: &str = "twitch_subscriptions";
+const TWITCH_LIVE_STORAGE: &str = "twitch_live";
+
+pub const MAX_TWITCH_SUBSCRIPTIONS: usize = 10;
+pub const DEFAULT_TWITCH_TEMPLATE: &str =
+    "🔴 **{twitch.name}** is now live on Twitch! {twitch.url}";
+
+pub struct TwitchSubscriptions;
+pub type TwitchSubscriptionsMap = DashMap<GuildId, Vec<TwitchSubscription>>;
+impl TypeMapKey for TwitchSubscriptions {
+    type Value = Arc<TwitchSubscriptionsMap>;
+}
+
+// Streams that are currently announced, keyed by the Twitch user ID.
+pub struct TwitchLiveStreams;
+pub type TwitchLiveStreamsMap = DashMap<String, LiveStream>;
+impl TypeMapKey for TwitchLiveStreams {
+    type Value = Arc<TwitchLiveStreamsMap>;
+}
+
+// Only present when the Twitch credentials are configured.
+pub struct Twitch;
+impl TypeMapKey for Twitch {
+    type Value = Arc<TwitchIntegration>;
+}
+
+pub struct TwitchIntegration {
+    pub api: Arc<TwitchApi>,
+    pub watcher: Arc<dyn StreamWatcher>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwitchSubscription {
+    pub user_id: String,
+    pub login: String,
+    pub channel: ChannelId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<RoleId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveStream {
+    pub stream_id: String,
+    pub display_name: String,
+    pub login: String,
+    pub title: String,
+    pub game: String,
+    pub started_at: DateTime<Utc>,
+    pub messages: Vec<LiveMessage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveMessage {
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+}
+
+pub fn load_twitch_subscriptions() -> TwitchSubscriptionsMap {
+    storage::load(TWITCH_SUBSCRIPTIONS_STORAGE)
+}
+
+pub fn save_twitch_subscriptions(repo: &TwitchSubscriptionsMap) {
+    storage::save(TWITCH_SUBSCRIPTIONS_STORAGE, repo);
+}
+
+pub fn load_twitch_live() -> TwitchLiveStreamsMap {
+    storage::load(TWITCH_LIVE_STORAGE)
+}
+
+pub fn save_twitch_live(repo: &TwitchLiveStreamsMap) {
+    storage::save(TWITCH_LIVE_STORAGE, repo);
+}
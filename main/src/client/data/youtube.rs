@@ -0,0 +1,151 @@
+use std::{fmt::Display, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{ChannelId, GuildId, RoleId},
+    prelude::TypeMapKey,
+};
+
+use crate::{storage, youtube::YoutubeProvider};
+
+const YOUTUBE_SUBSCRIPTIONS_STORAGE: &str = "youtube_subscriptions";
+const YOUTUBE_SEEN_STORAGE: &str = "youtube_seen";
+
+pub const MAX_YOUTUBE_SUBSCRIPTIONS: usize = 10;
+// The feed only ever lists the latest 15 videos.
+pub const MAX_YOUTUBE_SEEN: usize = 50;
+pub const DEFAULT_YOUTUBE_TEMPLATE: &str = "**{youtube.channel}** {youtube.action}: {youtube.url}";
+
+pub struct YoutubeSubscriptions;
+pub type YoutubeSubscriptionsMap = DashMap<GuildId, Vec<YoutubeSubscription>>;
+impl TypeMapKey for YoutubeSubscriptions {
+    type Value = Arc<YoutubeSubscriptionsMap>;
+}
+
+// Video IDs already handled, keyed by `YoutubeSubscription::key`.
+pub struct YoutubeSeenVideos;
+pub type YoutubeSeenVideosMap = DashMap<String, Vec<String>>;
+impl TypeMapKey for YoutubeSeenVideos {
+    type Value = Arc<YoutubeSeenVideosMap>;
+}
+
+pub struct YoutubeFeed;
+impl TypeMapKey for YoutubeFeed {
+    type Value = Arc<dyn YoutubeProvider>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoKind {
+    Upload,
+    Short,
+    Premiere,
+}
+
+impl VideoKind {
+    pub const ALL: [VideoKind; 3] = [VideoKind::Upload, VideoKind::Short, VideoKind::Premiere];
+
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.to_lowercase().trim_end_matches('s') {
+            "upload" | "video" => Some(VideoKind::Upload),
+            "short" => Some(VideoKind::Short),
+            "premiere" => Some(VideoKind::Premiere),
+            _ => None,
+        }
+    }
+
+    // Used for `{youtube.action}`, e.g. "**Channel** uploaded a new video".
+    pub fn action(&self) -> &'static str {
+        match self {
+            VideoKind::Upload => "uploaded a new video",
+            VideoKind::Short => "posted a new short",
+            VideoKind::Premiere => "scheduled a premiere",
+        }
+    }
+}
+
+impl Display for VideoKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VideoKind::Upload => write!(f, "uploads"),
+            VideoKind::Short => write!(f, "shorts"),
+            VideoKind::Premiere => write!(f, "premieres"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YoutubeSubscription {
+    pub channel_id: String,
+    pub name: String,
+    pub channel: ChannelId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<RoleId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(default = "default_kinds")]
+    pub kinds: Vec<VideoKind>,
+    // Case-insensitive words the title must contain or must not contain.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+fn default_kinds() -> Vec<VideoKind> {
+    VideoKind::ALL.to_vec()
+}
+
+impl YoutubeSubscription {
+    pub fn new(channel_id: String, name: String, channel: ChannelId) -> Self {
+        Self {
+            channel_id,
+            name,
+            channel,
+            role: None,
+            template: None,
+            kinds: default_kinds(),
+            include: vec![],
+            exclude: vec![],
+        }
+    }
+
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.channel, self.channel_id)
+    }
+
+    pub fn accepts_title(&self, title: &str) -> bool {
+        let title = title.to_lowercase();
+        (self.include.is_empty() || self.include.iter().any(|w| title.contains(w.as_str())))
+            && !self.exclude.iter().any(|w| title.contains(w.as_str()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct YoutubeVideo {
+    pub id: String,
+    pub title: String,
+    pub channel_name: String,
+    pub url: String,
+    pub thumbnail: Option<String>,
+    pub description: String,
+    pub published: DateTime<Utc>,
+}
+
+pub fn load_youtube_subscriptions() -> YoutubeSubscriptionsMap {
+    storage::load(YOUTUBE_SUBSCRIPTIONS_STORAGE)
+}
+
+pub fn save_youtube_subscriptions(repo: &YoutubeSubscriptionsMap) {
+    storage::save(YOUTUBE_SUBSCRIPTIONS_STORAGE, repo);
+}
+
+pub fn load_youtube_seen() -> YoutubeSeenVideosMap {
+    storage::load(YOUTUBE_SEEN_STORAGE)
+}
+
+pub fn save_youtube_seen(repo: &YoutubeSeenVideosMap) {
+    storage::save(YOUTUBE_SEEN_STORAGE, repo);
+}
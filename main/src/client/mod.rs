@@ -11,25 +11,31 @@ mod command_registering;
 mod data;
 mod processes;
 use command_registering::run as register_commands;
+pub use command_registering::sync_guild as sync_guild_commands;
 pub use data::*;
 
 use crate::events::Handler;
 
 pub async fn create_client(env: Env, shard_count: usize) -> Client {
     info!("Creating client");
-    let (commands_vec, commands_map) = commands::load_commands();
+    let (commands_vec, commands_map, aliases_map, help_map) = commands::load_commands();
     let application_id = env.application_id();
     match ClientBuilder::new(env.token(), get_guild_intents())
         .raw_event_handler(Handler::new(shard_count))
         .cache_settings(get_settings())
         .application_id(application_id)
-        .type_map(initialize_type_map(env, commands_map))
+        .type_map(initialize_type_map(
+            env,
+            commands_map,
+            aliases_map,
+            help_map,
+        ))
         .register_songbird()
         .await
     {
         Ok(client) => {
             processes::initialize_processes(&client).await;
-            register_commands(client.http.clone(), commands_vec).await;
+            register_commands(client.http.clone(), client.data.clone(), commands_vec).await;
             build_dynamic_data(client.data.clone(), client.shard_manager.clone()).await;
             client
         }
@@ -51,6 +57,7 @@ fn get_guild_intents() -> GatewayIntents {
         .union(GatewayIntents::GUILD_INVITES)
         .union(GatewayIntents::GUILD_SCHEDULED_EVENTS)
         .union(GatewayIntents::MESSAGE_CONTENT)
+        .union(GatewayIntents::DIRECT_MESSAGES)
 }
 
 fn get_settings() -> Settings {
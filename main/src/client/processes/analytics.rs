@@ -0,0 +1,31 @@
+use utils::{Data, info};
+
+use crate::{
+    ServerAnalytics, VoiceSessions,
+    analytics::{flush_voice_sessions, prune},
+    save_analytics,
+};
+
+const SAVE_INTERVAL: u64 = 300; // seconds
+
+// Counters are updated in memory on every event and only written to disk here.
+pub async fn handle_analytics_loop(data: Data) {
+    info!("Started analytics loop.");
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(SAVE_INTERVAL)).await;
+        let (analytics, sessions) = {
+            let data = data.read().await;
+            (
+                data.get::<ServerAnalytics>()
+                    .cloned()
+                    .expect("Expected ServerAnalytics in TypeMap."),
+                data.get::<VoiceSessions>()
+                    .cloned()
+                    .expect("Expected VoiceSessions in TypeMap."),
+            )
+        };
+        flush_voice_sessions(&analytics, &sessions);
+        prune(&analytics);
+        save_analytics(&analytics);
+    }
+}
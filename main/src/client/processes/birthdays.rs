@@ -0,0 +1,185 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use serenity::all::{Cache, CreateMessage, Http};
+use utils::{BotStringParser, Data, error, info, position_suffix};
+
+use crate::{
+    BirthdayCelebration, BirthdayCelebrations, DEFAULT_BIRTHDAY_TEMPLATE, ElapsedTime,
+    ServerBirthdayConfigRepo, UserBirthdays, save_celebrations,
+};
+
+const CHECK_INTERVAL: u64 = 60; // seconds
+
+pub async fn handle_birthday_loop(data: Data, http: Arc<Http>, cache: Arc<Cache>) {
+    info!("Started birthday loop.");
+    loop {
+        announce_birthdays(&data, &http, &cache).await;
+        expire_birthday_roles(&data, &http).await;
+        tokio::time::sleep(std::time::Duration::from_secs(CHECK_INTERVAL)).await;
+    }
+}
+
+async fn announce_birthdays(data: &Data, http: &Http, cache: &Cache) {
+    let (birthdays, configs, celebrations) = {
+        let data = data.read().await;
+        (
+            data.get::<UserBirthdays>()
+                .cloned()
+                .expect("Expected UserBirthdays in TypeMap."),
+            data.get::<ServerBirthdayConfigRepo>()
+                .cloned()
+                .expect("Expected ServerBirthdayConfigRepo in TypeMap."),
+            data.get::<BirthdayCelebrations>()
+                .cloned()
+                .expect("Expected BirthdayCelebrations in TypeMap."),
+        )
+    };
+
+    let now = Utc::now();
+    let todays_birthdays = birthdays
+        .iter()
+        .filter(|entry| entry.value().is_today(now))
+        .map(|entry| (*entry.key(), entry.value().clone()))
+        .collect::<Vec<_>>();
+    if todays_birthdays.is_empty() {
+        return;
+    }
+
+    let configs = configs
+        .iter()
+        .filter_map(|entry| {
+            let channel_id = entry.value().channel?;
+            Some((*entry.key(), channel_id, entry.value().clone()))
+        })
+        .collect::<Vec<_>>();
+
+    let mut new_celebrations = vec![];
+    for (guild_id, channel_id, config) in configs {
+        let Some(guild) = cache.guild(guild_id).map(|g| g.clone()) else {
+            continue;
+        };
+        let Some(channel) = guild.channels.get(&channel_id) else {
+            continue;
+        };
+
+        for (user_id, birthday) in &todays_birthdays {
+            let Some(member) = guild.members.get(user_id) else {
+                continue;
+            };
+            let date = birthday.local_today(now);
+            let already_celebrated = celebrations
+                .read()
+                .await
+                .iter()
+                .any(|c| c.guild_id == guild_id && c.user_id == *user_id && c.date == date);
+            if already_celebrated {
+                continue;
+            }
+
+            let timer = ElapsedTime::new();
+            let template = config
+                .template
+                .clone()
+                .unwrap_or(DEFAULT_BIRTHDAY_TEMPLATE.to_string());
+            let template = template
+                .replace(
+                    "{birthday.age}",
+                    &birthday
+                        .age_on(date)
+                        .map(|age| age.to_string())
+                        .unwrap_or("`n/a`".to_string()),
+                )
+                .replace(
+                    "{birthday.age_suffix}",
+                    &birthday
+                        .age_on(date)
+                        .map(|age| position_suffix(age as usize))
+                        .unwrap_or("`n/a`".to_string()),
+                );
+            let content =
+                BotStringParser::from_cache(cache, &guild, channel, member).render(&template);
+
+            if let Err(e) = channel_id
+                .send_message(http, CreateMessage::new().content(content))
+                .await
+            {
+                error!(
+                    "Failed to announce birthday of {} in guild {}: {}",
+                    user_id, guild_id, e
+                );
+                continue;
+            }
+
+            let mut role = None;
+            if let Some(role_id) = config.role {
+                match http
+                    .add_member_role(guild_id, *user_id, role_id, Some("Birthday"))
+                    .await
+                {
+                    Ok(_) => role = Some(role_id),
+                    Err(e) => error!(
+                        "Failed to add birthday role to {} in guild {}: {}",
+                        user_id, guild_id, e
+                    ),
+                }
+            }
+
+            new_celebrations.push(BirthdayCelebration::new(guild_id, *user_id, date, role));
+            info!(
+                "Announced birthday of {} in guild {} ({}ms)",
+                user_id,
+                guild_id,
+                timer.elapsed_ms()
+            );
+        }
+    }
+
+    if !new_celebrations.is_empty() {
+        let mut celebrations = celebrations.write().await;
+        celebrations.extend(new_celebrations);
+        save_celebrations(&celebrations);
+    }
+}
+
+async fn expire_birthday_roles(data: &Data, http: &Http) {
+    let celebrations = {
+        let data = data.read().await;
+        data.get::<BirthdayCelebrations>()
+            .cloned()
+            .expect("Expected BirthdayCelebrations in TypeMap.")
+    };
+
+    let mut celebrations = celebrations.write().await;
+    let previous_len = celebrations.len();
+    let mut changed = false;
+    for celebration in celebrations.iter_mut() {
+        if !celebration.is_expired() {
+            continue;
+        }
+        let Some(role_id) = celebration.role else {
+            continue;
+        };
+        if let Err(e) = http
+            .remove_member_role(
+                celebration.guild_id,
+                celebration.user_id,
+                role_id,
+                Some("Birthday ended"),
+            )
+            .await
+        {
+            error!(
+                "Failed to remove birthday role from {} in guild {}: {}",
+                celebration.user_id, celebration.guild_id, e
+            );
+        }
+        celebration.role = None;
+        changed = true;
+    }
+    celebrations.retain(|c| !c.is_stale());
+
+    if changed || celebrations.len() != previous_len {
+        save_celebrations(&celebrations);
+    }
+}
@@ -0,0 +1,30 @@
+use chrono::Utc;
+use utils::{Data, info};
+
+use crate::{CommandRateLimits, Cooldowns, rate_limit_window};
+
+const SWEEP_INTERVAL: u64 = 60; // seconds
+
+pub async fn handle_cooldown_sweep_loop(data: Data) {
+    info!("Started cooldown sweep loop.");
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(SWEEP_INTERVAL)).await;
+        let (cooldowns, rate_limits) = {
+            let data = data.read().await;
+            (
+                data.get::<Cooldowns>()
+                    .cloned()
+                    .expect("Expected Cooldowns in TypeMap."),
+                data.get::<CommandRateLimits>()
+                    .cloned()
+                    .expect("Expected CommandRateLimits in TypeMap."),
+            )
+        };
+        let now = Utc::now();
+        cooldowns.retain(|_, ends| *ends > now);
+        rate_limits.retain(|_, limit| {
+            limit.uses.retain(|at| *at + rate_limit_window() > now);
+            !limit.uses.is_empty()
+        });
+    }
+}
@@ -1,12 +1,42 @@
 use serenity::Client;
 use utils::Data;
 
+mod analytics;
+mod birthdays;
+mod cooldowns;
 mod pagination;
+mod reddit;
+mod snipes;
+mod twitch;
 mod websocket;
+mod youtube;
 
 pub async fn initialize_processes(client: &Client) {
     tokio::spawn(pagination::handle_pagination_timeout_loop(
         client.data.clone(),
         client.http.clone(),
     ));
+    tokio::spawn(analytics::handle_analytics_loop(client.data.clone()));
+    tokio::spawn(snipes::handle_snipe_sweep_loop(client.data.clone()));
+    tokio::spawn(cooldowns::handle_cooldown_sweep_loop(client.data.clone()));
+    tokio::spawn(birthdays::handle_birthday_loop(
+        client.data.clone(),
+        client.http.clone(),
+        client.cache.clone(),
+    ));
+    tokio::spawn(reddit::handle_reddit_loop(
+        client.data.clone(),
+        client.http.clone(),
+        client.cache.clone(),
+    ));
+    tokio::spawn(twitch::handle_twitch_loop(
+        client.data.clone(),
+        client.http.clone(),
+        client.cache.clone(),
+    ));
+    tokio::spawn(youtube::handle_youtube_loop(
+        client.data.clone(),
+        client.http.clone(),
+        client.cache.clone(),
+    ));
 }
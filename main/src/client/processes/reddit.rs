@@ -0,0 +1,229 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::Utc;
+use serenity::all::{Cache, CreateMessage, GuildId, Http};
+use utils::{Data, error, info};
+
+use crate::{
+    MAX_REDDIT_SEEN, RedditFeed, RedditPost, RedditSeenPosts, RedditSubscription,
+    RedditSubscriptions, reddit::post_embed, save_reddit_seen,
+};
+
+const POLL_INTERVAL: u64 = 120; // seconds
+const MAX_POST_AGE: i64 = 60 * 60 * 24; // seconds
+
+pub async fn handle_reddit_loop(data: Data, http: Arc<Http>, cache: Arc<Cache>) {
+    info!("Started reddit loop.");
+    loop {
+        poll_subscriptions(&data, &http, &cache).await;
+        tokio::time::sleep(std::time::Duration::from_secs(POLL_INTERVAL)).await;
+    }
+}
+
+async fn poll_subscriptions(data: &Data, http: &Http, cache: &Cache) {
+    let (subscriptions, seen, provider) = {
+        let data = data.read().await;
+        (
+            data.get::<RedditSubscriptions>()
+                .cloned()
+                .expect("Expected RedditSubscriptions in TypeMap."),
+            data.get::<RedditSeenPosts>()
+                .cloned()
+                .expect("Expected RedditSeenPosts in TypeMap."),
+            data.get::<RedditFeed>()
+                .cloned()
+                .expect("Expected RedditFeed in TypeMap."),
+        )
+    };
+
+    // Every subreddit is only fetched once, however many channels follow it.
+    let mut by_subreddit: HashMap<String, Vec<(GuildId, RedditSubscription)>> = HashMap::new();
+    for entry in subscriptions.iter() {
+        for subscription in entry.value() {
+            by_subreddit
+                .entry(subscription.subreddit.to_lowercase())
+                .or_default()
+                .push((*entry.key(), subscription.clone()));
+        }
+    }
+    if by_subreddit.is_empty() {
+        return;
+    }
+
+    let now = Utc::now().timestamp();
+    let mut changed = false;
+    for (subreddit, subscriptions) in by_subreddit {
+        let mut posts = match provider.fetch_new(&subreddit).await {
+            Ok(posts) => posts,
+            Err(e) => {
+                error!("Failed to fetch r/{}: {}", subreddit, e);
+                continue;
+            }
+        };
+        posts.reverse();
+        posts.retain(|p| now - (p.created_utc as i64) < MAX_POST_AGE);
+
+        for (guild_id, subscription) in subscriptions {
+            let key = subscription.key();
+            // The first poll of a subscription only records the current posts so following a
+            // subreddit doesn't flood the channel with its backlog.
+            let Some(mut seen_ids) = seen.get(&key).map(|s| s.clone()) else {
+                seen.insert(key, posts.iter().map(|p| p.id.clone()).collect());
+                changed = true;
+                continue;
+            };
+
+            let nsfw_channel = cache
+                .guild(guild_id)
+                .and_then(|g| g.channels.get(&subscription.channel).map(|c| c.nsfw))
+                .unwrap_or(false);
+
+            for post in unseen_posts(&posts, &mut seen_ids, &subscription, nsfw_channel) {
+                let message = CreateMessage::new().embed(post_embed(post));
+                if let Err(e) = subscription.channel.send_message(http, message).await {
+                    error!(
+                        "Failed to post r/{} item {} in {}: {}",
+                        subreddit, post.id, subscription.channel, e
+                    );
+                }
+            }
+
+            if seen_ids.len() > MAX_REDDIT_SEEN {
+                seen_ids.drain(..seen_ids.len() - MAX_REDDIT_SEEN);
+            }
+            seen.insert(key, seen_ids);
+            changed = true;
+        }
+    }
+
+    if changed {
+        save_reddit_seen(&seen);
+    }
+}
+
+// The posts to send for a subscription, oldest first, marking them as seen. Filtered out posts
+// are marked too, but posts below the minimum score aren't since their score can still grow.
+fn unseen_posts<'a>(
+    posts: &'a [RedditPost],
+    seen_ids: &mut Vec<String>,
+    subscription: &RedditSubscription,
+    nsfw_channel: bool,
+) -> Vec<&'a RedditPost> {
+    let mut unseen = vec![];
+    for post in posts {
+        if seen_ids.contains(&post.id) {
+            continue;
+        }
+        if !subscription.accepts(post, nsfw_channel) {
+            seen_ids.push(post.id.clone());
+            continue;
+        }
+        if post.score < subscription.min_score {
+            continue;
+        }
+        seen_ids.push(post.id.clone());
+        unseen.push(post);
+    }
+    unseen
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use serenity::all::ChannelId;
+
+    use super::*;
+    use crate::reddit::fixtures;
+
+    fn subscription() -> RedditSubscription {
+        RedditSubscription {
+            subreddit: "rust".to_string(),
+            channel: ChannelId::new(1),
+            flair: None,
+            allow_nsfw: false,
+            min_score: 0,
+        }
+    }
+
+    fn ids(posts: &[&RedditPost]) -> Vec<String> {
+        posts.iter().map(|p| p.id.clone()).collect()
+    }
+
+    #[test]
+    fn posts_are_only_sent_once() {
+        let posts = vec![
+            fixtures::parse(fixtures::post("a", 100)),
+            fixtures::parse(fixtures::post("b", 200)),
+        ];
+        let mut seen = vec![];
+
+        let first = unseen_posts(&posts, &mut seen, &subscription(), false);
+        assert_eq!(ids(&first), ["a", "b"]);
+        assert!(unseen_posts(&posts, &mut seen, &subscription(), false).is_empty());
+    }
+
+    #[test]
+    fn filtered_posts_are_marked_as_seen() {
+        let mut nsfw = fixtures::post("nsfw", 100);
+        nsfw["over_18"] = json!(true);
+        let mut flaired = fixtures::post("flaired", 200);
+        flaired["link_flair_text"] = json!("Meme");
+        let posts = vec![fixtures::parse(nsfw), fixtures::parse(flaired)];
+        let subscription = RedditSubscription {
+            flair: Some("news".to_string()),
+            ..subscription()
+        };
+        let mut seen = vec![];
+
+        assert!(unseen_posts(&posts, &mut seen, &subscription, true).is_empty());
+        assert_eq!(seen, ["nsfw", "flaired"]);
+    }
+
+    #[test]
+    fn nsfw_posts_need_an_nsfw_channel() {
+        let mut post = fixtures::post("nsfw", 100);
+        post["over_18"] = json!(true);
+        let posts = vec![fixtures::parse(post)];
+        let subscription = RedditSubscription {
+            allow_nsfw: true,
+            ..subscription()
+        };
+
+        assert!(unseen_posts(&posts, &mut vec![], &subscription, false).is_empty());
+        let sent = unseen_posts(&posts, &mut vec![], &subscription, true);
+        assert_eq!(ids(&sent), ["nsfw"]);
+    }
+
+    #[test]
+    fn low_scores_are_checked_again_later() {
+        let subscription = RedditSubscription {
+            min_score: 10,
+            ..subscription()
+        };
+        let mut seen = vec![];
+
+        let posts = vec![fixtures::parse(fixtures::post("a", 100))];
+        assert!(unseen_posts(&posts, &mut seen, &subscription, false).is_empty());
+        assert!(seen.is_empty());
+
+        let mut upvoted = fixtures::post("a", 100);
+        upvoted["score"] = json!(25);
+        let posts = vec![fixtures::parse(upvoted)];
+        let sent = unseen_posts(&posts, &mut seen, &subscription, false);
+        assert_eq!(ids(&sent), ["a"]);
+    }
+
+    #[test]
+    fn flairs_match_ignoring_case() {
+        let mut post = fixtures::post("a", 100);
+        post["link_flair_text"] = json!("News");
+        let posts = vec![fixtures::parse(post)];
+        let subscription = RedditSubscription {
+            flair: Some("news".to_string()),
+            ..subscription()
+        };
+
+        let sent = unseen_posts(&posts, &mut vec![], &subscription, false);
+        assert_eq!(ids(&sent), ["a"]);
+    }
+}
@@ -0,0 +1,14 @@
+use utils::{Data, info};
+
+use crate::{message_log, snipes};
+
+const SWEEP_INTERVAL: u64 = 30; // seconds
+
+pub async fn handle_snipe_sweep_loop(data: Data) {
+    info!("Started snipe sweep loop.");
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(SWEEP_INTERVAL)).await;
+        snipes::sweep(&data).await;
+        message_log::prune(&data).await;
+    }
+}
@@ -0,0 +1,72 @@
+use std::{collections::HashMap, sync::Arc};
+
+use serenity::all::{Cache, Http};
+use tokio::sync::mpsc;
+use utils::{Data, error, info};
+
+use crate::{
+    Twitch, TwitchLiveStreams, TwitchSubscriptions, save_twitch_live,
+    twitch::{end, handle_event},
+};
+
+pub async fn handle_twitch_loop(data: Data, http: Arc<Http>, cache: Arc<Cache>) {
+    let (integration, subscriptions, live) = {
+        let data = data.read().await;
+        let Some(integration) = data.get::<Twitch>().cloned() else {
+            return;
+        };
+        (
+            integration,
+            data.get::<TwitchSubscriptions>()
+                .cloned()
+                .expect("Expected TwitchSubscriptions in TypeMap."),
+            data.get::<TwitchLiveStreams>()
+                .cloned()
+                .expect("Expected TwitchLiveStreams in TypeMap."),
+        )
+    };
+    info!("Started twitch loop ({} mode).", integration.watcher.mode());
+
+    let mut watched = HashMap::new();
+    for entry in subscriptions.iter() {
+        for subscription in entry.value() {
+            watched.insert(subscription.user_id.clone(), subscription.login.clone());
+        }
+    }
+    for (user_id, login) in &watched {
+        if let Err(e) = integration.watcher.watch(user_id, login).await {
+            error!("Failed to watch {} on Twitch: {}", login, e);
+        }
+    }
+
+    // Streams that ended while the bot was offline never get an offline event, so they are
+    // closed here. Streams that are still live are picked up again by the watcher.
+    let recorded = live.iter().map(|l| l.key().clone()).collect::<Vec<_>>();
+    if !recorded.is_empty() {
+        match integration.api.streams(&recorded).await {
+            Ok(streams) => {
+                for user_id in recorded {
+                    let still_live = live
+                        .get(&user_id)
+                        .is_some_and(|l| streams.iter().any(|s| s.id == l.stream_id));
+                    if still_live {
+                        continue;
+                    }
+                    if let Some((_, stream)) = live.remove(&user_id) {
+                        end(&stream, &http).await;
+                    }
+                }
+                save_twitch_live(&live);
+            }
+            Err(e) => error!("Failed to reconcile Twitch streams: {}", e),
+        }
+    }
+
+    let (tx, mut rx) = mpsc::channel(64);
+    let watcher = integration.watcher.clone();
+    tokio::spawn(async move { watcher.run(tx).await });
+
+    while let Some(event) = rx.recv().await {
+        handle_event(event, &integration, &subscriptions, &live, &http, &cache).await;
+    }
+}
@@ -0,0 +1,275 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use serenity::all::{Cache, CreateAllowedMentions, CreateMessage, GuildId, Http};
+use utils::{BotStringParser, Data, error, info};
+
+use crate::{
+    DEFAULT_YOUTUBE_TEMPLATE, MAX_YOUTUBE_SEEN, VideoKind, YoutubeFeed, YoutubeSeenVideos,
+    YoutubeSubscription, YoutubeSubscriptions, YoutubeVideo, save_youtube_seen,
+    youtube::video_embed,
+};
+
+const POLL_INTERVAL: u64 = 300; // seconds
+const MAX_VIDEO_AGE: i64 = 60 * 60 * 24 * 2; // seconds
+
+pub async fn handle_youtube_loop(data: Data, http: Arc<Http>, cache: Arc<Cache>) {
+    info!("Started youtube loop.");
+    loop {
+        poll_subscriptions(&data, &http, &cache).await;
+        tokio::time::sleep(std::time::Duration::from_secs(POLL_INTERVAL)).await;
+    }
+}
+
+async fn poll_subscriptions(data: &Data, http: &Http, cache: &Cache) {
+    let (subscriptions, seen, provider) = {
+        let data = data.read().await;
+        (
+            data.get::<YoutubeSubscriptions>()
+                .cloned()
+                .expect("Expected YoutubeSubscriptions in TypeMap."),
+            data.get::<YoutubeSeenVideos>()
+                .cloned()
+                .expect("Expected YoutubeSeenVideos in TypeMap."),
+            data.get::<YoutubeFeed>()
+                .cloned()
+                .expect("Expected YoutubeFeed in TypeMap."),
+        )
+    };
+
+    // Every feed is only fetched once, however many channels follow it.
+    let mut by_channel: HashMap<String, Vec<(GuildId, YoutubeSubscription)>> = HashMap::new();
+    for entry in subscriptions.iter() {
+        for subscription in entry.value() {
+            by_channel
+                .entry(subscription.channel_id.clone())
+                .or_default()
+                .push((*entry.key(), subscription.clone()));
+        }
+    }
+    if by_channel.is_empty() {
+        return;
+    }
+
+    let now = Utc::now();
+    let mut changed = false;
+    for (channel_id, subscriptions) in by_channel {
+        let mut videos = match provider.fetch_feed(&channel_id).await {
+            Ok((_, videos)) => videos,
+            Err(e) => {
+                error!("Failed to fetch the YouTube feed of {}: {}", channel_id, e);
+                continue;
+            }
+        };
+        videos.reverse();
+
+        // Classifying needs extra requests, so it's done at most once per video.
+        let mut kinds: HashMap<String, VideoKind> = HashMap::new();
+        for (guild_id, subscription) in subscriptions {
+            let key = subscription.key();
+            // The first poll of a subscription only records the current videos so following a
+            // channel doesn't flood the channel with its backlog.
+            let Some(mut seen_ids) = seen.get(&key).map(|s| s.clone()) else {
+                seen.insert(key, videos.iter().map(|v| v.id.clone()).collect());
+                changed = true;
+                continue;
+            };
+
+            for video in unseen_videos(&videos, &mut seen_ids, &subscription, now) {
+                let kind = match kinds.get(&video.id) {
+                    Some(kind) => *kind,
+                    None => {
+                        let kind = provider.classify(video).await;
+                        kinds.insert(video.id.clone(), kind);
+                        kind
+                    }
+                };
+                if !subscription.kinds.contains(&kind) {
+                    continue;
+                }
+                announce(http, cache, guild_id, &subscription, video, kind).await;
+            }
+
+            if seen_ids.len() > MAX_YOUTUBE_SEEN {
+                seen_ids.drain(..seen_ids.len() - MAX_YOUTUBE_SEEN);
+            }
+            seen.insert(key, seen_ids);
+            changed = true;
+        }
+    }
+
+    if changed {
+        save_youtube_seen(&seen);
+    }
+}
+
+// The videos to send for a subscription, oldest first, marking them as seen. Old videos and
+// titles the filters reject are only marked as seen.
+fn unseen_videos<'a>(
+    videos: &'a [YoutubeVideo],
+    seen_ids: &mut Vec<String>,
+    subscription: &YoutubeSubscription,
+    now: DateTime<Utc>,
+) -> Vec<&'a YoutubeVideo> {
+    let mut unseen = vec![];
+    for video in videos {
+        if seen_ids.contains(&video.id) {
+            continue;
+        }
+        seen_ids.push(video.id.clone());
+        if (now - video.published).num_seconds() <= MAX_VIDEO_AGE
+            && subscription.accepts_title(&video.title)
+        {
+            unseen.push(video);
+        }
+    }
+    unseen
+}
+
+async fn announce(
+    http: &Http,
+    cache: &Cache,
+    guild_id: GuildId,
+    subscription: &YoutubeSubscription,
+    video: &YoutubeVideo,
+    kind: VideoKind,
+) {
+    let template = subscription
+        .template
+        .clone()
+        .unwrap_or(DEFAULT_YOUTUBE_TEMPLATE.to_string())
+        .replace("{youtube.channel}", &video.channel_name)
+        .replace("{youtube.title}", &video.title)
+        .replace("{youtube.url}", &video.url)
+        .replace("{youtube.action}", kind.action());
+    let content = {
+        let Some(guild) = cache.guild(guild_id).map(|g| g.clone()) else {
+            return;
+        };
+        let (Some(channel), Some(member)) = (
+            guild.channels.get(&subscription.channel),
+            guild.members.get(&cache.current_user().id),
+        ) else {
+            return;
+        };
+        BotStringParser::from_cache(cache, &guild, channel, member).render(&template)
+    };
+
+    let mut mentions = CreateAllowedMentions::new();
+    let content = match subscription.role {
+        Some(role) => {
+            mentions = mentions.roles(vec![role]);
+            format!("<@&{}> {}", role, content)
+        }
+        None => content,
+    };
+    let message = CreateMessage::new()
+        .content(content)
+        .embed(video_embed(video, kind))
+        .allowed_mentions(mentions);
+    if let Err(e) = subscription.channel.send_message(http, message).await {
+        error!(
+            "Failed to announce video {} in {}: {}",
+            video.id, subscription.channel, e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::all::ChannelId;
+
+    use super::*;
+    use crate::{YoutubeSeenVideosMap, youtube::fixtures};
+
+    fn subscription() -> YoutubeSubscription {
+        YoutubeSubscription::new(
+            fixtures::CHANNEL_ID.to_string(),
+            "Channel".to_string(),
+            ChannelId::new(1),
+        )
+    }
+
+    // Feeds list the newest video first, the loop reverses them.
+    fn videos(entries: &[(&str, &str)]) -> Vec<YoutubeVideo> {
+        let entries = entries
+            .iter()
+            .map(|(id, title)| fixtures::entry(id, title, "2024-01-02T00:00:00+00:00"))
+            .collect::<Vec<_>>();
+        let mut videos = fixtures::parse(&entries);
+        videos.reverse();
+        videos
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-01-02T12:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn ids(videos: &[&YoutubeVideo]) -> Vec<String> {
+        videos.iter().map(|v| v.id.clone()).collect()
+    }
+
+    #[test]
+    fn videos_are_only_sent_once() {
+        let videos = videos(&[("b", "Second"), ("a", "First")]);
+        let mut seen = vec![];
+
+        let first = unseen_videos(&videos, &mut seen, &subscription(), now());
+        assert_eq!(ids(&first), ["a", "b"]);
+        assert!(unseen_videos(&videos, &mut seen, &subscription(), now()).is_empty());
+    }
+
+    #[test]
+    fn seen_videos_survive_restarts() {
+        let subscription = subscription();
+        let mut seen_ids = vec![];
+        unseen_videos(
+            &videos(&[("a", "First")]),
+            &mut seen_ids,
+            &subscription,
+            now(),
+        );
+        let seen = YoutubeSeenVideosMap::new();
+        seen.insert(subscription.key(), seen_ids);
+
+        // The seen videos are saved as JSON and loaded again on startup.
+        let saved = serde_json::to_string(&seen).unwrap();
+        let loaded = serde_json::from_str::<YoutubeSeenVideosMap>(&saved).unwrap();
+        let mut seen_ids = loaded.get(&subscription.key()).unwrap().clone();
+
+        let more = videos(&[("b", "Second"), ("a", "First")]);
+        let sent = unseen_videos(&more, &mut seen_ids, &subscription, now());
+        assert_eq!(ids(&sent), ["b"]);
+    }
+
+    #[test]
+    fn old_videos_are_marked_as_seen() {
+        let videos = videos(&[("a", "First")]);
+        let later = now() + chrono::Duration::seconds(MAX_VIDEO_AGE);
+        let mut seen = vec![];
+
+        assert!(unseen_videos(&videos, &mut seen, &subscription(), later).is_empty());
+        assert_eq!(seen, ["a"]);
+    }
+
+    #[test]
+    fn titles_are_filtered_ignoring_case() {
+        let videos = videos(&[
+            ("live", "LIVE: Speedrun"),
+            ("vod", "Speedrun VOD"),
+            ("vlog", "Weekly vlog"),
+        ]);
+        let subscription = YoutubeSubscription {
+            include: vec!["speedrun".to_string()],
+            exclude: vec!["vod".to_string()],
+            ..subscription()
+        };
+        let mut seen = vec![];
+
+        let sent = unseen_videos(&videos, &mut seen, &subscription, now());
+        assert_eq!(ids(&sent), ["live"]);
+        assert_eq!(seen.len(), 3);
+    }
+}
@@ -0,0 +1,402 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use chrono_tz::{TZ_VARIANTS, Tz};
+use serenity::{
+    all::{
+        AutocompleteOption, Colour, CommandInteraction, CommandType, Context, CreateEmbed,
+        CreateEmbedFooter, Guild, GuildChannel, UserId,
+    },
+    async_trait,
+    json::Value,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, AutocompleteResponse, CommandArguments, CommandResponse,
+    CommandTemplate, CommandTrait, ICommand, ParsedArguments, UserType, truncate,
+};
+
+use crate::{
+    Birthday, Paginations, UserBirthdays, UserBirthdaysRepo, commands::require_guild,
+    save_birthdays,
+};
+
+const COMMAND_NAME: &str = "birthday";
+const COMMAND_DESCRIPTION: &str = "Register your birthday and view upcoming birthdays.";
+const UPCOMING_PER_PAGE: usize = 10;
+// `YYYY-MM-DD`, and longer than any timezone name.
+const MAX_DATE_LENGTH: u16 = 10;
+const MAX_TIMEZONE_LENGTH: u16 = 64;
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![],
+    )
+    .subcommand(Set::node())
+    .subcommand(Remove::node())
+    .subcommand(View::node())
+    .subcommand(Upcoming::node())
+    // `birthday @user` and a bare `birthday` view a birthday, as they did before subcommands.
+    .fallback("view")
+    .default("view")
+    .routed()
+}
+
+struct SetArguments {
+    date: String,
+    timezone: Option<String>,
+}
+
+impl Arguments for SetArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(
+                Argument::string("date", "Your birthday as MM-DD or YYYY-MM-DD")
+                    .max_length(MAX_DATE_LENGTH)
+                    .required(),
+            )
+            .argument(
+                Argument::string(
+                    "timezone",
+                    "Your timezone, e.g. Europe/London (defaults to UTC)",
+                )
+                .max_length(MAX_TIMEZONE_LENGTH)
+                .autocomplete(),
+            )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            date: parsed
+                .get("date")
+                .ok_or("`date` is required.".to_string())?,
+            timezone: parsed.get("timezone"),
+        })
+    }
+}
+
+struct ViewArguments {
+    user: Option<UserId>,
+}
+
+impl Arguments for ViewArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(Argument::user("user", "The user to view the birthday of"))
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            user: parsed.get("user"),
+        })
+    }
+}
+
+struct Set;
+
+#[utils::command(
+    name = "set",
+    description = "Register your birthday",
+    arguments = SetArguments,
+    subcommand,
+    slash,
+    legacy,
+    autocomplete
+)]
+#[async_trait]
+impl CommandTrait for Set {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        require_guild(location)?;
+        let arguments = match SetArguments::parse(&format!("{} set", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let timezone = match arguments.timezone {
+            Some(name) => match name.parse::<Tz>() {
+                Ok(tz) => tz,
+                Err(_) => {
+                    return Ok(Some(
+                        CommandResponse::new_content(format!(
+                            "`{}` is not a valid timezone. Try something like `Europe/London` or `America/New_York`.",
+                            truncate(&name, MAX_TIMEZONE_LENGTH as usize)
+                        ))
+                        .ephemeral()
+                        .reply(),
+                    ));
+                }
+            },
+            None => Tz::UTC,
+        };
+        let date = arguments.date;
+        let birthday = Birthday::parse_date(&date)
+            .and_then(|(month, day, year)| Birthday::new(month, day, year, timezone));
+        let Some(birthday) = birthday else {
+            return Ok(Some(
+                CommandResponse::new_content(format!(
+                    "`{}` is not a valid date. Use `MM-DD` or `YYYY-MM-DD`.",
+                    truncate(&date, MAX_DATE_LENGTH as usize)
+                ))
+                .ephemeral()
+                .reply(),
+            ));
+        };
+
+        let birthdays = repo(ctx).await?;
+        let content = format!(
+            "🎂 Your birthday is set to **{}** ({}).",
+            birthday.date_str(),
+            birthday.timezone.name()
+        );
+        birthdays.insert(user_id(&user), birthday);
+        save_birthdays(&birthdays);
+        Ok(Some(CommandResponse::new_content(content).reply()))
+    }
+    async fn autocomplete<'a>(
+        &self,
+        _: &'a Context,
+        _: UserType,
+        _: Option<(Guild, GuildChannel)>,
+        focused: AutocompleteOption<'a>,
+        _: &'a CommandInteraction,
+    ) -> Option<AutocompleteResponse> {
+        if focused.name != "timezone" {
+            return None;
+        }
+        let query = focused.value.to_lowercase();
+        let response = TZ_VARIANTS
+            .iter()
+            .map(|tz| tz.name())
+            .filter(|name| name.to_lowercase().contains(&query))
+            .take(25)
+            .map(|name| (name.to_string(), Value::String(name.to_string())))
+            .collect::<AutocompleteResponse>();
+        Some(response)
+    }
+}
+
+struct Remove;
+
+#[utils::command(
+    name = "remove",
+    description = "Remove your birthday",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Remove {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        require_guild(location)?;
+        let birthdays = repo(ctx).await?;
+        let response = match birthdays.remove(&user_id(&user)) {
+            Some(_) => {
+                save_birthdays(&birthdays);
+                CommandResponse::new_content("Your birthday has been removed.")
+            }
+            None => CommandResponse::new_content("You haven't registered a birthday."),
+        };
+        Ok(Some(response.reply()))
+    }
+}
+
+struct View;
+
+#[utils::command(
+    name = "view",
+    description = "View someone's birthday",
+    arguments = ViewArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for View {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        require_guild(location)?;
+        let arguments = match ViewArguments::parse(&format!("{} view", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let birthdays = repo(ctx).await?;
+        let user_id = user_id(&user);
+        let target = arguments.user.unwrap_or(user_id);
+        let response = match birthdays.get(&target).map(|b| b.clone()) {
+            Some(birthday) => view_embed(target, &birthday),
+            None if target == user_id => CommandResponse::new_content(
+                "You haven't registered a birthday. Use `birthday set` to add one.",
+            ),
+            None => {
+                CommandResponse::new_content(format!("<@{}> hasn't registered a birthday.", target))
+            }
+        };
+        Ok(Some(response.reply()))
+    }
+}
+
+struct Upcoming;
+
+#[utils::command(
+    name = "upcoming",
+    description = "List upcoming birthdays in this server",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Upcoming {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let birthdays = repo(ctx).await?;
+        let embeds = upcoming_embeds(&guild, &birthdays);
+        let response = if embeds.len() > 1 {
+            let data = ctx.data.read().await;
+            let pages = data
+                .get::<Paginations>()
+                .ok_or("Failed to get paginations data.".to_string())?
+                .insert(embeds, user_id(&user).get())
+                .await;
+            CommandResponse::new_embeds(vec![pages.0]).components(vec![pages.1])
+        } else {
+            CommandResponse::new_embeds(embeds)
+        };
+        Ok(Some(response.reply()))
+    }
+}
+
+async fn repo(ctx: &Context) -> Result<Arc<UserBirthdaysRepo>, String> {
+    ctx.data
+        .read()
+        .await
+        .get::<UserBirthdays>()
+        .cloned()
+        .ok_or("Failed to get birthday data.".to_string())
+}
+
+fn user_id(user: &UserType) -> UserId {
+    match user {
+        UserType::User(u) => u.id,
+        UserType::Member(m) => m.user.id,
+    }
+}
+
+fn view_embed(user_id: UserId, birthday: &Birthday) -> CommandResponse {
+    let now = Utc::now();
+    let mut description = format!(
+        "<@{}>'s birthday is on **{}**",
+        user_id,
+        birthday.date_str()
+    );
+    if let Some(next) = birthday.next_occurrence(now) {
+        let days = (next - birthday.local_today(now)).num_days();
+        let when = match days {
+            0 => "today! 🎉".to_string(),
+            1 => "tomorrow".to_string(),
+            n => format!("in {} days", n),
+        };
+        description.push_str(&format!("\nNext birthday is {}", when));
+        if let Some(age) = birthday.age_on(next) {
+            description.push_str(&format!(" (turning {})", age));
+        }
+    }
+    let embed = CreateEmbed::default()
+        .title("🎂 Birthday")
+        .description(description)
+        .footer(CreateEmbedFooter::new(format!(
+            "Timezone: {}",
+            birthday.timezone.name()
+        )))
+        .color(Colour::BLITZ_BLUE);
+    CommandResponse::new_embeds(vec![embed])
+}
+
+fn upcoming_embeds(guild: &Guild, birthdays: &UserBirthdaysRepo) -> Vec<CreateEmbed> {
+    let now = Utc::now();
+    let mut upcoming = birthdays
+        .iter()
+        .filter(|entry| guild.members.contains_key(entry.key()))
+        .filter_map(|entry| {
+            let birthday = entry.value();
+            let next = birthday.next_occurrence(now)?;
+            let days = (next - birthday.local_today(now)).num_days();
+            Some((days, *entry.key(), birthday.clone(), next))
+        })
+        .collect::<Vec<_>>();
+    upcoming.sort_by_key(|(days, ..)| *days);
+
+    if upcoming.is_empty() {
+        return vec![
+            CreateEmbed::default()
+                .title("🎂 Upcoming Birthdays")
+                .description("Nobody in this server has registered a birthday yet.")
+                .color(Colour::BLITZ_BLUE),
+        ];
+    }
+
+    let pages = upcoming.len().div_ceil(UPCOMING_PER_PAGE);
+    upcoming
+        .chunks(UPCOMING_PER_PAGE)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let lines = chunk
+                .iter()
+                .map(|(days, user_id, birthday, next)| {
+                    let when = match days {
+                        0 => "today 🎉".to_string(),
+                        1 => "tomorrow".to_string(),
+                        n => format!("in {} days", n),
+                    };
+                    let age = birthday
+                        .age_on(*next)
+                        .map(|age| format!(" (turns {})", age))
+                        .unwrap_or_default();
+                    format!(
+                        "**{}** — <@{}>{} · {}",
+                        next.format("%B %-d"),
+                        user_id,
+                        age,
+                        when
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            CreateEmbed::default()
+                .title("🎂 Upcoming Birthdays")
+                .description(lines)
+                .footer(CreateEmbedFooter::new(format!(
+                    "Page {}/{}",
+                    index + 1,
+                    pages
+                )))
+                .color(Colour::BLITZ_BLUE)
+        })
+        .collect()
+}
@@ -0,0 +1,302 @@
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use serenity::{
+    all::{
+        ChannelId, ChannelType, Colour, CommandType, Context, CreateEmbed, Guild, GuildChannel,
+        GuildId, RoleId,
+    },
+    async_trait,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, BotPermission, CommandArguments, CommandResponse,
+    CommandTemplate, CommandTrait, ICommand, ParsedArguments, UserType, truncate,
+};
+
+use crate::{
+    BirthdayConfig, DEFAULT_BIRTHDAY_TEMPLATE, MAX_BIRTHDAY_TEMPLATE_LENGTH,
+    ServerBirthdayConfigRepo, commands::require_guild, save_birthday_configs,
+};
+
+const COMMAND_NAME: &str = "birthdayconfig";
+const COMMAND_DESCRIPTION: &str = "Configure birthday announcements for the server.";
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![BotPermission::ManageGuild],
+    )
+    .subcommand(Channel::node())
+    .subcommand(Message::node())
+    .subcommand(Role::node())
+    .subcommand(View::node())
+    .default("view")
+    .routed()
+}
+
+struct ChannelArguments {
+    channel: Option<ChannelId>,
+}
+
+impl Arguments for ChannelArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::channel("channel", "The channel to announce birthdays in")
+                .channel_types(vec![ChannelType::Text, ChannelType::News]),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            channel: parsed.get("channel"),
+        })
+    }
+}
+
+struct MessageArguments {
+    template: Option<String>,
+}
+
+impl Arguments for MessageArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::string(
+                "template",
+                "The message template, e.g. Happy birthday {user}!",
+            )
+            .max_length(MAX_BIRTHDAY_TEMPLATE_LENGTH as u16)
+            .rest(),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            template: parsed.get("template"),
+        })
+    }
+}
+
+struct RoleArguments {
+    role: Option<RoleId>,
+}
+
+impl Arguments for RoleArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(Argument::role("role", "The birthday role"))
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            role: parsed.get("role"),
+        })
+    }
+}
+
+struct Channel;
+
+#[utils::command(
+    name = "channel",
+    description = "Set the birthday announcement channel (leave empty to disable)",
+    arguments = ChannelArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Channel {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match ChannelArguments::parse(&format!("{} channel", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let response = match arguments.channel {
+            Some(channel_id) => {
+                if !guild.channels.contains_key(&channel_id) {
+                    return Ok(Some(
+                        CommandResponse::new_content("That channel is not in this server.").reply(),
+                    ));
+                }
+                update(ctx, &guild, |config| config.channel = Some(channel_id)).await?;
+                CommandResponse::new_content(format!(
+                    "Birthdays will be announced in <#{}>.",
+                    channel_id
+                ))
+            }
+            None => {
+                update(ctx, &guild, |config| config.channel = None).await?;
+                CommandResponse::new_content("Birthday announcements have been disabled.")
+            }
+        };
+        Ok(Some(response.reply()))
+    }
+}
+
+struct Message;
+
+#[utils::command(
+    name = "message",
+    description = "Set the birthday announcement message (leave empty to reset)",
+    arguments = MessageArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Message {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match MessageArguments::parse(&format!("{} message", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let response = match arguments.template {
+            Some(template) => {
+                let content = format!("Birthday message set to:\n>>> {}", template);
+                update(ctx, &guild, |config| config.template = Some(template)).await?;
+                CommandResponse::new_content(content)
+            }
+            None => {
+                update(ctx, &guild, |config| config.template = None).await?;
+                CommandResponse::new_content("Birthday message reset to the default.")
+            }
+        };
+        Ok(Some(response.reply()))
+    }
+}
+
+struct Role;
+
+#[utils::command(
+    name = "role",
+    description = "Set the role given for 24 hours on a birthday (leave empty to disable)",
+    arguments = RoleArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Role {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match RoleArguments::parse(&format!("{} role", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let response = match arguments.role {
+            Some(role_id) => {
+                if !guild.roles.contains_key(&role_id) {
+                    return Ok(Some(
+                        CommandResponse::new_content("That role is not in this server.").reply(),
+                    ));
+                }
+                update(ctx, &guild, |config| config.role = Some(role_id)).await?;
+                CommandResponse::new_content(format!(
+                    "Members will receive <@&{}> for 24 hours on their birthday.",
+                    role_id
+                ))
+            }
+            None => {
+                update(ctx, &guild, |config| config.role = None).await?;
+                CommandResponse::new_content("The birthday role has been disabled.")
+            }
+        };
+        Ok(Some(response.reply()))
+    }
+}
+
+struct View;
+
+#[utils::command(
+    name = "view",
+    description = "View the birthday configuration",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for View {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let config = repo(ctx)
+            .await?
+            .get(&guild.id)
+            .map(|c| c.clone())
+            .unwrap_or_default();
+        Ok(Some(view_embed(&config).reply()))
+    }
+}
+
+async fn repo(ctx: &Context) -> Result<Arc<DashMap<GuildId, BirthdayConfig>>, String> {
+    ctx.data
+        .read()
+        .await
+        .get::<ServerBirthdayConfigRepo>()
+        .cloned()
+        .ok_or("Failed to get birthday configuration.".to_string())
+}
+
+async fn update(
+    ctx: &Context,
+    guild: &Guild,
+    change: impl FnOnce(&mut BirthdayConfig),
+) -> Result<(), String> {
+    let configs = repo(ctx).await?;
+    change(&mut configs.entry(guild.id).or_default());
+    save_birthday_configs(&configs);
+    Ok(())
+}
+
+fn view_embed(config: &BirthdayConfig) -> CommandResponse {
+    let channel = config
+        .channel
+        .map(|id| format!("<#{}>", id))
+        .unwrap_or("Disabled".to_string());
+    let role = config
+        .role
+        .map(|id| format!("<@&{}>", id))
+        .unwrap_or("Disabled".to_string());
+    let template = config
+        .template
+        .clone()
+        .unwrap_or(DEFAULT_BIRTHDAY_TEMPLATE.to_string());
+
+    let embed = CreateEmbed::default()
+        .title("🎂 Birthday Configuration")
+        .field("Channel", channel, true)
+        .field("Role", role, true)
+        .field("Message", truncate(&template, 1024), false)
+        .color(Colour::BLITZ_BLUE);
+    CommandResponse::new_embeds(vec![embed])
+}
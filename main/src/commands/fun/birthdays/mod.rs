@@ -0,0 +1,6 @@
+mod birthday;
+mod birthday_config;
+
+pub fn get_commands() -> Vec<utils::CommandTemplate> {
+    vec![birthday::command(), birthday_config::command()]
+}
@@ -0,0 +1,294 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{ChannelId, ChannelType, Colour, CommandType, Context, CreateEmbed, Guild, GuildChannel},
+    async_trait,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, BotPermission, CommandArguments, CommandResponse,
+    CommandTemplate, CommandTrait, ICommand, ParsedArguments, UserType, truncate,
+};
+
+use crate::{
+    BoostConfig, DEFAULT_BOOST_TEMPLATE, MAX_BOOST_TEMPLATE_LENGTH, ServerBoostConfigMap,
+    ServerBoostConfigRepo, commands::require_guild, save_boost_configs,
+};
+
+const COMMAND_NAME: &str = "boostconfig";
+const COMMAND_DESCRIPTION: &str = "Configure boost announcements and booster perks.";
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![BotPermission::ManageGuild],
+    )
+    .subcommand(Channel::node())
+    .subcommand(Message::node())
+    .subcommand(Perks::node())
+    .subcommand(View::node())
+    .default("view")
+    .routed()
+}
+
+struct ChannelArguments {
+    channel: Option<ChannelId>,
+}
+
+impl Arguments for ChannelArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::channel("channel", "The channel to thank boosters in")
+                .channel_types(vec![ChannelType::Text, ChannelType::News]),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            channel: parsed.get("channel"),
+        })
+    }
+}
+
+struct MessageArguments {
+    template: Option<String>,
+}
+
+impl Arguments for MessageArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::string(
+                "template",
+                "The message template, e.g. Thanks for boosting {user}!",
+            )
+            .max_length(MAX_BOOST_TEMPLATE_LENGTH as u16)
+            .rest(),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            template: parsed.get("template"),
+        })
+    }
+}
+
+struct PerksArguments {
+    enabled: bool,
+}
+
+impl Arguments for PerksArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::boolean("enabled", "Whether booster custom roles are enabled").required(),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            enabled: parsed
+                .get("enabled")
+                .ok_or("`enabled` is required.".to_string())?,
+        })
+    }
+}
+
+struct Channel;
+
+#[utils::command(
+    name = "channel",
+    description = "Set the boost announcement channel (leave empty to disable)",
+    arguments = ChannelArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Channel {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match ChannelArguments::parse(&format!("{} channel", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let response = match arguments.channel {
+            Some(channel_id) => {
+                if !guild.channels.contains_key(&channel_id) {
+                    return Ok(Some(
+                        CommandResponse::new_content("That channel is not in this server.").reply(),
+                    ));
+                }
+                update(ctx, &guild, |config| config.channel = Some(channel_id)).await?;
+                CommandResponse::new_content(format!(
+                    "Boosters will be thanked in <#{}>.",
+                    channel_id
+                ))
+            }
+            None => {
+                update(ctx, &guild, |config| config.channel = None).await?;
+                CommandResponse::new_content("Boost announcements have been disabled.")
+            }
+        };
+        Ok(Some(response.reply()))
+    }
+}
+
+struct Message;
+
+#[utils::command(
+    name = "message",
+    description = "Set the thank-you message (leave empty to reset)",
+    arguments = MessageArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Message {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match MessageArguments::parse(&format!("{} message", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let response = match arguments.template {
+            Some(template) => {
+                let content = format!("Boost message set to:\n>>> {}", template);
+                update(ctx, &guild, |config| config.template = Some(template)).await?;
+                CommandResponse::new_content(content)
+            }
+            None => {
+                update(ctx, &guild, |config| config.template = None).await?;
+                CommandResponse::new_content("Boost message reset to the default.")
+            }
+        };
+        Ok(Some(response.reply()))
+    }
+}
+
+struct Perks;
+
+#[utils::command(
+    name = "perks",
+    description = "Allow boosters to manage their own custom role",
+    arguments = PerksArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Perks {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match PerksArguments::parse(&format!("{} perks", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let enabled = arguments.enabled;
+        update(ctx, &guild, |config| config.custom_roles = enabled).await?;
+        Ok(Some(
+            CommandResponse::new_content(if enabled {
+                "Boosters can now manage their own custom role with `boostrole`."
+            } else {
+                "Booster custom roles have been disabled."
+            })
+            .reply(),
+        ))
+    }
+}
+
+struct View;
+
+#[utils::command(
+    name = "view",
+    description = "View the boost configuration",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for View {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let config = repo(ctx)
+            .await?
+            .get(&guild.id)
+            .map(|c| c.clone())
+            .unwrap_or_default();
+        Ok(Some(view_embed(&config).reply()))
+    }
+}
+
+async fn repo(ctx: &Context) -> Result<Arc<ServerBoostConfigMap>, String> {
+    ctx.data
+        .read()
+        .await
+        .get::<ServerBoostConfigRepo>()
+        .cloned()
+        .ok_or("Failed to get boost configuration.".to_string())
+}
+
+async fn update(
+    ctx: &Context,
+    guild: &Guild,
+    change: impl FnOnce(&mut BoostConfig),
+) -> Result<(), String> {
+    let configs = repo(ctx).await?;
+    change(&mut configs.entry(guild.id).or_default());
+    save_boost_configs(&configs);
+    Ok(())
+}
+
+fn view_embed(config: &BoostConfig) -> CommandResponse {
+    let channel = config
+        .channel
+        .map(|id| format!("<#{}>", id))
+        .unwrap_or("Disabled".to_string());
+    let perks = if config.custom_roles {
+        "Enabled"
+    } else {
+        "Disabled"
+    };
+    let template = config
+        .template
+        .clone()
+        .unwrap_or(DEFAULT_BOOST_TEMPLATE.to_string());
+
+    let embed = CreateEmbed::default()
+        .title("💎 Boost Configuration")
+        .field("Channel", channel, true)
+        .field("Custom Roles", perks, true)
+        .field("Message", truncate(&template, 1024), false)
+        .color(Colour::FABLED_PINK);
+    CommandResponse::new_embeds(vec![embed])
+}
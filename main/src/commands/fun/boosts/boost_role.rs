@@ -0,0 +1,480 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serenity::{
+    all::{
+        AttachmentId, CacheHttp, Colour, CommandType, Context, CreateAttachment, EditRole, Guild,
+        GuildChannel, Member, RoleId,
+    },
+    async_trait,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, CommandArguments, CommandResponse, CommandTemplate,
+    CommandTrait, ICommand, ParsedArguments, UserType, error, truncate,
+};
+
+use crate::{
+    Boosters, BoostersMap, ServerBoostConfigRepo, commands::require_guild, save_boosters,
+    set_booster_role,
+};
+
+const COMMAND_NAME: &str = "boostrole";
+const COMMAND_DESCRIPTION: &str = "Manage your custom booster role.";
+const ROLE_ICONS_FEATURE: &str = "ROLE_ICONS";
+// `#rrggbb`
+const MAX_COLOR_LENGTH: u16 = 7;
+const MAX_ROLE_NAME_LENGTH: u16 = 100;
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![],
+    )
+    .subcommand(Name::node())
+    .subcommand(Color::node())
+    .subcommand(Icon::node())
+    .subcommand(Remove::node())
+    .routed()
+}
+
+struct NameArguments {
+    name: String,
+}
+
+impl Arguments for NameArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::string("name", "The role name")
+                .max_length(MAX_ROLE_NAME_LENGTH)
+                .required()
+                .rest(),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            name: parsed
+                .get("name")
+                .ok_or("`name` is required.".to_string())?,
+        })
+    }
+}
+
+struct ColorArguments {
+    color: String,
+}
+
+impl Arguments for ColorArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::string("color", "A hex color, e.g. #ff73fa")
+                .max_length(MAX_COLOR_LENGTH)
+                .required(),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            color: parsed
+                .get("color")
+                .ok_or("`color` is required.".to_string())?,
+        })
+    }
+}
+
+struct IconArguments {
+    emoji: Option<String>,
+    image: Option<AttachmentId>,
+}
+
+impl Arguments for IconArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(Argument::string(
+                "emoji",
+                "A unicode emoji to use as the icon",
+            ))
+            .argument(Argument::attachment("image", "An image to use as the icon"))
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            emoji: parsed.get("emoji"),
+            image: parsed.get("image"),
+        })
+    }
+}
+
+// The member asking and their current booster role, once they're allowed to have one.
+struct Booster {
+    guild: Guild,
+    member: Member,
+    since: DateTime<Utc>,
+    role: Option<RoleId>,
+    boosters: Arc<BoostersMap>,
+}
+
+impl Booster {
+    // `Ok(Err(..))` is the reply for members who can't have a custom role.
+    async fn get(
+        ctx: &Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+    ) -> Result<Result<Self, CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let UserType::Member(member) = user else {
+            return Err("This command can only be used by a guild member".into());
+        };
+
+        let (configs, boosters) = {
+            let data = ctx.data.read().await;
+            let configs = data
+                .get::<ServerBoostConfigRepo>()
+                .cloned()
+                .ok_or("Failed to get boost configuration.".to_string())?;
+            let boosters = data
+                .get::<Boosters>()
+                .cloned()
+                .ok_or("Failed to get booster data.".to_string())?;
+            (configs, boosters)
+        };
+
+        if !configs.get(&guild.id).is_some_and(|c| c.custom_roles) {
+            return Ok(Err(CommandResponse::new_content(
+                "Booster custom roles are not enabled in this server.",
+            )
+            .reply()));
+        }
+        let Some(since) = member.premium_since else {
+            return Ok(Err(CommandResponse::new_content(
+                "Only server boosters can have a custom role. 💎",
+            )
+            .reply()));
+        };
+
+        let role = boosters
+            .get(&guild.id)
+            .and_then(|records| records.get(&member.user.id).and_then(|r| r.role))
+            .filter(|role_id| guild.roles.contains_key(role_id));
+
+        Ok(Ok(Self {
+            guild,
+            member,
+            since: since.to_utc(),
+            role,
+            boosters,
+        }))
+    }
+
+    // Applies the edit and stores the role, which may have just been created.
+    async fn edit(&self, ctx: &Context, builder: EditRole<'_>) -> Result<(), String> {
+        let role_id = apply(ctx, &self.guild, &self.member, self.role, builder).await?;
+        set_record_role(
+            &self.boosters,
+            &self.guild,
+            &self.member,
+            self.since,
+            Some(role_id),
+        );
+        Ok(())
+    }
+}
+
+struct Name;
+
+#[utils::command(
+    name = "name",
+    description = "Set the name of your booster role",
+    arguments = NameArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Name {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let booster = match Booster::get(ctx, user, location).await? {
+            Ok(booster) => booster,
+            Err(response) => return Ok(Some(response)),
+        };
+        let arguments = match NameArguments::parse(&format!("{} name", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        booster
+            .edit(ctx, EditRole::new().name(arguments.name.clone()))
+            .await?;
+        Ok(Some(
+            CommandResponse::new_content(format!(
+                "Your booster role is now named **{}**.",
+                arguments.name
+            ))
+            .reply(),
+        ))
+    }
+}
+
+struct Color;
+
+#[utils::command(
+    name = "color",
+    description = "Set the color of your booster role",
+    arguments = ColorArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Color {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let booster = match Booster::get(ctx, user, location).await? {
+            Ok(booster) => booster,
+            Err(response) => return Ok(Some(response)),
+        };
+        let arguments = match ColorArguments::parse(&format!("{} color", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let Some(colour) = parse_colour(&arguments.color) else {
+            return Ok(Some(
+                CommandResponse::new_content(format!(
+                    "`{}` is not a valid hex color. Try something like `#ff73fa`.",
+                    truncate(&arguments.color, MAX_COLOR_LENGTH as usize)
+                ))
+                .reply(),
+            ));
+        };
+        booster.edit(ctx, EditRole::new().colour(colour)).await?;
+        Ok(Some(
+            CommandResponse::new_content(format!(
+                "Your booster role color is now `#{}`.",
+                colour.hex()
+            ))
+            .reply(),
+        ))
+    }
+}
+
+struct Icon;
+
+#[utils::command(
+    name = "icon",
+    description = "Set the icon of your booster role",
+    arguments = IconArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Icon {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let booster = match Booster::get(ctx, user, location).await? {
+            Ok(booster) => booster,
+            Err(response) => return Ok(Some(response)),
+        };
+        let arguments = match IconArguments::parse(&format!("{} icon", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        if !booster
+            .guild
+            .features
+            .iter()
+            .any(|f| f == ROLE_ICONS_FEATURE)
+        {
+            return Ok(Some(
+                CommandResponse::new_content("This server needs boost level 2 to use role icons.")
+                    .reply(),
+            ));
+        }
+
+        let image = match &args {
+            CommandArguments::Slash(_, interaction) => arguments
+                .image
+                .and_then(|id| interaction.data.resolved.attachments.get(&id))
+                .map(|a| a.url.clone()),
+            CommandArguments::Legacy(_, msg) => msg.attachments.first().map(|a| a.url.clone()),
+        };
+        let attachment = match image {
+            Some(url) => Some(
+                CreateAttachment::url(ctx.http(), &url)
+                    .await
+                    .map_err(|e| format!("Failed to download role icon: {}", e))?,
+            ),
+            None => None,
+        };
+        let builder = match (&attachment, arguments.emoji) {
+            (Some(attachment), _) => EditRole::new().icon(Some(attachment)).unicode_emoji(None),
+            (None, Some(emoji)) => EditRole::new().icon(None).unicode_emoji(Some(emoji)),
+            (None, None) => {
+                return Ok(Some(
+                    CommandResponse::new_content(
+                        "Provide an emoji or attach an image to use as the icon.",
+                    )
+                    .reply(),
+                ));
+            }
+        };
+        booster.edit(ctx, builder).await?;
+        Ok(Some(
+            CommandResponse::new_content("Your booster role icon has been updated.").reply(),
+        ))
+    }
+}
+
+struct Remove;
+
+#[utils::command(
+    name = "remove",
+    description = "Delete your booster role",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Remove {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let booster = match Booster::get(ctx, user, location).await? {
+            Ok(booster) => booster,
+            Err(response) => return Ok(Some(response)),
+        };
+
+        let Some(role_id) = booster.role else {
+            return Ok(Some(
+                CommandResponse::new_content("You don't have a booster role.").reply(),
+            ));
+        };
+        if let Err(e) = booster.guild.id.delete_role(ctx.http(), role_id).await {
+            error!("Failed to delete booster role {}: {}", role_id, e);
+            return Err(format!("Failed to delete booster role: {}", e));
+        }
+        set_record_role(
+            &booster.boosters,
+            &booster.guild,
+            &booster.member,
+            booster.since,
+            None,
+        );
+        Ok(Some(
+            CommandResponse::new_content("Your booster role has been deleted.").reply(),
+        ))
+    }
+}
+
+fn parse_colour(input: &str) -> Option<Colour> {
+    let hex = input.strip_prefix('#').unwrap_or(input);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok().map(Colour::new)
+}
+
+// Edits the member's booster role, creating it first when they don't have one yet.
+async fn apply(
+    ctx: &Context,
+    guild: &Guild,
+    member: &Member,
+    role: Option<RoleId>,
+    builder: EditRole<'_>,
+) -> Result<RoleId, String> {
+    let (role_id, created) = match role {
+        Some(role_id) => (role_id, false),
+        None => {
+            let role = guild
+                .id
+                .create_role(
+                    ctx.http(),
+                    EditRole::new()
+                        .name(member.display_name())
+                        .audit_log_reason("Booster custom role"),
+                )
+                .await
+                .map_err(|e| format!("Failed to create booster role: {}", e))?;
+            (role.id, true)
+        }
+    };
+
+    let result = async {
+        if created {
+            member
+                .add_role(ctx.http(), role_id)
+                .await
+                .map_err(|e| format!("Failed to give booster role: {}", e))?;
+        }
+        guild
+            .id
+            .edit_role(ctx.http(), role_id, builder)
+            .await
+            .map_err(|e| format!("Failed to edit booster role: {}", e))
+    }
+    .await;
+
+    // A role created here isn't stored until everything succeeded, so it's removed again.
+    if let Err(e) = result {
+        if created && let Err(why) = guild.id.delete_role(ctx.http(), role_id).await {
+            error!("Failed to delete unused booster role {}: {}", role_id, why);
+        }
+        return Err(e);
+    }
+    Ok(role_id)
+}
+
+fn set_record_role(
+    boosters: &BoostersMap,
+    guild: &Guild,
+    member: &Member,
+    since: DateTime<Utc>,
+    role: Option<RoleId>,
+) {
+    set_booster_role(boosters, guild.id, member.user.id, since, role);
+    save_boosters(boosters);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_colours() {
+        assert_eq!(parse_colour("#ff73fa"), Some(Colour::new(0xff73fa)));
+        assert_eq!(parse_colour("FF73FA"), Some(Colour::new(0xff73fa)));
+        assert_eq!(parse_colour("#000000"), Some(Colour::new(0)));
+        assert_eq!(parse_colour("##ff73fa"), None);
+        assert_eq!(parse_colour("#ff73f"), None);
+        assert_eq!(parse_colour("#ff73fa0"), None);
+        assert_eq!(parse_colour("+ff73f"), None);
+        assert_eq!(parse_colour("#gg73fa"), None);
+        assert_eq!(parse_colour("#ff73\u{e9}"), None);
+    }
+}
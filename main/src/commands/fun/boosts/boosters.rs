@@ -0,0 +1,109 @@
+use serenity::{
+    all::{Colour, Context, CreateEmbed, CreateEmbedFooter, Guild, GuildChannel},
+    async_trait,
+};
+
+use utils::{CommandArguments, CommandResponse, CommandTrait, UserType};
+
+use crate::{Boosters, Paginations, handler::boosts::boost_duration_str};
+
+const BOOSTERS_PER_PAGE: usize = 10;
+
+pub struct Command;
+
+#[utils::command(
+    name = "boosters",
+    description = "View the server's boosters, longest boosting first.",
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Command {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let Some((guild, _)) = location else {
+            return Err("This command can only be used in a server.".to_string());
+        };
+        let user_id = match user {
+            UserType::User(u) => u.id,
+            UserType::Member(m) => m.user.id,
+        };
+
+        let boosters = {
+            let data = ctx.data.read().await;
+            data.get::<Boosters>()
+                .cloned()
+                .ok_or("Failed to get booster data.".to_string())?
+        };
+
+        let mut records = boosters
+            .get(&guild.id)
+            .map(|records| {
+                records
+                    .iter()
+                    .map(|(user_id, record)| (*user_id, record.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        records.sort_by_key(|(_, record)| record.since);
+
+        if records.is_empty() {
+            let embed = CreateEmbed::default()
+                .title("💎 Boosters")
+                .description("Nobody is boosting this server yet.")
+                .color(Colour::FABLED_PINK);
+            return Ok(Some(CommandResponse::new_embeds(vec![embed]).reply()));
+        }
+
+        let pages = records.len().div_ceil(BOOSTERS_PER_PAGE);
+        let embeds = records
+            .chunks(BOOSTERS_PER_PAGE)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let lines = chunk
+                    .iter()
+                    .enumerate()
+                    .map(|(position, (user_id, record))| {
+                        format!(
+                            "`#{}` <@{}> · since <t:{}:D> ({})",
+                            index * BOOSTERS_PER_PAGE + position + 1,
+                            user_id,
+                            record.since.timestamp(),
+                            boost_duration_str(record)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                CreateEmbed::default()
+                    .title(format!("💎 Boosters of {}", guild.name))
+                    .description(lines)
+                    .footer(CreateEmbedFooter::new(format!(
+                        "Page {}/{} · {} boosts",
+                        index + 1,
+                        pages,
+                        guild.premium_subscription_count.unwrap_or_default()
+                    )))
+                    .color(Colour::FABLED_PINK)
+            })
+            .collect::<Vec<_>>();
+
+        let response = if embeds.len() > 1 {
+            let data = ctx.data.read().await;
+            let pages = data
+                .get::<Paginations>()
+                .ok_or("Failed to get paginations data.".to_string())?
+                .insert(embeds, user_id.get())
+                .await;
+            CommandResponse::new_embeds(vec![pages.0]).components(vec![pages.1])
+        } else {
+            CommandResponse::new_embeds(embeds)
+        };
+
+        Ok(Some(response.reply()))
+    }
+}
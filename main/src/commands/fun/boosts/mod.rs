@@ -0,0 +1,11 @@
+mod boost_config;
+mod boost_role;
+mod boosters;
+
+pub fn get_commands() -> Vec<utils::CommandTemplate> {
+    vec![
+        boost_config::command(),
+        boost_role::command(),
+        boosters::command(),
+    ]
+}
@@ -1,5 +1,14 @@
+mod birthdays;
+mod boosts;
+mod reactions;
+mod roleplay;
+
 pub fn get_modules() -> Vec<utils::CommandTemplate> {
     let mut modules = vec![];
+    modules.extend(birthdays::get_commands());
+    modules.extend(boosts::get_commands());
+    modules.extend(reactions::get_commands());
+    modules.extend(roleplay::get_commands());
 
     modules
 }
@@ -0,0 +1,520 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{
+        ChannelId, ChannelType, Colour, CommandType, Context, CreateEmbed, Guild, GuildChannel,
+        ReactionType,
+    },
+    async_trait,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, BotPermission, CommandArguments, CommandResponse,
+    CommandTemplate, CommandTrait, ICommand, ParsedArguments, UserType, truncate,
+};
+
+use crate::{
+    AutoReaction, AutoReactions, AutoReactionsMap, MAX_AUTO_REACTIONS, MAX_REACTION_EMOJIS,
+    MAX_REACTION_KEYWORD_LENGTH, MAX_REACTION_PATTERN_LENGTH, ReactionTrigger,
+    commands::{MAX_ECHOED_LENGTH, parse_emoji, require_guild},
+    reaction_regex, save_auto_reactions,
+};
+
+const COMMAND_NAME: &str = "autoreact";
+const COMMAND_DESCRIPTION: &str = "Automatically react to messages matching a trigger.";
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![BotPermission::ManageGuild],
+    )
+    .subcommand(Keyword::node())
+    .subcommand(Regex::node())
+    .subcommand(Channel::node())
+    .subcommand(Remove::node())
+    .subcommand(List::node())
+    .default("list")
+    .routed()
+}
+
+fn emojis_argument() -> Argument {
+    Argument::string("emojis", "The emojis to react with, separated by spaces")
+        .required()
+        .rest()
+}
+
+fn emojis(parsed: &ParsedArguments) -> Result<Vec<String>, String> {
+    Ok(parsed
+        .get::<String>("emojis")
+        .ok_or("`emojis` is required.".to_string())?
+        .split_whitespace()
+        .map(|s| s.to_string())
+        .collect())
+}
+
+struct KeywordArguments {
+    keyword: String,
+    emojis: Vec<String>,
+}
+
+impl Arguments for KeywordArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(
+                Argument::string("keyword", "The keyword to match")
+                    .max_length(MAX_REACTION_KEYWORD_LENGTH as u16)
+                    .required(),
+            )
+            .argument(emojis_argument())
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            emojis: emojis(&parsed)?,
+            keyword: parsed
+                .get("keyword")
+                .ok_or("`keyword` is required.".to_string())?,
+        })
+    }
+}
+
+struct RegexArguments {
+    pattern: String,
+    emojis: Vec<String>,
+}
+
+impl Arguments for RegexArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(
+                Argument::string("pattern", "The pattern to match")
+                    .max_length(MAX_REACTION_PATTERN_LENGTH as u16)
+                    .required(),
+            )
+            .argument(emojis_argument())
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            emojis: emojis(&parsed)?,
+            pattern: parsed
+                .get("pattern")
+                .ok_or("`pattern` is required.".to_string())?,
+        })
+    }
+}
+
+struct ChannelArguments {
+    channel: ChannelId,
+    emojis: Vec<String>,
+}
+
+impl Arguments for ChannelArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(
+                Argument::channel("channel", "The channel to watch")
+                    .channel_types(vec![ChannelType::Text, ChannelType::News])
+                    .required(),
+            )
+            .argument(emojis_argument())
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            emojis: emojis(&parsed)?,
+            channel: parsed
+                .get("channel")
+                .ok_or("`channel` is required.".to_string())?,
+        })
+    }
+}
+
+struct RemoveArguments {
+    id: usize,
+}
+
+impl Arguments for RemoveArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::integer("id", "The number of the auto-reaction in the list")
+                .range(1, MAX_AUTO_REACTIONS as i64)
+                .required(),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            id: parsed
+                .get::<i64>("id")
+                .ok_or("`id` is required.".to_string())? as usize,
+        })
+    }
+}
+
+struct Keyword;
+
+#[utils::command(
+    name = "keyword",
+    description = "React to messages containing a keyword",
+    arguments = KeywordArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Keyword {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match KeywordArguments::parse(&format!("{} keyword", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+        let (keyword, emojis) = split_legacy_trigger(&args, arguments.keyword, arguments.emojis);
+        add(ctx, &guild, ReactionTrigger::Keyword(keyword), &emojis).await
+    }
+}
+
+struct Regex;
+
+#[utils::command(
+    name = "regex",
+    description = "React to messages matching a regular expression",
+    arguments = RegexArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Regex {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match RegexArguments::parse(&format!("{} regex", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+        let (pattern, emojis) = split_legacy_trigger(&args, arguments.pattern, arguments.emojis);
+        add(ctx, &guild, ReactionTrigger::Regex(pattern), &emojis).await
+    }
+}
+
+struct Channel;
+
+#[utils::command(
+    name = "channel",
+    description = "React to every message in a channel",
+    arguments = ChannelArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Channel {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match ChannelArguments::parse(&format!("{} channel", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+        let trigger = ReactionTrigger::Channel(arguments.channel);
+        add(ctx, &guild, trigger, &arguments.emojis).await
+    }
+}
+
+struct Remove;
+
+#[utils::command(
+    name = "remove",
+    description = "Remove an auto-reaction",
+    arguments = RemoveArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Remove {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match RemoveArguments::parse(&format!("{} remove", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let auto_reactions = repo(ctx).await?;
+        let removed = {
+            let mut reactions = auto_reactions.entry(guild.id).or_default();
+            if arguments.id > reactions.len() {
+                return Ok(Some(
+                    CommandResponse::new_content(
+                        "There is no auto-reaction with that number. Use `autoreact list` to see them.",
+                    )
+                    .reply(),
+                ));
+            }
+            reactions.remove(arguments.id - 1)
+        };
+        save_auto_reactions(&auto_reactions);
+
+        Ok(Some(
+            CommandResponse::new_content(format!(
+                "Removed the auto-reaction for {}.",
+                removed.trigger
+            ))
+            .reply(),
+        ))
+    }
+}
+
+struct List;
+
+#[utils::command(
+    name = "list",
+    description = "List the auto-reactions of the server",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for List {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let reactions = repo(ctx)
+            .await?
+            .get(&guild.id)
+            .map(|r| r.clone())
+            .unwrap_or_default();
+        Ok(Some(list_embed(&reactions).reply()))
+    }
+}
+
+async fn repo(ctx: &Context) -> Result<Arc<AutoReactionsMap>, String> {
+    let data = ctx.data.read().await;
+    data.get::<AutoReactions>()
+        .cloned()
+        .ok_or("Failed to get auto-reactions.".to_string())
+}
+
+// Legacy keywords and patterns can contain spaces, only the trailing emojis are emojis.
+fn split_legacy_trigger(
+    args: &CommandArguments,
+    trigger: String,
+    emojis: Vec<String>,
+) -> (String, Vec<String>) {
+    if !matches!(args, CommandArguments::Legacy(..)) {
+        return (trigger, emojis);
+    }
+    split_trailing_emojis(trigger, emojis)
+}
+
+fn split_trailing_emojis(trigger: String, emojis: Vec<String>) -> (String, Vec<String>) {
+    let split = emojis
+        .iter()
+        .rposition(|w| parse_emoji(w).is_none())
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let mut words = emojis;
+    let emojis = words.split_off(split);
+    let trigger = std::iter::once(trigger)
+        .chain(words)
+        .collect::<Vec<_>>()
+        .join(" ");
+    (trigger, emojis)
+}
+
+async fn add(
+    ctx: &Context,
+    guild: &Guild,
+    trigger: ReactionTrigger,
+    emojis: &[String],
+) -> Result<Option<CommandResponse>, String> {
+    if let Err(e) = validate_trigger(guild, &trigger) {
+        return Ok(Some(CommandResponse::new_content(e).reply()));
+    }
+    let emojis = match parse_emojis(emojis) {
+        Ok(emojis) => emojis,
+        Err(e) => return Ok(Some(CommandResponse::new_content(e).reply())),
+    };
+
+    let auto_reactions = repo(ctx).await?;
+    let content = format!(
+        "Reacting with {} to messages matching {}.",
+        emojis
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join(" "),
+        trigger
+    );
+    {
+        let mut reactions = auto_reactions.entry(guild.id).or_default();
+        if let Some(existing) = reactions.iter_mut().find(|r| r.trigger == trigger) {
+            *existing = AutoReaction::new(trigger, emojis);
+        } else if reactions.len() >= MAX_AUTO_REACTIONS {
+            return Ok(Some(
+                CommandResponse::new_content(format!(
+                    "This server already has the maximum of {} auto-reactions.",
+                    MAX_AUTO_REACTIONS
+                ))
+                .reply(),
+            ));
+        } else {
+            reactions.push(AutoReaction::new(trigger, emojis));
+        }
+    }
+    save_auto_reactions(&auto_reactions);
+
+    Ok(Some(CommandResponse::new_content(content).reply()))
+}
+
+fn validate_trigger(guild: &Guild, trigger: &ReactionTrigger) -> Result<(), String> {
+    match trigger {
+        ReactionTrigger::Keyword(keyword)
+            if keyword.chars().count() > MAX_REACTION_KEYWORD_LENGTH =>
+        {
+            Err(format!(
+                "Keywords cannot exceed {} characters.",
+                MAX_REACTION_KEYWORD_LENGTH
+            ))
+        }
+        ReactionTrigger::Regex(pattern)
+            if pattern.chars().count() > MAX_REACTION_PATTERN_LENGTH =>
+        {
+            Err(format!(
+                "Patterns cannot exceed {} characters.",
+                MAX_REACTION_PATTERN_LENGTH
+            ))
+        }
+        ReactionTrigger::Regex(pattern) => reaction_regex(pattern)
+            .map(|_| ())
+            .map_err(|e| format!("That is not a valid regex:\n```\n{}\n```", e)),
+        ReactionTrigger::Channel(channel_id) if !guild.channels.contains_key(channel_id) => {
+            Err("That channel is not in this server.".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn parse_emojis(words: &[String]) -> Result<Vec<ReactionType>, String> {
+    if words.is_empty() {
+        return Err("Provide at least one emoji to react with.".to_string());
+    }
+    if words.len() > MAX_REACTION_EMOJIS {
+        return Err(format!(
+            "You can only use up to {} emojis per auto-reaction.",
+            MAX_REACTION_EMOJIS
+        ));
+    }
+    words
+        .iter()
+        .map(|w| {
+            parse_emoji(w).ok_or(format!(
+                "`{}` is not a valid emoji.",
+                truncate(w, MAX_ECHOED_LENGTH)
+            ))
+        })
+        .collect()
+}
+
+fn list_embed(reactions: &[AutoReaction]) -> CommandResponse {
+    let description = if reactions.is_empty() {
+        "This server has no auto-reactions.".to_string()
+    } else {
+        reactions
+            .iter()
+            .enumerate()
+            .map(|(index, reaction)| {
+                format!(
+                    "`{}.` {} → {}",
+                    index + 1,
+                    reaction.trigger,
+                    reaction
+                        .emojis
+                        .iter()
+                        .map(|e| e.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = CreateEmbed::default()
+        .title("✨ Auto-reactions")
+        .description(description)
+        .color(Colour::GOLD);
+    CommandResponse::new_embeds(vec![embed])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_emojis() {
+        let emojis = parse_emojis(&words(&["👍", "<:cat:123>"])).unwrap();
+        assert_eq!(emojis.len(), 2);
+        assert!(parse_emojis(&[]).is_err());
+        assert!(parse_emojis(&words(&["👍"; MAX_REACTION_EMOJIS + 1])).is_err());
+    }
+
+    #[test]
+    fn keeps_spaces_in_legacy_triggers() {
+        let (trigger, emojis) =
+            split_trailing_emojis("good".to_string(), words(&["morning", "👋", "☀️"]));
+        assert_eq!(trigger, "good morning");
+        assert_eq!(emojis, words(&["👋", "☀️"]));
+
+        let (trigger, emojis) = split_trailing_emojis("hi".to_string(), words(&["👋"]));
+        assert_eq!(trigger, "hi");
+        assert_eq!(emojis, words(&["👋"]));
+
+        let (trigger, emojis) = split_trailing_emojis("a".to_string(), words(&["b"]));
+        assert_eq!(trigger, "a b");
+        assert!(emojis.is_empty());
+    }
+
+    #[test]
+    fn truncates_echoed_emojis() {
+        let error = parse_emojis(&words(&["👍", &"x".repeat(5000)])).unwrap_err();
+        assert!(error.starts_with("`xxx"));
+        assert!(error.chars().count() < 200);
+    }
+}
@@ -0,0 +1,6 @@
+mod autoreact;
+mod reaction_role;
+
+pub fn get_commands() -> Vec<utils::CommandTemplate> {
+    vec![autoreact::command(), reaction_role::command()]
+}
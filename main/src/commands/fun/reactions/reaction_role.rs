@@ -0,0 +1,628 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{
+        CacheHttp, ChannelId, Colour, CommandType, Context, CreateEmbed, Guild, GuildChannel,
+        Member, MessageId, RoleId, UserId,
+    },
+    async_trait,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, BotPermission, CommandArguments, CommandResponse,
+    CommandTemplate, CommandTrait, ICommand, ParsedArguments, UserType, error, truncate,
+};
+
+use crate::{
+    MAX_REACTION_ROLES, ReactionRole, ReactionRoleMessage, ReactionRoleMode, ReactionRoles,
+    ReactionRolesMap,
+    commands::{MAX_ECHOED_LENGTH, parse_emoji, parse_message_reference, require_guild},
+    handler::reactions::message_link,
+    same_emoji, save_reaction_roles,
+};
+
+const COMMAND_NAME: &str = "reactionrole";
+const COMMAND_DESCRIPTION: &str = "Give members roles when they react to a message.";
+const MODES: [&str; 3] = ["toggle", "unique", "verify"];
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![BotPermission::ManageRoles],
+    )
+    .subcommand(Add::node())
+    .subcommand(Remove::node())
+    .subcommand(Mode::node())
+    .subcommand(List::node())
+    .default("list")
+    .routed()
+}
+
+fn message_argument() -> Argument {
+    Argument::string(
+        "message",
+        "A message link, or the ID of a message in this channel",
+    )
+    .required()
+}
+
+fn mode_argument() -> Argument {
+    Argument::choice(
+        "mode",
+        "How the roles of the message behave",
+        MODES.to_vec(),
+    )
+}
+
+fn mode(parsed: &ParsedArguments) -> Result<Option<ReactionRoleMode>, String> {
+    match parsed.get::<String>("mode") {
+        Some(mode) => ReactionRoleMode::parse(&mode)
+            .map(Some)
+            .ok_or("The mode must be `toggle`, `unique` or `verify`.".to_string()),
+        None => Ok(None),
+    }
+}
+
+struct AddArguments {
+    message: String,
+    emoji: String,
+    role: RoleId,
+    mode: Option<ReactionRoleMode>,
+}
+
+impl Arguments for AddArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(message_argument())
+            .argument(Argument::string("emoji", "The emoji to react with").required())
+            .argument(Argument::role("role", "The role to give").required())
+            .argument(mode_argument())
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            mode: mode(&parsed)?,
+            message: parsed
+                .get("message")
+                .ok_or("`message` is required.".to_string())?,
+            emoji: parsed
+                .get("emoji")
+                .ok_or("`emoji` is required.".to_string())?,
+            role: parsed
+                .get("role")
+                .ok_or("`role` is required.".to_string())?,
+        })
+    }
+}
+
+struct RemoveArguments {
+    message: String,
+    emoji: Option<String>,
+}
+
+impl Arguments for RemoveArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(message_argument())
+            .argument(Argument::string(
+                "emoji",
+                "The emoji to remove (leave empty to remove all)",
+            ))
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            message: parsed
+                .get("message")
+                .ok_or("`message` is required.".to_string())?,
+            emoji: parsed.get("emoji"),
+        })
+    }
+}
+
+struct ModeArguments {
+    message: String,
+    mode: ReactionRoleMode,
+}
+
+impl Arguments for ModeArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(message_argument())
+            .argument(mode_argument().required())
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            mode: mode(&parsed)?.ok_or("`mode` is required.".to_string())?,
+            message: parsed
+                .get("message")
+                .ok_or("`message` is required.".to_string())?,
+        })
+    }
+}
+
+struct Add;
+
+#[utils::command(
+    name = "add",
+    description = "Add a reaction role to a message",
+    arguments = AddArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Add {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let UserType::Member(member) = user else {
+            return Err("This command can only be used in a server.".to_string());
+        };
+        let Some((guild, channel)) = location else {
+            return Err("This command can only be used in a server.".to_string());
+        };
+        let arguments = match AddArguments::parse(&format!("{} add", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let (channel_id, message_id) = match resolve_message(&guild, &channel, &arguments.message) {
+            Ok(reference) => reference,
+            Err(e) => return Ok(Some(CommandResponse::new_content(e).reply())),
+        };
+        let Some(emoji) = parse_emoji(&arguments.emoji) else {
+            return Ok(Some(invalid_emoji(&arguments.emoji)));
+        };
+        let bot_id = ctx.cache.current_user().id;
+        let role_id = match validate_role(bot_id, &guild, &member, Some(arguments.role)) {
+            Ok(role_id) => role_id,
+            Err(e) => return Ok(Some(CommandResponse::new_content(e).reply())),
+        };
+
+        let message = channel_id
+            .message(ctx.http(), message_id)
+            .await
+            .map_err(|_| "I couldn't find that message.".to_string())?;
+        if let Err(e) = message.react(ctx.http(), emoji.clone()).await {
+            error!("Failed to react with {} on {}: {}", emoji, message_id, e);
+            return Ok(Some(
+                CommandResponse::new_content(
+                    "I couldn't react with that emoji. Make sure I can use it here.",
+                )
+                .reply(),
+            ));
+        }
+
+        let reaction_roles = repo(ctx).await?;
+        let content = {
+            let mut config = reaction_roles.entry(message_id).or_insert_with(|| {
+                ReactionRoleMessage::new(guild.id, channel_id, arguments.mode.unwrap_or_default())
+            });
+            if let Some(mode) = arguments.mode {
+                config.mode = mode;
+            }
+            if let Some(existing) = config
+                .roles
+                .iter_mut()
+                .find(|r| same_emoji(&r.emoji, &emoji))
+            {
+                existing.role = role_id;
+            } else if config.roles.len() >= MAX_REACTION_ROLES {
+                return Ok(Some(
+                    CommandResponse::new_content(format!(
+                        "A message can have at most {} reaction roles.",
+                        MAX_REACTION_ROLES
+                    ))
+                    .reply(),
+                ));
+            } else {
+                config.roles.push(ReactionRole {
+                    emoji: emoji.clone(),
+                    role: role_id,
+                });
+            }
+            format!(
+                "Reacting with {} on {} now gives <@&{}> ({} mode).",
+                emoji,
+                message_link(&config, message_id),
+                role_id,
+                config.mode
+            )
+        };
+        save_reaction_roles(&reaction_roles);
+
+        Ok(Some(CommandResponse::new_content(content).reply()))
+    }
+}
+
+struct Remove;
+
+#[utils::command(
+    name = "remove",
+    description = "Remove a reaction role, or every reaction role of a message",
+    arguments = RemoveArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Remove {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let Some((guild, channel)) = location else {
+            return Err("This command can only be used in a server.".to_string());
+        };
+        let arguments = match RemoveArguments::parse(&format!("{} remove", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let (channel_id, message_id) = match resolve_message(&guild, &channel, &arguments.message) {
+            Ok(reference) => reference,
+            Err(e) => return Ok(Some(CommandResponse::new_content(e).reply())),
+        };
+        let reaction_roles = repo(ctx).await?;
+        let Some(mut config) = reaction_roles
+            .get(&message_id)
+            .filter(|c| c.guild_id == guild.id)
+            .map(|c| c.clone())
+        else {
+            return Ok(Some(
+                CommandResponse::new_content("That message has no reaction roles.").reply(),
+            ));
+        };
+
+        let removed = match arguments.emoji {
+            Some(emoji) => {
+                let Some(emoji) = parse_emoji(&emoji) else {
+                    return Ok(Some(invalid_emoji(&emoji)));
+                };
+                let (removed, kept) = config
+                    .roles
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|r| same_emoji(&r.emoji, &emoji));
+                config.roles = kept;
+                removed
+            }
+            None => std::mem::take(&mut config.roles),
+        };
+        if removed.is_empty() {
+            return Ok(Some(
+                CommandResponse::new_content("That emoji has no reaction role.").reply(),
+            ));
+        }
+
+        for entry in &removed {
+            let bot_id = ctx.cache.current_user().id;
+            let _ = channel_id
+                .delete_reaction(ctx.http(), message_id, Some(bot_id), entry.emoji.clone())
+                .await;
+        }
+        let count = removed.len();
+        if config.roles.is_empty() {
+            reaction_roles.remove(&message_id);
+        } else {
+            reaction_roles.insert(message_id, config);
+        }
+        save_reaction_roles(&reaction_roles);
+
+        Ok(Some(
+            CommandResponse::new_content(format!(
+                "Removed {} reaction role{}.",
+                count,
+                if count == 1 { "" } else { "s" }
+            ))
+            .reply(),
+        ))
+    }
+}
+
+struct Mode;
+
+#[utils::command(
+    name = "mode",
+    description = "Change how the roles of a message behave",
+    arguments = ModeArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Mode {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let Some((guild, channel)) = location else {
+            return Err("This command can only be used in a server.".to_string());
+        };
+        let arguments = match ModeArguments::parse(&format!("{} mode", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let (_, message_id) = match resolve_message(&guild, &channel, &arguments.message) {
+            Ok(reference) => reference,
+            Err(e) => return Ok(Some(CommandResponse::new_content(e).reply())),
+        };
+        let reaction_roles = repo(ctx).await?;
+        {
+            let Some(mut config) = reaction_roles
+                .get_mut(&message_id)
+                .filter(|c| c.guild_id == guild.id)
+            else {
+                return Ok(Some(
+                    CommandResponse::new_content("That message has no reaction roles.").reply(),
+                ));
+            };
+            config.mode = arguments.mode;
+        }
+        save_reaction_roles(&reaction_roles);
+
+        Ok(Some(
+            CommandResponse::new_content(format!(
+                "Reaction roles on that message now use **{}** mode: {}.",
+                arguments.mode,
+                arguments.mode.description()
+            ))
+            .reply(),
+        ))
+    }
+}
+
+struct List;
+
+#[utils::command(
+    name = "list",
+    description = "List the reaction role messages of the server",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for List {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let mut messages = repo(ctx)
+            .await?
+            .iter()
+            .filter(|entry| entry.guild_id == guild.id)
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect::<Vec<_>>();
+        messages.sort_by_key(|(message_id, _)| *message_id);
+        Ok(Some(list_embed(&messages).reply()))
+    }
+}
+
+async fn repo(ctx: &Context) -> Result<Arc<ReactionRolesMap>, String> {
+    let data = ctx.data.read().await;
+    data.get::<ReactionRoles>()
+        .cloned()
+        .ok_or("Failed to get reaction roles.".to_string())
+}
+
+fn invalid_emoji(input: &str) -> CommandResponse {
+    CommandResponse::new_content(format!(
+        "`{}` is not a valid emoji.",
+        truncate(input, MAX_ECHOED_LENGTH)
+    ))
+    .reply()
+}
+
+fn resolve_message(
+    guild: &Guild,
+    channel: &GuildChannel,
+    input: &str,
+) -> Result<(ChannelId, MessageId), String> {
+    let Some((channel_id, message_id)) = parse_message_reference(input, channel.id) else {
+        return Err(format!(
+            "`{}` is not a message link or ID.",
+            truncate(input, MAX_ECHOED_LENGTH)
+        ));
+    };
+    if !guild.channels.contains_key(&channel_id) {
+        return Err("That message is not in this server.".to_string());
+    }
+    Ok((channel_id, message_id))
+}
+
+// Only roles below the highest role of both the bot and the member setting it up can be handed
+// out, so nobody can give away roles they couldn't manage themselves.
+fn validate_role(
+    bot_id: UserId,
+    guild: &Guild,
+    member: &Member,
+    role: Option<RoleId>,
+) -> Result<RoleId, String> {
+    let Some(role) = role.and_then(|id| guild.roles.get(&id)) else {
+        return Err("That role is not in this server.".to_string());
+    };
+    if role.id.get() == guild.id.get() || role.managed {
+        return Err("That role can't be given to members.".to_string());
+    }
+    let bot_position = guild
+        .members
+        .get(&bot_id)
+        .map(|bot| highest_position(guild, bot))
+        .unwrap_or_default();
+    if role.position >= bot_position {
+        return Err(format!(
+            "<@&{}> is higher than my highest role, so I can't give it out.",
+            role.id
+        ));
+    }
+    if member.user.id != guild.owner_id && role.position >= highest_position(guild, member) {
+        return Err(format!(
+            "<@&{}> is not below your highest role, so you can't give it out.",
+            role.id
+        ));
+    }
+    Ok(role.id)
+}
+
+fn highest_position(guild: &Guild, member: &Member) -> u16 {
+    member
+        .roles
+        .iter()
+        .filter_map(|id| guild.roles.get(id))
+        .map(|r| r.position)
+        .max()
+        .unwrap_or_default()
+}
+
+fn list_embed(messages: &[(MessageId, ReactionRoleMessage)]) -> CommandResponse {
+    let mut embed = CreateEmbed::default()
+        .title("🎭 Reaction Roles")
+        .color(Colour::GOLD);
+
+    if messages.is_empty() {
+        embed = embed.description("This server has no reaction roles.");
+    }
+    for (message_id, config) in messages.iter().take(25) {
+        let roles = config
+            .roles
+            .iter()
+            .map(|r| format!("{} → <@&{}>", r.emoji, r.role))
+            .collect::<Vec<_>>()
+            .join("\n");
+        embed = embed.field(
+            format!("{} mode", config.mode),
+            format!("{}\n{}", message_link(config, *message_id), roles),
+            false,
+        );
+    }
+    CommandResponse::new_embeds(vec![embed])
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::all::{GuildId, Role};
+
+    use super::*;
+
+    const GUILD: u64 = 1;
+    const BOT: u64 = 2;
+    const OWNER: u64 = 3;
+    const MODERATOR: u64 = 4;
+
+    fn role(id: u64, position: u16) -> Role {
+        let mut role = Role::default();
+        role.id = RoleId::new(id);
+        role.position = position;
+        role
+    }
+
+    fn member(id: u64, roles: &[u64]) -> Member {
+        let mut member = Member::default();
+        member.user.id = UserId::new(id);
+        member.roles = roles.iter().map(|r| RoleId::new(*r)).collect();
+        member
+    }
+
+    // The bot's highest role is at 10 and the moderator's at 5.
+    fn guild() -> Guild {
+        let mut guild = Guild::default();
+        guild.id = GuildId::new(GUILD);
+        guild.owner_id = UserId::new(OWNER);
+        let mut managed = role(13, 1);
+        managed.managed = true;
+        for role in [
+            role(GUILD, 0),
+            role(10, 10),
+            role(11, 5),
+            role(12, 3),
+            role(14, 7),
+            managed,
+        ] {
+            guild.roles.insert(role.id, role);
+        }
+        for member in [member(BOT, &[10]), member(MODERATOR, &[11])] {
+            guild.members.insert(member.user.id, member);
+        }
+        guild
+    }
+
+    fn validate(user: u64, role: u64) -> Result<RoleId, String> {
+        let guild = guild();
+        let member = guild
+            .members
+            .get(&UserId::new(user))
+            .cloned()
+            .unwrap_or_else(|| member(user, &[]));
+        validate_role(UserId::new(BOT), &guild, &member, Some(RoleId::new(role)))
+    }
+
+    #[test]
+    fn gives_roles_below_the_bot_and_the_member() {
+        assert_eq!(validate(MODERATOR, 12), Ok(RoleId::new(12)));
+        // The owner can give any role the bot can.
+        assert_eq!(validate(OWNER, 14), Ok(RoleId::new(14)));
+    }
+
+    #[test]
+    fn rejects_roles_members_cant_manage() {
+        assert!(
+            validate(MODERATOR, 11)
+                .unwrap_err()
+                .contains("not below your highest role")
+        );
+        assert!(
+            validate(MODERATOR, 14)
+                .unwrap_err()
+                .contains("not below your highest role")
+        );
+        assert!(
+            validate(OWNER, 10)
+                .unwrap_err()
+                .contains("higher than my highest role")
+        );
+    }
+
+    #[test]
+    fn rejects_roles_that_cant_be_given() {
+        assert!(
+            validate(OWNER, GUILD)
+                .unwrap_err()
+                .contains("can't be given")
+        );
+        assert!(validate(OWNER, 13).unwrap_err().contains("can't be given"));
+        assert!(
+            validate(OWNER, 99)
+                .unwrap_err()
+                .contains("not in this server")
+        );
+        let guild = guild();
+        assert!(validate_role(UserId::new(BOT), &guild, &member(OWNER, &[]), None).is_err());
+    }
+
+    #[test]
+    fn truncates_echoed_message_references() {
+        let guild = guild();
+        let channel = GuildChannel::default();
+        let error = resolve_message(&guild, &channel, &"x".repeat(5000)).unwrap_err();
+        assert!(error.chars().count() < 200);
+    }
+}
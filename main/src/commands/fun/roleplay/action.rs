@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{
+        Colour, CommandOptionType, CommandType, Context, CreateCommandOption, CreateEmbed,
+        CreateEmbedFooter, Guild, GuildChannel,
+    },
+    async_trait,
+};
+
+use utils::{CommandArguments, CommandResponse, CommandTemplate, CommandTrait, ICommand, UserType};
+
+use crate::{
+    RoleplayAction,
+    commands::command_member_target,
+    roleplay::{fetch_media, record_interaction},
+};
+
+pub struct Command {
+    action: RoleplayAction,
+}
+
+// One command per action, named at runtime, so this isn't declared with `utils::command`.
+pub fn command(action: RoleplayAction) -> CommandTemplate {
+    let user_option = CreateCommandOption::new(
+        CommandOptionType::User,
+        "user",
+        format!("The user to {}", action.name()),
+    )
+    .required(true);
+
+    (
+        ICommand::new(
+            action.name().to_string(),
+            format!("Roleplay: {} someone", action.name()),
+            CommandType::ChatInput,
+            vec![user_option],
+            vec![],
+        ),
+        Arc::new(Command { action }),
+    )
+}
+
+#[async_trait]
+impl CommandTrait for Command {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let Some((guild, _)) = location else {
+            return Err("This command can only be used in a server.".to_string());
+        };
+        let UserType::Member(author) = user else {
+            return Err("This command can only be used by a guild member".into());
+        };
+        let Some(target) = command_member_target(ctx, &args, &guild).await else {
+            return Ok(Some(
+                CommandResponse::new_content(format!(
+                    "Mention someone to {}, e.g. `{} @user`.",
+                    self.action.name(),
+                    self.action.name()
+                ))
+                .reply(),
+            ));
+        };
+        if target.user.id == author.user.id {
+            return Ok(Some(
+                CommandResponse::new_content(format!(
+                    "You can't {} yourself... but here's a virtual one anyway. 💞",
+                    self.action.name()
+                ))
+                .reply(),
+            ));
+        }
+
+        let media = fetch_media(ctx, self.action).await;
+        let (given, received) =
+            record_interaction(ctx, self.action, author.user.id, target.user.id).await;
+
+        let mut footer = format!(
+            "{} has {} {} {} time{}",
+            author.display_name(),
+            self.action.past(),
+            target.display_name(),
+            given,
+            if given == 1 { "" } else { "s" }
+        );
+        if received > 0 {
+            footer.push_str(&format!(
+                " · {} has {} them back {} time{}",
+                target.display_name(),
+                self.action.past(),
+                received,
+                if received == 1 { "" } else { "s" }
+            ));
+        }
+
+        let mut embed = CreateEmbed::default()
+            .description(format!(
+                "**{}** {} **{}**!",
+                author.display_name(),
+                self.action.verb(),
+                target.display_name()
+            ))
+            .footer(CreateEmbedFooter::new(footer))
+            .color(Colour::FABLED_PINK);
+        if let Some(url) = media {
+            embed = embed.image(url);
+        }
+
+        Ok(Some(CommandResponse::new_embeds(vec![embed]).reply()))
+    }
+    fn is_legacy(&self) -> bool {
+        true
+    }
+    fn is_slash(&self) -> bool {
+        true
+    }
+}
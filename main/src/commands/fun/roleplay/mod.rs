@@ -0,0 +1,10 @@
+use crate::RoleplayAction;
+
+mod action;
+
+pub fn get_commands() -> Vec<utils::CommandTemplate> {
+    RoleplayAction::ALL
+        .into_iter()
+        .map(action::command)
+        .collect()
+}
@@ -1,5 +1,12 @@
+mod reddit;
+mod twitch;
+mod youtube;
+
 pub fn get_modules() -> Vec<utils::CommandTemplate> {
     let mut modules = vec![];
+    modules.extend(reddit::get_commands());
+    modules.extend(twitch::get_commands());
+    modules.extend(youtube::get_commands());
 
     modules
 }
@@ -0,0 +1,359 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{ChannelId, ChannelType, Colour, CommandType, Context, CreateEmbed, Guild, GuildChannel},
+    async_trait,
+    prelude::TypeMap,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, BotPermission, CommandArguments, CommandResponse,
+    CommandTemplate, CommandTrait, ICommand, ParsedArguments, UserType, truncate,
+};
+
+use crate::{
+    MAX_FLAIR_LENGTH, MAX_REDDIT_SUBSCRIPTIONS, MAX_SUBREDDIT_LENGTH, RedditFeed, RedditSeenPosts,
+    RedditSubscription, RedditSubscriptions, RedditSubscriptionsMap, commands::require_guild,
+    reddit::is_valid_subreddit, save_reddit_seen, save_reddit_subscriptions,
+};
+
+const COMMAND_NAME: &str = "reddit";
+const COMMAND_DESCRIPTION: &str = "Post new subreddit posts to a channel.";
+const MAX_MIN_SCORE: i64 = 1_000_000;
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![BotPermission::ManageGuild],
+    )
+    .subcommand(Add::node())
+    .subcommand(Remove::node())
+    .subcommand(List::node())
+    .default("list")
+    .routed()
+}
+
+fn subreddit(parsed: &ParsedArguments) -> Result<String, String> {
+    parsed
+        .get::<String>("subreddit")
+        .map(|s| normalize_subreddit(&s))
+        .ok_or("`subreddit` is required.".to_string())
+}
+
+struct AddArguments {
+    subscription: RedditSubscription,
+}
+
+impl Arguments for AddArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(
+                Argument::string("subreddit", "The subreddit, without r/")
+                    .max_length(MAX_SUBREDDIT_LENGTH as u16)
+                    .required(),
+            )
+            .argument(
+                Argument::channel("channel", "The channel to post in")
+                    .channel_types(vec![ChannelType::Text, ChannelType::News])
+                    .required(),
+            )
+            .argument(Argument::boolean(
+                "nsfw",
+                "Allow NSFW posts (only in age-restricted channels)",
+            ))
+            .argument(
+                Argument::integer(
+                    "min_score",
+                    "Only post submissions with at least this score",
+                )
+                .range(0, MAX_MIN_SCORE),
+            )
+            .argument(
+                Argument::string("flair", "Only post submissions with this flair")
+                    .max_length(MAX_FLAIR_LENGTH as u16)
+                    .rest(),
+            )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            subscription: RedditSubscription {
+                subreddit: subreddit(&parsed)?,
+                channel: parsed
+                    .get("channel")
+                    .ok_or("`channel` is required.".to_string())?,
+                flair: parsed.get("flair"),
+                allow_nsfw: parsed.get("nsfw").unwrap_or(false),
+                min_score: parsed.get("min_score").unwrap_or(0),
+            },
+        })
+    }
+}
+
+struct RemoveArguments {
+    subreddit: String,
+    channel: Option<ChannelId>,
+}
+
+impl Arguments for RemoveArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(
+                Argument::string("subreddit", "The subreddit")
+                    .max_length(MAX_SUBREDDIT_LENGTH as u16)
+                    .required(),
+            )
+            .argument(
+                Argument::channel("channel", "Only remove it from this channel")
+                    .channel_types(vec![ChannelType::Text, ChannelType::News]),
+            )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            subreddit: subreddit(&parsed)?,
+            channel: parsed.get("channel"),
+        })
+    }
+}
+
+struct Add;
+
+#[utils::command(
+    name = "add",
+    description = "Follow a subreddit in a channel",
+    arguments = AddArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Add {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let subscription = match AddArguments::parse(&format!("{} add", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments.subscription,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        if !is_valid_subreddit(&subscription.subreddit) {
+            return Ok(Some(invalid_subreddit(&subscription.subreddit)));
+        }
+        if !guild.channels.contains_key(&subscription.channel) {
+            return Ok(Some(
+                CommandResponse::new_content("That channel is not in this server.").reply(),
+            ));
+        }
+        let (subscriptions, provider) = {
+            let data = ctx.data.read().await;
+            (
+                repo(&data)?,
+                data.get::<RedditFeed>()
+                    .cloned()
+                    .ok_or("Failed to get reddit feed.".to_string())?,
+            )
+        };
+        let existing = subscriptions
+            .get(&guild.id)
+            .map(|s| s.clone())
+            .unwrap_or_default();
+        let replaces = existing.iter().any(|s| s.key() == subscription.key());
+        if !replaces && existing.len() >= MAX_REDDIT_SUBSCRIPTIONS {
+            return Ok(Some(
+                CommandResponse::new_content(format!(
+                    "This server already follows the maximum of {} subreddits.",
+                    MAX_REDDIT_SUBSCRIPTIONS
+                ))
+                .reply(),
+            ));
+        }
+        if let Err(e) = provider.fetch_new(&subscription.subreddit).await {
+            return Ok(Some(CommandResponse::new_content(e).reply()));
+        }
+
+        let content = format!(
+            "New posts from **r/{}** will be posted in <#{}>{}.",
+            subscription.subreddit,
+            subscription.channel,
+            filters_str(&subscription)
+                .map(|f| format!(" ({})", f))
+                .unwrap_or_default()
+        );
+        {
+            let mut guild_subscriptions = subscriptions.entry(guild.id).or_default();
+            guild_subscriptions.retain(|s| s.key() != subscription.key());
+            guild_subscriptions.push(subscription);
+        }
+        save_reddit_subscriptions(&subscriptions);
+
+        Ok(Some(CommandResponse::new_content(content).reply()))
+    }
+}
+
+struct Remove;
+
+#[utils::command(
+    name = "remove",
+    description = "Stop following a subreddit",
+    arguments = RemoveArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Remove {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let RemoveArguments { subreddit, channel } =
+            match RemoveArguments::parse(&format!("{} remove", COMMAND_NAME), &args) {
+                Ok(arguments) => arguments,
+                Err(e) => return Ok(Some(e.response())),
+            };
+
+        if !is_valid_subreddit(&subreddit) {
+            return Ok(Some(invalid_subreddit(&subreddit)));
+        }
+        let (subscriptions, seen) = {
+            let data = ctx.data.read().await;
+            (
+                repo(&data)?,
+                data.get::<RedditSeenPosts>()
+                    .cloned()
+                    .ok_or("Failed to get reddit posts.".to_string())?,
+            )
+        };
+        let removed = {
+            let mut guild_subscriptions = subscriptions.entry(guild.id).or_default();
+            let (removed, kept) = guild_subscriptions.drain(..).partition::<Vec<_>, _>(|s| {
+                s.subreddit.eq_ignore_ascii_case(&subreddit)
+                    && channel.is_none_or(|c| c == s.channel)
+            });
+            *guild_subscriptions = kept;
+            removed
+        };
+        if removed.is_empty() {
+            return Ok(Some(
+                CommandResponse::new_content(format!(
+                    "This server doesn't follow r/{}.",
+                    subreddit
+                ))
+                .reply(),
+            ));
+        }
+        for subscription in &removed {
+            seen.remove(&subscription.key());
+        }
+        save_reddit_seen(&seen);
+        save_reddit_subscriptions(&subscriptions);
+
+        Ok(Some(
+            CommandResponse::new_content(format!("Stopped following r/{}.", subreddit)).reply(),
+        ))
+    }
+}
+
+struct List;
+
+#[utils::command(
+    name = "list",
+    description = "List the followed subreddits",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for List {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let subscriptions = repo(&*ctx.data.read().await)?;
+        let list = subscriptions
+            .get(&guild.id)
+            .map(|s| s.clone())
+            .unwrap_or_default();
+        Ok(Some(list_embed(&list).reply()))
+    }
+}
+
+fn repo(data: &TypeMap) -> Result<Arc<RedditSubscriptionsMap>, String> {
+    data.get::<RedditSubscriptions>()
+        .cloned()
+        .ok_or("Failed to get reddit subscriptions.".to_string())
+}
+
+fn invalid_subreddit(name: &str) -> CommandResponse {
+    CommandResponse::new_content(format!(
+        "`{}` is not a valid subreddit name.",
+        truncate(name, MAX_SUBREDDIT_LENGTH)
+    ))
+    .reply()
+}
+
+fn normalize_subreddit(input: &str) -> String {
+    input
+        .trim()
+        .trim_start_matches('/')
+        .trim_start_matches("r/")
+        .to_string()
+}
+
+fn filters_str(subscription: &RedditSubscription) -> Option<String> {
+    let mut filters = vec![];
+    if let Some(flair) = &subscription.flair {
+        filters.push(format!("flair: {}", flair));
+    }
+    if subscription.min_score > 0 {
+        filters.push(format!("min score: {}", subscription.min_score));
+    }
+    if subscription.allow_nsfw {
+        filters.push("NSFW allowed".to_string());
+    }
+    (!filters.is_empty()).then(|| filters.join(", "))
+}
+
+fn list_embed(subscriptions: &[RedditSubscription]) -> CommandResponse {
+    let description = if subscriptions.is_empty() {
+        "This server doesn't follow any subreddits.".to_string()
+    } else {
+        subscriptions
+            .iter()
+            .map(|s| {
+                format!(
+                    "**r/{}** → <#{}>{}",
+                    s.subreddit,
+                    s.channel,
+                    filters_str(s)
+                        .map(|f| format!(" · {}", f))
+                        .unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = CreateEmbed::default()
+        .title("📰 Reddit Feeds")
+        .description(description)
+        .color(Colour::new(0xFF4500));
+    CommandResponse::new_embeds(vec![embed])
+}
@@ -0,0 +1,5 @@
+mod feed;
+
+pub fn get_commands() -> Vec<utils::CommandTemplate> {
+    vec![feed::command()]
+}
@@ -0,0 +1,5 @@
+mod notify;
+
+pub fn get_commands() -> Vec<utils::CommandTemplate> {
+    vec![notify::command()]
+}
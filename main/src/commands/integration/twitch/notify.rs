@@ -0,0 +1,349 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{
+        ChannelId, ChannelType, Colour, CommandType, Context, CreateEmbed, Guild, GuildChannel,
+        RoleId,
+    },
+    async_trait,
+    prelude::TypeMap,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, BotPermission, CommandArguments, CommandResponse,
+    CommandTemplate, CommandTrait, ICommand, ParsedArguments, UserType,
+};
+
+use crate::{
+    MAX_TWITCH_SUBSCRIPTIONS, Twitch, TwitchSubscription, TwitchSubscriptions,
+    TwitchSubscriptionsMap, commands::require_guild, save_twitch_subscriptions, twitch::stream_url,
+};
+
+const COMMAND_NAME: &str = "twitch";
+const COMMAND_DESCRIPTION: &str = "Announce when Twitch streamers go live.";
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![BotPermission::ManageGuild],
+    )
+    .subcommand(Add::node())
+    .subcommand(Remove::node())
+    .subcommand(List::node())
+    .default("list")
+    .routed()
+}
+
+fn login(parsed: &ParsedArguments) -> Result<String, String> {
+    parsed
+        .get::<String>("streamer")
+        .map(|s| normalize_login(&s))
+        .ok_or("`streamer` is required.".to_string())
+}
+
+struct AddArguments {
+    login: String,
+    channel: ChannelId,
+    role: Option<RoleId>,
+    template: Option<String>,
+}
+
+impl Arguments for AddArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(
+                Argument::string("streamer", "The Twitch username")
+                    .max_length(25)
+                    .required(),
+            )
+            .argument(
+                Argument::channel("channel", "The channel to announce in")
+                    .channel_types(vec![ChannelType::Text, ChannelType::News])
+                    .required(),
+            )
+            .argument(Argument::role(
+                "role",
+                "A role to ping when the stream starts",
+            ))
+            .argument(
+                Argument::string(
+                    "message",
+                    "The announcement, supports {twitch.name}, {twitch.title}, {twitch.game} and {twitch.url}",
+                )
+                .max_length(1000)
+                .rest(),
+            )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            login: login(&parsed)?,
+            channel: parsed
+                .get("channel")
+                .ok_or("`channel` is required.".to_string())?,
+            role: parsed.get("role"),
+            template: parsed.get("message"),
+        })
+    }
+}
+
+struct RemoveArguments {
+    login: String,
+}
+
+impl Arguments for RemoveArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(Argument::string("streamer", "The Twitch username").required())
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            login: login(&parsed)?,
+        })
+    }
+}
+
+struct Add;
+
+#[utils::command(
+    name = "add",
+    description = "Announce a streamer in a channel",
+    arguments = AddArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Add {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let new = match AddArguments::parse(&format!("{} add", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let (subscriptions, twitch) = {
+            let data = ctx.data.read().await;
+            (repo(&data)?, data.get::<Twitch>().cloned())
+        };
+        let Some(twitch) = twitch else {
+            return Ok(Some(not_configured()));
+        };
+        if !guild.channels.contains_key(&new.channel) {
+            return Ok(Some(
+                CommandResponse::new_content("That channel is not in this server.").reply(),
+            ));
+        }
+        if let Some(role) = new.role
+            && !guild.roles.contains_key(&role)
+        {
+            return Ok(Some(
+                CommandResponse::new_content("That role is not in this server.").reply(),
+            ));
+        }
+        let existing = subscriptions
+            .get(&guild.id)
+            .map(|s| s.clone())
+            .unwrap_or_default();
+        let replaces = existing
+            .iter()
+            .any(|s| s.login.eq_ignore_ascii_case(&new.login));
+        if !replaces && existing.len() >= MAX_TWITCH_SUBSCRIPTIONS {
+            return Ok(Some(
+                CommandResponse::new_content(format!(
+                    "This server already follows the maximum of {} streamers.",
+                    MAX_TWITCH_SUBSCRIPTIONS
+                ))
+                .reply(),
+            ));
+        }
+
+        let user = match twitch.api.user_by_login(&new.login).await {
+            Ok(Some(user)) => user,
+            Ok(None) => {
+                return Ok(Some(
+                    CommandResponse::new_content(format!(
+                        "There is no Twitch user called `{}`.",
+                        new.login
+                    ))
+                    .reply(),
+                ));
+            }
+            Err(e) => return Ok(Some(CommandResponse::new_content(e).reply())),
+        };
+        if !is_watched(&subscriptions, &user.id)
+            && let Err(e) = twitch.watcher.watch(&user.id, &user.login).await
+        {
+            return Ok(Some(CommandResponse::new_content(e).reply()));
+        }
+
+        let content = format!(
+            "**{}** will be announced in <#{}> when they go live.",
+            user.display_name, new.channel
+        );
+        {
+            let mut guild_subscriptions = subscriptions.entry(guild.id).or_default();
+            guild_subscriptions.retain(|s| s.user_id != user.id);
+            guild_subscriptions.push(TwitchSubscription {
+                user_id: user.id,
+                login: user.login,
+                channel: new.channel,
+                role: new.role,
+                template: new.template,
+            });
+        }
+        save_twitch_subscriptions(&subscriptions);
+
+        Ok(Some(CommandResponse::new_content(content).reply()))
+    }
+}
+
+struct Remove;
+
+#[utils::command(
+    name = "remove",
+    description = "Stop announcing a streamer",
+    arguments = RemoveArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Remove {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let login = match RemoveArguments::parse(&format!("{} remove", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments.login,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let (subscriptions, twitch) = {
+            let data = ctx.data.read().await;
+            (repo(&data)?, data.get::<Twitch>().cloned())
+        };
+        let removed = {
+            let mut guild_subscriptions = subscriptions.entry(guild.id).or_default();
+            let index = guild_subscriptions
+                .iter()
+                .position(|s| s.login.eq_ignore_ascii_case(&login));
+            index.map(|i| guild_subscriptions.remove(i))
+        };
+        let Some(removed) = removed else {
+            return Ok(Some(
+                CommandResponse::new_content(format!("This server doesn't follow `{}`.", login))
+                    .reply(),
+            ));
+        };
+        // The watcher is only stopped once no server follows the streamer anymore.
+        if let Some(twitch) = twitch
+            && !is_watched(&subscriptions, &removed.user_id)
+            && let Err(e) = twitch.watcher.unwatch(&removed.user_id).await
+        {
+            utils::error!("Failed to unwatch {}: {}", removed.login, e);
+        }
+        save_twitch_subscriptions(&subscriptions);
+
+        Ok(Some(
+            CommandResponse::new_content(format!("Stopped announcing `{}`.", removed.login))
+                .reply(),
+        ))
+    }
+}
+
+struct List;
+
+#[utils::command(
+    name = "list",
+    description = "List the announced streamers",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for List {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let subscriptions = repo(&*ctx.data.read().await)?;
+        let list = subscriptions
+            .get(&guild.id)
+            .map(|s| s.clone())
+            .unwrap_or_default();
+        Ok(Some(list_embed(&list).reply()))
+    }
+}
+
+fn repo(data: &TypeMap) -> Result<Arc<TwitchSubscriptionsMap>, String> {
+    data.get::<TwitchSubscriptions>()
+        .cloned()
+        .ok_or("Failed to get twitch subscriptions.".to_string())
+}
+
+fn not_configured() -> CommandResponse {
+    CommandResponse::new_content("Twitch notifications are not configured on this bot.").reply()
+}
+
+fn normalize_login(input: &str) -> String {
+    input
+        .trim()
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+fn is_watched(subscriptions: &TwitchSubscriptionsMap, user_id: &str) -> bool {
+    subscriptions
+        .iter()
+        .any(|entry| entry.value().iter().any(|s| s.user_id == user_id))
+}
+
+fn list_embed(subscriptions: &[TwitchSubscription]) -> CommandResponse {
+    let description = if subscriptions.is_empty() {
+        "This server doesn't follow any streamers.".to_string()
+    } else {
+        subscriptions
+            .iter()
+            .map(|s| {
+                format!(
+                    "[**{}**]({}) → <#{}>{}",
+                    s.login,
+                    stream_url(&s.login),
+                    s.channel,
+                    s.role
+                        .map(|r| format!(" · pings <@&{}>", r))
+                        .unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = CreateEmbed::default()
+        .title("📺 Twitch Notifications")
+        .description(description)
+        .color(Colour::new(0x9146FF));
+    CommandResponse::new_embeds(vec![embed])
+}
@@ -0,0 +1,5 @@
+mod uploads;
+
+pub fn get_commands() -> Vec<utils::CommandTemplate> {
+    vec![uploads::command()]
+}
@@ -0,0 +1,398 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{
+        ChannelId, ChannelType, Colour, CommandType, Context, CreateEmbed, Guild, GuildChannel,
+        RoleId,
+    },
+    async_trait,
+    prelude::TypeMap,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, BotPermission, CommandArguments, CommandResponse,
+    CommandTemplate, CommandTrait, ICommand, ParsedArguments, UserType,
+};
+
+use crate::{
+    MAX_YOUTUBE_SUBSCRIPTIONS, VideoKind, YoutubeFeed, YoutubeSeenVideos, YoutubeSubscription,
+    YoutubeSubscriptions, YoutubeSubscriptionsMap, commands::require_guild, save_youtube_seen,
+    save_youtube_subscriptions,
+};
+
+const COMMAND_NAME: &str = "youtube";
+const COMMAND_DESCRIPTION: &str = "Announce new YouTube videos in a channel.";
+const KINDS: [&str; 4] = [
+    "uploads,shorts,premieres",
+    "uploads",
+    "shorts",
+    "uploads,premieres",
+];
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![BotPermission::ManageGuild],
+    )
+    .subcommand(Add::node())
+    .subcommand(Remove::node())
+    .subcommand(List::node())
+    .default("list")
+    .routed()
+}
+
+struct AddArguments {
+    youtube: String,
+    channel: ChannelId,
+    role: Option<RoleId>,
+    template: Option<String>,
+    kinds: Option<Vec<VideoKind>>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl Arguments for AddArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(Argument::string("youtube", "The channel ID, URL or @handle").required())
+            .argument(
+                Argument::channel("channel", "The channel to announce in")
+                    .channel_types(vec![ChannelType::Text, ChannelType::News])
+                    .required(),
+            )
+            .argument(Argument::role("role", "A role to ping for new videos"))
+            .argument(Argument::choice("types", "Which videos to announce", KINDS.to_vec()).named())
+            .argument(
+                Argument::string(
+                    "include",
+                    "Only announce titles containing one of these comma-separated words",
+                )
+                .named(),
+            )
+            .argument(
+                Argument::string(
+                    "exclude",
+                    "Skip titles containing any of these comma-separated words",
+                )
+                .named(),
+            )
+            .argument(
+                Argument::string(
+                    "message",
+                    "The announcement, supports {youtube.channel}, {youtube.title}, {youtube.url} and {youtube.action}",
+                )
+                .max_length(1000)
+                .rest(),
+            )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        let words = |name: &str| {
+            parsed
+                .get::<String>(name)
+                .map(|t| parse_words(&t))
+                .unwrap_or_default()
+        };
+        Ok(Self {
+            include: words("include"),
+            exclude: words("exclude"),
+            youtube: parsed
+                .get("youtube")
+                .ok_or("`youtube` is required.".to_string())?,
+            channel: parsed
+                .get("channel")
+                .ok_or("`channel` is required.".to_string())?,
+            role: parsed.get("role"),
+            template: parsed.get("message"),
+            kinds: parsed.get::<String>("types").and_then(|t| parse_kinds(&t)),
+        })
+    }
+}
+
+struct RemoveArguments {
+    youtube: String,
+}
+
+impl Arguments for RemoveArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::string("youtube", "The channel ID or name")
+                .required()
+                .rest(),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            youtube: parsed
+                .get("youtube")
+                .ok_or("`youtube` is required.".to_string())?,
+        })
+    }
+}
+
+struct Add;
+
+#[utils::command(
+    name = "add",
+    description = "Announce a YouTube channel's videos",
+    arguments = AddArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Add {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let new = match AddArguments::parse(&format!("{} add", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        if !guild.channels.contains_key(&new.channel) {
+            return Ok(Some(
+                CommandResponse::new_content("That channel is not in this server.").reply(),
+            ));
+        }
+        if let Some(role) = new.role
+            && !guild.roles.contains_key(&role)
+        {
+            return Ok(Some(
+                CommandResponse::new_content("That role is not in this server.").reply(),
+            ));
+        }
+        let (subscriptions, provider) = {
+            let data = ctx.data.read().await;
+            (
+                repo(&data)?,
+                data.get::<YoutubeFeed>()
+                    .cloned()
+                    .ok_or("Failed to get youtube feed.".to_string())?,
+            )
+        };
+        let channel_id = match provider.resolve_channel(&new.youtube).await {
+            Ok(channel_id) => channel_id,
+            Err(e) => return Ok(Some(CommandResponse::new_content(e).reply())),
+        };
+        let name = match provider.fetch_feed(&channel_id).await {
+            Ok((name, _)) if !name.is_empty() => name,
+            Ok(_) => channel_id.clone(),
+            Err(e) => return Ok(Some(CommandResponse::new_content(e).reply())),
+        };
+
+        let existing = subscriptions
+            .get(&guild.id)
+            .map(|s| s.clone())
+            .unwrap_or_default();
+        let mut subscription = YoutubeSubscription::new(channel_id, name, new.channel);
+        subscription.role = new.role;
+        subscription.template = new.template;
+        subscription.include = new.include;
+        subscription.exclude = new.exclude;
+        if let Some(kinds) = new.kinds {
+            subscription.kinds = kinds;
+        }
+        let replaces = existing.iter().any(|s| s.key() == subscription.key());
+        if !replaces && existing.len() >= MAX_YOUTUBE_SUBSCRIPTIONS {
+            return Ok(Some(
+                CommandResponse::new_content(format!(
+                    "This server already follows the maximum of {} YouTube channels.",
+                    MAX_YOUTUBE_SUBSCRIPTIONS
+                ))
+                .reply(),
+            ));
+        }
+
+        let content = format!(
+            "New {} from **{}** will be announced in <#{}>{}.",
+            kinds_str(&subscription.kinds),
+            subscription.name,
+            subscription.channel,
+            filters_str(&subscription)
+                .map(|f| format!(" ({})", f))
+                .unwrap_or_default()
+        );
+        {
+            let mut guild_subscriptions = subscriptions.entry(guild.id).or_default();
+            guild_subscriptions.retain(|s| s.key() != subscription.key());
+            guild_subscriptions.push(subscription);
+        }
+        save_youtube_subscriptions(&subscriptions);
+
+        Ok(Some(CommandResponse::new_content(content).reply()))
+    }
+}
+
+struct Remove;
+
+#[utils::command(
+    name = "remove",
+    description = "Stop announcing a YouTube channel",
+    arguments = RemoveArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Remove {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match RemoveArguments::parse(&format!("{} remove", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let (subscriptions, seen) = {
+            let data = ctx.data.read().await;
+            (
+                repo(&data)?,
+                data.get::<YoutubeSeenVideos>()
+                    .cloned()
+                    .ok_or("Failed to get youtube videos.".to_string())?,
+            )
+        };
+        let query = arguments.youtube.trim().trim_start_matches('@');
+        let removed = {
+            let mut guild_subscriptions = subscriptions.entry(guild.id).or_default();
+            let (removed, kept) = guild_subscriptions.drain(..).partition::<Vec<_>, _>(|s| {
+                s.channel_id == query || s.name.eq_ignore_ascii_case(query)
+            });
+            *guild_subscriptions = kept;
+            removed
+        };
+        let Some(first) = removed.first() else {
+            return Ok(Some(
+                CommandResponse::new_content(format!("This server doesn't follow `{}`.", query))
+                    .reply(),
+            ));
+        };
+        let content = format!("Stopped announcing **{}**.", first.name);
+        for subscription in &removed {
+            seen.remove(&subscription.key());
+        }
+        save_youtube_seen(&seen);
+        save_youtube_subscriptions(&subscriptions);
+
+        Ok(Some(CommandResponse::new_content(content).reply()))
+    }
+}
+
+struct List;
+
+#[utils::command(
+    name = "list",
+    description = "List the announced YouTube channels",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for List {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let subscriptions = repo(&*ctx.data.read().await)?;
+        let list = subscriptions
+            .get(&guild.id)
+            .map(|s| s.clone())
+            .unwrap_or_default();
+        Ok(Some(list_embed(&list).reply()))
+    }
+}
+
+fn repo(data: &TypeMap) -> Result<Arc<YoutubeSubscriptionsMap>, String> {
+    data.get::<YoutubeSubscriptions>()
+        .cloned()
+        .ok_or("Failed to get youtube subscriptions.".to_string())
+}
+
+fn parse_kinds(input: &str) -> Option<Vec<VideoKind>> {
+    let kinds = input
+        .split(',')
+        .filter_map(|k| VideoKind::parse(k.trim()))
+        .collect::<Vec<_>>();
+    (!kinds.is_empty()).then_some(kinds)
+}
+
+fn parse_words(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(|w| w.trim().to_lowercase())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+fn kinds_str(kinds: &[VideoKind]) -> String {
+    kinds
+        .iter()
+        .map(|k| k.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn filters_str(subscription: &YoutubeSubscription) -> Option<String> {
+    let mut filters = vec![];
+    if !subscription.include.is_empty() {
+        filters.push(format!("titles with: {}", subscription.include.join(", ")));
+    }
+    if !subscription.exclude.is_empty() {
+        filters.push(format!(
+            "titles without: {}",
+            subscription.exclude.join(", ")
+        ));
+    }
+    if let Some(role) = subscription.role {
+        filters.push(format!("pings <@&{}>", role));
+    }
+    (!filters.is_empty()).then(|| filters.join(", "))
+}
+
+fn list_embed(subscriptions: &[YoutubeSubscription]) -> CommandResponse {
+    let description = if subscriptions.is_empty() {
+        "This server doesn't follow any YouTube channels.".to_string()
+    } else {
+        subscriptions
+            .iter()
+            .map(|s| {
+                format!(
+                    "**{}** (`{}`) → <#{}> · {}{}",
+                    s.name,
+                    s.channel_id,
+                    s.channel,
+                    kinds_str(&s.kinds),
+                    filters_str(s)
+                        .map(|f| format!(" · {}", f))
+                        .unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = CreateEmbed::default()
+        .title("▶️ YouTube Notifications")
+        .description(description)
+        .color(Colour::new(0xFF0000));
+    CommandResponse::new_embeds(vec![embed])
+}
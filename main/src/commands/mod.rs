@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use serenity::all::{
     CacheHttp, ChannelId, CommandDataOptionValue, Context, Guild, GuildChannel, Http, Member,
-    PartialGuild, User, UserId,
+    MessageId, PartialGuild, ReactionType, User, UserId,
 };
 use utils::{BotPermission, CommandArguments, CommandTrait, LegacyOption, error};
 
@@ -17,6 +17,9 @@ pub mod utilities;
 
 pub type CommandModule = (Arc<dyn CommandTrait>, Vec<BotPermission>);
 
+// Input quoted back in replies is cut to this, long enough for a message link or a custom emoji.
+const MAX_ECHOED_LENGTH: usize = 100;
+
 async fn command_user_target<'a>(ctx: &'a Context, args: &'a CommandArguments<'a>) -> Option<User> {
     let args = args.clone();
     match args {
@@ -70,6 +73,40 @@ async fn command_channel_target<'a>(
     }
 }
 
+// Subcommands of server-only commands each check this.
+fn require_guild(location: Option<(Guild, GuildChannel)>) -> Result<Guild, String> {
+    location
+        .map(|(guild, _)| guild)
+        .ok_or("This command can only be used in a server.".to_string())
+}
+
+fn parse_emoji(word: &str) -> Option<ReactionType> {
+    if word.starts_with('<') {
+        return ReactionType::try_from(word).ok();
+    }
+    // Emojis are often typed without their variation selector, Discord accepts both.
+    let known = emojis::get(word).is_some() || emojis::get(&word.replace('\u{fe0f}', "")).is_some();
+    known.then(|| ReactionType::Unicode(word.to_string()))
+}
+
+// Accepts a message link or a bare message ID, which is looked up in the given channel.
+fn parse_message_reference(word: &str, channel_id: ChannelId) -> Option<(ChannelId, MessageId)> {
+    let parts = word
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .rsplit('/')
+        .take(2)
+        .map(|part| part.parse::<u64>().ok().filter(|id| *id != 0))
+        .collect::<Vec<_>>();
+    match parts.as_slice() {
+        [Some(message_id), Some(channel)] if word.contains("/channels/") => {
+            Some((ChannelId::new(*channel), MessageId::new(*message_id)))
+        }
+        [Some(message_id)] => Some((channel_id, MessageId::new(*message_id))),
+        _ => None,
+    }
+}
+
 async fn user_interaction_option(
     ctx: &Context,
     user_option: Option<CommandDataOptionValue>,
@@ -0,0 +1,6 @@
+mod rank;
+mod stats;
+
+pub fn get_commands() -> Vec<utils::CommandTemplate> {
+    vec![stats::command(), rank::command()]
+}
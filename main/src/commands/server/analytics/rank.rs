@@ -0,0 +1,131 @@
+use chrono::{Duration, Utc};
+use serenity::{
+    all::{
+        CommandOptionType, Context, CreateAttachment, CreateCommandOption, Guild, GuildChannel,
+        User,
+    },
+    async_trait,
+};
+
+use utils::{CommandArguments, CommandResponse, CommandTrait, UserType, error};
+
+use crate::{
+    AnalyticsSummary, DEFAULT_RETENTION_DAYS, ServerAnalytics, commands::command_user_target,
+    render::RankCard,
+};
+
+pub struct Command;
+
+fn options() -> Vec<CreateCommandOption> {
+    let user_option = CreateCommandOption::new(
+        CommandOptionType::User,
+        "user",
+        "The member to show the rank of",
+    );
+
+    vec![user_option]
+}
+
+#[utils::command(
+    name = "rank",
+    description = "Show a member's activity rank in the server.",
+    options = options(),
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Command {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let Some((guild, _)) = location else {
+            return Err("This command can only be used in a server.".to_string());
+        };
+        let target = command_user_target(ctx, &args)
+            .await
+            .unwrap_or_else(|| match user {
+                UserType::Member(m) => m.user.clone(),
+                UserType::User(u) => u.clone(),
+            });
+
+        let (retention, summary) = {
+            let data = ctx.data.read().await;
+            let analytics = data
+                .get::<ServerAnalytics>()
+                .ok_or("Failed to get server analytics.".to_string())?;
+            match analytics.get(&guild.id) {
+                Some(g) => (
+                    g.retention_days,
+                    g.summarize(Utc::now() - Duration::days(g.retention_days as i64)),
+                ),
+                None => (DEFAULT_RETENTION_DAYS, AnalyticsSummary::default()),
+            }
+        };
+
+        // Members are ranked by messages sent within the retention period.
+        let ranking = AnalyticsSummary::top(&summary.users, summary.users.len());
+        let position = ranking.iter().position(|(id, _)| *id == target.id);
+        let messages = position.map(|i| ranking[i].1).unwrap_or(0);
+        let rank = position.unwrap_or(ranking.len()) + 1;
+        let ahead = match position {
+            Some(0) => None,
+            Some(i) => Some(ranking[i - 1].1),
+            None => ranking.last().map(|(_, count)| *count),
+        };
+
+        let name = guild
+            .members
+            .get(&target.id)
+            .map(|m| m.display_name().to_string())
+            .unwrap_or(target.display_name().to_string());
+        let mut card = RankCard::new(name, rank);
+        card.subtitle = format!("@{}", target.name);
+        card.stats = vec![
+            ("MESSAGES".to_string(), messages.to_string()),
+            (
+                "VOICE".to_string(),
+                format!(
+                    "{}m",
+                    summary.voice.get(&target.id).copied().unwrap_or(0) / 60
+                ),
+            ),
+        ];
+        (card.progress, card.progress_label) = match ahead {
+            Some(next) if next > 0 => (
+                messages as f32 / next as f32,
+                format!("{} / {} messages to pass #{}", messages, next, rank - 1),
+            ),
+            _ => (1.0, format!("Top of the last {} days", retention)),
+        };
+        card.avatar = fetch_avatar(&target).await;
+
+        let png = card.render()?;
+        Ok(Some(
+            CommandResponse::new_attachments(vec![CreateAttachment::bytes(png, "rank.png")])
+                .reply(),
+        ))
+    }
+}
+
+// Discord serves avatars as webp by default, which the renderer can't decode.
+async fn fetch_avatar(user: &User) -> Option<Vec<u8>> {
+    let url = user
+        .static_face()
+        .replace(".webp", ".png")
+        .replace("?size=1024", "?size=256");
+    let response = match reqwest::get(&url).await {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Failed to fetch avatar of {}: {}", user.id, e);
+            return None;
+        }
+    };
+    if !response.status().is_success() {
+        return None;
+    }
+    response.bytes().await.ok().map(|bytes| bytes.to_vec())
+}
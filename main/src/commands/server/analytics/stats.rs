@@ -0,0 +1,564 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use serenity::{
+    all::{
+        Colour, CommandType, Context, CreateAttachment, CreateEmbed, CreateEmbedFooter, Guild,
+        GuildChannel,
+    },
+    async_trait,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, BotPermission, CommandArguments, CommandResponse,
+    CommandTemplate, CommandTrait, ICommand, ParsedArguments, UserType,
+};
+
+use crate::{
+    AnalyticsSummary, DEFAULT_RETENTION_DAYS, HOUR, HourBucket, MAX_RETENTION_DAYS,
+    ServerAnalytics, ServerAnalyticsMap,
+    commands::require_guild,
+    render::{Chart, ChartKind},
+    save_analytics,
+};
+
+const COMMAND_NAME: &str = "analytics";
+const COMMAND_DESCRIPTION: &str = "View the server's activity statistics.";
+const DEFAULT_DAYS: i64 = 7;
+const DEFAULT_TOP: usize = 10;
+const MAX_TOP: usize = 25;
+const ANALYTICS_COLOUR: Colour = Colour::new(0x5865F2);
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![BotPermission::ManageGuild],
+    )
+    .subcommand(Summary::node())
+    .subcommand(Top::node())
+    .subcommand(ChartCommand::node())
+    .subcommand(Retention::node())
+    .default("summary")
+    .routed()
+}
+
+#[derive(Clone, Copy)]
+enum Category {
+    Channels,
+    Users,
+    Commands,
+    Voice,
+}
+
+impl Category {
+    fn parse(input: &str) -> Option<Self> {
+        match input.to_lowercase().as_str() {
+            "channels" | "channel" => Some(Category::Channels),
+            "users" | "user" | "members" => Some(Category::Users),
+            "commands" | "command" => Some(Category::Commands),
+            "voice" => Some(Category::Voice),
+            _ => None,
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Category::Channels => "Most active channels",
+            Category::Users => "Most active members",
+            Category::Commands => "Most used commands",
+            Category::Voice => "Most time in voice",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Metric {
+    Messages,
+    Voice,
+    Hours,
+    Commands,
+}
+
+impl Metric {
+    fn parse(input: &str) -> Option<Self> {
+        match input.to_lowercase().as_str() {
+            "messages" | "message" => Some(Metric::Messages),
+            "voice" => Some(Metric::Voice),
+            "hours" | "hour" => Some(Metric::Hours),
+            "commands" | "command" => Some(Metric::Commands),
+            _ => None,
+        }
+    }
+}
+
+fn days_argument() -> Argument {
+    Argument::integer("days", "How many days to look back (default 7)")
+        .range(1, MAX_RETENTION_DAYS as i64)
+}
+
+fn days(parsed: &ParsedArguments) -> i64 {
+    parsed.get("days").unwrap_or(DEFAULT_DAYS)
+}
+
+struct SummaryArguments {
+    days: i64,
+}
+
+impl Arguments for SummaryArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(days_argument())
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            days: days(&parsed),
+        })
+    }
+}
+
+struct TopArguments {
+    category: Category,
+    days: i64,
+    count: usize,
+}
+
+impl Arguments for TopArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(
+                Argument::choice(
+                    "category",
+                    "What to rank",
+                    vec!["channels", "users", "commands", "voice"],
+                )
+                .required(),
+            )
+            .argument(days_argument())
+            .argument(Argument::integer("count", "How many entries").range(1, MAX_TOP as i64))
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            category: parsed
+                .get::<String>("category")
+                .and_then(|c| Category::parse(&c))
+                .ok_or("`category` is required.".to_string())?,
+            days: days(&parsed),
+            count: parsed
+                .get::<i64>("count")
+                .map(|c| c as usize)
+                .unwrap_or(DEFAULT_TOP),
+        })
+    }
+}
+
+struct ChartArguments {
+    metric: Metric,
+    days: i64,
+}
+
+impl Arguments for ChartArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(
+                Argument::choice(
+                    "metric",
+                    "What to chart",
+                    vec!["messages", "voice", "hours", "commands"],
+                )
+                .required(),
+            )
+            .argument(days_argument())
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            metric: parsed
+                .get::<String>("metric")
+                .and_then(|m| Metric::parse(&m))
+                .ok_or("`metric` is required.".to_string())?,
+            days: days(&parsed),
+        })
+    }
+}
+
+struct RetentionArguments {
+    days: Option<u32>,
+}
+
+impl Arguments for RetentionArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(Argument::integer("days", "Days to keep").range(1, MAX_RETENTION_DAYS as i64))
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            days: parsed.get::<i64>("days").map(|d| d as u32),
+        })
+    }
+}
+
+struct Summary;
+
+#[utils::command(
+    name = "summary",
+    description = "Show an overview of the server's activity",
+    arguments = SummaryArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Summary {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match SummaryArguments::parse(&format!("{} summary", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let analytics = repo(ctx).await?;
+        let (days, summary) = summarize(&analytics, &guild, arguments.days);
+        Ok(Some(summary_embed(&guild, days, &summary).reply()))
+    }
+}
+
+struct Top;
+
+#[utils::command(
+    name = "top",
+    description = "Show the most active channels, members or commands",
+    arguments = TopArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Top {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match TopArguments::parse(&format!("{} top", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let analytics = repo(ctx).await?;
+        let (days, summary) = summarize(&analytics, &guild, arguments.days);
+        Ok(Some(
+            top_embed(arguments.category, days, arguments.count, &summary).reply(),
+        ))
+    }
+}
+
+// Named so it doesn't shadow the rendered `Chart`.
+struct ChartCommand;
+
+#[utils::command(
+    name = "chart",
+    description = "Show the server's activity as a chart",
+    arguments = ChartArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for ChartCommand {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match ChartArguments::parse(&format!("{} chart", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let analytics = repo(ctx).await?;
+        let response = chart_response(&analytics, &guild, arguments.metric, arguments.days)?;
+        Ok(Some(response.reply()))
+    }
+}
+
+struct Retention;
+
+#[utils::command(
+    name = "retention",
+    description = "View or change how long statistics are kept",
+    arguments = RetentionArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Retention {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments =
+            match RetentionArguments::parse(&format!("{} retention", COMMAND_NAME), &args) {
+                Ok(arguments) => arguments,
+                Err(e) => return Ok(Some(e.response())),
+            };
+
+        let analytics = repo(ctx).await?;
+        let content = match arguments.days {
+            None => {
+                let days = analytics
+                    .get(&guild.id)
+                    .map(|g| g.retention_days)
+                    .unwrap_or(DEFAULT_RETENTION_DAYS);
+                format!("Statistics are kept for **{}** days.", days)
+            }
+            Some(days) => {
+                {
+                    let mut guild_analytics = analytics.entry(guild.id).or_default();
+                    guild_analytics.retention_days = days;
+                    guild_analytics.prune(Utc::now());
+                }
+                save_analytics(&analytics);
+                format!("Statistics will now be kept for **{}** days.", days)
+            }
+        };
+        Ok(Some(CommandResponse::new_content(content).reply()))
+    }
+}
+
+async fn repo(ctx: &Context) -> Result<Arc<ServerAnalyticsMap>, String> {
+    ctx.data
+        .read()
+        .await
+        .get::<ServerAnalytics>()
+        .cloned()
+        .ok_or("Failed to get server analytics.".to_string())
+}
+
+// The period is capped to the retention, since older buckets no longer exist.
+fn summarize(analytics: &ServerAnalyticsMap, guild: &Guild, days: i64) -> (i64, AnalyticsSummary) {
+    let Some(guild_analytics) = analytics.get(&guild.id) else {
+        return (days.max(1), AnalyticsSummary::default());
+    };
+    let days = days.clamp(1, guild_analytics.retention_days as i64);
+    let summary = guild_analytics.summarize(Utc::now() - Duration::days(days));
+    (days, summary)
+}
+
+fn period_str(days: i64) -> String {
+    if days == 1 {
+        "the last 24 hours".to_string()
+    } else {
+        format!("the last {} days", days)
+    }
+}
+
+fn summary_embed(guild: &Guild, days: i64, summary: &AnalyticsSummary) -> CommandResponse {
+    let mut hours_of_day = [0u64; 24];
+    for (hour, count) in &summary.messages_by_hour {
+        hours_of_day[hour.rem_euclid(24) as usize] += count;
+    }
+    let busiest_hour = hours_of_day
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, count)| **count)
+        .filter(|(_, count)| **count > 0)
+        .map(|(hour, _)| format!("{:02}:00 UTC", hour))
+        .unwrap_or("`n/a`".to_string());
+
+    let top_channels = AnalyticsSummary::top(&summary.channels, 3)
+        .into_iter()
+        .map(|(channel, count)| format!("<#{}> · {}", channel, count))
+        .collect::<Vec<_>>();
+    let net = summary.joins as i64 - summary.leaves as i64;
+
+    let embed = CreateEmbed::default()
+        .title(format!("📊 {} activity", guild.name))
+        .description(format!("Statistics for {}.", period_str(days)))
+        .field("Messages", summary.messages.to_string(), true)
+        .field("Active members", summary.users.len().to_string(), true)
+        .field(
+            "Commands used",
+            summary.commands.values().sum::<u64>().to_string(),
+            true,
+        )
+        .field(
+            "Members",
+            format!("+{} / -{} ({:+})", summary.joins, summary.leaves, net),
+            true,
+        )
+        .field(
+            "Voice",
+            format!(
+                "{} minutes · {} members",
+                summary.voice_minutes(),
+                summary.voice.len()
+            ),
+            true,
+        )
+        .field("Busiest hour", busiest_hour, true)
+        .field(
+            "Top channels",
+            if top_channels.is_empty() {
+                "`n/a`".to_string()
+            } else {
+                top_channels.join("\n")
+            },
+            false,
+        )
+        .footer(CreateEmbedFooter::new("Times are in UTC"))
+        .color(ANALYTICS_COLOUR);
+    CommandResponse::new_embeds(vec![embed])
+}
+
+fn top_embed(
+    category: Category,
+    days: i64,
+    count: usize,
+    summary: &AnalyticsSummary,
+) -> CommandResponse {
+    let lines = match category {
+        Category::Channels => AnalyticsSummary::top(&summary.channels, count)
+            .into_iter()
+            .map(|(channel, n)| format!("<#{}> · {} messages", channel, n))
+            .collect::<Vec<_>>(),
+        Category::Users => AnalyticsSummary::top(&summary.users, count)
+            .into_iter()
+            .map(|(user, n)| format!("<@{}> · {} messages", user, n))
+            .collect(),
+        Category::Commands => AnalyticsSummary::top(&summary.commands, count)
+            .into_iter()
+            .map(|(command, n)| format!("`{}` · {} uses", command, n))
+            .collect(),
+        Category::Voice => AnalyticsSummary::top(&summary.voice, count)
+            .into_iter()
+            .map(|(user, seconds)| format!("<@{}> · {} minutes", user, seconds / 60))
+            .collect(),
+    };
+    let description = if lines.is_empty() {
+        format!("No activity recorded in {}.", period_str(days))
+    } else {
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| format!("**{}.** {}", i + 1, line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = CreateEmbed::default()
+        .title(format!("🏆 {}", category.title()))
+        .description(description)
+        .footer(CreateEmbedFooter::new(format!("Over {}", period_str(days))))
+        .color(ANALYTICS_COLOUR);
+    CommandResponse::new_embeds(vec![embed])
+}
+
+fn chart_response(
+    analytics: &ServerAnalyticsMap,
+    guild: &Guild,
+    metric: Metric,
+    days: i64,
+) -> Result<CommandResponse, String> {
+    let retention = analytics
+        .get(&guild.id)
+        .map(|g| g.retention_days)
+        .unwrap_or(DEFAULT_RETENTION_DAYS);
+    let days = days.clamp(1, retention as i64);
+    let since = Utc::now() - Duration::days(days);
+    // A single day is charted per hour, longer periods per day.
+    let hours_per_point = if days == 1 { 1 } else { 24 };
+    let label = |hour: i64| {
+        let at = DateTime::from_timestamp(hour * HOUR, 0).unwrap_or_default();
+        if days == 1 {
+            at.format("%H:00").to_string()
+        } else {
+            at.format("%m/%d").to_string()
+        }
+    };
+    let series = |value: fn(&HourBucket) -> u64| {
+        analytics
+            .get(&guild.id)
+            .map(|g| g.series(since, hours_per_point, value))
+            .unwrap_or_default()
+    };
+
+    let chart = match metric {
+        Metric::Messages => Chart::new(
+            ChartKind::Line,
+            format!("Messages over {}", period_str(days)),
+            series(|b| b.messages.values().sum())
+                .into_iter()
+                .map(|(hour, count)| (label(hour), count as f64))
+                .collect(),
+        ),
+        Metric::Voice => Chart::new(
+            ChartKind::Line,
+            format!("Voice minutes over {}", period_str(days)),
+            series(|b| b.voice.values().sum())
+                .into_iter()
+                .map(|(hour, seconds)| (label(hour), (seconds / 60) as f64))
+                .collect(),
+        ),
+        Metric::Hours => {
+            let (_, summary) = summarize(analytics, guild, days);
+            let mut hours_of_day = [0u64; 24];
+            for (hour, count) in &summary.messages_by_hour {
+                hours_of_day[hour.rem_euclid(24) as usize] += count;
+            }
+            Chart::new(
+                ChartKind::Bar,
+                format!(
+                    "Messages by hour of the day (UTC) over {}",
+                    period_str(days)
+                ),
+                hours_of_day
+                    .iter()
+                    .enumerate()
+                    .map(|(hour, count)| (format!("{:02}", hour), *count as f64))
+                    .collect(),
+            )
+        }
+        Metric::Commands => {
+            let (_, summary) = summarize(analytics, guild, days);
+            Chart::new(
+                ChartKind::Bar,
+                format!("Most used commands over {}", period_str(days)),
+                AnalyticsSummary::top(&summary.commands, DEFAULT_TOP)
+                    .into_iter()
+                    .map(|(command, count)| (command, count as f64))
+                    .collect(),
+            )
+        }
+    };
+
+    let png = chart.render()?;
+    let embed = CreateEmbed::default()
+        .title(format!("📈 {} activity", guild.name))
+        .image("attachment://chart.png")
+        .footer(CreateEmbedFooter::new("Times are in UTC"))
+        .color(ANALYTICS_COLOUR);
+    Ok(CommandResponse::new_embeds(vec![embed])
+        .add_attachment(CreateAttachment::bytes(png, "chart.png")))
+}
@@ -7,5 +7,6 @@ pub fn get_modules() -> Vec<utils::CommandTemplate> {
     let mut modules = vec![];
     modules.extend(settings::get_commands());
     modules.extend(moderation::get_commands());
+    modules.extend(analytics::get_commands());
     modules
 }
@@ -1,32 +1,20 @@
-use std::sync::Arc;
-
 use serenity::{
-    all::{CacheHttp, CommandType, Context, Guild, GuildChannel, ReactionType},
+    all::{CacheHttp, Context, Guild, GuildChannel, ReactionType},
     async_trait,
 };
 
-use utils::{CommandArguments, CommandResponse, CommandTemplate, CommandTrait, ICommand, UserType};
+use utils::{CommandArguments, CommandResponse, CommandTrait, UserType};
 
 use crate::{EditSnipes, ReactionSnipes, Snipes};
 
-const COMMAND_NAME: &str = "clearsnipe";
-const COMMAND_DESCRIPTION: &str = "Clears the stored snipe data for the server.";
-
 pub struct Command;
 
-pub fn command() -> CommandTemplate {
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![],
-            vec![],
-        ),
-        Arc::new(Command),
-    )
-}
-
+#[utils::command(
+    name = "clearsnipe",
+    description = "Clears the stored snipe data for the server.",
+    slash,
+    legacy
+)]
 #[async_trait]
 impl CommandTrait for Command {
     async fn execute<'a>(
@@ -90,10 +78,4 @@ impl CommandTrait for Command {
             }
         }
     }
-    fn is_legacy(&self) -> bool {
-        true
-    }
-    fn is_slash(&self) -> bool {
-        true
-    }
 }
@@ -1,36 +1,26 @@
-use std::sync::Arc;
-
 use serenity::{
     all::{
-        AutocompleteOption, Channel, ChannelId, CommandInteraction, CommandType, Context, Guild,
-        GuildChannel, GuildId, Member, User,
+        Colour, Context, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, Guild, GuildChannel,
     },
     async_trait,
 };
 
-use utils::{
-    AutocompleteResponse, CommandArguments, CommandResponse, CommandTemplate, CommandTrait,
-    ICommand, UserType,
-};
+use utils::{CommandArguments, CommandResponse, CommandTrait, UserType};
 
-const COMMAND_NAME: &str = "editsnipe";
-const COMMAND_DESCRIPTION: &str = "View the last edited message in this channel.";
+use crate::{EditSnipe, EditSnipes, Paginations, snipes::word_diff};
 
 pub struct Command;
 
-pub fn command() -> CommandTemplate {
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![],
-            vec![],
-        ),
-        Arc::new(Command),
-    )
-}
-
+#[utils::command(
+    name = "editsnipe",
+    description = "View the last edited message in this channel.",
+    permissions = [ManageMessages],
+    aliases = ["es"],
+    cooldown = 3,
+    bucket = Channel,
+    slash,
+    legacy
+)]
 #[async_trait]
 impl CommandTrait for Command {
     async fn execute<'a>(
@@ -38,30 +28,139 @@ impl CommandTrait for Command {
         ctx: &'a Context,
         user: UserType,
         channel: Option<(Guild, GuildChannel)>,
-        args: CommandArguments<'a>,
+        _: CommandArguments<'a>,
     ) -> Result<Option<CommandResponse>, String> {
-        Ok(Some(CommandResponse::default()))
-    }
-    async fn autocomplete<'a>(
-        &self,
-        ctx: &'a Context,
-        user: UserType,
-        channel: Option<(Guild, GuildChannel)>,
-        focused: AutocompleteOption<'a>,
-        interaction: &'a CommandInteraction,
-    ) -> Option<AutocompleteResponse> {
-        // Handle autocomplete logic here
-        let mut response = AutocompleteResponse::new();
+        let Some((guild, channel)) = channel else {
+            return Err("This command can only be used in a server.".to_string());
+        };
+
+        let user = match user {
+            UserType::User(u) => u,
+            UserType::Member(m) => m.user.clone(),
+        };
+
+        let snipes = {
+            let data = ctx.data.read().await;
+            data.get::<EditSnipes>()
+                .cloned()
+                .ok_or("Failed to get edit snipe data.".to_string())?
+        };
+
+        let mut embeds = vec![];
+        if let Some(snipes) = snipes.get(&channel.id).map(|s| s.clone()) {
+            let length = if snipes.len() > 1 {
+                format!("{} messages", snipes.len())
+            } else {
+                "1 message".to_string()
+            };
+            for (index, snipe) in snipes.iter().rev().enumerate() {
+                let footer = CreateEmbedFooter::new(format!("{}/{}", index + 1, length));
+                embeds.push(snipe_embed(&guild, &channel, snipe).footer(footer));
+            }
+        }
 
-        Some(response)
+        let mut response = CommandResponse::default();
+
+        if embeds.is_empty() {
+            let embed = CreateEmbed::default()
+                .title("No Edit Snipes Found")
+                .description("There are no edited messages to view in this channel.")
+                .color(Colour::ORANGE);
+            response = response.embeds(vec![embed]);
+        } else if embeds.len() > 1 {
+            let data = ctx.data.read().await;
+            let pages = data
+                .get::<Paginations>()
+                .ok_or("Failed to get paginations data.".to_string())?
+                .insert(embeds, user.id.get())
+                .await;
+            response = response.embeds(vec![pages.0]).components(vec![pages.1]);
+        } else {
+            response = response.embeds(vec![embeds[0].clone()]);
+        };
+
+        Ok(Some(response.reply()))
     }
-    fn is_legacy(&self) -> bool {
-        false
+}
+
+fn snipe_embed(guild: &Guild, channel: &GuildChannel, snipe: &EditSnipe) -> CreateEmbed {
+    let author = CreateEmbedAuthor::new(&snipe.author.name).icon_url(snipe.author.face());
+    let link = format!(
+        "https://discord.com/channels/{}/{}/{}",
+        guild.id, channel.id, snipe.message_id
+    );
+    let mut embed = CreateEmbed::default()
+        .author(author)
+        .url(link)
+        .color(Colour::ORANGE);
+
+    let Some((before, after)) = snipe.last_edit() else {
+        // The original was sent before the bot could store it.
+        let content = snipe
+            .revisions
+            .last()
+            .map(|r| r.content.as_str())
+            .unwrap_or_default();
+        return embed
+            .title("Edited message")
+            .description("The original message is no longer available.")
+            .field("After", field_text(content), false);
+    };
+
+    embed = embed
+        .title("Edited message")
+        .description(truncate(&word_diff(&before.content, &after.content), 4000))
+        .field("Before", field_text(&before.content), false)
+        .field("After", field_text(&after.content), false)
+        .timestamp(after.at);
+
+    if snipe.revisions.len() > 2 {
+        let history = snipe
+            .revisions
+            .iter()
+            .enumerate()
+            .map(|(i, revision)| {
+                format!(
+                    "`{}.` <t:{}:R> {}",
+                    i + 1,
+                    revision.at.unix_timestamp(),
+                    truncate(&revision.content.replace('\n', " "), 120)
+                )
+            })
+            .collect::<Vec<_>>();
+        // Newest revisions are kept when the history doesn't fit in one field.
+        let mut lines = vec![];
+        let mut length = 0;
+        for line in history.iter().rev() {
+            if length + line.len() + 1 > 1024 {
+                break;
+            }
+            length += line.len() + 1;
+            lines.push(line.as_str());
+        }
+        lines.reverse();
+        embed = embed.field(
+            format!("History ({} revisions)", snipe.revisions.len()),
+            lines.join("\n"),
+            false,
+        );
     }
-    fn is_slash(&self) -> bool {
-        true
+    embed
+}
+
+fn field_text(content: &str) -> String {
+    if content.is_empty() {
+        "`empty`".to_string()
+    } else {
+        truncate(content, 1024)
     }
-    fn supports_autocomplete(&self) -> bool {
-        true
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
     }
+    let mut truncated = text.chars().take(max - 1).collect::<String>();
+    truncated.push('…');
+    truncated
 }
@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{ChannelId, ChannelType, CommandType, Context, Guild, GuildChannel},
+    async_trait,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, BotPermission, CommandArguments, CommandResponse,
+    CommandTemplate, CommandTrait, ICommand, ParsedArguments, UserType,
+};
+
+use crate::{
+    MessageLogChannelMap, MessageLogChannels, commands::require_guild, save_message_log_channels,
+};
+
+const COMMAND_NAME: &str = "messagelog";
+const COMMAND_DESCRIPTION: &str = "Log deleted messages to a channel.";
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![BotPermission::ManageGuild],
+    )
+    .subcommand(Channel::node())
+    .subcommand(View::node())
+    .default("view")
+    .routed()
+}
+
+struct ChannelArguments {
+    channel: Option<ChannelId>,
+}
+
+impl Arguments for ChannelArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::channel("channel", "The channel to log deleted messages in")
+                .channel_types(vec![ChannelType::Text]),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            channel: parsed.get("channel"),
+        })
+    }
+}
+
+struct Channel;
+
+#[utils::command(
+    name = "channel",
+    description = "Set the message log channel (leave empty to disable)",
+    arguments = ChannelArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Channel {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments = match ChannelArguments::parse(&format!("{} channel", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let channels = repo(ctx).await?;
+        let response = match arguments.channel {
+            Some(channel_id) => {
+                if !guild.channels.contains_key(&channel_id) {
+                    return Ok(Some(
+                        CommandResponse::new_content("That channel is not in this server.").reply(),
+                    ));
+                }
+                channels.insert(guild.id, channel_id);
+                CommandResponse::new_content(format!(
+                    "Deleted messages will be logged in <#{}>.",
+                    channel_id
+                ))
+            }
+            None => {
+                channels.remove(&guild.id);
+                CommandResponse::new_content("Message logging has been disabled.")
+            }
+        };
+        save_message_log_channels(&channels);
+
+        Ok(Some(response.reply()))
+    }
+}
+
+struct View;
+
+#[utils::command(
+    name = "view",
+    description = "View the message log channel",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for View {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let content = match repo(ctx).await?.get(&guild.id) {
+            Some(channel_id) => format!("Deleted messages are logged in <#{}>.", *channel_id),
+            None => "Message logging is disabled.".to_string(),
+        };
+        Ok(Some(CommandResponse::new_content(content).reply()))
+    }
+}
+
+async fn repo(ctx: &Context) -> Result<Arc<MessageLogChannelMap>, String> {
+    ctx.data
+        .read()
+        .await
+        .get::<MessageLogChannels>()
+        .cloned()
+        .ok_or("Failed to get message log channels.".to_string())
+}
@@ -1,15 +1,27 @@
 mod clear_snipe;
 mod edit_snipe;
+mod message_log;
 mod reaction_history;
 mod reaction_snipe;
+mod report_message;
 mod snipe;
+mod snipe_author;
+mod snipe_blacklist;
+mod snipe_config;
+mod snipe_opt_out;
 
 pub fn get_commands() -> Vec<utils::CommandTemplate> {
     vec![
         snipe::command(),
+        snipe_config::command(),
+        snipe_blacklist::command(),
+        snipe_opt_out::command(),
         clear_snipe::command(),
         edit_snipe::command(),
+        message_log::command(),
         reaction_history::command(),
         reaction_snipe::command(),
+        snipe_author::command(),
+        report_message::command(),
     ]
 }
@@ -1,36 +1,54 @@
-use std::sync::Arc;
-
+use serde_json::Value;
 use serenity::{
     all::{
-        AutocompleteOption, Channel, ChannelId, CommandInteraction, CommandType, Context, Guild,
-        GuildChannel, GuildId, Member, User,
+        AutocompleteOption, Colour, CommandInteraction, Context, CreateEmbed, CreateEmbedFooter,
+        Guild, GuildChannel,
     },
     async_trait,
 };
 
 use utils::{
-    AutocompleteResponse, CommandArguments, CommandResponse, CommandTemplate, CommandTrait,
-    ICommand, UserType,
+    Argument, ArgumentSchema, Arguments, AutocompleteResponse, CommandArguments, CommandResponse,
+    CommandTrait, ParsedArguments, UserType,
 };
 
-const COMMAND_NAME: &str = "reactionhistory";
-const COMMAND_DESCRIPTION: &str = "View the reaction history for a message.";
+use crate::{Paginations, ReactionHistory, commands::parse_message_reference, message_log};
+
+const EVENTS_PER_PAGE: usize = 20;
 
 pub struct Command;
 
-pub fn command() -> CommandTemplate {
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![],
-            vec![],
-        ),
-        Arc::new(Command),
-    )
+struct HistoryArguments {
+    message: String,
+}
+
+impl Arguments for HistoryArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::string("message", "A message link or ID")
+                .autocomplete()
+                .required(),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            message: parsed
+                .get("message")
+                .ok_or("`message` is required.".to_string())?,
+        })
+    }
 }
 
+#[utils::command(
+    name = "reactionhistory",
+    description = "View the reaction history for a message.",
+    permissions = [ManageMessages],
+    arguments = HistoryArguments,
+    slash,
+    legacy,
+    autocomplete
+)]
 #[async_trait]
 impl CommandTrait for Command {
     async fn execute<'a>(
@@ -40,28 +58,150 @@ impl CommandTrait for Command {
         channel: Option<(Guild, GuildChannel)>,
         args: CommandArguments<'a>,
     ) -> Result<Option<CommandResponse>, String> {
-        Ok(Some(CommandResponse::default()))
+        let Some((guild, channel)) = channel else {
+            return Err("This command can only be used in a server.".to_string());
+        };
+        let user = match user {
+            UserType::User(u) => u,
+            UserType::Member(m) => m.user.clone(),
+        };
+
+        let arguments = match HistoryArguments::parse(Command::NAME, &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+        let Some((_, message_id)) = parse_message_reference(&arguments.message, channel.id) else {
+            return Ok(Some(
+                CommandResponse::new_content("That is not a valid message link or ID.").reply(),
+            ));
+        };
+
+        let history = {
+            let data = ctx.data.read().await;
+            data.get::<ReactionHistory>()
+                .cloned()
+                .ok_or("Failed to get reaction history.".to_string())?
+        };
+        let Some(entry) = history
+            .get(&message_id)
+            .filter(|entry| entry.guild_id == guild.id)
+            .map(|entry| entry.clone())
+        else {
+            let embed = CreateEmbed::default()
+                .title("No Reaction History")
+                .description("No reactions have been recorded for that message recently.")
+                .color(Colour::RED);
+            return Ok(Some(CommandResponse::new_embeds(vec![embed]).reply()));
+        };
+
+        let link = format!(
+            "https://discord.com/channels/{}/{}/{}",
+            guild.id, entry.channel_id, message_id
+        );
+        let added = entry.events.iter().filter(|e| e.added).count();
+        let removed = entry.events.len() - added;
+        let pages = entry.events.len().div_ceil(EVENTS_PER_PAGE);
+        let embeds = entry
+            .events
+            .chunks(EVENTS_PER_PAGE)
+            .enumerate()
+            .map(|(page, events)| {
+                let lines = events
+                    .iter()
+                    .map(|event| {
+                        format!(
+                            "<t:{}:T> {} {} <@{}>",
+                            event.at.timestamp(),
+                            if event.added { "➕" } else { "➖" },
+                            event.emoji,
+                            event.user_id
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                CreateEmbed::default()
+                    .title("Reaction history")
+                    .url(&link)
+                    .description(format!(
+                        "Message in <#{}>\n\n{}",
+                        entry.channel_id,
+                        lines.join("\n")
+                    ))
+                    .footer(CreateEmbedFooter::new(format!(
+                        "{} added · {} removed · page {}/{}",
+                        added,
+                        removed,
+                        page + 1,
+                        pages
+                    )))
+                    .color(Colour::BLURPLE)
+            })
+            .collect::<Vec<_>>();
+
+        let response = if embeds.len() > 1 {
+            let data = ctx.data.read().await;
+            let pages = data
+                .get::<Paginations>()
+                .ok_or("Failed to get paginations data.".to_string())?
+                .insert(embeds, user.id.get())
+                .await;
+            CommandResponse::new_embeds(vec![pages.0]).components(vec![pages.1])
+        } else {
+            CommandResponse::new_embeds(embeds)
+        };
+
+        Ok(Some(response.reply()))
     }
     async fn autocomplete<'a>(
         &self,
         ctx: &'a Context,
-        user: UserType,
+        _: UserType,
         channel: Option<(Guild, GuildChannel)>,
         focused: AutocompleteOption<'a>,
-        interaction: &'a CommandInteraction,
+        _: &'a CommandInteraction,
     ) -> Option<AutocompleteResponse> {
-        // Handle autocomplete logic here
-        let mut response = AutocompleteResponse::new();
+        if focused.name != "message" {
+            return None;
+        }
+        let (guild, channel) = channel?;
+        let history = {
+            let data = ctx.data.read().await;
+            data.get::<ReactionHistory>().cloned()?
+        };
+
+        // Messages in the current channel come first, then the most recently reacted to.
+        let mut recent = history
+            .iter()
+            .filter(|entry| entry.guild_id == guild.id)
+            .filter(|entry| entry.key().to_string().contains(focused.value))
+            .map(|entry| {
+                (
+                    entry.channel_id != channel.id,
+                    std::cmp::Reverse(entry.last_activity()),
+                    *entry.key(),
+                    entry.channel_id,
+                    entry.events.len(),
+                )
+            })
+            .collect::<Vec<_>>();
+        recent.sort();
 
+        let mut response = AutocompleteResponse::new();
+        for (_, _, message_id, channel_id, count) in recent.into_iter().take(25) {
+            let channel_name = guild
+                .channels
+                .get(&channel_id)
+                .map(|c| c.name.clone())
+                .unwrap_or(channel_id.to_string());
+            let preview = message_log::lookup(ctx, channel_id, message_id)
+                .await
+                .map(|m| format!("{}: {}", m.author.name, m.content.replace('\n', " ")))
+                .unwrap_or(format!("Message {}", message_id));
+            let name = format!("#{} · {} reactions · {}", channel_name, count, preview);
+            response.insert(
+                name.chars().take(100).collect(),
+                Value::String(message_id.to_string()),
+            );
+        }
         Some(response)
     }
-    fn is_legacy(&self) -> bool {
-        false
-    }
-    fn is_slash(&self) -> bool {
-        true
-    }
-    fn supports_autocomplete(&self) -> bool {
-        true
-    }
 }
@@ -1,36 +1,21 @@
-use std::sync::Arc;
-
 use serenity::{
     all::{
-        AutocompleteOption, Channel, ChannelId, CommandInteraction, CommandType, Context, Guild,
-        GuildChannel, GuildId, Member, User,
+        AutocompleteOption, Channel, ChannelId, CommandInteraction, Context, Guild, GuildChannel,
+        GuildId, Member, User,
     },
     async_trait,
 };
 
-use utils::{
-    AutocompleteResponse, CommandArguments, CommandResponse, CommandTemplate, CommandTrait,
-    ICommand, UserType,
-};
-
-const COMMAND_NAME: &str = "reaction_snipe";
-const COMMAND_DESCRIPTION: &str = "Displays the last reaction added to a message in the channel.";
+use utils::{AutocompleteResponse, CommandArguments, CommandResponse, CommandTrait, UserType};
 
 pub struct Command;
 
-pub fn command() -> CommandTemplate {
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![],
-            vec![],
-        ),
-        Arc::new(Command),
-    )
-}
-
+#[utils::command(
+    name = "reaction_snipe",
+    description = "Displays the last reaction added to a message in the channel.",
+    slash,
+    autocomplete
+)]
 #[async_trait]
 impl CommandTrait for Command {
     async fn execute<'a>(
@@ -55,13 +40,4 @@ impl CommandTrait for Command {
 
         Some(response)
     }
-    fn is_legacy(&self) -> bool {
-        false
-    }
-    fn is_slash(&self) -> bool {
-        true
-    }
-    fn supports_autocomplete(&self) -> bool {
-        true
-    }
 }
@@ -0,0 +1,40 @@
+use serenity::{
+    all::{Context, Guild, GuildChannel, ResolvedTarget},
+    async_trait,
+};
+
+use utils::{CommandArguments, CommandResponse, CommandTrait, UserType};
+
+use crate::message_log;
+
+pub struct Command;
+
+#[utils::command(name = "Report message", kind = Message)]
+#[async_trait]
+impl CommandTrait for Command {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let Some((guild, _)) = location else {
+            return Err("This command can only be used in a server.".to_string());
+        };
+        let Some(ResolvedTarget::Message(message)) = args.target() else {
+            return Err("No message was targeted.".to_string());
+        };
+
+        let reporter = match user {
+            UserType::User(u) => u,
+            UserType::Member(m) => m.user,
+        };
+
+        let content = match message_log::reported(ctx, guild.id, message, &reporter).await {
+            Ok(()) => "The message was reported to the moderators.".to_string(),
+            Err(e) => e,
+        };
+        Ok(Some(CommandResponse::new_content(content).ephemeral()))
+    }
+}
@@ -1,38 +1,32 @@
-use std::{sync::Arc, vec};
+use std::vec;
 
 use serenity::{
     all::{
-        Colour, CommandType, Context, CreateActionRow, CreateButton, CreateCommandOption,
-        CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, Guild, GuildChannel, ReactionType,
+        Colour, Context, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, Guild, GuildChannel,
+        Message, UserId,
     },
     async_trait,
 };
 
-use utils::{
-    BotPermission, CommandArguments, CommandResponse, CommandTemplate, CommandTrait, ICommand,
-    UserType,
-};
+use utils::{CommandArguments, CommandResponse, CommandTrait, UserType};
 
-use crate::{Paginations, Snipes, commands::server::moderation::snipe};
+use crate::{Paginations, Snipes, snipes::attachments};
 
-const COMMAND_NAME: &str = "snipe";
-const COMMAND_DESCRIPTION: &str = "View the last deleted message in this channel.";
+// Discord's limit of files per message.
+const MAX_FILES: usize = 10;
 
 pub struct Command;
 
-pub fn command() -> CommandTemplate {
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![],
-            vec![BotPermission::ManageMessages],
-        ),
-        Arc::new(Command),
-    )
-}
-
+#[utils::command(
+    name = "snipe",
+    description = "View the last deleted message in this channel.",
+    permissions = [ManageMessages],
+    aliases = ["s"],
+    cooldown = 3,
+    bucket = Channel,
+    slash,
+    legacy
+)]
 #[async_trait]
 impl CommandTrait for Command {
     async fn execute<'a>(
@@ -40,7 +34,7 @@ impl CommandTrait for Command {
         ctx: &'a Context,
         user: UserType,
         channel: Option<(Guild, GuildChannel)>,
-        args: CommandArguments<'a>,
+        _: CommandArguments<'a>,
     ) -> Result<Option<CommandResponse>, String> {
         let Some((_, channel)) = channel else {
             return Err("This command can only be used in a server.".to_string());
@@ -58,67 +52,90 @@ impl CommandTrait for Command {
                 .ok_or("Failed to get snipe data.".to_string())?
         };
 
-        let mut embeds = vec![];
-
-        if let Some(snipes) = snipes.get(&channel.id).map(|s| s.clone()) {
-            for (index, msg) in snipes.iter().rev().enumerate() {
-                let author = CreateEmbedAuthor::new(&msg.author.name).icon_url(
-                    msg.author
-                        .avatar_url()
-                        .unwrap_or(msg.author.default_avatar_url()),
-                );
-
-                let length = if snipes.len() > 1 {
-                    format!("{} messages", snipes.len())
-                } else {
-                    "1 message".to_string()
-                };
-                let footer = CreateEmbedFooter::new(format!("{}/{}", index + 1, length));
-
-                let content = &msg.content;
-                let timestamp = msg.timestamp;
-                let mut embed = CreateEmbed::default()
-                    .author(author)
-                    .description(content)
-                    .timestamp(timestamp)
-                    .footer(footer)
-                    .color(Colour::RED);
-
-                if let Some(attachment) = msg.attachments.first() {
-                    embed = embed.image(attachment.url.clone());
-                }
-
-                embeds.push(embed);
-            }
-        }
+        let messages = snipes
+            .get(&channel.id)
+            .map(|s| s.iter().rev().cloned().collect())
+            .unwrap_or_default();
+        let response = response(
+            ctx,
+            user.id,
+            messages,
+            "There are no snipes to view in this channel.",
+        )
+        .await?;
 
-        let mut response = CommandResponse::default();
+        Ok(Some(response.reply()))
+    }
+}
 
-        if embeds.is_empty() {
-            let embed = CreateEmbed::default()
-                .title("No Snipes Found")
-                .description("There are no snipes to view in this channel.")
-                .color(Colour::RED);
-            response = response.embeds(vec![embed]);
-        } else if embeds.len() > 1 {
-            let data = ctx.data.read().await;
-            let pages = data
-                .get::<Paginations>()
-                .ok_or("Failed to get paginations data.".to_string())?
-                .insert(embeds, user.id.get())
-                .await;
-            response = response.embeds(vec![pages.0]).components(vec![pages.1]);
+// One page per message, newest first, with the cached files of every page uploaded.
+pub(super) async fn response(
+    ctx: &Context,
+    user_id: UserId,
+    messages: Vec<Message>,
+    empty: &str,
+) -> Result<CommandResponse, String> {
+    let mut embeds = vec![];
+    let mut files = vec![];
+
+    for (index, msg) in messages.iter().enumerate() {
+        let author = CreateEmbedAuthor::new(&msg.author.name).icon_url(
+            msg.author
+                .avatar_url()
+                .unwrap_or(msg.author.default_avatar_url()),
+        );
+
+        let length = if messages.len() > 1 {
+            format!("{} messages", messages.len())
         } else {
-            response = response.embeds(vec![embeds[0].clone()]);
+            "1 message".to_string()
         };
+        let footer = CreateEmbedFooter::new(format!("{}/{}", index + 1, length));
+
+        let content = &msg.content;
+        let timestamp = msg.timestamp;
+        let mut embed = CreateEmbed::default()
+            .author(author)
+            .description(content)
+            .timestamp(timestamp)
+            .footer(footer)
+            .color(Colour::RED);
+
+        // Cached copies are uploaded with the response, since the original URLs expire.
+        let mut cached = attachments::uploads(&ctx.data, msg.id).await;
+        cached.truncate(MAX_FILES - files.len());
+        if let Some((attachment, _)) = cached.iter().find(|(a, _)| a.is_image()) {
+            embed = embed.image(format!("attachment://{}", attachment.filename));
+        } else if let Some(attachment) = msg.attachments.first() {
+            embed = embed.image(attachment.url.clone());
+        }
+        files.extend(cached.into_iter().map(|(_, upload)| upload));
 
-        Ok(Some(response.reply()))
+        embeds.push(embed);
     }
 
-    fn is_legacy(&self) -> bool {
-        true
-    }
-    fn is_slash(&self) -> bool {
-        true
-    }
+    let mut response = files
+        .into_iter()
+        .fold(CommandResponse::default(), |response, file| {
+            response.add_attachment(file)
+        });
+
+    if embeds.is_empty() {
+        let embed = CreateEmbed::default()
+            .title("No Snipes Found")
+            .description(empty)
+            .color(Colour::RED);
+        response = response.embeds(vec![embed]);
+    } else if embeds.len() > 1 {
+        let data = ctx.data.read().await;
+        let pages = data
+            .get::<Paginations>()
+            .ok_or("Failed to get paginations data.".to_string())?
+            .insert(embeds, user_id.get())
+            .await;
+        response = response.embeds(vec![pages.0]).components(vec![pages.1]);
+    } else {
+        response = response.embeds(vec![embeds[0].clone()]);
+    };
+    Ok(response)
 }
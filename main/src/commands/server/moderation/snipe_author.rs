@@ -0,0 +1,66 @@
+use serenity::{
+    all::{Context, Guild, GuildChannel, ResolvedTarget},
+    async_trait,
+};
+
+use utils::{CommandArguments, CommandResponse, CommandTrait, UserType};
+
+use crate::Snipes;
+
+use super::snipe;
+
+pub struct Command;
+
+#[utils::command(name = "Snipe author", kind = User, permissions = [ManageMessages])]
+#[async_trait]
+impl CommandTrait for Command {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let Some((guild, _)) = location else {
+            return Err("This command can only be used in a server.".to_string());
+        };
+        let Some(ResolvedTarget::User(target, _)) = args.target() else {
+            return Err("No user was targeted.".to_string());
+        };
+
+        let user_id = match user {
+            UserType::User(u) => u.id,
+            UserType::Member(m) => m.user.id,
+        };
+
+        let snipes = {
+            let data = ctx.data.read().await;
+            data.get::<Snipes>()
+                .cloned()
+                .ok_or("Failed to get snipe data.".to_string())?
+        };
+
+        // Deleted messages of the user across every channel of the server, newest first.
+        let mut messages = snipes
+            .iter()
+            .filter(|buffer| buffer.guild_id == guild.id)
+            .flat_map(|buffer| {
+                buffer
+                    .iter()
+                    .filter(|msg| msg.author.id == target.id)
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        messages.sort_by_key(|msg| std::cmp::Reverse(msg.timestamp));
+
+        let response = snipe::response(
+            ctx,
+            user_id,
+            messages,
+            &format!("There are no snipes of {} to view.", target.tag()),
+        )
+        .await?;
+        Ok(Some(response))
+    }
+}
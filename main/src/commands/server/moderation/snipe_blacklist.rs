@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{ChannelId, ChannelType, Colour, CommandType, Context, CreateEmbed, Guild, GuildChannel},
+    async_trait,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, BotPermission, CommandArguments, CommandResponse,
+    CommandTemplate, CommandTrait, ICommand, ParsedArguments, UserType,
+};
+
+use crate::{
+    BlacklistedSnipes, BlacklistedSnipesMap, EditSnipes, ReactionSnipes, Snipes,
+    commands::require_guild, save_snipe_blacklist, snipes::channel_ancestors,
+};
+
+const COMMAND_NAME: &str = "snipeblacklist";
+const COMMAND_DESCRIPTION: &str = "Exclude channels and categories from sniping.";
+const MAX_BLACKLISTED: usize = 50;
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![BotPermission::ManageGuild],
+    )
+    .subcommand(Add::node())
+    .subcommand(Remove::node())
+    .subcommand(List::node())
+    .default("list")
+    .routed()
+}
+
+struct AddArguments {
+    channel: ChannelId,
+}
+
+impl Arguments for AddArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(channel_argument("The channel or category to exclude"))
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            channel: channel(&parsed)?,
+        })
+    }
+}
+
+struct RemoveArguments {
+    channel: ChannelId,
+}
+
+impl Arguments for RemoveArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(channel_argument("The channel or category to include"))
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            channel: channel(&parsed)?,
+        })
+    }
+}
+
+fn channel_argument(description: &'static str) -> Argument {
+    Argument::channel("channel", description)
+        .channel_types(vec![
+            ChannelType::Text,
+            ChannelType::News,
+            ChannelType::Voice,
+            ChannelType::Stage,
+            ChannelType::Forum,
+            ChannelType::Category,
+        ])
+        .required()
+}
+
+fn channel(parsed: &ParsedArguments) -> Result<ChannelId, String> {
+    parsed
+        .get("channel")
+        .ok_or("`channel` is required.".to_string())
+}
+
+struct Add;
+
+#[utils::command(
+    name = "add",
+    description = "Stop sniping in a channel or category",
+    arguments = AddArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Add {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let channel_id = match AddArguments::parse(&format!("{} add", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments.channel,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let Some(channel) = guild.channels.get(&channel_id) else {
+            return Ok(Some(
+                CommandResponse::new_content("That channel is not in this server.").reply(),
+            ));
+        };
+        let blacklist = repo(ctx).await?;
+        {
+            let mut blacklisted = blacklist.entry(guild.id).or_default();
+            if blacklisted.contains(&channel_id) {
+                return Ok(Some(
+                    CommandResponse::new_content(format!(
+                        "{} is already excluded from sniping.",
+                        channel_label(channel)
+                    ))
+                    .reply(),
+                ));
+            }
+            if blacklisted.len() >= MAX_BLACKLISTED {
+                return Ok(Some(
+                    CommandResponse::new_content(format!(
+                        "You can exclude at most {} channels and categories.",
+                        MAX_BLACKLISTED
+                    ))
+                    .reply(),
+                ));
+            }
+            blacklisted.push(channel_id);
+        }
+        save_snipe_blacklist(&blacklist);
+        clear_snipes(ctx, &guild, channel_id).await;
+
+        Ok(Some(
+            CommandResponse::new_content(format!(
+                "{} is now excluded from sniping.",
+                channel_label(channel)
+            ))
+            .reply(),
+        ))
+    }
+}
+
+struct Remove;
+
+#[utils::command(
+    name = "remove",
+    description = "Allow sniping in a channel or category again",
+    arguments = RemoveArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Remove {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let channel_id = match RemoveArguments::parse(&format!("{} remove", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments.channel,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let blacklist = repo(ctx).await?;
+        let removed = blacklist
+            .get_mut(&guild.id)
+            .map(|mut blacklisted| {
+                let before = blacklisted.len();
+                blacklisted.retain(|id| *id != channel_id);
+                before != blacklisted.len()
+            })
+            .unwrap_or(false);
+        if !removed {
+            return Ok(Some(
+                CommandResponse::new_content(format!(
+                    "<#{}> is not excluded from sniping.",
+                    channel_id
+                ))
+                .reply(),
+            ));
+        }
+        blacklist.remove_if(&guild.id, |_, blacklisted| blacklisted.is_empty());
+        save_snipe_blacklist(&blacklist);
+
+        Ok(Some(
+            CommandResponse::new_content(format!("<#{}> can be sniped again.", channel_id)).reply(),
+        ))
+    }
+}
+
+struct List;
+
+#[utils::command(
+    name = "list",
+    description = "List the excluded channels and categories",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for List {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let lines = repo(ctx)
+            .await?
+            .get(&guild.id)
+            .map(|blacklisted| {
+                blacklisted
+                    .iter()
+                    .map(|id| match guild.channels.get(id) {
+                        Some(channel) => channel_label(channel),
+                        None => format!("`{}` (deleted)", id),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let embed = CreateEmbed::default()
+            .title("Snipe blacklist")
+            .description(if lines.is_empty() {
+                "No channels are excluded from sniping.".to_string()
+            } else {
+                lines.join("\n")
+            })
+            .color(Colour::RED);
+        Ok(Some(CommandResponse::new_embeds(vec![embed]).reply()))
+    }
+}
+
+async fn repo(ctx: &Context) -> Result<Arc<BlacklistedSnipesMap>, String> {
+    ctx.data
+        .read()
+        .await
+        .get::<BlacklistedSnipes>()
+        .cloned()
+        .ok_or("Failed to get snipe blacklist.".to_string())
+}
+
+fn channel_label(channel: &GuildChannel) -> String {
+    if channel.kind == ChannelType::Category {
+        format!("Category **{}**", channel.name)
+    } else {
+        format!("<#{}>", channel.id)
+    }
+}
+
+// Snipes already taken in a newly excluded channel or category are dropped as well.
+async fn clear_snipes(ctx: &Context, guild: &Guild, channel_id: ChannelId) {
+    let (snipes, edit_snipes, reaction_snipes) = {
+        let data = ctx.data.read().await;
+        (
+            data.get::<Snipes>().cloned(),
+            data.get::<EditSnipes>().cloned(),
+            data.get::<ReactionSnipes>().cloned(),
+        )
+    };
+    let excluded =
+        |id: &ChannelId| channel_ancestors(&ctx.cache, &guild.id, *id).contains(&channel_id);
+    if let Some(snipes) = snipes {
+        snipes.retain(|id, _| !excluded(id));
+    }
+    if let Some(edit_snipes) = edit_snipes {
+        edit_snipes.retain(|id, _| !excluded(id));
+    }
+    if let Some(reaction_snipes) = reaction_snipes {
+        reaction_snipes.retain(|id, _| !excluded(id));
+    }
+}
@@ -0,0 +1,336 @@
+use std::sync::Arc;
+
+use chrono::Duration;
+use serenity::{
+    all::{Colour, CommandType, Context, CreateEmbed, Guild, GuildChannel},
+    async_trait,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, BotPermission, CommandArguments, CommandResponse,
+    CommandTemplate, CommandTrait, ICommand, LegacyOption, ParsedArguments, UserType,
+};
+
+use crate::{
+    AttachmentCacheConfig, MAX_ATTACHMENT_CACHE_SIZE, MAX_ATTACHMENT_CACHE_TYPES,
+    MAX_SNIPE_CAPACITY, MAX_SNIPE_TTL, ServerSnipeConfigMap, ServerSnipeConfigs, SnipeConfig,
+    commands::require_guild, save_snipe_configs,
+};
+
+const COMMAND_NAME: &str = "snipeconfig";
+const COMMAND_DESCRIPTION: &str = "Configure how many snipes are kept and for how long.";
+const MEGABYTE: u64 = 1024 * 1024;
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![BotPermission::ManageGuild],
+    )
+    .subcommand(Limit::node())
+    .subcommand(Expiry::node())
+    .subcommand(Attachments::node())
+    .subcommand(View::node())
+    .default("view")
+    .routed()
+}
+
+struct LimitArguments {
+    count: usize,
+}
+
+impl Arguments for LimitArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::integer("count", "Snipes per channel")
+                .range(1, MAX_SNIPE_CAPACITY as i64)
+                .required(),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            count: parsed
+                .get::<i64>("count")
+                .ok_or("`count` is required.".to_string())? as usize,
+        })
+    }
+}
+
+struct ExpiryArguments {
+    duration: Duration,
+}
+
+impl Arguments for ExpiryArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::duration("duration", "How long snipes can be viewed, e.g. 2h30m")
+                .range(60, MAX_SNIPE_TTL as i64)
+                .required(),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            duration: parsed
+                .get("duration")
+                .ok_or("`duration` is required.".to_string())?,
+        })
+    }
+}
+
+struct AttachmentsArguments {
+    enabled: bool,
+    max_size: Option<u64>,
+    types: Option<Vec<String>>,
+}
+
+impl Arguments for AttachmentsArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(Argument::boolean("enabled", "Whether attachments are cached").required())
+            .argument(
+                Argument::integer("max_size", "Largest file to cache in MB")
+                    .range(1, (MAX_ATTACHMENT_CACHE_SIZE / MEGABYTE) as i64),
+            )
+            .argument(
+                Argument::string("types", "Content types to cache, e.g. `image, video/mp4`").rest(),
+            )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        let types = parsed.get::<String>("types").map(|t| parse_types(&t));
+        if let Some(types) = &types
+            && (types.is_empty() || types.len() > MAX_ATTACHMENT_CACHE_TYPES)
+        {
+            return Err(format!(
+                "Provide between 1 and {} content types, like `image` or `video/mp4`.",
+                MAX_ATTACHMENT_CACHE_TYPES
+            ));
+        }
+        Ok(Self {
+            enabled: parsed
+                .get("enabled")
+                .ok_or("`enabled` is required.".to_string())?,
+            max_size: parsed.get::<i64>("max_size").map(|size| size as u64),
+            types,
+        })
+    }
+}
+
+struct Limit;
+
+#[utils::command(
+    name = "limit",
+    description = "Set how many snipes are kept per channel",
+    arguments = LimitArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Limit {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let count = match LimitArguments::parse(&format!("{} limit", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments.count,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        update(ctx, &guild, |config| config.capacity = count).await?;
+        Ok(Some(
+            CommandResponse::new_content(format!(
+                "Up to **{}** snipes will be kept per channel.",
+                count
+            ))
+            .reply(),
+        ))
+    }
+}
+
+struct Expiry;
+
+#[utils::command(
+    name = "expiry",
+    description = "Set how long snipes can be viewed",
+    arguments = ExpiryArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Expiry {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let duration = match ExpiryArguments::parse(&format!("{} expiry", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments.duration,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        update(ctx, &guild, |config| {
+            config.ttl = duration.num_seconds() as u64
+        })
+        .await?;
+        Ok(Some(
+            CommandResponse::new_content(format!(
+                "Snipes will now expire after **{}**.",
+                LegacyOption::time_str(&duration)
+            ))
+            .reply(),
+        ))
+    }
+}
+
+struct Attachments;
+
+#[utils::command(
+    name = "attachments",
+    description = "Keep copies of deleted attachments so they can still be viewed",
+    arguments = AttachmentsArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Attachments {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let arguments =
+            match AttachmentsArguments::parse(&format!("{} attachments", COMMAND_NAME), &args) {
+                Ok(arguments) => arguments,
+                Err(e) => return Ok(Some(e.response())),
+            };
+
+        let summary = update(ctx, &guild, |config| {
+            config.attachments.enabled = arguments.enabled;
+            if let Some(size) = arguments.max_size {
+                config.attachments.max_size = size * MEGABYTE;
+            }
+            if let Some(types) = arguments.types {
+                config.attachments.types = types;
+            }
+            attachments_summary(&config.attachments)
+        })
+        .await?;
+        let response = if arguments.enabled {
+            CommandResponse::new_content(format!(
+                "Deleted attachments will be cached: {}.",
+                summary
+            ))
+        } else {
+            CommandResponse::new_content("Deleted attachments will no longer be cached.")
+        };
+        Ok(Some(response.reply()))
+    }
+}
+
+struct View;
+
+#[utils::command(
+    name = "view",
+    description = "View the snipe configuration",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for View {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild = require_guild(location)?;
+        let config = repo(ctx)
+            .await?
+            .get(&guild.id)
+            .map(|c| c.clone())
+            .unwrap_or_default();
+
+        let embed = CreateEmbed::default()
+            .title("Snipe configuration")
+            .field("Limit", format!("{} per channel", config.capacity), true)
+            .field(
+                "Expiry",
+                LegacyOption::time_str(&Duration::seconds(config.ttl as i64)),
+                true,
+            )
+            .field(
+                "Attachments",
+                if config.attachments.enabled {
+                    attachments_summary(&config.attachments)
+                } else {
+                    "Not cached".to_string()
+                },
+                false,
+            )
+            .color(Colour::RED);
+        Ok(Some(CommandResponse::new_embeds(vec![embed]).reply()))
+    }
+}
+
+async fn repo(ctx: &Context) -> Result<Arc<ServerSnipeConfigMap>, String> {
+    ctx.data
+        .read()
+        .await
+        .get::<ServerSnipeConfigs>()
+        .cloned()
+        .ok_or("Failed to get snipe configuration.".to_string())
+}
+
+async fn update<T>(
+    ctx: &Context,
+    guild: &Guild,
+    change: impl FnOnce(&mut SnipeConfig) -> T,
+) -> Result<T, String> {
+    let configs = repo(ctx).await?;
+    let result = change(&mut configs.entry(guild.id).or_default());
+    save_snipe_configs(&configs);
+    Ok(result)
+}
+
+// Content types are separated by commas or spaces, e.g. `image, video/mp4`.
+fn parse_types(input: &str) -> Vec<String> {
+    let mut types = vec![];
+    for t in input.split([',', ' ']).map(|t| t.trim().to_lowercase()) {
+        if !t.is_empty() && !types.contains(&t) {
+            types.push(t);
+        }
+    }
+    types
+}
+
+fn attachments_summary(config: &AttachmentCacheConfig) -> String {
+    format!(
+        "up to {} MB of {}",
+        config.max_size / MEGABYTE,
+        config
+            .types
+            .iter()
+            .map(|t| format!("`{}`", t))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
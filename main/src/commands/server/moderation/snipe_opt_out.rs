@@ -0,0 +1,83 @@
+use chrono::Utc;
+use serenity::{
+    all::{Context, Guild, GuildChannel},
+    async_trait,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, CommandArguments, CommandResponse, CommandTrait,
+    ParsedArguments, UserType,
+};
+
+use crate::{SnipeOptOuts, save_snipe_opt_outs, snipes::forget_user};
+
+pub struct Command;
+
+struct OptOutArguments {
+    enabled: Option<bool>,
+}
+
+impl Arguments for OptOutArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(Argument::boolean(
+            "enabled",
+            "Whether to opt out (toggles when empty)",
+        ))
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            enabled: parsed.get("enabled"),
+        })
+    }
+}
+
+#[utils::command(
+    name = "snipeoptout",
+    description = "Stop the bot from storing your deleted and edited messages.",
+    arguments = OptOutArguments,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Command {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        _: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let user = match user {
+            UserType::User(u) => u,
+            UserType::Member(m) => m.user.clone(),
+        };
+
+        let enabled = match OptOutArguments::parse(Command::NAME, &args) {
+            Ok(arguments) => arguments.enabled,
+            Err(e) => return Ok(Some(e.response().ephemeral())),
+        };
+
+        let opt_outs = {
+            let data = ctx.data.read().await;
+            data.get::<SnipeOptOuts>()
+                .cloned()
+                .ok_or("Failed to get snipe opt-outs.".to_string())?
+        };
+
+        let opted_out = opt_outs.contains_key(&user.id);
+        let response = if enabled.unwrap_or(!opted_out) {
+            if !opted_out {
+                opt_outs.insert(user.id, Utc::now());
+            }
+            forget_user(&ctx.data, user.id).await;
+            "🔒 Your deleted and edited messages will no longer be stored or sniped."
+        } else {
+            opt_outs.remove(&user.id);
+            "🔓 Your deleted and edited messages can be sniped again."
+        };
+        save_snipe_opt_outs(&opt_outs);
+
+        Ok(Some(CommandResponse::new_content(response).ephemeral()))
+    }
+}
@@ -0,0 +1,777 @@
+use serenity::{
+    all::{
+        AutocompleteOption, ChannelId, Colour, CommandInteraction, CommandType, Context,
+        CreateEmbed, Guild, GuildChannel, GuildId, RoleId,
+    },
+    async_trait,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, AutocompleteResponse, BotPermission, CommandArguments,
+    CommandResponse, CommandTemplate, CommandTrait, ICommand, ParsedArguments, UserType, truncate,
+};
+
+use crate::{
+    ALWAYS_ENABLED, CommandAliases, CommandSettings, Commands, GuildCommandSettings, MODULES,
+    find_command, save_command_settings, sync_guild_commands,
+};
+
+const COMMAND_NAME: &str = "commands";
+const COMMAND_DESCRIPTION: &str = "Enable, disable and restrict commands in this server.";
+// Discord's limit for the value of an embed field.
+const MAX_FIELD_LENGTH: usize = 1024;
+// Names from the message are echoed back, cut so a long one can't fill the reply.
+const MAX_ECHOED_NAME_LENGTH: usize = 32;
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![BotPermission::ManageGuild],
+    )
+    .subcommand(Enable::node())
+    .subcommand(Disable::node())
+    .subcommand(Restrict::node())
+    .subcommand(Unrestrict::node())
+    .subcommand(Alias::node())
+    .subcommand(Unalias::node())
+    .subcommand(View::node())
+    .routed()
+}
+
+enum Target {
+    Module(&'static str),
+    Command(String, &'static str),
+}
+
+struct NameArguments {
+    name: String,
+}
+
+impl Arguments for NameArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::string("name", "A command or module")
+                .required()
+                .max_length(32)
+                .autocomplete(),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            name: parsed
+                .get("name")
+                .ok_or("`name` is required.".to_string())?,
+        })
+    }
+}
+
+struct RestrictArguments {
+    command: String,
+    channel: Option<ChannelId>,
+    role: Option<RoleId>,
+}
+
+impl Arguments for RestrictArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(
+                Argument::string("command", "The command to restrict")
+                    .required()
+                    .max_length(32)
+                    .autocomplete(),
+            )
+            .argument(Argument::channel(
+                "channel",
+                "A channel or category the command can be used in",
+            ))
+            .argument(Argument::role("role", "A role that can use the command"))
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        let arguments = Self {
+            command: parsed
+                .get("command")
+                .ok_or("`command` is required.".to_string())?,
+            channel: parsed.get("channel"),
+            role: parsed.get("role"),
+        };
+        if arguments.channel.is_none() && arguments.role.is_none() {
+            return Err("Give a channel, a role or both.".to_string());
+        }
+        Ok(arguments)
+    }
+}
+
+struct AliasArguments {
+    alias: String,
+    command: String,
+}
+
+impl Arguments for AliasArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(
+                Argument::string("alias", "The new name, a single word")
+                    .required()
+                    .max_length(32),
+            )
+            .argument(
+                Argument::string("command", "The command it stands for")
+                    .required()
+                    .max_length(32)
+                    .autocomplete(),
+            )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            alias: parsed
+                .get("alias")
+                .ok_or("`alias` is required.".to_string())?,
+            command: parsed
+                .get("command")
+                .ok_or("`command` is required.".to_string())?,
+        })
+    }
+}
+
+struct Enable;
+
+#[utils::command(
+    name = "enable",
+    description = "Enable a command or module",
+    arguments = NameArguments,
+    subcommand,
+    slash,
+    legacy,
+    autocomplete
+)]
+#[async_trait]
+impl CommandTrait for Enable {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild_id = guild_id(location)?;
+        let arguments = match NameArguments::parse(&format!("{} enable", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+        let target = match resolve(ctx, &arguments.name).await {
+            Ok(target) => target,
+            Err(response) => return Ok(Some(response)),
+        };
+        let content = match target {
+            Target::Module(module) => {
+                update(ctx, guild_id, |settings| {
+                    settings
+                        .disabled_modules
+                        .retain(|m| !m.eq_ignore_ascii_case(module));
+                })
+                .await;
+                format!("Enabled the `{}` module.", module)
+            }
+            Target::Command(name, module) => {
+                let settings = update(ctx, guild_id, |settings| {
+                    settings.disabled_commands.retain(|c| *c != name);
+                })
+                .await;
+                if settings.module_enabled(module) {
+                    format!("Enabled `{}`.", name)
+                } else {
+                    format!(
+                        "Enabled `{}`, but its module `{}` is still disabled.",
+                        name, module
+                    )
+                }
+            }
+        };
+        Ok(Some(CommandResponse::new_content(content).reply()))
+    }
+    async fn autocomplete<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        _: Option<(Guild, GuildChannel)>,
+        focused: AutocompleteOption<'a>,
+        _: &'a CommandInteraction,
+    ) -> Option<AutocompleteResponse> {
+        Some(suggestions(ctx, focused.value, true).await)
+    }
+}
+
+struct Disable;
+
+#[utils::command(
+    name = "disable",
+    description = "Disable a command or module",
+    arguments = NameArguments,
+    subcommand,
+    slash,
+    legacy,
+    autocomplete
+)]
+#[async_trait]
+impl CommandTrait for Disable {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild_id = guild_id(location)?;
+        let arguments = match NameArguments::parse(&format!("{} disable", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+        let target = match resolve(ctx, &arguments.name).await {
+            Ok(target) => target,
+            Err(response) => return Ok(Some(response)),
+        };
+        let content = match target {
+            Target::Module(module) => {
+                update(ctx, guild_id, |settings| {
+                    if settings.module_enabled(module) {
+                        settings.disabled_modules.push(module.to_string());
+                    }
+                })
+                .await;
+                format!("Disabled the `{}` module.", module)
+            }
+            Target::Command(name, _) => {
+                update(ctx, guild_id, |settings| {
+                    if !settings.disabled_commands.contains(&name) {
+                        settings.disabled_commands.push(name.clone());
+                    }
+                })
+                .await;
+                format!("Disabled `{}`.", name)
+            }
+        };
+        Ok(Some(CommandResponse::new_content(content).reply()))
+    }
+    async fn autocomplete<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        _: Option<(Guild, GuildChannel)>,
+        focused: AutocompleteOption<'a>,
+        _: &'a CommandInteraction,
+    ) -> Option<AutocompleteResponse> {
+        Some(suggestions(ctx, focused.value, true).await)
+    }
+}
+
+struct Restrict;
+
+#[utils::command(
+    name = "restrict",
+    description = "Limit a command to a channel or a role",
+    arguments = RestrictArguments,
+    subcommand,
+    slash,
+    legacy,
+    autocomplete
+)]
+#[async_trait]
+impl CommandTrait for Restrict {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild_id = guild_id(location)?;
+        let arguments = match RestrictArguments::parse(&format!("{} restrict", COMMAND_NAME), &args)
+        {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+        let name = match resolve(ctx, &arguments.command).await {
+            Ok(Target::Command(name, _)) => name,
+            Ok(Target::Module(_)) => {
+                return Ok(Some(
+                    CommandResponse::new_content("Only commands can be restricted, not modules.")
+                        .reply(),
+                ));
+            }
+            Err(response) => return Ok(Some(response)),
+        };
+        let settings = update(ctx, guild_id, |settings| {
+            let restriction = settings.restrictions.entry(name.clone()).or_default();
+            if let Some(channel) = arguments.channel
+                && !restriction.channels.contains(&channel)
+            {
+                restriction.channels.push(channel);
+            }
+            if let Some(role) = arguments.role
+                && !restriction.roles.contains(&role)
+            {
+                restriction.roles.push(role);
+            }
+        })
+        .await;
+        Ok(Some(
+            CommandResponse::new_content(format!(
+                "`{}` is now limited to {}.",
+                name,
+                restriction_summary(&settings, &name)
+            ))
+            .reply(),
+        ))
+    }
+    async fn autocomplete<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        _: Option<(Guild, GuildChannel)>,
+        focused: AutocompleteOption<'a>,
+        _: &'a CommandInteraction,
+    ) -> Option<AutocompleteResponse> {
+        if focused.name != "command" {
+            return None;
+        }
+        Some(suggestions(ctx, focused.value, false).await)
+    }
+}
+
+struct Unrestrict;
+
+#[utils::command(
+    name = "unrestrict",
+    description = "Remove the channel and role limits of a command",
+    arguments = NameArguments,
+    subcommand,
+    slash,
+    legacy,
+    autocomplete
+)]
+#[async_trait]
+impl CommandTrait for Unrestrict {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild_id = guild_id(location)?;
+        let arguments = match NameArguments::parse(&format!("{} unrestrict", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+        let name = match resolve(ctx, &arguments.name).await {
+            Ok(Target::Command(name, _)) => name,
+            Ok(Target::Module(_)) => {
+                return Ok(Some(
+                    CommandResponse::new_content("Only commands can be restricted, not modules.")
+                        .reply(),
+                ));
+            }
+            Err(response) => return Ok(Some(response)),
+        };
+        update(ctx, guild_id, |settings| {
+            settings.restrictions.remove(&name);
+        })
+        .await;
+        Ok(Some(
+            CommandResponse::new_content(format!("`{}` can be used anywhere again.", name)).reply(),
+        ))
+    }
+    async fn autocomplete<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        _: Option<(Guild, GuildChannel)>,
+        focused: AutocompleteOption<'a>,
+        _: &'a CommandInteraction,
+    ) -> Option<AutocompleteResponse> {
+        Some(suggestions(ctx, focused.value, false).await)
+    }
+}
+
+struct Alias;
+
+#[utils::command(
+    name = "alias",
+    description = "Add another name for a command",
+    arguments = AliasArguments,
+    subcommand,
+    slash,
+    legacy,
+    autocomplete
+)]
+#[async_trait]
+impl CommandTrait for Alias {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild_id = guild_id(location)?;
+        let arguments = match AliasArguments::parse(&format!("{} alias", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+        let alias = arguments.alias.to_lowercase();
+        if alias.chars().any(char::is_whitespace) {
+            return Ok(Some(
+                CommandResponse::new_content("Aliases must be a single word.").reply(),
+            ));
+        }
+
+        let (target, taken) = {
+            let data = ctx.data.read().await;
+            let commands = data.get::<Commands>().expect("Commands not initialized");
+            let aliases = data
+                .get::<CommandAliases>()
+                .expect("CommandAliases not initialized");
+            let target = find_command(commands, aliases, None, &arguments.command)
+                .filter(|name| commands.get(name).is_some_and(|(c, _, _)| c.is_legacy()));
+            (target, find_command(commands, aliases, None, &alias))
+        };
+        let Some(target) = target else {
+            return Ok(Some(
+                CommandResponse::new_content(format!(
+                    "There is no text command called `{}`.",
+                    truncate(&arguments.command, MAX_ECHOED_NAME_LENGTH)
+                ))
+                .reply(),
+            ));
+        };
+        if let Some(taken) = taken {
+            return Ok(Some(
+                CommandResponse::new_content(format!("`{}` is already `{}`.", alias, taken))
+                    .reply(),
+            ));
+        }
+
+        update(ctx, guild_id, |settings| {
+            settings.aliases.insert(alias.clone(), target.clone());
+        })
+        .await;
+        Ok(Some(
+            CommandResponse::new_content(format!("`{}` now runs `{}`.", alias, target)).reply(),
+        ))
+    }
+    async fn autocomplete<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        _: Option<(Guild, GuildChannel)>,
+        focused: AutocompleteOption<'a>,
+        _: &'a CommandInteraction,
+    ) -> Option<AutocompleteResponse> {
+        if focused.name != "command" {
+            return None;
+        }
+        Some(suggestions(ctx, focused.value, false).await)
+    }
+}
+
+struct Unalias;
+
+#[utils::command(
+    name = "unalias",
+    description = "Remove a name added with alias",
+    arguments = NameArguments,
+    subcommand,
+    slash,
+    legacy,
+    autocomplete
+)]
+#[async_trait]
+impl CommandTrait for Unalias {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild_id = guild_id(location)?;
+        let arguments = match NameArguments::parse(&format!("{} unalias", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+        let alias = arguments.name.to_lowercase();
+        let mut removed = None;
+        update(ctx, guild_id, |settings| {
+            removed = settings.aliases.remove(&alias);
+        })
+        .await;
+        let alias = truncate(&alias, MAX_ECHOED_NAME_LENGTH);
+        let content = match removed {
+            Some(command) => format!("`{}` no longer runs `{}`.", alias, command),
+            None => format!("`{}` isn't an alias of this server.", alias),
+        };
+        Ok(Some(CommandResponse::new_content(content).reply()))
+    }
+    async fn autocomplete<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        focused: AutocompleteOption<'a>,
+        _: &'a CommandInteraction,
+    ) -> Option<AutocompleteResponse> {
+        let (guild, _) = location?;
+        let aliases = {
+            let data = ctx.data.read().await;
+            data.get::<CommandSettings>()
+                .and_then(|s| s.get(&guild.id).map(|s| s.aliases.clone()))
+                .unwrap_or_default()
+        };
+        let value = focused.value.to_lowercase();
+        let mut aliases = aliases
+            .into_iter()
+            .filter(|(alias, _)| alias.contains(&value))
+            .collect::<Vec<_>>();
+        aliases.sort();
+
+        let mut response = AutocompleteResponse::new();
+        for (alias, command) in aliases.into_iter().take(25) {
+            response.insert(format!("{} → {}", alias, command), alias.into());
+        }
+        Some(response)
+    }
+}
+
+struct View;
+
+#[utils::command(
+    name = "view",
+    description = "View the disabled, restricted and aliased commands",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for View {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild_id = guild_id(location)?;
+        let settings = {
+            let data = ctx.data.read().await;
+            data.get::<CommandSettings>()
+                .and_then(|s| s.get(&guild_id).map(|s| s.clone()))
+                .unwrap_or_default()
+        };
+
+        let names = |names: &[String]| names.iter().map(|n| format!("`{}`", n)).collect();
+        let mut restricted = settings
+            .restrictions
+            .iter()
+            .filter(|(_, r)| !r.is_empty())
+            .map(|(name, _)| format!("`{}`: {}", name, restriction_summary(&settings, name)))
+            .collect::<Vec<_>>();
+        restricted.sort();
+        let mut aliases = settings
+            .aliases
+            .iter()
+            .map(|(alias, command)| format!("`{}` → `{}`", alias, command))
+            .collect::<Vec<_>>();
+        aliases.sort();
+
+        let embed = CreateEmbed::default()
+            .title("Command settings")
+            .field(
+                "Disabled modules",
+                field_list(names(&settings.disabled_modules), ", "),
+                false,
+            )
+            .field(
+                "Disabled commands",
+                field_list(names(&settings.disabled_commands), ", "),
+                false,
+            )
+            .field("Aliases", field_list(aliases, ", "), false)
+            .field("Restricted commands", field_list(restricted, "\n"), false)
+            .color(Colour::BLITZ_BLUE);
+        Ok(Some(CommandResponse::new_embeds(vec![embed]).reply()))
+    }
+}
+
+// Joins as many entries as fit in an embed field, leaving room to count the rest.
+fn field_list(entries: Vec<String>, separator: &str) -> String {
+    if entries.is_empty() {
+        return "None".to_string();
+    }
+    let mut value = String::new();
+    for (shown, entry) in entries.iter().enumerate() {
+        let length = value.chars().count() + separator.len() + entry.chars().count();
+        if length > MAX_FIELD_LENGTH - 20 {
+            if !value.is_empty() {
+                value.push_str(separator);
+            }
+            value.push_str(&format!("… and {} more", entries.len() - shown));
+            break;
+        }
+        if !value.is_empty() {
+            value.push_str(separator);
+        }
+        value.push_str(entry);
+    }
+    value
+}
+
+fn guild_id(location: Option<(Guild, GuildChannel)>) -> Result<GuildId, String> {
+    match location {
+        Some((guild, _)) => Ok(guild.id),
+        None => Err("This command can only be used in a server.".into()),
+    }
+}
+
+// Modules are matched first, then commands by their exact name or ignoring case. The error is the
+// reply for a name that can't be changed.
+async fn resolve(ctx: &Context, name: &str) -> Result<Target, CommandResponse> {
+    let name = name.trim();
+    if ALWAYS_ENABLED.iter().any(|c| c.eq_ignore_ascii_case(name)) {
+        return Err(CommandResponse::new_content(format!(
+            "`{}` can't be disabled or restricted.",
+            name
+        ))
+        .reply());
+    }
+    if let Some(module) = MODULES.iter().find(|m| m.eq_ignore_ascii_case(name)) {
+        return Ok(Target::Module(module));
+    }
+    let data = ctx.data.read().await;
+    let commands = data.get::<Commands>().expect("Commands not initialized");
+    commands
+        .get_key_value(name)
+        .or_else(|| {
+            commands
+                .iter()
+                .find(|(command, _)| command.eq_ignore_ascii_case(name))
+        })
+        .map(|(command, (_, _, module))| Target::Command(command.clone(), module))
+        .ok_or_else(|| {
+            CommandResponse::new_content(format!(
+                "There is no command or module called `{}`.",
+                truncate(name, MAX_ECHOED_NAME_LENGTH)
+            ))
+            .reply()
+        })
+}
+
+// Applies the change, saves it and registers the guild's commands again.
+async fn update(
+    ctx: &Context,
+    guild_id: GuildId,
+    change: impl FnOnce(&mut GuildCommandSettings),
+) -> GuildCommandSettings {
+    let repo = {
+        let data = ctx.data.read().await;
+        data.get::<CommandSettings>()
+            .cloned()
+            .expect("Expected CommandSettings in TypeMap")
+    };
+    let settings = {
+        let mut settings = repo.entry(guild_id).or_default();
+        change(&mut settings);
+        settings.restrictions.retain(|_, r| !r.is_empty());
+        settings.clone()
+    };
+    save_command_settings(&repo);
+    sync_guild_commands(&ctx.http, &ctx.data, guild_id).await;
+    settings
+}
+
+async fn suggestions(ctx: &Context, value: &str, modules: bool) -> AutocompleteResponse {
+    let value = value.to_lowercase();
+    let mut names = {
+        let data = ctx.data.read().await;
+        data.get::<Commands>()
+            .map(|commands| commands.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default()
+    };
+    names.retain(|name| !ALWAYS_ENABLED.contains(&name.as_str()));
+    names.sort();
+    if modules {
+        names.splice(0..0, MODULES.iter().map(|m| m.to_string()));
+    }
+
+    let mut response = AutocompleteResponse::new();
+    for name in names
+        .into_iter()
+        .filter(|name| name.to_lowercase().contains(&value))
+        .take(25)
+    {
+        response.insert(name.clone(), name.into());
+    }
+    response
+}
+
+fn restriction_summary(settings: &GuildCommandSettings, command: &str) -> String {
+    let Some(restriction) = settings.restrictions.get(command) else {
+        return "anywhere".to_string();
+    };
+    let mut parts = vec![];
+    if !restriction.channels.is_empty() {
+        parts.push(
+            restriction
+                .channels
+                .iter()
+                .map(|c| format!("<#{}>", c))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+    }
+    if !restriction.roles.is_empty() {
+        parts.push(
+            restriction
+                .roles
+                .iter()
+                .map(|r| format!("<@&{}>", r))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+    }
+    parts.join(" for ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_every_entry_that_fits() {
+        assert_eq!(field_list(vec![], ", "), "None");
+        let entries = vec!["`a`".to_string(), "`b`".to_string()];
+        assert_eq!(field_list(entries, ", "), "`a`, `b`");
+    }
+
+    #[test]
+    fn counts_the_entries_that_dont_fit() {
+        let entries = (0..500)
+            .map(|i| format!("`command{}`", i))
+            .collect::<Vec<_>>();
+        let value = field_list(entries, ", ");
+        assert!(value.chars().count() <= MAX_FIELD_LENGTH);
+        let shown = value.matches('`').count() / 2;
+        assert!(value.ends_with(&format!("… and {} more", 500 - shown)));
+    }
+
+    #[test]
+    fn counts_an_entry_too_long_for_the_field() {
+        let entries = vec!["x".repeat(2000)];
+        assert_eq!(field_list(entries, "\n"), "… and 1 more");
+    }
+}
@@ -1,5 +1,11 @@
+mod command_settings;
+mod personal_prefix;
 mod prefix;
 
 pub fn get_commands() -> Vec<utils::CommandTemplate> {
-    vec![prefix::command()]
+    vec![
+        prefix::command(),
+        personal_prefix::command(),
+        command_settings::command(),
+    ]
 }
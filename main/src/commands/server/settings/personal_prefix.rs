@@ -0,0 +1,242 @@
+use serenity::{
+    all::{CommandType, Context, Guild, GuildChannel, GuildId, UserId},
+    async_trait,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, CommandArguments, CommandResponse, CommandTemplate,
+    CommandTrait, Data, ICommand, ParsedArguments, UserType,
+};
+
+use super::prefix::validate_prefix;
+use crate::{MAX_PREFIX_LENGTH, UserPrefixes, save_user_prefixes, user_prefix, user_prefix_key};
+
+const COMMAND_NAME: &str = "myprefix";
+const COMMAND_DESCRIPTION: &str = "Your personal prefix, used next to the server prefixes.";
+
+pub fn command() -> CommandTemplate {
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![],
+    )
+    .subcommand(Set::node())
+    .subcommand(Remove::node())
+    .subcommand(Get::node())
+    .fallback("set")
+    .routed()
+}
+
+struct SetArguments {
+    value: String,
+    everywhere: bool,
+}
+
+impl Arguments for SetArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(
+                Argument::string("value", "Your prefix")
+                    .max_length(MAX_PREFIX_LENGTH as u16)
+                    .required(),
+            )
+            .argument(Argument::boolean(
+                "everywhere",
+                "Use it in every server instead of only this one",
+            ))
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            value: parsed
+                .get("value")
+                .ok_or("`value` is required.".to_string())?,
+            everywhere: parsed.get("everywhere").unwrap_or(false),
+        })
+    }
+}
+
+struct ScopeArguments {
+    everywhere: bool,
+}
+
+impl Arguments for ScopeArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(Argument::boolean(
+            "everywhere",
+            "Your prefix for every server instead of only this one",
+        ))
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            everywhere: parsed.get("everywhere").unwrap_or(false),
+        })
+    }
+}
+
+struct Set;
+
+#[utils::command(
+    name = "set",
+    description = "Set your personal prefix",
+    arguments = SetArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Set {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let arguments = match SetArguments::parse(&format!("{} set", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+        let guild_id = scope(&location, arguments.everywhere);
+        Ok(Some(
+            set(&ctx.data, arguments.value, guild_id, user_id(&user))
+                .await
+                .reply(),
+        ))
+    }
+}
+
+struct Remove;
+
+#[utils::command(
+    name = "remove",
+    description = "Remove your personal prefix",
+    arguments = ScopeArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Remove {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let arguments = match ScopeArguments::parse(&format!("{} remove", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+        let guild_id = scope(&location, arguments.everywhere);
+        Ok(Some(
+            remove(&ctx.data, guild_id, user_id(&user)).await.reply(),
+        ))
+    }
+}
+
+struct Get;
+
+#[utils::command(
+    name = "get",
+    description = "Get your personal prefix",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Get {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild_id = location.map(|(guild, _)| guild.id);
+        Ok(Some(get(&ctx.data, guild_id, user_id(&user)).await.reply()))
+    }
+}
+
+fn user_id(user: &UserType) -> UserId {
+    match user {
+        UserType::User(u) => u.id,
+        UserType::Member(m) => m.user.id,
+    }
+}
+
+// The server the prefix is for, `None` for the global one. Outside of servers it's always global.
+fn scope(location: &Option<(Guild, GuildChannel)>, everywhere: bool) -> Option<GuildId> {
+    match location {
+        Some((guild, _)) if !everywhere => Some(guild.id),
+        _ => None,
+    }
+}
+
+fn scope_name(guild_id: Option<GuildId>) -> &'static str {
+    match guild_id {
+        Some(_) => "in this server",
+        None => "everywhere",
+    }
+}
+
+async fn set(
+    data: &Data,
+    value: String,
+    guild_id: Option<GuildId>,
+    user_id: UserId,
+) -> CommandResponse {
+    if let Err(e) = validate_prefix(&value) {
+        return CommandResponse::new_content(e);
+    }
+    let data = data.read().await;
+    let prefixes = data
+        .get::<UserPrefixes>()
+        .expect("Expected UserPrefixes in TypeMap");
+
+    prefixes.insert(user_prefix_key(guild_id, user_id), value.clone());
+    save_user_prefixes(prefixes);
+    CommandResponse::new_content(format!(
+        "Your prefix {} is now `{}`",
+        scope_name(guild_id),
+        value
+    ))
+}
+
+async fn remove(data: &Data, guild_id: Option<GuildId>, user_id: UserId) -> CommandResponse {
+    let data = data.read().await;
+    let prefixes = data
+        .get::<UserPrefixes>()
+        .expect("Expected UserPrefixes in TypeMap");
+
+    match prefixes.remove_exact(&user_prefix_key(guild_id, user_id)) {
+        Some((_, value)) => {
+            save_user_prefixes(prefixes);
+            CommandResponse::new_content(format!(
+                "Removed your prefix `{}` {}",
+                value,
+                scope_name(guild_id)
+            ))
+        }
+        None => CommandResponse::new_content(format!(
+            "You don't have a prefix {}.",
+            scope_name(guild_id)
+        )),
+    }
+}
+
+async fn get(data: &Data, guild_id: Option<GuildId>, user_id: UserId) -> CommandResponse {
+    let data = data.read().await;
+    let prefixes = data
+        .get::<UserPrefixes>()
+        .expect("Expected UserPrefixes in TypeMap");
+
+    match user_prefix(prefixes, guild_id, user_id) {
+        Some(value) => CommandResponse::new_content(format!("Your prefix is `{}`", value)),
+        None => CommandResponse::new_content("You don't have a personal prefix.".to_string()),
+    }
+}
@@ -1,66 +1,94 @@
-use std::sync::Arc;
-
 use serenity::{
-    all::{
-        CommandDataOptionValue, CommandType, Context, CreateCommandOption, Guild, GuildChannel,
-        GuildId,
-    },
+    all::{CommandType, Context, Guild, GuildChannel, GuildId},
     async_trait,
 };
 
 use utils::{
-    BotPermission, CommandArguments, CommandResponse, CommandTemplate, CommandTrait, Data,
-    ICommand, LegacyOption, UserType, error,
+    Argument, ArgumentSchema, Arguments, BotPermission, CommandArguments, CommandResponse,
+    CommandTemplate, CommandTrait, Data, ICommand, ParsedArguments, UserType, error,
 };
 
-use crate::{ServerPrefix, ServerPrefixes};
+use crate::{
+    MAX_PREFIX_LENGTH, MAX_PREFIXES, ServerPrefix, ServerPrefixes, ServerPrefixesMap,
+    save_server_prefixes, server_prefixes,
+};
 
 const COMMAND_NAME: &str = "prefix";
-const COMMAND_DESCRIPTION: &str = "Server prefix.";
-
-pub struct Command;
+const COMMAND_DESCRIPTION: &str = "Server prefixes.";
 
 pub fn command() -> CommandTemplate {
-    let set_options = CreateCommandOption::new(
-        serenity::all::CommandOptionType::SubCommand,
-        "set",
-        "Set the server prefix",
+    ICommand::new(
+        COMMAND_NAME.to_string(),
+        COMMAND_DESCRIPTION.to_string(),
+        CommandType::ChatInput,
+        vec![],
+        vec![BotPermission::BotMaster],
     )
-    .add_sub_option(
-        CreateCommandOption::new(
-            serenity::all::CommandOptionType::String,
-            "value",
-            "The new prefix",
+    .subcommand(Set::node())
+    .subcommand(Add::node())
+    .subcommand(Remove::node())
+    .subcommand(Get::node())
+    // `prefix !` sets the prefix, as it did before subcommands.
+    .fallback("set")
+    .routed()
+}
+
+struct PrefixArguments {
+    value: String,
+}
+
+impl Arguments for PrefixArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::string("value", "The prefix")
+                .max_length(MAX_PREFIX_LENGTH as u16)
+                .required(),
         )
-        .required(true),
-    );
-
-    let remove_options = CreateCommandOption::new(
-        serenity::all::CommandOptionType::SubCommand,
-        "remove",
-        "Remove the custom server prefix",
-    );
-
-    let get_options = CreateCommandOption::new(
-        serenity::all::CommandOptionType::SubCommand,
-        "get",
-        "Get the current server prefix",
-    );
-
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![set_options, remove_options, get_options],
-            vec![BotPermission::BotMaster],
-        ),
-        Arc::new(Command),
-    )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            value: parsed
+                .get("value")
+                .ok_or("`value` is required.".to_string())?,
+        })
+    }
+}
+
+struct RemoveArguments {
+    value: Option<String>,
 }
 
+impl Arguments for RemoveArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::string(
+                "value",
+                "The prefix to remove, all custom prefixes when empty",
+            )
+            .max_length(MAX_PREFIX_LENGTH as u16),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            value: parsed.get("value"),
+        })
+    }
+}
+
+struct Set;
+
+#[utils::command(
+    name = "set",
+    description = "Replace the server prefixes with a single one",
+    arguments = PrefixArguments,
+    subcommand,
+    slash,
+    legacy
+)]
 #[async_trait]
-impl CommandTrait for Command {
+impl CommandTrait for Set {
     async fn execute<'a>(
         &self,
         ctx: &'a Context,
@@ -68,132 +96,243 @@ impl CommandTrait for Command {
         location: Option<(Guild, GuildChannel)>,
         args: CommandArguments<'a>,
     ) -> Result<Option<CommandResponse>, String> {
-        let Some((guild, _)) = location else {
-            error!("This command can only be used in a server");
-            return Err("This command can only be used in a server.".into());
+        let guild_id = guild_id(location)?;
+        let arguments = match PrefixArguments::parse(&format!("{} set", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
         };
-        let data = ctx.data.clone();
-        let res = match args {
-            CommandArguments::Slash(Some(options), _) => {
-                let options = options
-                    .iter()
-                    .map(|opt| (opt.0.as_str(), opt.1))
-                    .collect::<Vec<_>>();
-                match options.first() {
-                    Some(("set", CommandDataOptionValue::SubCommand(sub_cmd))) => {
-                        let CommandDataOptionValue::String(value) = sub_cmd[0].clone().value else {
-                            error!("Expected string value for prefix");
-                            panic!()
-                        };
-                        set(data, value, guild.id).await
-                    }
-                    Some(("remove", _)) => remove(data, guild.id).await,
-                    Some(("get", _)) => get(data, guild.id).await,
-                    _ => {
-                        error!("Unexpected command arguments");
-                        panic!()
-                    }
-                }
-            }
-            CommandArguments::Legacy(options, _) => {
-                let Some(options) = options else {
-                    return Ok(Some(get(data, guild.id).await));
-                };
-
-                match options.first() {
-                    Some(LegacyOption::Text(value)) if value == "remove" => {
-                        remove(data, guild.id).await
-                    }
-                    Some(LegacyOption::Text(value)) => set(data, value.clone(), guild.id).await,
-                    _ => get(data, guild.id).await,
-                }
-            }
-            _ => {
-                error!("Unexpected command arguments");
-                panic!()
-            }
+        Ok(Some(
+            set(ctx.data.clone(), arguments.value, guild_id)
+                .await
+                .reply(),
+        ))
+    }
+}
+
+struct Add;
+
+#[utils::command(
+    name = "add",
+    description = "Add a server prefix",
+    arguments = PrefixArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Add {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild_id = guild_id(location)?;
+        let arguments = match PrefixArguments::parse(&format!("{} add", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
         };
-        Ok(Some(res.reply()))
+        Ok(Some(
+            add(ctx.data.clone(), arguments.value, guild_id)
+                .await
+                .reply(),
+        ))
     }
-    fn is_legacy(&self) -> bool {
-        true
+}
+
+struct Remove;
+
+#[utils::command(
+    name = "remove",
+    description = "Remove a server prefix, or all custom ones",
+    arguments = RemoveArguments,
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Remove {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild_id = guild_id(location)?;
+        let arguments = match RemoveArguments::parse(&format!("{} remove", COMMAND_NAME), &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+        Ok(Some(
+            remove(ctx.data.clone(), arguments.value, guild_id)
+                .await
+                .reply(),
+        ))
     }
-    fn is_slash(&self) -> bool {
-        true
+}
+
+struct Get;
+
+#[utils::command(
+    name = "get",
+    description = "Get the current server prefixes",
+    subcommand,
+    slash,
+    legacy
+)]
+#[async_trait]
+impl CommandTrait for Get {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        _: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        _: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let guild_id = guild_id(location)?;
+        Ok(Some(get(ctx.data.clone(), guild_id).await.reply()))
     }
 }
 
-async fn set(data: Data, value: String, guild_id: GuildId) -> CommandResponse {
-    let data = data.read().await;
+fn guild_id(location: Option<(Guild, GuildChannel)>) -> Result<GuildId, String> {
+    match location {
+        Some((guild, _)) => Ok(guild.id),
+        None => {
+            error!("This command can only be used in a server");
+            Err("This command can only be used in a server.".into())
+        }
+    }
+}
 
-    let prefixes = data
-        .get::<ServerPrefixes>()
-        .cloned()
-        .expect("Expected ServerPrefixes in TypeMap");
+pub(super) fn validate_prefix(value: &str) -> Result<(), String> {
+    if value.is_empty() || value.chars().any(char::is_whitespace) {
+        return Err("A prefix can't be empty or contain spaces.".to_string());
+    }
+    if value.chars().count() > MAX_PREFIX_LENGTH {
+        return Err(format!(
+            "A prefix can't be longer than {} characters.",
+            MAX_PREFIX_LENGTH
+        ));
+    }
+    Ok(())
+}
 
-    let default_prefix = prefixes
-        .get(&ServerPrefix::Default)
-        .expect("Default prefix must be set")
-        .to_string();
+pub(super) fn list(prefixes: &[String]) -> String {
+    prefixes
+        .iter()
+        .map(|p| format!("`{}`", p))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
-    let previous_prefix = prefixes
-        .get(&ServerPrefix::Guild(guild_id))
-        .map(|s| s.to_string())
-        .unwrap_or(default_prefix.clone());
+async fn repo(data: &Data) -> std::sync::Arc<ServerPrefixesMap> {
+    data.read()
+        .await
+        .get::<ServerPrefixes>()
+        .cloned()
+        .expect("Expected ServerPrefixes in TypeMap")
+}
 
-    if value == default_prefix {
+// Keeps the default prefixes out of the map so servers follow them when they change.
+fn store(prefixes: &ServerPrefixesMap, guild_id: GuildId, value: Vec<String>) {
+    if value == server_prefixes(prefixes, None) {
         prefixes.remove(&ServerPrefix::Guild(guild_id));
+    } else {
+        prefixes.insert(ServerPrefix::Guild(guild_id), value);
+    }
+    save_server_prefixes(prefixes);
+}
 
-        CommandResponse::new_content(format!(
-            "Server prefix reset to default: `{}`",
-            default_prefix
-        ))
-    } else if value == previous_prefix {
-        CommandResponse::new_content(format!(
+async fn set(data: Data, value: String, guild_id: GuildId) -> CommandResponse {
+    if let Err(e) = validate_prefix(&value) {
+        return CommandResponse::new_content(e);
+    }
+    let prefixes = repo(&data).await;
+
+    let previous = server_prefixes(&prefixes, Some(guild_id));
+    if previous == [value.clone()] {
+        return CommandResponse::new_content(format!(
             "Server prefix is already set to: `{}`",
-            previous_prefix
-        ))
+            value
+        ));
+    }
+
+    store(&prefixes, guild_id, vec![value.clone()]);
+    if server_prefixes(&prefixes, None) == [value.clone()] {
+        CommandResponse::new_content(format!("Server prefix reset to default: `{}`", value))
     } else {
-        prefixes.insert(ServerPrefix::Guild(guild_id), value.clone());
         CommandResponse::new_content(format!("Server prefix set to: `{}`", value))
     }
 }
 
-async fn remove(data: Data, guild_id: GuildId) -> CommandResponse {
-    let data = data.read().await;
-    let prefixes = data
-        .get::<ServerPrefixes>()
-        .cloned()
-        .expect("Expected ServerPrefixes in TypeMap");
+async fn add(data: Data, value: String, guild_id: GuildId) -> CommandResponse {
+    if let Err(e) = validate_prefix(&value) {
+        return CommandResponse::new_content(e);
+    }
+    let prefixes = repo(&data).await;
 
-    let default_prefix = prefixes
-        .get(&ServerPrefix::Default)
-        .expect("Default prefix must be set")
-        .to_string();
-    if prefixes.remove(&ServerPrefix::Guild(guild_id)).is_none() {
+    let mut current = server_prefixes(&prefixes, Some(guild_id));
+    if current.contains(&value) {
+        return CommandResponse::new_content(format!("`{}` is already a server prefix.", value));
+    }
+    if current.len() >= MAX_PREFIXES {
         return CommandResponse::new_content(format!(
-            "Server prefix is already the default: `{}`",
-            default_prefix
+            "A server can't have more than {} prefixes.",
+            MAX_PREFIXES
         ));
     }
 
+    current.push(value.clone());
+    store(&prefixes, guild_id, current.clone());
     CommandResponse::new_content(format!(
-        "Server prefix reset to default: `{}`",
-        default_prefix
+        "Added `{}`, the server prefixes are: {}",
+        value,
+        list(&current)
     ))
 }
 
-async fn get(data: Data, guild_id: GuildId) -> CommandResponse {
-    let data = data.read().await;
-    let prefix_repo = data
-        .get::<ServerPrefixes>()
-        .expect("Expected ServerPrefixes in TypeMap");
+async fn remove(data: Data, value: Option<String>, guild_id: GuildId) -> CommandResponse {
+    let prefixes = repo(&data).await;
+    let default_prefixes = server_prefixes(&prefixes, None);
+
+    let Some(value) = value else {
+        if prefixes.remove(&ServerPrefix::Guild(guild_id)).is_none() {
+            return CommandResponse::new_content(format!(
+                "Server prefixes are already the default: {}",
+                list(&default_prefixes)
+            ));
+        }
+        save_server_prefixes(&prefixes);
+        return CommandResponse::new_content(format!(
+            "Server prefixes reset to default: {}",
+            list(&default_prefixes)
+        ));
+    };
+
+    let mut current = server_prefixes(&prefixes, Some(guild_id));
+    if !current.contains(&value) {
+        return CommandResponse::new_content(format!("`{}` is not a server prefix.", value));
+    }
+    if current.len() == 1 {
+        return CommandResponse::new_content(
+            "A server needs at least one prefix, use `set` to replace it.".to_string(),
+        );
+    }
+
+    current.retain(|p| *p != value);
+    store(&prefixes, guild_id, current.clone());
+    CommandResponse::new_content(format!(
+        "Removed `{}`, the server prefixes are: {}",
+        value,
+        list(&current)
+    ))
+}
 
-    let prefix = prefix_repo
-        .get(&ServerPrefix::Guild(guild_id))
-        .or(prefix_repo.get(&ServerPrefix::Default))
-        .expect("Default prefix must be set")
-        .to_string();
+async fn get(data: Data, guild_id: GuildId) -> CommandResponse {
+    let prefixes = repo(&data).await;
+    let prefixes = server_prefixes(&prefixes, Some(guild_id));
 
-    CommandResponse::new_content(format!("Current server prefix: `{}`", prefix))
+    CommandResponse::new_content(format!("Current server prefixes: {}", list(&prefixes)))
 }
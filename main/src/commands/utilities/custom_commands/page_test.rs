@@ -1,39 +1,23 @@
-use std::sync::Arc;
-
 use serenity::{
     all::{
-        AutocompleteOption, Channel, ChannelId, CommandInteraction, CommandType, Context,
-        CreateActionRow, CreateButton, CreateEmbed, Guild, GuildChannel, GuildId, Member,
-        ReactionType, User,
+        AutocompleteOption, Channel, ChannelId, CommandInteraction, Context, CreateActionRow,
+        CreateButton, CreateEmbed, Guild, GuildChannel, GuildId, Member, ReactionType, User,
     },
     async_trait,
 };
 
-use utils::{
-    AutocompleteResponse, CommandArguments, CommandResponse, CommandTemplate, CommandTrait,
-    ICommand, UserType,
-};
+use utils::{AutocompleteResponse, CommandArguments, CommandResponse, CommandTrait, UserType};
 
 use crate::Paginations;
 
-const COMMAND_NAME: &str = "testpages";
-const COMMAND_DESCRIPTION: &str = "Test pagination with multiple embeds.";
-
 pub struct Command;
 
-pub fn command() -> CommandTemplate {
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![],
-            vec![],
-        ),
-        Arc::new(Command),
-    )
-}
-
+#[utils::command(
+    name = "testpages",
+    description = "Test pagination with multiple embeds.",
+    slash,
+    legacy
+)]
 #[async_trait]
 impl CommandTrait for Command {
     async fn execute<'a>(
@@ -67,11 +51,4 @@ impl CommandTrait for Command {
             CommandResponse::new_embeds(vec![pages.0]).components(vec![pages.1]),
         ))
     }
-
-    fn is_legacy(&self) -> bool {
-        true
-    }
-    fn is_slash(&self) -> bool {
-        true
-    }
 }
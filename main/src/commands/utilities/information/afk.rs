@@ -1,43 +1,17 @@
-use std::sync::Arc;
-
 use serenity::{
-    all::{CommandType, Context, Guild, GuildChannel},
+    all::{Context, Guild, GuildChannel},
     async_trait,
 };
 
-use utils::{
-    CommandArguments, CommandResponse, CommandTemplate, CommandTrait, ICommand, UserGlobalType,
-    UserType,
-};
+use utils::{CommandArguments, CommandResponse, CommandTrait, UserGlobalType, UserType};
 
 use crate::{UserAFK, UserAFKData};
 
-const COMMAND_NAME: &str = "afk";
-const COMMAND_DESCRIPTION: &str = "Set your AFK status";
-
 pub struct Command;
 
-pub fn command() -> CommandTemplate {
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![],
-            vec![],
-        ),
-        Arc::new(Command),
-    )
-}
-
+#[utils::command(name = "afk", description = "Set your AFK status", slash, legacy)]
 #[async_trait]
 impl CommandTrait for Command {
-    fn is_legacy(&self) -> bool {
-        true
-    }
-    fn is_slash(&self) -> bool {
-        true
-    }
     async fn execute<'a>(
         &self,
         ctx: &'a Context,
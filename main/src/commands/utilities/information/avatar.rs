@@ -1,40 +1,34 @@
-use std::sync::Arc;
-
 use serenity::{
     all::{
-        Colour, CommandOptionType, CommandType, Context, CreateCommandOption, CreateEmbed, Guild,
-        GuildChannel,
+        Colour, CommandOptionType, Context, CreateCommandOption, CreateEmbed, Guild, GuildChannel,
     },
     async_trait,
 };
 
-use utils::{CommandArguments, CommandResponse, CommandTemplate, CommandTrait, ICommand, UserType};
+use utils::{CommandArguments, CommandResponse, CommandTrait, UserType};
 
 use crate::commands::command_user_target;
 
-const COMMAND_NAME: &str = "avatar";
-const COMMAND_DESCRIPTION: &str = "Get the avatar of a user";
-
 pub struct Command;
 
-pub fn command() -> CommandTemplate {
+fn options() -> Vec<CreateCommandOption> {
     let user_option = CreateCommandOption::new(
         CommandOptionType::User,
         "user",
         "The user to get the avatar of",
     );
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![user_option],
-            vec![],
-        ),
-        Arc::new(Command),
-    )
+
+    vec![user_option]
 }
 
+#[utils::command(
+    name = "avatar",
+    description = "Get the avatar of a user",
+    options = options(),
+    aliases = ["av", "pfp"],
+    slash,
+    legacy
+)]
 #[async_trait]
 impl CommandTrait for Command {
     async fn execute<'a>(
@@ -57,10 +51,4 @@ impl CommandTrait for Command {
             .color(Colour::BLITZ_BLUE);
         Ok(Some(CommandResponse::new_embeds(vec![embed])))
     }
-    fn is_legacy(&self) -> bool {
-        true
-    }
-    fn is_slash(&self) -> bool {
-        true
-    }
 }
@@ -1,40 +1,33 @@
-use std::sync::Arc;
-
 use serenity::{
     all::{
-        Colour, CommandOptionType, CommandType, Context, CreateCommandOption, CreateEmbed, Guild,
-        GuildChannel,
+        Colour, CommandOptionType, Context, CreateCommandOption, CreateEmbed, Guild, GuildChannel,
     },
     async_trait,
 };
 
-use utils::{CommandArguments, CommandResponse, CommandTemplate, CommandTrait, ICommand, UserType};
+use utils::{CommandArguments, CommandResponse, CommandTrait, UserType};
 
 use crate::commands::command_user_target;
 
-const COMMAND_NAME: &str = "banner";
-const COMMAND_DESCRIPTION: &str = "Get the banner of a user";
-
 pub struct Command;
 
-pub fn command() -> CommandTemplate {
+fn options() -> Vec<CreateCommandOption> {
     let user_option = CreateCommandOption::new(
         CommandOptionType::User,
         "user",
         "The user to get the banner of",
     );
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![user_option],
-            vec![],
-        ),
-        Arc::new(Command),
-    )
+
+    vec![user_option]
 }
 
+#[utils::command(
+    name = "banner",
+    description = "Get the banner of a user",
+    options = options(),
+    slash,
+    legacy
+)]
 #[async_trait]
 impl CommandTrait for Command {
     async fn execute<'a>(
@@ -60,10 +53,4 @@ impl CommandTrait for Command {
             CommandResponse::new_content(format!("{} does not have a banner set.", target.tag()))
         }))
     }
-    fn is_legacy(&self) -> bool {
-        true
-    }
-    fn is_slash(&self) -> bool {
-        true
-    }
 }
@@ -1,45 +1,36 @@
-use std::sync::Arc;
-
 use serenity::{
     all::{
         AutocompleteOption, Channel, ChannelId, Colour, CommandInteraction, CommandOptionType,
-        CommandType, Context, CreateCommandOption, CreateEmbed, FormattedTimestamp,
-        FormattedTimestampStyle, Guild, GuildChannel, GuildId, Member, User,
+        Context, CreateCommandOption, CreateEmbed, FormattedTimestamp, FormattedTimestampStyle,
+        Guild, GuildChannel, GuildId, Member, User,
     },
     async_trait,
     futures::channel,
 };
 
-use utils::{
-    AutocompleteResponse, CommandArguments, CommandResponse, CommandTemplate, CommandTrait,
-    ICommand, UserType,
-};
+use utils::{AutocompleteResponse, CommandArguments, CommandResponse, CommandTrait, UserType};
 
 use crate::commands::command_channel_target;
 
-const COMMAND_NAME: &str = "channel";
-const COMMAND_DESCRIPTION: &str = "View information about a channel";
-
 pub struct Command;
 
-pub fn command() -> CommandTemplate {
+fn options() -> Vec<CreateCommandOption> {
     let channel_option = CreateCommandOption::new(
         CommandOptionType::Channel,
         "channel",
         "The channel to get information about",
     );
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![channel_option],
-            vec![],
-        ),
-        Arc::new(Command),
-    )
+
+    vec![channel_option]
 }
 
+#[utils::command(
+    name = "channel",
+    description = "View information about a channel",
+    options = options(),
+    slash,
+    legacy
+)]
 #[async_trait]
 impl CommandTrait for Command {
     async fn execute<'a>(
@@ -83,12 +74,6 @@ impl CommandTrait for Command {
 
         Ok(Some(CommandResponse::new_embeds(vec![embed])))
     }
-    fn is_legacy(&self) -> bool {
-        true
-    }
-    fn is_slash(&self) -> bool {
-        true
-    }
 }
 
 fn fetch_category_name(guild: &Guild, channel: &GuildChannel) -> Option<String> {
@@ -1,24 +1,16 @@
-use std::sync::Arc;
-
 use serenity::{
     all::{
-        AutocompleteOption, Channel, ChannelId, CommandInteraction, CommandType, Context,
-        CreateCommandOption, Guild, GuildChannel, GuildId, Member, User,
+        AutocompleteOption, Channel, ChannelId, CommandInteraction, Context, CreateCommandOption,
+        Guild, GuildChannel, GuildId, Member, User,
     },
     async_trait,
 };
 
-use utils::{
-    AutocompleteResponse, CommandArguments, CommandResponse, CommandTemplate, CommandTrait,
-    ICommand, UserType,
-};
-
-const COMMAND_NAME: &str = "guild";
-const COMMAND_DESCRIPTION: &str = "View information about a server";
+use utils::{AutocompleteResponse, CommandArguments, CommandResponse, CommandTrait, UserType};
 
 pub struct Command;
 
-pub fn command() -> CommandTemplate {
+fn options() -> Vec<CreateCommandOption> {
     let icon_option = CreateCommandOption::new(
         serenity::all::CommandOptionType::SubCommand,
         "icon",
@@ -29,18 +21,17 @@ pub fn command() -> CommandTemplate {
         "banner",
         "View the server banner",
     );
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![],
-            vec![],
-        ),
-        Arc::new(Command),
-    )
+
+    vec![]
 }
 
+#[utils::command(
+    name = "guild",
+    description = "View information about a server",
+    options = options(),
+    slash,
+    autocomplete
+)]
 #[async_trait]
 impl CommandTrait for Command {
     async fn execute<'a>(
@@ -65,13 +56,4 @@ impl CommandTrait for Command {
 
         Some(response)
     }
-    fn is_legacy(&self) -> bool {
-        false
-    }
-    fn is_slash(&self) -> bool {
-        true
-    }
-    fn supports_autocomplete(&self) -> bool {
-        true
-    }
 }
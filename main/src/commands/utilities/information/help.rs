@@ -0,0 +1,479 @@
+use serenity::{
+    all::{
+        AutocompleteOption, Colour, CommandInteraction, CommandType, Context, CreateEmbed,
+        CreateEmbedFooter, Guild, GuildChannel, GuildId, UserId,
+    },
+    async_trait,
+};
+
+use utils::{
+    Argument, ArgumentSchema, Arguments, AutocompleteResponse, BotPermission, CommandArguments,
+    CommandResponse, CommandTrait, ParsedArguments, UserType, truncate,
+};
+
+use crate::{
+    CommandAliases, CommandHelp, CommandHelpMap, CommandSettings, Commands, CommandsMap,
+    GuildCommandSettings, HelpEntry, MODULES, Paginations, ServerPrefixes, find_command,
+    handler::commands::missing_permissions, server_prefixes,
+};
+
+// Keeps pages short enough to read at a glance, well under the embed limit.
+const PAGE_LENGTH: usize = 1500;
+// Command names are at most 32 characters, context menus included.
+const MAX_NAME_LENGTH: usize = 32;
+
+pub struct Command;
+
+struct HelpArguments {
+    command: Option<String>,
+}
+
+impl Arguments for HelpArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new().argument(
+            Argument::string("command", "A command to show the details of")
+                .rest()
+                .max_length(MAX_NAME_LENGTH as u16)
+                .autocomplete(),
+        )
+    }
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String> {
+        Ok(Self {
+            command: parsed.get("command"),
+        })
+    }
+}
+
+// A command the user can run, with the module it belongs to and the permissions it needs.
+struct Visible {
+    entry: HelpEntry,
+    module: &'static str,
+    permissions: Vec<BotPermission>,
+}
+
+#[utils::command(
+    name = "help",
+    description = "List the commands you can use, or the details of one.",
+    arguments = HelpArguments,
+    aliases = ["h"],
+    slash,
+    legacy,
+    autocomplete
+)]
+#[async_trait]
+impl CommandTrait for Command {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let arguments = match HelpArguments::parse(Command::NAME, &args) {
+            Ok(arguments) => arguments,
+            Err(e) => return Ok(Some(e.response())),
+        };
+
+        let guild = location.as_ref().map(|(guild, _)| guild.id);
+        let prefix = {
+            let data = ctx.data.read().await;
+            let prefixes = data
+                .get::<ServerPrefixes>()
+                .expect("Expected ServerPrefixes in TypeMap");
+            server_prefixes(prefixes, guild).remove(0)
+        };
+        let commands = visible(ctx, &user, &location).await;
+
+        let Some(name) = arguments.command else {
+            let user_id = match &user {
+                UserType::User(u) => u.id,
+                UserType::Member(m) => m.user.id,
+            };
+            return pages(ctx, user_id, &commands, &prefix).await.map(Some);
+        };
+
+        let Some(name) = resolve(ctx, &location, &name).await else {
+            return Ok(Some(
+                CommandResponse::new_content(format!(
+                    "There's no command named `{}`.",
+                    truncate(&name, MAX_NAME_LENGTH)
+                ))
+                .reply(),
+            ));
+        };
+        let Some(command) = commands.iter().find(|c| c.entry.name == name) else {
+            return Ok(Some(
+                CommandResponse::new_content(format!("You can't use `{}` here.", name)).reply(),
+            ));
+        };
+        let server_aliases = match guild {
+            Some(guild_id) => server_aliases(ctx, guild_id, &name).await,
+            None => vec![],
+        };
+        Ok(Some(
+            CommandResponse::new_embeds(vec![details(command, &server_aliases, &prefix)]).reply(),
+        ))
+    }
+
+    async fn autocomplete<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        focused: AutocompleteOption<'a>,
+        _: &'a CommandInteraction,
+    ) -> Option<AutocompleteResponse> {
+        let value = focused.value.to_lowercase();
+        let mut response = AutocompleteResponse::new();
+        for command in visible(ctx, &user, &location)
+            .await
+            .into_iter()
+            .filter(|c| c.entry.name.to_lowercase().contains(&value))
+            .take(25)
+        {
+            response.insert(command.entry.name.clone(), command.entry.name.into());
+        }
+        Some(response)
+    }
+}
+
+// The commands the user could run here.
+async fn visible(
+    ctx: &Context,
+    user: &UserType,
+    location: &Option<(Guild, GuildChannel)>,
+) -> Vec<Visible> {
+    let data = ctx.data.read().await;
+    let commands = data.get::<Commands>().expect("Commands not initialized");
+    let help = data
+        .get::<CommandHelp>()
+        .expect("CommandHelp not initialized");
+    let settings = location
+        .as_ref()
+        .and_then(|(guild, _)| {
+            data.get::<CommandSettings>()
+                .and_then(|s| s.get(&guild.id).map(|s| s.clone()))
+        })
+        .unwrap_or_default();
+    visible_commands(commands, help, &settings, user, location)
+}
+
+// The commands that are enabled, not restricted away from the user and within their permissions,
+// by module and then by name. Outside of servers only the commands that need no permissions are
+// listed.
+fn visible_commands(
+    commands: &CommandsMap,
+    help: &CommandHelpMap,
+    settings: &GuildCommandSettings,
+    user: &UserType,
+    location: &Option<(Guild, GuildChannel)>,
+) -> Vec<Visible> {
+    let roles = match user {
+        UserType::Member(member) => member.roles.clone(),
+        UserType::User(_) => vec![],
+    };
+    let channel = location.as_ref().map(|(_, channel)| channel);
+
+    let mut visible = commands
+        .iter()
+        .filter(|(name, (_, permissions, module))| {
+            settings.blocked(name, module, channel, &roles).is_none()
+                && missing_permissions(permissions, user, location).is_none()
+        })
+        .filter_map(|(name, (_, permissions, module))| {
+            Some(Visible {
+                entry: help.get(name)?.clone(),
+                module,
+                permissions: permissions.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+    visible.sort_by_key(|c| {
+        (
+            MODULES.iter().position(|m| *m == c.module),
+            c.entry.name.to_lowercase(),
+        )
+    });
+    visible
+}
+
+// The command a name stands for, like a legacy message would use it. Context menu names have
+// spaces so they're also matched ignoring case.
+async fn resolve(
+    ctx: &Context,
+    location: &Option<(Guild, GuildChannel)>,
+    name: &str,
+) -> Option<String> {
+    let name = name.trim().trim_start_matches('/');
+    let data = ctx.data.read().await;
+    let commands = data.get::<Commands>().expect("Commands not initialized");
+    let aliases = data
+        .get::<CommandAliases>()
+        .expect("CommandAliases not initialized");
+    let settings = location.as_ref().and_then(|(guild, _)| {
+        data.get::<CommandSettings>()
+            .and_then(|s| s.get(&guild.id).map(|s| s.clone()))
+    });
+    find_command(commands, aliases, settings.as_ref(), name).or_else(|| {
+        commands
+            .keys()
+            .find(|command| command.eq_ignore_ascii_case(name))
+            .cloned()
+    })
+}
+
+// The aliases a server added for a command.
+async fn server_aliases(ctx: &Context, guild_id: GuildId, name: &str) -> Vec<String> {
+    let data = ctx.data.read().await;
+    data.get::<CommandSettings>()
+        .and_then(|s| s.get(&guild_id).map(|s| s.clone()))
+        .map(|settings| {
+            settings
+                .aliases
+                .into_iter()
+                .filter(|(_, command)| command == name)
+                .map(|(alias, _)| alias)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Each way to use a command once, with the syntaxes it works with, and what it does.
+fn usage(entry: &HelpEntry, prefix: &str) -> Vec<(String, String)> {
+    let target = match entry.kind {
+        CommandType::User => Some("user"),
+        CommandType::Message => Some("message"),
+        _ => None,
+    };
+    if let Some(target) = target {
+        return vec![(
+            format!("Right click a {} › Apps › {}", target, entry.name),
+            entry.description.clone(),
+        )];
+    }
+
+    let mut lines: Vec<(String, String)> = vec![];
+    let mut seen: Vec<&str> = vec![];
+    for (line, description) in entry.legacy.iter().chain(&entry.slash) {
+        if seen.contains(&line.as_str()) {
+            continue;
+        }
+        seen.push(line);
+        let mut syntaxes = vec![];
+        if entry.legacy.iter().any(|(l, _)| l == line) {
+            syntaxes.push(format!("`{}{}`", prefix, line));
+        }
+        if entry.slash.iter().any(|(l, _)| l == line) {
+            syntaxes.push(format!("`/{}`", line));
+        }
+        lines.push((syntaxes.join(" · "), description.clone()));
+    }
+    lines
+}
+
+// One page per module, split when a module has too many commands to read at once.
+async fn pages(
+    ctx: &Context,
+    user_id: UserId,
+    commands: &[Visible],
+    prefix: &str,
+) -> Result<CommandResponse, String> {
+    let mut pages: Vec<(&str, String)> = vec![];
+    for module in MODULES {
+        let mut page = String::new();
+        for command in commands.iter().filter(|c| c.module == module) {
+            let mut block = if command.entry.description.is_empty() {
+                format!("**{}**", command.entry.name)
+            } else {
+                format!("**{}** — {}", command.entry.name, command.entry.description)
+            };
+            for (line, _) in usage(&command.entry, prefix) {
+                block.push('\n');
+                block.push_str(&line);
+            }
+            if !page.is_empty() && page.len() + block.len() > PAGE_LENGTH {
+                pages.push((module, std::mem::take(&mut page)));
+            }
+            if !page.is_empty() {
+                page.push_str("\n\n");
+            }
+            page.push_str(&block);
+        }
+        if !page.is_empty() {
+            pages.push((module, page));
+        }
+    }
+
+    if pages.is_empty() {
+        return Ok(CommandResponse::new_content("There are no commands you can use here.").reply());
+    }
+
+    let total = pages.len();
+    let embeds = pages
+        .into_iter()
+        .enumerate()
+        .map(|(index, (module, page))| {
+            CreateEmbed::default()
+                .title(format!("Help · {}", capitalize(module)))
+                .description(page)
+                .footer(CreateEmbedFooter::new(format!(
+                    "Page {}/{} · {}help <command> for details",
+                    index + 1,
+                    total,
+                    prefix
+                )))
+                .color(Colour::BLITZ_BLUE)
+        })
+        .collect::<Vec<_>>();
+
+    let response = CommandResponse::default().reply();
+    if embeds.len() == 1 {
+        return Ok(response.embeds(embeds));
+    }
+    let data = ctx.data.read().await;
+    let (embed, components) = data
+        .get::<Paginations>()
+        .ok_or("Failed to get paginations data.".to_string())?
+        .insert(embeds, user_id.get())
+        .await;
+    Ok(response.embeds(vec![embed]).components(vec![components]))
+}
+
+fn details(command: &Visible, server_aliases: &[String], prefix: &str) -> CreateEmbed {
+    let entry = &command.entry;
+    let lines = usage(entry, prefix)
+        .into_iter()
+        .map(|(line, description)| {
+            if description == entry.description {
+                line
+            } else {
+                format!("{} — {}", line, description)
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut description = entry.description.clone();
+    if !description.is_empty() {
+        description.push_str("\n\n");
+    }
+    description.push_str(&format!("**Usage**\n{}", lines.join("\n")));
+
+    let mut embed = CreateEmbed::default()
+        .title(&entry.name)
+        .description(description)
+        .field("Module", capitalize(command.module), true)
+        .footer(CreateEmbedFooter::new("<required> [optional]"))
+        .color(Colour::BLITZ_BLUE);
+
+    let aliases = entry
+        .aliases
+        .iter()
+        .chain(server_aliases)
+        .map(|alias| format!("`{}`", alias))
+        .collect::<Vec<_>>();
+    if !aliases.is_empty() {
+        embed = embed.field("Aliases", aliases.join(", "), true);
+    }
+    if !command.permissions.is_empty() {
+        let permissions = command
+            .permissions
+            .iter()
+            .map(|p| format!("`{}`", p))
+            .collect::<Vec<_>>();
+        embed = embed.field("Permissions", permissions.join(", "), true);
+    }
+    embed
+}
+
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use serenity::all::User;
+
+    use super::*;
+
+    fn entry(name: &str) -> HelpEntry {
+        HelpEntry {
+            name: name.to_string(),
+            description: String::new(),
+            kind: CommandType::ChatInput,
+            aliases: vec![],
+            legacy: vec![],
+            slash: vec![],
+        }
+    }
+
+    // Each command is `(name, module, permissions)`.
+    fn maps(
+        commands: &[(&str, &'static str, Vec<BotPermission>)],
+    ) -> (CommandsMap, CommandHelpMap) {
+        let mut map = CommandsMap::new();
+        let mut help = HashMap::new();
+        for (name, module, permissions) in commands {
+            let handler: Arc<dyn CommandTrait> = Arc::new(Command);
+            map.insert(name.to_string(), (handler, permissions.clone(), *module));
+            help.insert(name.to_string(), entry(name));
+        }
+        (map, help)
+    }
+
+    fn names(visible: &[Visible]) -> Vec<&str> {
+        visible.iter().map(|c| c.entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn hides_commands_the_user_cant_run() {
+        let (commands, help) = maps(&[
+            ("ping", "utilities", vec![]),
+            ("ban", "security", vec![BotPermission::BanMembers]),
+            ("meme", "fun", vec![]),
+        ]);
+        let user = UserType::User(User::default());
+        let settings = GuildCommandSettings::default();
+        // Outside of servers permissions can't be granted.
+        assert_eq!(
+            names(&visible_commands(&commands, &help, &settings, &user, &None)),
+            ["meme", "ping"]
+        );
+
+        let settings = GuildCommandSettings {
+            disabled_modules: vec!["fun".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            names(&visible_commands(&commands, &help, &settings, &user, &None)),
+            ["ping"]
+        );
+    }
+
+    #[test]
+    fn sorts_by_module_then_name() {
+        let (commands, help) = maps(&[
+            ("Zoo", "utilities", vec![]),
+            ("avatar", "utilities", vec![]),
+            ("meme", "fun", vec![]),
+            ("birthday", "server", vec![]),
+            ("Ask", "fun", vec![]),
+        ]);
+        let user = UserType::User(User::default());
+        assert_eq!(
+            names(&visible_commands(
+                &commands,
+                &help,
+                &GuildCommandSettings::default(),
+                &user,
+                &None
+            )),
+            ["birthday", "Ask", "meme", "avatar", "Zoo"]
+        );
+    }
+}
@@ -1,44 +1,35 @@
-use std::sync::Arc;
-
 use serenity::{
     all::{
-        Colour, CommandOptionType, CommandType, Context, CreateCommandOption, CreateEmbed,
-        CreateEmbedAuthor, FormattedTimestamp, FormattedTimestampStyle, Guild, GuildChannel,
-        Member, Mentionable, Permissions, Role,
+        Colour, CommandOptionType, Context, CreateCommandOption, CreateEmbed, CreateEmbedAuthor,
+        FormattedTimestamp, FormattedTimestampStyle, Guild, GuildChannel, Member, Mentionable,
+        Permissions, Role,
     },
     async_trait,
 };
 
-use utils::{
-    CommandArguments, CommandResponse, CommandTemplate, CommandTrait, ICommand,
-    PERMISSION_PRIORITY, UserType,
-};
+use utils::{CommandArguments, CommandResponse, CommandTrait, PERMISSION_PRIORITY, UserType};
 
 use crate::commands::command_member_target;
 
-const COMMAND_NAME: &str = "info";
-const COMMAND_DESCRIPTION: &str = "Get information about a user";
-
 pub struct Command;
 
-pub fn command() -> CommandTemplate {
+fn options() -> Vec<CreateCommandOption> {
     let user_option = CreateCommandOption::new(
         CommandOptionType::User,
         "user",
         "The user to get the banner of",
     );
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![user_option],
-            vec![],
-        ),
-        Arc::new(Command),
-    )
+
+    vec![user_option]
 }
 
+#[utils::command(
+    name = "info",
+    description = "Get information about a user",
+    options = options(),
+    slash,
+    legacy
+)]
 #[async_trait]
 impl CommandTrait for Command {
     async fn execute<'a>(
@@ -99,12 +90,6 @@ impl CommandTrait for Command {
 
         Ok(Some(CommandResponse::new_embeds(vec![embed])))
     }
-    fn is_legacy(&self) -> bool {
-        true
-    }
-    fn is_slash(&self) -> bool {
-        true
-    }
 }
 
 fn get_permissions<'a>(permissions: &Permissions) -> (&'a str, String, bool) {
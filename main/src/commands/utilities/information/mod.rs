@@ -3,12 +3,15 @@ mod avatar;
 mod banner;
 mod channel;
 mod guild;
+mod help;
 mod info;
 mod ping;
 mod serverinfo;
+mod view_avatar;
 
 pub fn get_commands() -> Vec<utils::CommandTemplate> {
     vec![
+        help::command(),
         ping::command(),
         afk::command(),
         avatar::command(),
@@ -17,5 +20,6 @@ pub fn get_commands() -> Vec<utils::CommandTemplate> {
         channel::command(),
         guild::command(),
         serverinfo::command(),
+        view_avatar::command(),
     ]
 }
@@ -1,46 +1,26 @@
-use std::sync::Arc;
-
 use chrono::Utc;
 use serenity::{
     all::{
-        CacheHttp, CommandType, Context, CreateInteractionResponse,
-        CreateInteractionResponseMessage, EditInteractionResponse, EditMessage, Guild,
-        GuildChannel,
+        CacheHttp, Context, CreateInteractionResponse, CreateInteractionResponseMessage,
+        EditInteractionResponse, EditMessage, Guild, GuildChannel,
     },
     async_trait,
 };
-use utils::{
-    CommandArguments, CommandResponse, CommandTemplate, CommandTrait, ICommand, UserType, error,
-};
+use utils::{CommandArguments, CommandResponse, CommandTrait, UserType, error};
 
 use crate::{ElapsedTime, ShardManagerContainer};
 
-const COMMAND_NAME: &str = "ping";
-const COMMAND_DESCRIPTION: &str = "Ping the bot to check latency";
-
 pub struct Command;
 
-pub fn command() -> CommandTemplate {
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![],
-            vec![],
-        ),
-        Arc::new(Command),
-    )
-}
-
+#[utils::command(
+    name = "ping",
+    description = "Ping the bot to check latency",
+    cooldown = 5,
+    slash,
+    legacy
+)]
 #[async_trait]
 impl CommandTrait for Command {
-    fn is_slash(&self) -> bool {
-        true
-    }
-    fn is_legacy(&self) -> bool {
-        true
-    }
     async fn execute<'a>(
         &self,
         ctx: &'a Context,
@@ -1,35 +1,23 @@
-use std::sync::Arc;
-
 use serenity::{
     all::{
-        Colour, CommandType, Context, CreateEmbed, FormattedTimestamp, FormattedTimestampStyle,
-        Guild, GuildChannel, VerificationLevel,
+        Colour, Context, CreateEmbed, FormattedTimestamp, FormattedTimestampStyle, Guild,
+        GuildChannel, VerificationLevel,
     },
     async_trait,
 };
 
-use utils::{CommandArguments, CommandResponse, CommandTemplate, CommandTrait, ICommand, UserType};
+use utils::{CommandArguments, CommandResponse, CommandTrait, UserType};
 
 use crate::ShardManagerContainer;
 
-const COMMAND_NAME: &str = "serverinfo";
-const COMMAND_DESCRIPTION: &str = "View information about the server";
-
 pub struct Command;
 
-pub fn command() -> CommandTemplate {
-    (
-        ICommand::new(
-            COMMAND_NAME.to_string(),
-            COMMAND_DESCRIPTION.to_string(),
-            CommandType::ChatInput,
-            vec![],
-            vec![],
-        ),
-        Arc::new(Command),
-    )
-}
-
+#[utils::command(
+    name = "serverinfo",
+    description = "View information about the server",
+    slash,
+    legacy
+)]
 #[async_trait]
 impl CommandTrait for Command {
     async fn execute<'a>(
@@ -71,13 +59,6 @@ impl CommandTrait for Command {
 
         Ok(Some(CommandResponse::new_embeds(vec![embed])))
     }
-
-    fn is_legacy(&self) -> bool {
-        true
-    }
-    fn is_slash(&self) -> bool {
-        true
-    }
 }
 
 async fn get_description(ctx: &Context, guild: &Guild) -> String {
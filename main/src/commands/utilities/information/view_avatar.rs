@@ -0,0 +1,30 @@
+use serenity::{
+    all::{Colour, Context, CreateEmbed, Guild, GuildChannel, ResolvedTarget},
+    async_trait,
+};
+
+use utils::{CommandArguments, CommandResponse, CommandTrait, UserType};
+
+pub struct Command;
+
+#[utils::command(name = "View avatar", kind = User)]
+#[async_trait]
+impl CommandTrait for Command {
+    async fn execute<'a>(
+        &self,
+        _: &'a Context,
+        _: UserType,
+        _: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        let Some(ResolvedTarget::User(target, _)) = args.target() else {
+            return Err("No user was targeted.".to_string());
+        };
+
+        let embed = CreateEmbed::default()
+            .title(format!("Avatar of {}", target.tag()))
+            .image(target.face())
+            .color(Colour::BLITZ_BLUE);
+        Ok(Some(CommandResponse::new_embeds(vec![embed]).ephemeral()))
+    }
+}
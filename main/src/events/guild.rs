@@ -1,5 +1,27 @@
-use serenity::all::{Context, Guild, UnavailableGuild};
+use serenity::all::{
+    Context, Guild, GuildId, GuildMemberUpdateEvent, Member, UnavailableGuild, User,
+};
 
-pub async fn create(ctx: Context, guild: Guild) {}
+use crate::{analytics, boosts, reactions, sync_guild_commands};
+
+pub async fn create(ctx: Context, guild: Guild) {
+    boosts::sync_guild(&ctx, &guild).await;
+    reactions::sync_guild(&ctx, &guild).await;
+    analytics::sync_guild(&ctx, &guild).await;
+    sync_guild_commands(&ctx.http, &ctx.data, guild.id).await;
+}
 
 pub async fn delete(ctx: Context, guild: UnavailableGuild) {}
+
+pub async fn member_update(ctx: Context, event: GuildMemberUpdateEvent) {
+    boosts::member_update(&ctx, &event).await;
+}
+
+pub async fn member_addition(ctx: Context, member: Member) {
+    analytics::record_join(&ctx, member.guild_id).await;
+}
+
+pub async fn member_removal(ctx: Context, guild_id: GuildId, user: User) {
+    analytics::record_leave(&ctx, guild_id).await;
+    boosts::member_removal(&ctx, guild_id, user.id).await;
+}
@@ -4,12 +4,11 @@ use serenity::all::{
 };
 use utils::parse_button_id;
 
-use crate::{Paginations, extras, handler::commands, snipes, user_afk};
+use crate::{
+    Paginations, analytics, extras, handler::commands, message_log, reactions, snipes, user_afk,
+};
 
 pub async fn create(ctx: Context, message: Message) {
-    let Some(guild_id) = message.guild_id else {
-        return;
-    };
     if message.author.bot {
         let current_bot = ctx.cache.current_user().clone();
         if message.author.id == current_bot.id {
@@ -18,8 +17,17 @@ pub async fn create(ctx: Context, message: Message) {
         return;
     }
 
-    tokio::spawn(user_afk::notify_afk_mentions(ctx.clone(), message.clone()));
-    user_afk::check_afk_status(&ctx, &message).await;
+    // DMs only run commands.
+    if let Some(guild_id) = message.guild_id {
+        analytics::record_message(&ctx, &message).await;
+        message_log::store(&ctx.data, &message, guild_id).await;
+        tokio::spawn(user_afk::notify_afk_mentions(ctx.clone(), message.clone()));
+        tokio::spawn({
+            let (ctx, message) = (ctx.clone(), message.clone());
+            async move { reactions::auto_react(&ctx, &message).await }
+        });
+        user_afk::check_afk_status(&ctx, &message).await;
+    }
     if extras::is_asking_for_bot_prefix(&ctx, &message).await {
         return;
     }
@@ -33,8 +41,12 @@ pub async fn update(ctx: Context, env: MessageUpdateEvent) {
         return;
     };
 
+    // The previous revision has to be read before the store is updated.
+    let before = message_log::lookup(&ctx, env.channel_id, env.id).await;
+    message_log::update(&ctx.data, &env).await;
+
     let message = {
-        let mut msg = Message::default();
+        let mut msg = before.clone().unwrap_or_default();
         env.apply_to_message(&mut msg);
         msg
     };
@@ -43,12 +55,14 @@ pub async fn update(ctx: Context, env: MessageUpdateEvent) {
         return;
     }
 
-    let old_message = ctx
-        .cache
-        .message(message.channel_id, message.id)
-        .map(|m| m.clone());
-
-    snipes::edit(&ctx.data, &message, &guild_id).await;
+    // Embed previews also arrive as updates, only content changes are sniped.
+    if env
+        .content
+        .as_ref()
+        .is_some_and(|content| before.as_ref().is_none_or(|b| b.content != *content))
+    {
+        snipes::edit(&ctx, before.as_ref(), &message, &guild_id).await;
+    }
 
     if extras::is_asking_for_bot_prefix(&ctx, &message).await {
         return;
@@ -67,8 +81,9 @@ pub async fn delete(
     let Some(guild_id) = guild_id else {
         return;
     };
+    reactions::message_deleted(&ctx, message_id).await;
 
-    let Some(message) = ctx.cache.message(channel_id, message_id).map(|m| m.clone()) else {
+    let Some(message) = message_log::take(&ctx, channel_id, message_id).await else {
         return;
     };
 
@@ -76,7 +91,9 @@ pub async fn delete(
         return;
     }
 
-    snipes::delete(&ctx.data, &message, &guild_id).await;
+    snipes::attachments::preserve(&ctx, &message, &guild_id).await;
+    snipes::delete(&ctx, &message, &guild_id).await;
+    message_log::deleted(&ctx, guild_id, &message).await;
 }
 
 pub async fn bulk_delete(
@@ -88,6 +105,24 @@ pub async fn bulk_delete(
     let Some(guild_id) = guild_id else {
         return;
     };
+
+    let mut messages = vec![];
+    for message_id in &ids {
+        reactions::message_deleted(&ctx, *message_id).await;
+        if let Some(message) = message_log::take(&ctx, channel_id, *message_id).await
+            && !message.author.bot
+        {
+            messages.push(message);
+        }
+    }
+    // Oldest first, so the newest message ends up on top of the snipe history.
+    messages.sort_by_key(|m| m.id);
+
+    for message in &messages {
+        snipes::attachments::preserve(&ctx, message, &guild_id).await;
+        snipes::delete(&ctx, message, &guild_id).await;
+    }
+    message_log::bulk_deleted(&ctx, guild_id, channel_id, &messages, ids.len()).await;
 }
 
 async fn organize_components(ctx: &Context, message: &Message) {
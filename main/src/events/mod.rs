@@ -67,6 +67,9 @@ async fn worker(mut receiver: mpsc::Receiver<(Context, Event)>) {
                     Event::Ready(ev) => state::ready(ctx, ev.ready).await,
                     Event::GuildCreate(ev) => guild::create(ctx, ev.guild).await,
                     Event::GuildDelete(ev) => guild::delete(ctx, ev.guild).await,
+                    Event::GuildMemberUpdate(ev) => guild::member_update(ctx, ev).await,
+                    Event::GuildMemberAdd(ev) => guild::member_addition(ctx, ev.member).await,
+                    Event::GuildMemberRemove(ev) => guild::member_removal(ctx, ev.guild_id, ev.user).await,
                     Event::VoiceStateUpdate(ev) => voice::state_update(ctx, ev.voice_state).await,
                     Event::ReactionAdd(ev) => reaction::add(ctx, ev.reaction).await,
                     Event::ReactionRemove(ev) => reaction::remove(ctx, ev.reaction).await,
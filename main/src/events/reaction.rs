@@ -1,13 +1,18 @@
 use serenity::all::{Context, Reaction};
 
-use crate::snipes;
+use crate::{reactions, snipes};
 
-pub async fn add(ctx: Context, reaction: Reaction) {}
+pub async fn add(ctx: Context, reaction: Reaction) {
+    reactions::reaction_add(&ctx, &reaction).await;
+    snipes::reaction_history(&ctx, &reaction, true).await;
+}
 
 pub async fn remove(ctx: Context, reaction: Reaction) {
     let Some(guild_id) = reaction.guild_id else {
         return;
     };
 
-    snipes::reaction(&ctx.data, &reaction, &guild_id).await;
+    reactions::reaction_remove(&ctx, &reaction).await;
+    snipes::reaction(&ctx, &reaction, &guild_id).await;
+    snipes::reaction_history(&ctx, &reaction, false).await;
 }
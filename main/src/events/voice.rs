@@ -1,9 +1,12 @@
 use serenity::all::{Context, VoiceState};
 
+use crate::analytics;
+
 pub async fn state_update(ctx: Context, voice_state: VoiceState) {
     let Some(guild_id) = voice_state.guild_id else {
         return;
     };
+    analytics::voice_state_update(&ctx, &voice_state).await;
     let guild = ctx.cache.guild(guild_id).map(|g| g.clone());
     let old_state = guild.and_then(|g| g.voice_states.get(&voice_state.user_id).cloned());
 }
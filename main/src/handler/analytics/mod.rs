@@ -0,0 +1,220 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serenity::all::{Context, Guild, GuildId, Message, UserId, VoiceState};
+use utils::Data;
+
+use crate::{
+    DEFAULT_RETENTION_DAYS, HOUR, ServerAnalytics, ServerAnalyticsMap, VoiceSessions,
+    VoiceSessionsMap,
+};
+
+async fn get_repos(data: &Data) -> (Arc<ServerAnalyticsMap>, Arc<VoiceSessionsMap>) {
+    let data = data.read().await;
+    (
+        data.get::<ServerAnalytics>()
+            .cloned()
+            .expect("Expected ServerAnalytics in TypeMap."),
+        data.get::<VoiceSessions>()
+            .cloned()
+            .expect("Expected VoiceSessions in TypeMap."),
+    )
+}
+
+pub async fn record_message(ctx: &Context, message: &Message) {
+    let Some(guild_id) = message.guild_id else {
+        return;
+    };
+    let (analytics, _) = get_repos(&ctx.data).await;
+    let mut guild = analytics.entry(guild_id).or_default();
+    let bucket = guild.bucket(Utc::now());
+    *bucket.messages.entry(message.channel_id).or_default() += 1;
+    *bucket.users.entry(message.author.id).or_default() += 1;
+}
+
+pub async fn record_command(data: &Data, guild_id: Option<GuildId>, name: &str) {
+    let Some(guild_id) = guild_id else {
+        return;
+    };
+    let (analytics, _) = get_repos(data).await;
+    let mut guild = analytics.entry(guild_id).or_default();
+    *guild
+        .bucket(Utc::now())
+        .commands
+        .entry(name.to_string())
+        .or_default() += 1;
+}
+
+pub async fn record_join(ctx: &Context, guild_id: GuildId) {
+    let (analytics, _) = get_repos(&ctx.data).await;
+    analytics
+        .entry(guild_id)
+        .or_default()
+        .bucket(Utc::now())
+        .joins += 1;
+}
+
+pub async fn record_leave(ctx: &Context, guild_id: GuildId) {
+    let (analytics, _) = get_repos(&ctx.data).await;
+    analytics
+        .entry(guild_id)
+        .or_default()
+        .bucket(Utc::now())
+        .leaves += 1;
+}
+
+// Closes the user's current session, if any, and opens a new one when they're still in a channel.
+pub async fn voice_state_update(ctx: &Context, state: &VoiceState) {
+    let Some(guild_id) = state.guild_id else {
+        return;
+    };
+    if state.member.as_ref().is_some_and(|m| m.user.bot) {
+        return;
+    }
+    let (analytics, sessions) = get_repos(&ctx.data).await;
+    let now = Utc::now();
+    let key = (guild_id, state.user_id);
+    if let Some((_, since)) = sessions.remove(&key) {
+        add_voice_time(&analytics, guild_id, state.user_id, since, now);
+    }
+    if state.channel_id.is_some() {
+        sessions.insert(key, now);
+    }
+}
+
+// Users already in voice when the bot starts never get a join event.
+pub async fn sync_guild(ctx: &Context, guild: &Guild) {
+    let (_, sessions) = get_repos(&ctx.data).await;
+    let now = Utc::now();
+    for state in guild.voice_states.values() {
+        if state.channel_id.is_none() {
+            continue;
+        }
+        let bot = guild
+            .members
+            .get(&state.user_id)
+            .is_some_and(|m| m.user.bot);
+        if !bot {
+            sessions.entry((guild.id, state.user_id)).or_insert(now);
+        }
+    }
+}
+
+// Moves the time of open sessions into the buckets so long sessions show up before they end.
+pub fn flush_voice_sessions(analytics: &ServerAnalyticsMap, sessions: &VoiceSessionsMap) {
+    let now = Utc::now();
+    for mut session in sessions.iter_mut() {
+        let (guild_id, user_id) = *session.key();
+        add_voice_time(analytics, guild_id, user_id, *session.value(), now);
+        *session.value_mut() = now;
+    }
+}
+
+// Splits the time across the hour buckets it spans.
+fn add_voice_time(
+    analytics: &ServerAnalyticsMap,
+    guild_id: GuildId,
+    user_id: UserId,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) {
+    let mut guild = analytics.entry(guild_id).or_default();
+    let (mut start, end) = (from.timestamp(), to.timestamp());
+    while start < end {
+        let hour = start.div_euclid(HOUR);
+        let hour_end = ((hour + 1) * HOUR).min(end);
+        *guild
+            .hours
+            .entry(hour)
+            .or_default()
+            .voice
+            .entry(user_id)
+            .or_default() += (hour_end - start) as u64;
+        start = hour_end;
+    }
+}
+
+pub fn prune(analytics: &ServerAnalyticsMap) {
+    let now = Utc::now();
+    for mut guild in analytics.iter_mut() {
+        guild.prune(now);
+    }
+    analytics.retain(|_, g| !g.hours.is_empty() || g.retention_days != DEFAULT_RETENTION_DAYS);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GuildAnalytics, hour_of};
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn splits_voice_time_across_hours() {
+        let analytics = ServerAnalyticsMap::new();
+        let (guild_id, user_id) = (GuildId::new(1), UserId::new(2));
+        let (from, to) = (at("2024-01-01T10:50:00Z"), at("2024-01-01T12:10:00Z"));
+        add_voice_time(&analytics, guild_id, user_id, from, to);
+
+        let guild = analytics.get(&guild_id).unwrap();
+        let seconds = guild
+            .hours
+            .values()
+            .map(|b| b.voice[&user_id])
+            .collect::<Vec<_>>();
+        assert_eq!(seconds, [600, 3600, 600]);
+        assert_eq!(*guild.hours.keys().next().unwrap(), hour_of(from));
+    }
+
+    #[test]
+    fn flushes_open_sessions() {
+        let analytics = ServerAnalyticsMap::new();
+        let sessions = VoiceSessionsMap::new();
+        let key = (GuildId::new(1), UserId::new(2));
+        let joined = Utc::now() - chrono::Duration::minutes(30);
+        sessions.insert(key, joined);
+
+        flush_voice_sessions(&analytics, &sessions);
+        let seconds = analytics
+            .get(&key.0)
+            .unwrap()
+            .hours
+            .values()
+            .map(|b| b.voice[&key.1])
+            .sum::<u64>();
+        assert!((1799..=1801).contains(&seconds));
+        assert!(*sessions.get(&key).unwrap() > joined);
+    }
+
+    #[test]
+    fn prunes_every_guild() {
+        let analytics = ServerAnalyticsMap::new();
+        let old = Utc::now() - chrono::Duration::days(DEFAULT_RETENTION_DAYS as i64 + 1);
+        analytics
+            .entry(GuildId::new(1))
+            .or_default()
+            .bucket(old)
+            .joins = 1;
+        analytics
+            .entry(GuildId::new(2))
+            .or_default()
+            .bucket(Utc::now())
+            .joins = 1;
+        let mut configured = GuildAnalytics {
+            retention_days: 7,
+            ..Default::default()
+        };
+        configured.bucket(old).joins = 1;
+        analytics.insert(GuildId::new(3), configured);
+
+        prune(&analytics);
+        // Guilds without data are dropped unless they changed their retention.
+        assert!(analytics.get(&GuildId::new(1)).is_none());
+        assert_eq!(analytics.get(&GuildId::new(2)).unwrap().hours.len(), 1);
+        assert!(analytics.get(&GuildId::new(3)).unwrap().hours.is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use serenity::all::{
+    CacheHttp, Context, CreateMessage, Guild, GuildId, GuildMemberUpdateEvent, Timestamp, UserId,
+};
+use utils::{BotStringParser, error, info};
+
+use crate::{
+    BoosterRecord, Boosters, BoostersMap, DEFAULT_BOOST_TEMPLATE, ElapsedTime,
+    ServerBoostConfigRepo, reconcile_boosters, record_boost, remove_booster, save_boosters,
+};
+
+pub async fn member_update(ctx: &Context, event: &GuildMemberUpdateEvent) {
+    let is_recorded = {
+        let boosters = get_boosters(ctx).await;
+        boosters
+            .get(&event.guild_id)
+            .is_some_and(|b| b.contains_key(&event.user.id))
+    };
+
+    match (event.premium_since, is_recorded) {
+        (Some(since), false) => boost_started(ctx, event.guild_id, event.user.id, since).await,
+        (None, true) => boost_ended(ctx, event.guild_id, event.user.id).await,
+        _ => {}
+    }
+}
+
+pub async fn member_removal(ctx: &Context, guild_id: GuildId, user_id: UserId) {
+    boost_ended(ctx, guild_id, user_id).await;
+}
+
+// Reconciles the stored boosters with the guild state, catching boosts that changed while the
+// bot was offline. No announcements are sent for these.
+pub async fn sync_guild(ctx: &Context, guild: &Guild) {
+    let boosters = get_boosters(ctx).await;
+
+    let ended = reconcile_boosters(
+        &mut boosters.entry(guild.id).or_default(),
+        guild
+            .members
+            .iter()
+            .map(|(user_id, member)| (*user_id, member.premium_since.map(|s| s.to_utc()))),
+    );
+    save_boosters(&boosters);
+
+    for user_id in ended {
+        boost_ended(ctx, guild.id, user_id).await;
+    }
+}
+
+async fn boost_started(ctx: &Context, guild_id: GuildId, user_id: UserId, since: Timestamp) {
+    let timer = ElapsedTime::new();
+    let boosters = get_boosters(ctx).await;
+    record_boost(&boosters, guild_id, user_id, since.to_utc());
+    save_boosters(&boosters);
+
+    let config = {
+        let data = ctx.data.read().await;
+        data.get::<ServerBoostConfigRepo>()
+            .cloned()
+            .expect("Expected ServerBoostConfigRepo in TypeMap.")
+            .get(&guild_id)
+            .map(|c| c.clone())
+    };
+    let Some(config) = config else {
+        return;
+    };
+    let Some(channel_id) = config.channel else {
+        return;
+    };
+    let Some(guild) = ctx.cache.guild(guild_id).map(|g| g.clone()) else {
+        return;
+    };
+    let Some(channel) = guild.channels.get(&channel_id) else {
+        return;
+    };
+    let member = match guild_id.member(ctx, user_id).await {
+        Ok(member) => member,
+        Err(e) => {
+            error!("Failed to fetch booster {} in {}: {}", user_id, guild_id, e);
+            return;
+        }
+    };
+
+    let template = config
+        .template
+        .clone()
+        .unwrap_or(DEFAULT_BOOST_TEMPLATE.to_string());
+    let content = BotStringParser::new(ctx, &guild, channel, &member).render(&template);
+    if let Err(e) = channel_id
+        .send_message(ctx.http(), CreateMessage::new().content(content))
+        .await
+    {
+        error!("Failed to send boost message in {}: {}", guild_id, e);
+        return;
+    }
+    info!(
+        "{} boosted {}({}) ({}ms)",
+        member.user.name,
+        guild.name,
+        guild_id,
+        timer.elapsed_ms()
+    );
+}
+
+async fn boost_ended(ctx: &Context, guild_id: GuildId, user_id: UserId) {
+    let boosters = get_boosters(ctx).await;
+    let Some(record) = remove_booster(&boosters, guild_id, user_id) else {
+        return;
+    };
+    save_boosters(&boosters);
+
+    if let Some(role_id) = record.role {
+        match guild_id.delete_role(ctx.http(), role_id).await {
+            Ok(_) => info!(
+                "Removed booster role {} of {} in {}",
+                role_id, user_id, guild_id
+            ),
+            Err(e) => error!(
+                "Failed to remove booster role {} of {} in {}: {}",
+                role_id, user_id, guild_id, e
+            ),
+        }
+    }
+}
+
+async fn get_boosters(ctx: &Context) -> Arc<BoostersMap> {
+    let data = ctx.data.read().await;
+    data.get::<Boosters>()
+        .cloned()
+        .expect("Expected Boosters in TypeMap.")
+}
+
+pub fn boost_duration_str(record: &BoosterRecord) -> String {
+    let elapsed = Utc::now() - record.since;
+    match elapsed.num_days() {
+        0 => "today".to_string(),
+        1 => "1 day".to_string(),
+        days if days < 60 => format!("{} days", days),
+        days => format!("{} months", days / 30),
+    }
+}
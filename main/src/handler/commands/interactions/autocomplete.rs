@@ -39,7 +39,7 @@ pub async fn handle(ctx: &Context, autocomplete: CommandInteraction) -> Option<S
             .clone()
     };
 
-    let Some((c, _perms)) = commands.get(&name) else {
+    let Some((c, _perms, _)) = commands.get(&name) else {
         error!("Command '{}' doesn't support autocomplete", name);
         return None;
     };
@@ -1,9 +1,12 @@
 use std::collections::HashMap;
 
 use serenity::all::{CacheHttp, CommandInteraction, Context, CreateInteractionResponse};
-use utils::{CommandArguments, UserType, error, warning};
+use utils::{CommandArguments, CommandResponse, UserType, error, warning};
 
-use crate::Commands;
+use crate::{
+    Commands, analytics,
+    handler::commands::{Throttle, blocked, cooldown, error_text, missing_permissions, release},
+};
 
 pub async fn handle(ctx: &Context, command: CommandInteraction) -> Option<String> {
     let Some(guild_id) = command.guild_id else {
@@ -31,7 +34,7 @@ pub async fn handle(ctx: &Context, command: CommandInteraction) -> Option<String
             .clone()
     };
     let c_name = command.data.name.clone();
-    let Some((c, _perms)) = commands.get(&c_name) else {
+    let Some((c, permissions, module)) = commands.get(&c_name) else {
         error!("Command '{}' not found", c_name);
         return None;
     };
@@ -41,6 +44,48 @@ pub async fn handle(ctx: &Context, command: CommandInteraction) -> Option<String
         return None;
     }
 
+    let roles = command
+        .member
+        .as_ref()
+        .map(|m| m.roles.clone())
+        .unwrap_or_default();
+    let channel = location.as_ref().map(|(_, channel)| channel);
+    let refusal = match blocked(&ctx.data, guild_id, &c_name, module, channel, &roles).await {
+        Some(reason) => Some(reason.to_string()),
+        None => missing_permissions(permissions, &user, &location),
+    };
+    let throttle = match refusal {
+        Some(refusal) => Throttle::Wait(refusal),
+        None => {
+            cooldown(
+                &ctx.data,
+                &c_name,
+                c.cooldown(),
+                command.user.id,
+                command.channel_id,
+                Some(guild_id),
+            )
+            .await
+        }
+    };
+    let reservation = match throttle {
+        Throttle::Run(reservation) => reservation,
+        Throttle::Wait(refusal) => {
+            let response = CommandResponse::new_content(refusal).ephemeral();
+            if let Err(e) = command
+                .create_response(
+                    ctx.http(),
+                    CreateInteractionResponse::Message(response.to_interaction_msg()),
+                )
+                .await
+            {
+                error!("Failed to send response to command '{}': {}", c_name, e);
+            }
+            return None;
+        }
+        Throttle::Ignore => return None,
+    };
+
     let options = {
         let mut hash_map = HashMap::new();
         let options = &command.data.options;
@@ -66,12 +111,25 @@ pub async fn handle(ctx: &Context, command: CommandInteraction) -> Option<String
                     .await
             {
                 error!("Failed to send response to command '{}': {}", c_name, e);
+                release(&ctx.data, reservation).await;
                 return None;
             }
+            analytics::record_command(&ctx.data, Some(guild_id), &c_name).await;
             Some(c_name)
         }
         Err(e) => {
             error!("Error executing command '{}': {}", c_name, e);
+            release(&ctx.data, reservation).await;
+            let response = CommandResponse::new_content(error_text(&e)).ephemeral();
+            if let Err(e) = command
+                .create_response(
+                    ctx.http(),
+                    CreateInteractionResponse::Message(response.to_interaction_msg()),
+                )
+                .await
+            {
+                error!("Failed to send response to command '{}': {}", c_name, e);
+            }
             None
         }
     }
@@ -12,17 +12,23 @@ use utils::{
     warning,
 };
 
-use crate::{Commands, ElapsedTime, ServerPrefix, ServerPrefixes};
+use crate::{
+    CommandAliases, CommandSettings, Commands, ElapsedTime, ServerPrefixes, UserPrefixes,
+    analytics, find_command, longest_prefix, server_prefixes, user_prefix,
+};
+
+use super::{Throttle, blocked, cooldown, error_text, missing_permissions, release};
 
 pub async fn is_command(ctx: &Context, msg: &Message) -> bool {
     let timer = ElapsedTime::new();
     let data = ctx.data.clone();
 
-    let Some(prefix) = get_prefix(&data, msg).await else {
+    let Some(prefix) = get_prefix(ctx, msg).await else {
         return false;
     };
 
-    let Some((c_name, command, permissions, content)) = get_command(&data, msg, prefix).await
+    let Some((c_name, command, permissions, module, content)) =
+        get_command(&data, msg, prefix).await
     else {
         return false;
     };
@@ -34,6 +40,48 @@ pub async fn is_command(ctx: &Context, msg: &Message) -> bool {
         .and_then(|(g, _)| g.members.get(&user.id).cloned().map(UserType::Member))
         .unwrap_or(UserType::User(user.clone()));
 
+    // Disabled and restricted commands are ignored as if they didn't exist.
+    if let Some(guild_id) = msg.guild_id {
+        let roles = match &member {
+            UserType::Member(m) => m.roles.clone(),
+            UserType::User(_) => vec![],
+        };
+        let channel = location.as_ref().map(|(_, channel)| channel);
+        if blocked(&data, guild_id, &c_name, module, channel, &roles)
+            .await
+            .is_some()
+        {
+            return false;
+        }
+    }
+
+    if let Some(reason) = missing_permissions(&permissions, &member, &location) {
+        if let Err(e) = msg.reply(ctx.http(), reason).await {
+            error!("Failed to send message: {}", e);
+        }
+        return true;
+    }
+
+    let reservation = match cooldown(
+        &data,
+        &c_name,
+        command.cooldown(),
+        msg.author.id,
+        msg.channel_id,
+        msg.guild_id,
+    )
+    .await
+    {
+        Throttle::Run(reservation) => reservation,
+        Throttle::Wait(wait) => {
+            if let Err(e) = msg.reply(ctx.http(), wait).await {
+                error!("Failed to send message: {}", e);
+            }
+            return true;
+        }
+        Throttle::Ignore => return true,
+    };
+
     let options = LegacyOption::parse(&content, &location);
 
     let args = CommandArguments::Legacy(Some(options), msg);
@@ -45,65 +93,91 @@ pub async fn is_command(ctx: &Context, msg: &Message) -> bool {
                 if msg_response.should_reply() {
                     new_msg = new_msg.reference_message(msg);
                 }
-                if let Some((_, channel)) = location
-                    && let Err(e) = channel.send_message(ctx.http(), new_msg).await
-                {
+                // Commands used in DMs have no location, they're answered in the same channel.
+                let channel_id = location
+                    .as_ref()
+                    .map_or(msg.channel_id, |(_, channel)| channel.id);
+                if let Err(e) = channel_id.send_message(ctx.http(), new_msg).await {
                     error!("Failed to send message: {}", e);
+                    release(&data, reservation).await;
                     return false;
                 }
             }
+            analytics::record_command(&data, msg.guild_id, &c_name).await;
             info!("Executed command '{}' ({}ms)", c_name, timer.elapsed_ms());
             true
         }
         Err(e) => {
             error!("Error executing command '{}': {}", c_name, e);
-            false
+            release(&data, reservation).await;
+            if let Err(e) = msg.reply(ctx.http(), error_text(&e)).await {
+                error!("Failed to send message: {}", e);
+            }
+            true
         }
     }
 }
 
-pub async fn get_prefix(data: &Data, msg: &Message) -> Option<String> {
+// The prefix the message starts with: a mention of the bot, the author's personal prefix or one
+// of the server's prefixes, the default ones in DMs.
+pub async fn get_prefix(ctx: &Context, msg: &Message) -> Option<String> {
     if msg.author.bot {
         return None;
     }
 
-    let guild_id = msg.guild_id?;
+    let bot_id = ctx.cache.current_user().id;
+    let mut candidates = vec![format!("<@{}>", bot_id), format!("<@!{}>", bot_id)];
+    {
+        let data = ctx.data.read().await;
+        let server = data
+            .get::<ServerPrefixes>()
+            .expect("ServerPrefixes not initialized");
+        let users = data
+            .get::<UserPrefixes>()
+            .expect("UserPrefixes not initialized");
+        candidates.extend(user_prefix(users, msg.guild_id, msg.author.id));
+        candidates.extend(server_prefixes(server, msg.guild_id));
+    }
 
-    let data = data.read().await;
-    let repo: Arc<dashmap::DashMap<ServerPrefix, String>> = data
-        .get::<ServerPrefixes>()
-        .cloned()
-        .expect("ServerPrefixes not initialized");
-
-    repo.get(&ServerPrefix::Guild(guild_id))
-        .or(repo.get(&ServerPrefix::Default))
-        .map(|s| s.clone())
+    longest_prefix(&msg.content, candidates)
 }
 
 pub async fn get_command(
     data: &Data,
     msg: &Message,
     prefix: String,
-) -> Option<(String, Arc<dyn CommandTrait>, Vec<BotPermission>, String)> {
+) -> Option<(
+    String,
+    Arc<dyn CommandTrait>,
+    Vec<BotPermission>,
+    &'static str,
+    String,
+)> {
     if !msg.content.starts_with(&prefix) {
         return None;
     }
 
-    let content = msg.content.trim_start_matches(&prefix);
-    let c_name = content.split_whitespace().next().unwrap_or("");
+    let content = msg.content.trim_start_matches(&prefix).trim_start();
+    let word = content.split_whitespace().next().unwrap_or("");
 
     let data = data.read().await;
-    let commands = data
-        .get::<Commands>()
-        .expect("Commands not initialized")
-        .clone();
-
-    match commands.get(c_name).cloned() {
-        Some((c, perms)) if c.is_legacy() => Some((
-            c_name.to_string(),
+    let commands = data.get::<Commands>().expect("Commands not initialized");
+    let aliases = data
+        .get::<CommandAliases>()
+        .expect("CommandAliases not initialized");
+    let settings = msg.guild_id.and_then(|guild_id| {
+        data.get::<CommandSettings>()
+            .and_then(|s| s.get(&guild_id).map(|s| s.clone()))
+    });
+    let c_name = find_command(commands, aliases, settings.as_ref(), word)?;
+
+    match commands.get(&c_name).cloned() {
+        Some((c, perms, module)) if c.is_legacy() => Some((
+            c_name,
             c,
             perms,
-            content.trim_start_matches(c_name).trim().to_string(),
+            module,
+            content[word.len()..].trim().to_string(),
         )),
         _ => None,
     }
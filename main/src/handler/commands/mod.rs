@@ -2,3 +2,359 @@ mod interactions;
 pub mod message;
 
 pub use interactions::*;
+
+use chrono::{DateTime, Duration, TimeDelta, Utc};
+use dashmap::Entry;
+use serenity::all::{ChannelId, Guild, GuildChannel, GuildId, Permissions, RoleId, UserId};
+use utils::{BotPermission, Cooldown, CooldownBucket, Data, LegacyOption, UserType, truncate};
+
+use crate::{
+    CommandRateLimits, CommandRateLimitsMap, CommandSettings, Cooldowns, CooldownsMap,
+    RATE_LIMIT_COMMANDS, rate_limit_window,
+};
+
+// Characters of an error shown to the user, even 4 byte ones stay below the message limit.
+const MAX_ERROR_LENGTH: usize = 500;
+
+// Errors of commands are shown to the user as they are, like "This command can only be used in
+// a server.".
+pub fn error_text(error: &str) -> String {
+    truncate(error, MAX_ERROR_LENGTH)
+}
+
+// Why a command can't be used in the channel by a member with these roles, `None` when it can.
+pub async fn blocked(
+    data: &Data,
+    guild_id: GuildId,
+    command: &str,
+    module: &str,
+    channel: Option<&GuildChannel>,
+    roles: &[RoleId],
+) -> Option<&'static str> {
+    let settings = {
+        let data = data.read().await;
+        data.get::<CommandSettings>()
+            .and_then(|s| s.get(&guild_id).map(|s| s.clone()))?
+    };
+    settings.blocked(command, module, channel, roles)
+}
+
+// Why the user can't run a command that needs these permissions here, `None` when they can.
+// Permissions only exist in servers, so outside of them only commands that need none can run.
+pub fn missing_permissions(
+    required: &[BotPermission],
+    user: &UserType,
+    location: &Option<(Guild, GuildChannel)>,
+) -> Option<String> {
+    if required.is_empty() {
+        return None;
+    }
+    let granted = match (user, location) {
+        (UserType::Member(member), Some((guild, channel))) => {
+            guild.user_permissions_in(channel, member)
+        }
+        (_, None) => return Some("This command can only be used in a server.".to_string()),
+        _ => Permissions::empty(),
+    };
+    let missing = required
+        .iter()
+        .filter(|p| !granted.contains(p.permissions()))
+        .map(|p| format!("`{}`", p))
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "You need the {} permission to use this command.",
+        missing.join(", ")
+    ))
+}
+
+// Whether a command can run now.
+pub enum Throttle {
+    // It can, with the cooldown it started. The cooldown is released if the command fails.
+    Run(Option<Reservation>),
+    // It can't, with what to tell the user.
+    Wait(String),
+    // It can't and the user was already told.
+    Ignore,
+}
+
+// A cooldown started for a run of a command, by its key in the cooldowns and when it ends.
+pub struct Reservation {
+    key: (String, CooldownBucket, u64),
+    ends: DateTime<Utc>,
+}
+
+// Checks the global rate limit and the cooldown of the command. Passing the check counts towards
+// the rate limit and starts the cooldown, which `release` takes back when the run fails.
+pub async fn cooldown(
+    data: &Data,
+    command: &str,
+    cooldown: Option<Cooldown>,
+    user_id: UserId,
+    channel_id: ChannelId,
+    guild_id: Option<GuildId>,
+) -> Throttle {
+    let (cooldowns, rate_limits) = {
+        let data = data.read().await;
+        (
+            data.get::<Cooldowns>()
+                .cloned()
+                .expect("Expected Cooldowns in TypeMap."),
+            data.get::<CommandRateLimits>()
+                .cloned()
+                .expect("Expected CommandRateLimits in TypeMap."),
+        )
+    };
+    let cooldown = cooldown.map(|cooldown| {
+        let id = match cooldown.bucket {
+            CooldownBucket::User => user_id.get(),
+            CooldownBucket::Channel => channel_id.get(),
+            // Direct messages have no guild, the channel is the closest thing.
+            CooldownBucket::Guild => guild_id.map(|g| g.get()).unwrap_or(channel_id.get()),
+        };
+        (
+            (command.to_string(), cooldown.bucket, id),
+            cooldown.duration,
+        )
+    });
+    throttle(&cooldowns, &rate_limits, cooldown, user_id, Utc::now())
+}
+
+// Called when a command that passed `cooldown` fails, so the user can try again right away.
+pub async fn release(data: &Data, reservation: Option<Reservation>) {
+    let Some(reservation) = reservation else {
+        return;
+    };
+    let cooldowns = {
+        let data = data.read().await;
+        data.get::<Cooldowns>()
+            .cloned()
+            .expect("Expected Cooldowns in TypeMap.")
+    };
+    release_reservation(&cooldowns, reservation);
+}
+
+fn throttle(
+    cooldowns: &CooldownsMap,
+    rate_limits: &CommandRateLimitsMap,
+    cooldown: Option<((String, CooldownBucket, u64), Duration)>,
+    user_id: UserId,
+    now: DateTime<Utc>,
+) -> Throttle {
+    // The entries stay locked until the command is counted, so concurrent runs can't both pass.
+    let mut limit = rate_limits.entry(user_id).or_default();
+    limit.uses.retain(|at| *at + rate_limit_window() > now);
+    if limit.uses.len() >= RATE_LIMIT_COMMANDS {
+        if limit
+            .notified
+            .is_some_and(|at| at + rate_limit_window() > now)
+        {
+            return Throttle::Ignore;
+        }
+        limit.notified = Some(now);
+        let wait = limit
+            .uses
+            .front()
+            .map(|at| *at + rate_limit_window() - now)
+            .unwrap_or_default();
+        return Throttle::Wait(format!(
+            "You're using commands too quickly, try again in {}.",
+            wait_str(wait)
+        ));
+    }
+
+    let mut reservation = None;
+    if let Some((key, duration)) = cooldown {
+        let command = key.0.clone();
+        match cooldowns.entry(key) {
+            Entry::Occupied(entry) if *entry.get() > now => {
+                return Throttle::Wait(format!(
+                    "`{}` is on cooldown, try again in {}.",
+                    command,
+                    wait_str(*entry.get() - now)
+                ));
+            }
+            entry => {
+                let ends = now + duration;
+                let entry = entry.insert(ends);
+                reservation = Some(Reservation {
+                    key: entry.key().clone(),
+                    ends,
+                });
+            }
+        }
+    }
+
+    limit.uses.push_back(now);
+    Throttle::Run(reservation)
+}
+
+// Only removes the cooldown if it's still the one the run started.
+fn release_reservation(cooldowns: &CooldownsMap, reservation: Reservation) {
+    cooldowns.remove_if(&reservation.key, |_, ends| *ends == reservation.ends);
+}
+
+// Rounded up, so it never says to try again in 0s.
+fn wait_str(wait: TimeDelta) -> String {
+    let seconds = (wait.num_milliseconds() + 999) / 1000;
+    LegacyOption::time_str(&TimeDelta::seconds(seconds.max(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER: UserId = UserId::new(1);
+
+    fn key(command: &str) -> (String, CooldownBucket, u64) {
+        (command.to_string(), CooldownBucket::User, USER.get())
+    }
+
+    fn run(
+        cooldowns: &CooldownsMap,
+        rate_limits: &CommandRateLimitsMap,
+        command: &str,
+        seconds: i64,
+        now: DateTime<Utc>,
+    ) -> Throttle {
+        let cooldown = (seconds > 0).then(|| (key(command), Duration::seconds(seconds)));
+        throttle(cooldowns, rate_limits, cooldown, USER, now)
+    }
+
+    #[test]
+    fn cooldowns_start_when_passed() {
+        let (cooldowns, rate_limits) = (CooldownsMap::new(), CommandRateLimitsMap::new());
+        let now = Utc::now();
+        assert!(matches!(
+            run(&cooldowns, &rate_limits, "ping", 5, now),
+            Throttle::Run(Some(_))
+        ));
+        assert_eq!(
+            *cooldowns.get(&key("ping")).unwrap(),
+            now + Duration::seconds(5)
+        );
+
+        let Throttle::Wait(wait) = run(
+            &cooldowns,
+            &rate_limits,
+            "ping",
+            5,
+            now + Duration::seconds(2),
+        ) else {
+            panic!("The cooldown should still be running");
+        };
+        assert_eq!(wait, "`ping` is on cooldown, try again in 3 seconds.");
+        assert!(matches!(
+            run(
+                &cooldowns,
+                &rate_limits,
+                "ping",
+                5,
+                now + Duration::seconds(5)
+            ),
+            Throttle::Run(Some(_))
+        ));
+        assert!(matches!(
+            run(
+                &cooldowns,
+                &rate_limits,
+                "help",
+                0,
+                now + Duration::seconds(5)
+            ),
+            Throttle::Run(None)
+        ));
+    }
+
+    #[test]
+    fn failed_runs_release_their_cooldown() {
+        let (cooldowns, rate_limits) = (CooldownsMap::new(), CommandRateLimitsMap::new());
+        let now = Utc::now();
+        let Throttle::Run(reservation) = run(&cooldowns, &rate_limits, "ping", 5, now) else {
+            panic!("The command should run");
+        };
+        release_reservation(&cooldowns, reservation.unwrap());
+        assert!(cooldowns.get(&key("ping")).is_none());
+        assert!(matches!(
+            run(&cooldowns, &rate_limits, "ping", 5, now),
+            Throttle::Run(Some(_))
+        ));
+    }
+
+    #[test]
+    fn stale_reservations_keep_newer_cooldowns() {
+        let (cooldowns, rate_limits) = (CooldownsMap::new(), CommandRateLimitsMap::new());
+        let now = Utc::now();
+        let Throttle::Run(Some(stale)) = run(&cooldowns, &rate_limits, "ping", 5, now) else {
+            panic!("The command should run");
+        };
+        let later = now + Duration::seconds(10);
+        assert!(matches!(
+            run(&cooldowns, &rate_limits, "ping", 5, later),
+            Throttle::Run(Some(_))
+        ));
+        release_reservation(&cooldowns, stale);
+        assert_eq!(
+            *cooldowns.get(&key("ping")).unwrap(),
+            later + Duration::seconds(5)
+        );
+    }
+
+    #[test]
+    fn rate_limit_notice_is_sent_once_per_window() {
+        let (cooldowns, rate_limits) = (CooldownsMap::new(), CommandRateLimitsMap::new());
+        let now = Utc::now();
+        for _ in 0..RATE_LIMIT_COMMANDS {
+            assert!(matches!(
+                run(&cooldowns, &rate_limits, "help", 0, now),
+                Throttle::Run(None)
+            ));
+        }
+        let Throttle::Wait(wait) = run(&cooldowns, &rate_limits, "help", 0, now) else {
+            panic!("The user should be rate limited");
+        };
+        assert_eq!(
+            wait,
+            "You're using commands too quickly, try again in 10 seconds."
+        );
+        assert!(matches!(
+            run(
+                &cooldowns,
+                &rate_limits,
+                "help",
+                0,
+                now + Duration::seconds(1)
+            ),
+            Throttle::Ignore
+        ));
+        // Refused commands don't count towards the limit or start cooldowns.
+        assert!(matches!(
+            run(
+                &cooldowns,
+                &rate_limits,
+                "ping",
+                5,
+                now + Duration::seconds(1)
+            ),
+            Throttle::Ignore
+        ));
+        assert!(cooldowns.get(&key("ping")).is_none());
+        assert_eq!(
+            rate_limits.get(&USER).unwrap().uses.len(),
+            RATE_LIMIT_COMMANDS
+        );
+
+        assert!(matches!(
+            run(
+                &cooldowns,
+                &rate_limits,
+                "ping",
+                5,
+                now + rate_limit_window()
+            ),
+            Throttle::Run(Some(_))
+        ));
+    }
+}
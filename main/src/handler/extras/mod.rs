@@ -1,37 +1,46 @@
-use serenity::all::{CacheHttp, Context, CreateMessage, Mentionable, Message};
+use serenity::all::{CacheHttp, Context, CreateMessage, Message};
 use utils::error;
 
-use crate::{ServerPrefix, ServerPrefixes};
+use crate::{ServerPrefixes, UserPrefixes, server_prefixes, user_prefix};
 
 pub async fn is_asking_for_bot_prefix(ctx: &Context, msg: &Message) -> bool {
     let bot_id = ctx.cache.current_user().id;
-    let Some(guild_id) = msg.guild_id else {
+    let content = msg.content.trim();
+    if content != format!("<@{}>", bot_id) && content != format!("<@!{}>", bot_id) {
         return false;
-    };
-
-    let data = ctx.data.clone();
-    if msg.content == bot_id.mention().to_string() {
-        let repo = {
-            let data_read = data.read().await;
-            data_read
-                .get::<ServerPrefixes>()
-                .cloned()
-                .expect("Expected ServerPrefixes in TypeMap")
-        };
-
-        let prefix = repo
-            .get(&ServerPrefix::Guild(guild_id))
-            .or(repo.get(&ServerPrefix::Default))
-            .expect("Default prefix must be set")
-            .clone();
+    }
 
-        let new_msg = format!("My prefix in this server is `{}`.", prefix);
-        let message = CreateMessage::new().content(new_msg).reference_message(msg);
+    let (prefixes, personal) = {
+        let data = ctx.data.read().await;
+        let server = data
+            .get::<ServerPrefixes>()
+            .expect("Expected ServerPrefixes in TypeMap");
+        let users = data
+            .get::<UserPrefixes>()
+            .expect("Expected UserPrefixes in TypeMap");
+        (
+            server_prefixes(server, msg.guild_id),
+            user_prefix(users, msg.guild_id, msg.author.id),
+        )
+    };
 
-        if let Err(e) = msg.channel_id.send_message(ctx.http(), message).await {
-            error!("Failed to send AFK mention reply: {}", e);
-        };
-        return true;
+    let prefixes = prefixes
+        .iter()
+        .map(|p| format!("`{}`", p))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut new_msg = match msg.guild_id {
+        Some(_) => format!("My prefixes in this server are {}.", prefixes),
+        None => format!("My prefixes here are {}.", prefixes),
+    };
+    if let Some(personal) = personal {
+        new_msg.push_str(&format!("\nYour personal prefix is `{}`.", personal));
     }
-    false
+    new_msg.push_str("\nYou can also mention me before a command.");
+    let message = CreateMessage::new().content(new_msg).reference_message(msg);
+
+    if let Err(e) = msg.channel_id.send_message(ctx.http(), message).await {
+        error!("Failed to send prefix reply: {}", e);
+    };
+    true
 }
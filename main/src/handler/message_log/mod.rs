@@ -0,0 +1,272 @@
+use chrono::{Duration, Utc};
+use serenity::all::{
+    ChannelId, Colour, Context, CreateAttachment, CreateEmbed, CreateEmbedAuthor,
+    CreateEmbedFooter, CreateMessage, GuildId, Message, MessageId, MessageUpdateEvent, User,
+};
+use utils::{Data, error};
+
+use crate::{
+    MAX_STORED_MESSAGES, MESSAGE_STORE_TTL, MessageLogChannels, MessageStore, SnipeOptOuts,
+    StoredMessage, snipes::attachments,
+};
+
+const MAX_LOG_LINES: usize = 20;
+const MAX_FILES: usize = 10;
+
+pub async fn store(data: &Data, message: &Message, guild_id: GuildId) {
+    let (store, opt_outs) = {
+        let data = data.read().await;
+        (
+            data.get::<MessageStore>()
+                .cloned()
+                .expect("Expected MessageStore in TypeMap."),
+            data.get::<SnipeOptOuts>()
+                .cloned()
+                .expect("Expected SnipeOptOuts in TypeMap."),
+        )
+    };
+    if opt_outs.contains_key(&message.author.id) {
+        return;
+    }
+    store.insert(message.id, StoredMessage::new(message, guild_id));
+}
+
+// Keeps the stored copy in sync so a later deletion shows the latest revision.
+pub async fn update(data: &Data, event: &MessageUpdateEvent) {
+    let store = {
+        let data = data.read().await;
+        data.get::<MessageStore>()
+            .cloned()
+            .expect("Expected MessageStore in TypeMap.")
+    };
+    if let Some(mut stored) = store.get_mut(&event.id) {
+        if let Some(content) = &event.content {
+            stored.content = content.clone();
+        }
+        if let Some(attachments) = &event.attachments {
+            stored.attachments = attachments.clone();
+        }
+    }
+}
+
+// Finds the current revision of a message without removing it from the store.
+pub async fn lookup(
+    ctx: &Context,
+    channel_id: ChannelId,
+    message_id: MessageId,
+) -> Option<Message> {
+    if let Some(message) = ctx.cache.message(channel_id, message_id) {
+        return Some(message.clone());
+    }
+    let data = ctx.data.read().await;
+    data.get::<MessageStore>()
+        .and_then(|store| store.get(&message_id).map(|stored| stored.to_message()))
+}
+
+// Resolves a deleted message from the cache, falling back to the bot's own store.
+pub async fn take(ctx: &Context, channel_id: ChannelId, message_id: MessageId) -> Option<Message> {
+    let store = {
+        let data = ctx.data.read().await;
+        data.get::<MessageStore>()
+            .cloned()
+            .expect("Expected MessageStore in TypeMap.")
+    };
+    let stored = store.remove(&message_id).map(|(_, stored)| stored);
+    ctx.cache
+        .message(channel_id, message_id)
+        .map(|m| m.clone())
+        .or_else(|| stored.map(|s| s.to_message()))
+}
+
+pub async fn prune(data: &Data) {
+    let store = {
+        let data = data.read().await;
+        data.get::<MessageStore>()
+            .cloned()
+            .expect("Expected MessageStore in TypeMap.")
+    };
+    let oldest = Utc::now() - Duration::seconds(MESSAGE_STORE_TTL);
+    store.retain(|_, stored| stored.stored_at >= oldest);
+
+    // Message ids are snowflakes, so sorting them drops the oldest messages first.
+    if store.len() > MAX_STORED_MESSAGES {
+        let mut ids = store.iter().map(|entry| *entry.key()).collect::<Vec<_>>();
+        ids.sort();
+        for id in ids.iter().take(ids.len() - MAX_STORED_MESSAGES) {
+            store.remove(id);
+        }
+    }
+}
+
+pub async fn deleted(ctx: &Context, guild_id: GuildId, message: &Message) {
+    let Some(log_channel) = log_channel(ctx, guild_id).await else {
+        return;
+    };
+
+    let author = CreateEmbedAuthor::new(&message.author.name).icon_url(message.author.face());
+    let mut embed = CreateEmbed::default()
+        .author(author)
+        .title("Message deleted")
+        .field("Channel", format!("<#{}>", message.channel_id), true)
+        .field("Author", format!("<@{}>", message.author.id), true)
+        .footer(CreateEmbedFooter::new(format!(
+            "Message ID: {}",
+            message.id
+        )))
+        .timestamp(message.timestamp)
+        .color(Colour::RED);
+    if !message.content.is_empty() {
+        embed = embed.description(truncate(&message.content, 4000));
+    }
+    if !message.attachments.is_empty() {
+        let attachments = message
+            .attachments
+            .iter()
+            .map(|a| format!("[{}]({})", a.filename, a.url))
+            .collect::<Vec<_>>()
+            .join("\n");
+        embed = embed.field("Attachments", truncate(&attachments, 1024), false);
+    }
+
+    let cached = attachments::uploads(&ctx.data, message.id).await;
+    if let Some((attachment, _)) = cached.iter().find(|(a, _)| a.is_image()) {
+        embed = embed.image(format!("attachment://{}", attachment.filename));
+    }
+    let builder = CreateMessage::new()
+        .embed(embed)
+        .add_files(cached.into_iter().take(MAX_FILES).map(|(_, upload)| upload));
+    if let Err(e) = log_channel.send_message(&ctx.http, builder).await {
+        error!("Failed to log deleted message in {}: {}", guild_id, e);
+    }
+}
+
+// Messages reported by members through the context menu, sent to the same channel.
+pub async fn reported(
+    ctx: &Context,
+    guild_id: GuildId,
+    message: &Message,
+    reporter: &User,
+) -> Result<(), String> {
+    let Some(log_channel) = log_channel(ctx, guild_id).await else {
+        return Err("This server has no message log channel to send reports to.".to_string());
+    };
+
+    let author = CreateEmbedAuthor::new(&message.author.name).icon_url(message.author.face());
+    let mut embed = CreateEmbed::default()
+        .author(author)
+        .title("Message reported")
+        .url(message.id.link(message.channel_id, Some(guild_id)))
+        .field("Channel", format!("<#{}>", message.channel_id), true)
+        .field("Author", format!("<@{}>", message.author.id), true)
+        .field("Reported by", format!("<@{}>", reporter.id), true)
+        .footer(CreateEmbedFooter::new(format!(
+            "Message ID: {}",
+            message.id
+        )))
+        .timestamp(message.timestamp)
+        .color(Colour::ORANGE);
+    if !message.content.is_empty() {
+        embed = embed.description(truncate(&message.content, 4000));
+    }
+    if let Some(attachment) = message.attachments.first() {
+        embed = embed.image(attachment.url.clone());
+    }
+
+    if let Err(e) = log_channel
+        .send_message(&ctx.http, CreateMessage::new().embed(embed))
+        .await
+    {
+        error!("Failed to log reported message in {}: {}", guild_id, e);
+        return Err("Failed to send the report.".to_string());
+    }
+    Ok(())
+}
+
+// Purges are logged as a single summary with the full transcript attached.
+pub async fn bulk_deleted(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    messages: &[Message],
+    total: usize,
+) {
+    let Some(log_channel) = log_channel(ctx, guild_id).await else {
+        return;
+    };
+
+    let line = |message: &Message| {
+        let mut line = format!(
+            "[{}] {}: {}",
+            message.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            message.author.name,
+            message.content
+        );
+        for attachment in &message.attachments {
+            line.push_str(&format!(" [{}]", attachment.url));
+        }
+        line
+    };
+
+    let mut description = messages
+        .iter()
+        .take(MAX_LOG_LINES)
+        .map(|m| truncate(&line(m), 180))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if messages.len() > MAX_LOG_LINES {
+        description.push_str(&format!("\n… and {} more", messages.len() - MAX_LOG_LINES));
+    }
+    if messages.is_empty() {
+        description = "None of the deleted messages were stored.".to_string();
+    }
+
+    let embed = CreateEmbed::default()
+        .title(format!("{} messages deleted", total))
+        .description(format!("```\n{}\n```", truncate(&description, 4000)))
+        .field("Channel", format!("<#{}>", channel_id), true)
+        .field("Captured", format!("{} of {}", messages.len(), total), true)
+        .timestamp(Utc::now())
+        .color(Colour::RED);
+
+    let mut builder = CreateMessage::new().embed(embed);
+    if !messages.is_empty() {
+        let transcript = messages.iter().map(line).collect::<Vec<_>>().join("\n");
+        builder = builder.add_file(CreateAttachment::bytes(
+            transcript.into_bytes(),
+            format!("deleted-{}.txt", channel_id),
+        ));
+        // Whatever was cached is attached next to the transcript, newest messages first.
+        let mut files = vec![];
+        for message in messages.iter().rev() {
+            files.extend(
+                attachments::uploads(&ctx.data, message.id)
+                    .await
+                    .into_iter()
+                    .map(|(_, upload)| upload),
+            );
+            if files.len() >= MAX_FILES - 1 {
+                break;
+            }
+        }
+        files.truncate(MAX_FILES - 1);
+        builder = builder.add_files(files);
+    }
+    if let Err(e) = log_channel.send_message(&ctx.http, builder).await {
+        error!("Failed to log bulk deletion in {}: {}", guild_id, e);
+    }
+}
+
+async fn log_channel(ctx: &Context, guild_id: GuildId) -> Option<ChannelId> {
+    let data = ctx.data.read().await;
+    data.get::<MessageLogChannels>()
+        .and_then(|channels| channels.get(&guild_id).map(|c| *c))
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated = text.chars().take(max - 1).collect::<String>();
+    truncated.push('…');
+    truncated
+}
@@ -1,7 +1,16 @@
+pub mod analytics;
+pub mod boosts;
 pub mod commands;
 pub mod extras;
+pub mod message_log;
 pub mod pagination;
+pub mod reactions;
 pub mod ready;
+pub mod reddit;
+pub mod render;
+pub mod roleplay;
 pub mod snipes;
+pub mod twitch;
 pub mod user_afk;
 pub mod voice;
+pub mod youtube;
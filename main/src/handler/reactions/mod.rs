@@ -0,0 +1,291 @@
+use std::{collections::HashSet, sync::Arc};
+
+use serenity::all::{
+    CacheHttp, Context, Guild, Message, MessageId, Reaction, ReactionType, RoleId, UserId,
+};
+use utils::{error, info, warning};
+
+use crate::{
+    AutoReactions, ElapsedTime, ReactionRoleMessage, ReactionRoleMode, ReactionRoles,
+    ReactionRolesMap, same_emoji, save_reaction_roles,
+};
+
+const REACTION_USERS_PAGE: u8 = 100;
+
+pub async fn auto_react(ctx: &Context, message: &Message) {
+    let Some(guild_id) = message.guild_id else {
+        return;
+    };
+    let auto_reactions = {
+        let data = ctx.data.read().await;
+        data.get::<AutoReactions>()
+            .cloned()
+            .expect("Expected AutoReactions in TypeMap.")
+    };
+
+    let emojis = {
+        let Some(reactions) = auto_reactions.get(&guild_id) else {
+            return;
+        };
+        let mut emojis: Vec<ReactionType> = vec![];
+        for reaction in reactions.iter().filter(|r| r.matches(message)) {
+            for emoji in &reaction.emojis {
+                if !emojis.iter().any(|e| same_emoji(e, emoji)) {
+                    emojis.push(emoji.clone());
+                }
+            }
+        }
+        emojis
+    };
+
+    for emoji in emojis {
+        if let Err(e) = message.react(ctx.http(), emoji.clone()).await {
+            error!(
+                "Failed to auto-react with {} in {}: {}",
+                emoji, message.channel_id, e
+            );
+        }
+    }
+}
+
+pub async fn reaction_add(ctx: &Context, reaction: &Reaction) {
+    let (Some(guild_id), Some(user_id)) = (reaction.guild_id, reaction.user_id) else {
+        return;
+    };
+    if reaction.member.as_ref().is_some_and(|m| m.user.bot) {
+        return;
+    }
+    let reaction_roles = get_reaction_roles(ctx).await;
+    let Some(config) = reaction_roles.get(&reaction.message_id).map(|c| c.clone()) else {
+        return;
+    };
+    let Some(role_id) = config.role_for(&reaction.emoji) else {
+        return;
+    };
+
+    if config.mode == ReactionRoleMode::Unique {
+        let held = reaction
+            .member
+            .as_ref()
+            .map(|m| m.roles.clone())
+            .unwrap_or_default();
+        for other in config.roles.iter().filter(|r| r.role != role_id) {
+            if held.contains(&other.role)
+                && let Err(e) = ctx
+                    .http()
+                    .remove_member_role(guild_id, user_id, other.role, Some("Reaction role"))
+                    .await
+            {
+                error!("Failed to remove reaction role {}: {}", other.role, e);
+            }
+            // Clearing the other reactions fires removal events, which are no-ops by then.
+            let _ = reaction
+                .channel_id
+                .delete_reaction(
+                    ctx.http(),
+                    reaction.message_id,
+                    Some(user_id),
+                    other.emoji.clone(),
+                )
+                .await;
+        }
+    }
+
+    if let Err(e) = ctx
+        .http()
+        .add_member_role(guild_id, user_id, role_id, Some("Reaction role"))
+        .await
+    {
+        error!(
+            "Failed to add reaction role {} to {} in {}: {}",
+            role_id, user_id, guild_id, e
+        );
+    }
+}
+
+pub async fn reaction_remove(ctx: &Context, reaction: &Reaction) {
+    let (Some(guild_id), Some(user_id)) = (reaction.guild_id, reaction.user_id) else {
+        return;
+    };
+    if user_id == ctx.cache.current_user().id {
+        return;
+    }
+    let reaction_roles = get_reaction_roles(ctx).await;
+    let Some(config) = reaction_roles.get(&reaction.message_id).map(|c| c.clone()) else {
+        return;
+    };
+    if config.mode == ReactionRoleMode::Verify {
+        return;
+    }
+    let Some(role_id) = config.role_for(&reaction.emoji) else {
+        return;
+    };
+
+    if let Err(e) = ctx
+        .http()
+        .remove_member_role(guild_id, user_id, role_id, Some("Reaction role"))
+        .await
+    {
+        error!(
+            "Failed to remove reaction role {} from {} in {}: {}",
+            role_id, user_id, guild_id, e
+        );
+    }
+}
+
+// Re-checks every reaction role message of the guild against Discord: messages and roles that
+// were deleted while the bot was offline are dropped, missing bot reactions are re-added and
+// members who reacted in the meantime receive their role.
+pub async fn sync_guild(ctx: &Context, guild: &Guild) {
+    let timer = ElapsedTime::new();
+    let reaction_roles = get_reaction_roles(ctx).await;
+    let message_ids = reaction_roles
+        .iter()
+        .filter(|entry| entry.guild_id == guild.id)
+        .map(|entry| *entry.key())
+        .collect::<Vec<_>>();
+    if message_ids.is_empty() {
+        return;
+    }
+
+    for message_id in &message_ids {
+        sync_message(ctx, guild, &reaction_roles, *message_id).await;
+    }
+    save_reaction_roles(&reaction_roles);
+
+    info!(
+        "Restored {} reaction role messages in {}({}) ({}ms)",
+        message_ids.len(),
+        guild.name,
+        guild.id,
+        timer.elapsed_ms()
+    );
+}
+
+async fn sync_message(
+    ctx: &Context,
+    guild: &Guild,
+    reaction_roles: &ReactionRolesMap,
+    message_id: MessageId,
+) {
+    let Some(mut config) = reaction_roles.get(&message_id).map(|c| c.clone()) else {
+        return;
+    };
+
+    let message = match config.channel_id.message(ctx.http(), message_id).await {
+        Ok(message) => message,
+        Err(serenity::Error::Http(e)) if e.status_code().is_some_and(|s| s.as_u16() == 404) => {
+            warning!(
+                "Reaction role message {} no longer exists, removing it",
+                message_id
+            );
+            reaction_roles.remove(&message_id);
+            return;
+        }
+        Err(e) => {
+            error!(
+                "Failed to fetch reaction role message {}: {}",
+                message_id, e
+            );
+            return;
+        }
+    };
+
+    config.roles.retain(|r| guild.roles.contains_key(&r.role));
+    if config.roles.is_empty() {
+        reaction_roles.remove(&message_id);
+        return;
+    }
+
+    let mut granted = HashSet::new();
+    for entry in &config.roles {
+        let reacted = message
+            .reactions
+            .iter()
+            .any(|r| r.me && same_emoji(&r.reaction_type, &entry.emoji));
+        if !reacted && let Err(e) = message.react(ctx.http(), entry.emoji.clone()).await {
+            error!(
+                "Failed to restore reaction {} on {}: {}",
+                entry.emoji, message_id, e
+            );
+        }
+
+        let users = reaction_users(ctx, &message, &entry.emoji).await;
+        for user_id in users {
+            let Some(member) = guild.members.get(&user_id) else {
+                continue;
+            };
+            if member.user.bot || member.roles.contains(&entry.role) {
+                continue;
+            }
+            if config.mode == ReactionRoleMode::Unique
+                && (granted.contains(&user_id)
+                    || config.roles.iter().any(|r| member.roles.contains(&r.role)))
+            {
+                continue;
+            }
+            grant(ctx, guild, user_id, entry.role).await;
+            granted.insert(user_id);
+        }
+    }
+
+    reaction_roles.insert(message_id, config);
+}
+
+async fn reaction_users(ctx: &Context, message: &Message, emoji: &ReactionType) -> Vec<UserId> {
+    let mut users = vec![];
+    let mut after = None;
+    loop {
+        let page = match message
+            .reaction_users(ctx.http(), emoji.clone(), Some(REACTION_USERS_PAGE), after)
+            .await
+        {
+            Ok(page) => page,
+            Err(e) => {
+                error!("Failed to fetch reactions of {}: {}", message.id, e);
+                break;
+            }
+        };
+        let done = page.len() < REACTION_USERS_PAGE as usize;
+        after = page.last().map(|u| u.id);
+        users.extend(page.into_iter().map(|u| u.id));
+        if done || after.is_none() {
+            break;
+        }
+    }
+    users
+}
+
+async fn grant(ctx: &Context, guild: &Guild, user_id: UserId, role_id: RoleId) {
+    if let Err(e) = ctx
+        .http()
+        .add_member_role(guild.id, user_id, role_id, Some("Reaction role"))
+        .await
+    {
+        error!(
+            "Failed to add reaction role {} to {} in {}: {}",
+            role_id, user_id, guild.id, e
+        );
+    }
+}
+
+async fn get_reaction_roles(ctx: &Context) -> Arc<ReactionRolesMap> {
+    let data = ctx.data.read().await;
+    data.get::<ReactionRoles>()
+        .cloned()
+        .expect("Expected ReactionRoles in TypeMap.")
+}
+
+pub fn message_link(config: &ReactionRoleMessage, message_id: MessageId) -> String {
+    format!(
+        "https://discord.com/channels/{}/{}/{}",
+        config.guild_id, config.channel_id, message_id
+    )
+}
+
+pub async fn message_deleted(ctx: &Context, message_id: MessageId) {
+    let reaction_roles = get_reaction_roles(ctx).await;
+    if reaction_roles.remove(&message_id).is_some() {
+        save_reaction_roles(&reaction_roles);
+    }
+}
@@ -0,0 +1,77 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    Router,
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+};
+use serde_json::{Value, json};
+
+use crate::RedditPost;
+
+// A local stand-in for Reddit that `RedditHttpProvider` can be pointed at. Subreddits with a
+// listing return it, `private` answers like a private subreddit and anything else is redirected
+// to the search page, as Reddit does for subreddits that don't exist.
+pub async fn serve(listings: HashMap<String, Value>) -> String {
+    let app = Router::new()
+        .route("/r/{subreddit}/new.json", get(new_posts))
+        .route("/subreddits/search", get(|| async { "search results" }))
+        .with_state(Arc::new(listings));
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+        .await
+        .expect("Failed to bind the fixture server");
+    let address = listener
+        .local_addr()
+        .expect("Failed to get the fixture server address");
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    format!("http://{}", address)
+}
+
+async fn new_posts(
+    State(listings): State<Arc<HashMap<String, Value>>>,
+    Path(subreddit): Path<String>,
+) -> Response {
+    if subreddit == "private" {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    match listings.get(&subreddit) {
+        Some(listing) => (
+            [(header::CONTENT_TYPE, "application/json")],
+            listing.to_string(),
+        )
+            .into_response(),
+        None => Redirect::to(&format!("/subreddits/search?q={}", subreddit)).into_response(),
+    }
+}
+
+// A listing of `/new` in Reddit's format, the posts given newest first.
+pub fn listing(posts: &[Value]) -> Value {
+    json!({
+        "kind": "Listing",
+        "data": {
+            "children": posts
+                .iter()
+                .map(|post| json!({ "kind": "t3", "data": post }))
+                .collect::<Vec<_>>(),
+        },
+    })
+}
+
+// A post with only the fields Reddit always sends, the rest can be added to the value.
+pub fn post(id: &str, created_utc: i64) -> Value {
+    json!({
+        "id": id,
+        "title": format!("Post {}", id),
+        "author": "someone",
+        "subreddit": "rust",
+        "permalink": format!("/r/rust/comments/{}/post/", id),
+        "score": 1,
+        "created_utc": created_utc as f64,
+    })
+}
+
+pub fn parse(post: Value) -> RedditPost {
+    serde_json::from_value(post).expect("Fixture post should deserialize")
+}
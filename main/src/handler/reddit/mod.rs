@@ -0,0 +1,203 @@
+#[cfg(test)]
+pub mod fixtures;
+
+use std::{sync::Arc, time::Duration};
+
+use serde::Deserialize;
+use serenity::{
+    all::{Colour, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, Timestamp},
+    async_trait,
+};
+
+use crate::{MAX_SUBREDDIT_LENGTH, RedditPost};
+
+const REDDIT_URL: &str = "https://www.reddit.com";
+const REDDIT_API_URL_VAR: &str = "REDDIT_API_URL";
+const USER_AGENT: &str = "discord:matchawave.discordbot:v0.1 (reddit feed)";
+const REDDIT_COLOUR: Colour = Colour::new(0xFF4500);
+
+#[async_trait]
+pub trait RedditProvider: Send + Sync {
+    // Newest posts of the subreddit, newest first.
+    async fn fetch_new(&self, subreddit: &str) -> Result<Vec<RedditPost>, String>;
+}
+
+#[derive(Deserialize)]
+struct Listing {
+    data: ListingData,
+}
+
+#[derive(Deserialize)]
+struct ListingData {
+    children: Vec<ListingChild>,
+}
+
+#[derive(Deserialize)]
+struct ListingChild {
+    data: RedditPost,
+}
+
+// Fetches the public JSON listings. The base URL can be overridden with `REDDIT_API_URL`, e.g.
+// to point the bot at a local fixture server.
+pub struct RedditHttpProvider {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl RedditHttpProvider {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to build HTTP client");
+        Self {
+            client,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(std::env::var(REDDIT_API_URL_VAR).unwrap_or(REDDIT_URL.to_string()))
+    }
+}
+
+#[async_trait]
+impl RedditProvider for RedditHttpProvider {
+    async fn fetch_new(&self, subreddit: &str) -> Result<Vec<RedditPost>, String> {
+        let url = format!("{}/r/{}/new.json", self.base_url, subreddit);
+        let response = self
+            .client
+            .get(&url)
+            .query(&[("limit", "25"), ("raw_json", "1")])
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach Reddit: {}", e))?;
+
+        // Reddit redirects unknown subreddits to the search page instead of returning a 404.
+        if response.url().path().starts_with("/subreddits/search") {
+            return Err(format!("r/{} does not exist.", subreddit));
+        }
+        let status = response.status();
+        if status.as_u16() == 403 {
+            return Err(format!("r/{} is private or banned.", subreddit));
+        }
+        if status.as_u16() == 404 {
+            return Err(format!("r/{} does not exist.", subreddit));
+        }
+        if !status.is_success() {
+            return Err(format!("Reddit returned {}", status));
+        }
+
+        let listing = response
+            .json::<Listing>()
+            .await
+            .map_err(|e| format!("Failed to parse Reddit listing: {}", e))?;
+        Ok(listing.data.children.into_iter().map(|c| c.data).collect())
+    }
+}
+
+pub fn default_provider() -> Arc<dyn RedditProvider> {
+    Arc::new(RedditHttpProvider::from_env())
+}
+
+pub fn is_valid_subreddit(name: &str) -> bool {
+    (2..=MAX_SUBREDDIT_LENGTH).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub fn post_embed(post: &RedditPost) -> CreateEmbed {
+    let title = truncate(&post.title, 256);
+    let mut embed = CreateEmbed::default()
+        .author(CreateEmbedAuthor::new(format!(
+            "r/{} · u/{}",
+            post.subreddit, post.author
+        )))
+        .title(title)
+        .url(format!("{}{}", REDDIT_URL, post.permalink))
+        .color(REDDIT_COLOUR);
+
+    if !post.selftext.is_empty() {
+        embed = embed.description(truncate(&post.selftext, 350));
+    }
+    if post.post_hint.as_deref() == Some("image")
+        && let Some(url) = &post.url
+    {
+        embed = embed.image(url);
+    }
+
+    let mut footer = format!("⬆ {} · 💬 {}", post.score, post.num_comments);
+    if let Some(flair) = post.link_flair_text.as_ref().filter(|f| !f.is_empty()) {
+        footer.push_str(&format!(" · {}", flair));
+    }
+    embed = embed.footer(CreateEmbedFooter::new(footer));
+
+    if let Ok(timestamp) = Timestamp::from_unix_timestamp(post.created_utc as i64) {
+        embed = embed.timestamp(timestamp);
+    }
+    embed
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated = text.chars().take(max - 1).collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::*;
+
+    async fn provider(listings: Vec<(&str, serde_json::Value)>) -> RedditHttpProvider {
+        let listings = listings
+            .into_iter()
+            .map(|(subreddit, listing)| (subreddit.to_string(), listing))
+            .collect::<HashMap<_, _>>();
+        RedditHttpProvider::new(fixtures::serve(listings).await)
+    }
+
+    #[tokio::test]
+    async fn fetches_posts_from_the_listing() {
+        let mut flaired = fixtures::post("b", 200);
+        flaired["link_flair_text"] = json!("News");
+        flaired["over_18"] = json!(true);
+        let listing = fixtures::listing(&[flaired, fixtures::post("a", 100)]);
+        let provider = provider(vec![("rust", listing)]).await;
+
+        let posts = provider.fetch_new("rust").await.unwrap();
+        assert_eq!(
+            posts.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(),
+            ["b", "a"]
+        );
+        assert_eq!(posts[0].link_flair_text.as_deref(), Some("News"));
+        assert!(posts[0].over_18);
+        assert!(!posts[1].over_18);
+        assert_eq!(posts[1].selftext, "");
+    }
+
+    #[tokio::test]
+    async fn reports_missing_and_private_subreddits() {
+        let provider = provider(vec![]).await;
+
+        let missing = provider.fetch_new("nothing_here").await.unwrap_err();
+        assert_eq!(missing, "r/nothing_here does not exist.");
+        let private = provider.fetch_new("private").await.unwrap_err();
+        assert_eq!(private, "r/private is private or banned.");
+    }
+
+    #[test]
+    fn validates_subreddit_names() {
+        assert!(is_valid_subreddit("rust"));
+        assert!(is_valid_subreddit("Rust_Gamedev"));
+        assert!(!is_valid_subreddit("r"));
+        assert!(!is_valid_subreddit("has space"));
+        assert!(!is_valid_subreddit("a_very_long_subreddit_name"));
+    }
+}
@@ -0,0 +1,236 @@
+use tiny_skia::PathBuilder;
+
+use super::{ACCENT, Align, BACKGROUND, Canvas, GRID, MUTED, Rgb, TEXT, fit_text, rounded_rect};
+
+const WIDTH: u32 = 900;
+const HEIGHT: u32 = 420;
+const PADDING: f32 = 24.0;
+const TITLE_HEIGHT: f32 = 48.0;
+const AXIS_WIDTH: f32 = 56.0;
+const LABEL_HEIGHT: f32 = 32.0;
+const GRID_LINES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    Line,
+    Bar,
+}
+
+#[derive(Debug, Clone)]
+pub struct Chart {
+    pub kind: ChartKind,
+    pub title: String,
+    pub points: Vec<(String, f64)>,
+    pub color: Rgb,
+}
+
+impl Chart {
+    pub fn new(kind: ChartKind, title: impl Into<String>, points: Vec<(String, f64)>) -> Self {
+        Self {
+            kind,
+            title: title.into(),
+            points,
+            color: ACCENT,
+        }
+    }
+
+    pub fn color(mut self, color: Rgb) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn render(&self) -> Result<Vec<u8>, String> {
+        let mut canvas = Canvas::new(WIDTH, HEIGHT, BACKGROUND)?;
+        let title = fit_text(
+            &canvas,
+            &self.title,
+            22.0,
+            true,
+            canvas.width() - PADDING * 2.0,
+        );
+        canvas.bold_text(&title, PADDING, PADDING + 22.0, 22.0, TEXT, Align::Left);
+
+        let left = PADDING + AXIS_WIDTH;
+        let top = PADDING + TITLE_HEIGHT;
+        let right = canvas.width() - PADDING;
+        let bottom = canvas.height() - PADDING - LABEL_HEIGHT;
+        let (width, height) = (right - left, bottom - top);
+
+        let max = nice_max(self.points.iter().map(|(_, v)| *v).fold(0.0, f64::max));
+        for i in 0..=GRID_LINES {
+            let value = max * i as f64 / GRID_LINES as f64;
+            let y = bottom - height * i as f32 / GRID_LINES as f32;
+            canvas.line((left, y), (right, y), GRID, 1.0);
+            canvas.text(
+                &short_number(value),
+                left - 10.0,
+                y + 5.0,
+                13.0,
+                MUTED,
+                Align::Right,
+            );
+        }
+
+        if self.points.is_empty() {
+            canvas.text(
+                "No data",
+                left + width / 2.0,
+                top + height / 2.0,
+                18.0,
+                MUTED,
+                Align::Center,
+            );
+            return canvas.encode();
+        }
+
+        let count = self.points.len();
+        let slot = width / count as f32;
+        let y_of = |value: f64| bottom - (value / max) as f32 * height;
+        match self.kind {
+            ChartKind::Bar => {
+                let bar = (slot * 0.7).min(64.0);
+                for (i, (_, value)) in self.points.iter().enumerate() {
+                    let x = left + slot * i as f32 + (slot - bar) / 2.0;
+                    let y = y_of(*value);
+                    if let Some(path) = rounded_rect(x, y, bar, bottom - y, 4.0) {
+                        canvas.fill_path(&path, self.color, 1.0);
+                    }
+                }
+            }
+            ChartKind::Line => {
+                let x_of = |i: usize| left + slot * i as f32 + slot / 2.0;
+                let mut line = PathBuilder::new();
+                let mut area = PathBuilder::new();
+                area.move_to(x_of(0), bottom);
+                for (i, (_, value)) in self.points.iter().enumerate() {
+                    let (x, y) = (x_of(i), y_of(*value));
+                    if i == 0 {
+                        line.move_to(x, y);
+                    } else {
+                        line.line_to(x, y);
+                    }
+                    area.line_to(x, y);
+                }
+                area.line_to(x_of(count - 1), bottom);
+                area.close();
+                if let Some(path) = area.finish() {
+                    canvas.fill_path(&path, self.color, 0.2);
+                }
+                if let Some(path) = line.finish() {
+                    canvas.stroke_path(&path, self.color, 3.0);
+                }
+                if count <= 31 {
+                    for (i, (_, value)) in self.points.iter().enumerate() {
+                        canvas.circle(x_of(i), y_of(*value), 4.0, self.color);
+                    }
+                }
+            }
+        }
+
+        // Only every n-th label is drawn so they never overlap.
+        let widest = self
+            .points
+            .iter()
+            .map(|(label, _)| canvas.text_width(label, 13.0, false))
+            .fold(0.0, f32::max);
+        let step = ((widest + 12.0) / slot).ceil().max(1.0) as usize;
+        for (i, (label, _)) in self.points.iter().enumerate().step_by(step) {
+            let x = left + slot * i as f32 + slot / 2.0;
+            canvas.text(label, x, bottom + 22.0, 13.0, MUTED, Align::Center);
+        }
+
+        canvas.encode()
+    }
+}
+
+// Rounds the axis maximum up to 1, 2 or 5 times a power of ten.
+fn nice_max(max: f64) -> f64 {
+    if max <= 0.0 {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(max.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|m| *m >= max)
+        .unwrap_or(max)
+}
+
+fn short_number(value: f64) -> String {
+    if value >= 1_000_000.0 {
+        format!("{:.1}M", value / 1_000_000.0)
+    } else if value >= 1_000.0 {
+        format!("{:.1}k", value / 1_000.0)
+    } else if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tiny_skia::Pixmap;
+
+    use super::*;
+
+    fn points(values: &[f64]) -> Vec<(String, f64)> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("Day {}", i + 1), *v))
+            .collect()
+    }
+
+    fn render(chart: Chart) -> Pixmap {
+        let png = chart.render().unwrap();
+        Pixmap::decode_png(&png).unwrap()
+    }
+
+    #[test]
+    fn renders_line_and_bar_charts() {
+        for kind in [ChartKind::Line, ChartKind::Bar] {
+            let chart = render(Chart::new(kind, "Messages", points(&[3.0, 8.0, 5.0])));
+            assert_eq!((chart.width(), chart.height()), (WIDTH, HEIGHT));
+        }
+    }
+
+    #[test]
+    fn renders_empty_and_long_charts() {
+        let empty = render(Chart::new(ChartKind::Line, "Nothing yet", vec![]));
+        assert_eq!((empty.width(), empty.height()), (WIDTH, HEIGHT));
+
+        let values = (0..500).map(|v| v as f64).collect::<Vec<_>>();
+        let title = "A very long title ".repeat(20);
+        let long = render(Chart::new(ChartKind::Bar, title, points(&values)));
+        assert_eq!((long.width(), long.height()), (WIDTH, HEIGHT));
+    }
+
+    #[test]
+    fn draws_bars_in_the_chart_colour() {
+        let colour = Rgb(0xFF0000);
+        let chart = render(Chart::new(ChartKind::Bar, "", points(&[1.0])).color(colour));
+
+        // A single bar at the axis maximum fills the middle of the plot.
+        let x = (PADDING + AXIS_WIDTH + WIDTH as f32 - PADDING) / 2.0;
+        let pixel = chart.pixel(x as u32, HEIGHT / 2).unwrap();
+        assert_eq!((pixel.red(), pixel.green(), pixel.blue()), (0xFF, 0, 0));
+    }
+
+    #[test]
+    fn rounds_the_axis_up() {
+        assert_eq!(nice_max(0.0), 1.0);
+        assert_eq!(nice_max(1.0), 1.0);
+        assert_eq!(nice_max(7.0), 10.0);
+        assert_eq!(nice_max(12.0), 20.0);
+        assert_eq!(nice_max(430.0), 500.0);
+    }
+
+    #[test]
+    fn shortens_axis_labels() {
+        assert_eq!(short_number(5.0), "5");
+        assert_eq!(short_number(2.5), "2.5");
+        assert_eq!(short_number(1_500.0), "1.5k");
+        assert_eq!(short_number(2_000_000.0), "2.0M");
+    }
+}
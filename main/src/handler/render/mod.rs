@@ -0,0 +1,300 @@
+use std::sync::LazyLock;
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont, point};
+use tiny_skia::{
+    Color, FillRule, Paint, Path, PathBuilder, Pixmap, PremultipliedColorU8, Rect, Stroke,
+    Transform,
+};
+
+mod chart;
+mod rank_card;
+
+pub use chart::*;
+pub use rank_card::*;
+
+// DejaVu Sans, see `assets/fonts/LICENSE`.
+static REGULAR: LazyLock<FontRef<'static>> = LazyLock::new(|| {
+    FontRef::try_from_slice(include_bytes!("../../../assets/fonts/DejaVuSans.ttf"))
+        .expect("Invalid regular font")
+});
+static BOLD: LazyLock<FontRef<'static>> = LazyLock::new(|| {
+    FontRef::try_from_slice(include_bytes!("../../../assets/fonts/DejaVuSans-Bold.ttf"))
+        .expect("Invalid bold font")
+});
+
+pub const BACKGROUND: Rgb = Rgb(0x2B2D31);
+pub const SURFACE: Rgb = Rgb(0x1E1F22);
+pub const GRID: Rgb = Rgb(0x3F4147);
+pub const TEXT: Rgb = Rgb(0xF2F3F5);
+pub const MUTED: Rgb = Rgb(0xB5BAC1);
+pub const ACCENT: Rgb = Rgb(0x5865F2);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Rgb(pub u32);
+
+impl Rgb {
+    fn color(&self, alpha: f32) -> Color {
+        let [_, r, g, b] = self.0.to_be_bytes();
+        Color::from_rgba8(r, g, b, (alpha.clamp(0.0, 1.0) * 255.0) as u8)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+// Thin drawing layer over a pixmap so the renderers only deal in shapes and text.
+pub struct Canvas {
+    pixmap: Pixmap,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, background: Rgb) -> Result<Self, String> {
+        let mut pixmap = Pixmap::new(width, height).ok_or("Invalid canvas size.".to_string())?;
+        pixmap.fill(background.color(1.0));
+        Ok(Self { pixmap })
+    }
+
+    pub fn width(&self) -> f32 {
+        self.pixmap.width() as f32
+    }
+
+    pub fn height(&self) -> f32 {
+        self.pixmap.height() as f32
+    }
+
+    fn paint(color: Rgb, alpha: f32) -> Paint<'static> {
+        let mut paint = Paint::default();
+        paint.set_color(color.color(alpha));
+        paint.anti_alias = true;
+        paint
+    }
+
+    pub fn fill_path(&mut self, path: &Path, color: Rgb, alpha: f32) {
+        self.pixmap.fill_path(
+            path,
+            &Self::paint(color, alpha),
+            FillRule::Winding,
+            Transform::identity(),
+            None,
+        );
+    }
+
+    pub fn stroke_path(&mut self, path: &Path, color: Rgb, width: f32) {
+        let stroke = Stroke {
+            width,
+            ..Default::default()
+        };
+        self.pixmap.stroke_path(
+            path,
+            &Self::paint(color, 1.0),
+            &stroke,
+            Transform::identity(),
+            None,
+        );
+    }
+
+    pub fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Rgb) {
+        if let Some(rect) = Rect::from_xywh(x, y, width, height) {
+            self.pixmap
+                .fill_rect(rect, &Self::paint(color, 1.0), Transform::identity(), None);
+        }
+    }
+
+    pub fn fill_rounded_rect(
+        &mut self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        radius: f32,
+        color: Rgb,
+    ) {
+        if let Some(path) = rounded_rect(x, y, width, height, radius) {
+            self.fill_path(&path, color, 1.0);
+        }
+    }
+
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), color: Rgb, width: f32) {
+        let mut builder = PathBuilder::new();
+        builder.move_to(from.0, from.1);
+        builder.line_to(to.0, to.1);
+        if let Some(path) = builder.finish() {
+            self.stroke_path(&path, color, width);
+        }
+    }
+
+    pub fn circle(&mut self, x: f32, y: f32, radius: f32, color: Rgb) {
+        if let Some(path) = PathBuilder::from_circle(x, y, radius) {
+            self.fill_path(&path, color, 1.0);
+        }
+    }
+
+    // Draws a PNG scaled into a circle, used for avatars.
+    pub fn image_circle(&mut self, png: &[u8], x: f32, y: f32, radius: f32) -> bool {
+        let (Ok(image), Some(path)) = (
+            Pixmap::decode_png(png),
+            PathBuilder::from_circle(x, y, radius),
+        ) else {
+            return false;
+        };
+        let scale = radius * 2.0 / image.width().min(image.height()) as f32;
+        let paint = Paint {
+            anti_alias: true,
+            shader: tiny_skia::Pattern::new(
+                image.as_ref(),
+                tiny_skia::SpreadMode::Pad,
+                tiny_skia::FilterQuality::Bicubic,
+                1.0,
+                Transform::from_scale(scale, scale).post_translate(x - radius, y - radius),
+            ),
+            ..Default::default()
+        };
+        self.pixmap.fill_path(
+            &path,
+            &paint,
+            FillRule::Winding,
+            Transform::identity(),
+            None,
+        );
+        true
+    }
+
+    pub fn text_width(&self, text: &str, size: f32, bold: bool) -> f32 {
+        let font = font(bold).as_scaled(PxScale::from(size));
+        let mut width = 0.0;
+        let mut previous = None;
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                width += font.kern(previous, id);
+            }
+            width += font.h_advance(id);
+            previous = Some(id);
+        }
+        width
+    }
+
+    // `y` is the baseline of the text.
+    pub fn text(&mut self, text: &str, x: f32, y: f32, size: f32, color: Rgb, align: Align) {
+        self.styled_text(text, x, y, size, color, align, false);
+    }
+
+    pub fn bold_text(&mut self, text: &str, x: f32, y: f32, size: f32, color: Rgb, align: Align) {
+        self.styled_text(text, x, y, size, color, align, true);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn styled_text(
+        &mut self,
+        text: &str,
+        x: f32,
+        y: f32,
+        size: f32,
+        color: Rgb,
+        align: Align,
+        bold: bool,
+    ) {
+        let width = self.text_width(text, size, bold);
+        let mut caret = match align {
+            Align::Left => x,
+            Align::Center => x - width / 2.0,
+            Align::Right => x - width,
+        };
+        let font = font(bold).as_scaled(PxScale::from(size));
+        let (canvas_width, canvas_height) = (self.pixmap.width(), self.pixmap.height());
+        let [_, r, g, b] = color.0.to_be_bytes();
+        let pixels = self.pixmap.pixels_mut();
+
+        let mut previous = None;
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                caret += font.kern(previous, id);
+            }
+            let glyph = id.with_scale_and_position(size, point(caret, y));
+            caret += font.h_advance(id);
+            previous = Some(id);
+
+            let Some(outlined) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i32 + gx as i32;
+                let py = bounds.min.y as i32 + gy as i32;
+                if px < 0 || py < 0 || px as u32 >= canvas_width || py as u32 >= canvas_height {
+                    return;
+                }
+                let index = (py as u32 * canvas_width + px as u32) as usize;
+                pixels[index] = blend(pixels[index], (r, g, b), coverage);
+            });
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        self.pixmap
+            .encode_png()
+            .map_err(|e| format!("Failed to encode image: {}", e))
+    }
+}
+
+fn font(bold: bool) -> &'static FontRef<'static> {
+    if bold { &BOLD } else { &REGULAR }
+}
+
+fn blend(
+    destination: PremultipliedColorU8,
+    (r, g, b): (u8, u8, u8),
+    coverage: f32,
+) -> PremultipliedColorU8 {
+    let alpha = coverage.clamp(0.0, 1.0);
+    let mix = |source: u8, destination: u8| {
+        (source as f32 * alpha + destination as f32 * (1.0 - alpha)).round() as u8
+    };
+    let a = mix(255, destination.alpha());
+    // Channels can't exceed alpha in premultiplied form.
+    PremultipliedColorU8::from_rgba(
+        mix(r, destination.red()).min(a),
+        mix(g, destination.green()).min(a),
+        mix(b, destination.blue()).min(a),
+        a,
+    )
+    .unwrap_or(destination)
+}
+
+pub fn rounded_rect(x: f32, y: f32, width: f32, height: f32, radius: f32) -> Option<Path> {
+    let radius = radius.min(width / 2.0).min(height / 2.0).max(0.0);
+    let (right, bottom) = (x + width, y + height);
+    let mut builder = PathBuilder::new();
+    builder.move_to(x + radius, y);
+    builder.line_to(right - radius, y);
+    builder.quad_to(right, y, right, y + radius);
+    builder.line_to(right, bottom - radius);
+    builder.quad_to(right, bottom, right - radius, bottom);
+    builder.line_to(x + radius, bottom);
+    builder.quad_to(x, bottom, x, bottom - radius);
+    builder.line_to(x, y + radius);
+    builder.quad_to(x, y, x + radius, y);
+    builder.close();
+    builder.finish()
+}
+
+// Shortens text with an ellipsis until it fits in `max_width`.
+pub fn fit_text(canvas: &Canvas, text: &str, size: f32, bold: bool, max_width: f32) -> String {
+    if canvas.text_width(text, size, bold) <= max_width {
+        return text.to_string();
+    }
+    let mut chars = text.chars().collect::<Vec<_>>();
+    while !chars.is_empty() {
+        chars.pop();
+        let candidate = format!("{}…", chars.iter().collect::<String>().trim_end());
+        if canvas.text_width(&candidate, size, bold) <= max_width {
+            return candidate;
+        }
+    }
+    String::new()
+}
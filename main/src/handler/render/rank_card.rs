@@ -0,0 +1,202 @@
+use super::{ACCENT, Align, BACKGROUND, Canvas, GRID, MUTED, Rgb, SURFACE, TEXT, fit_text};
+
+const WIDTH: u32 = 900;
+const HEIGHT: u32 = 260;
+const AVATAR_RADIUS: f32 = 80.0;
+
+#[derive(Debug, Clone)]
+pub struct RankCard {
+    pub name: String,
+    pub subtitle: String,
+    pub rank: usize,
+    // Shown next to the rank, e.g. ("LEVEL", "12") or ("MESSAGES", "1.2k").
+    pub stats: Vec<(String, String)>,
+    pub progress: f32,
+    pub progress_label: String,
+    // PNG bytes, a placeholder with the initial is drawn without it.
+    pub avatar: Option<Vec<u8>>,
+    pub accent: Rgb,
+}
+
+impl RankCard {
+    pub fn new(name: impl Into<String>, rank: usize) -> Self {
+        Self {
+            name: name.into(),
+            subtitle: String::new(),
+            rank,
+            stats: vec![],
+            progress: 0.0,
+            progress_label: String::new(),
+            avatar: None,
+            accent: ACCENT,
+        }
+    }
+
+    pub fn render(&self) -> Result<Vec<u8>, String> {
+        let mut canvas = Canvas::new(WIDTH, HEIGHT, SURFACE)?;
+        canvas.fill_rounded_rect(
+            12.0,
+            12.0,
+            canvas.width() - 24.0,
+            canvas.height() - 24.0,
+            18.0,
+            BACKGROUND,
+        );
+
+        let (avatar_x, avatar_y) = (48.0 + AVATAR_RADIUS, canvas.height() / 2.0);
+        canvas.circle(avatar_x, avatar_y, AVATAR_RADIUS + 5.0, self.accent);
+        let drawn = self
+            .avatar
+            .as_ref()
+            .is_some_and(|png| canvas.image_circle(png, avatar_x, avatar_y, AVATAR_RADIUS));
+        if !drawn {
+            canvas.circle(avatar_x, avatar_y, AVATAR_RADIUS, GRID);
+            let initial = self
+                .name
+                .chars()
+                .next()
+                .map(|c| c.to_uppercase().to_string())
+                .unwrap_or_default();
+            canvas.bold_text(
+                &initial,
+                avatar_x,
+                avatar_y + 24.0,
+                68.0,
+                TEXT,
+                Align::Center,
+            );
+        }
+
+        let left = avatar_x + AVATAR_RADIUS + 40.0;
+        let right = canvas.width() - 48.0;
+
+        // The rank and stats are laid out from the right edge inwards.
+        let mut x = right;
+        let rank = format!("#{}", self.rank);
+        canvas.bold_text(&rank, x, 88.0, 44.0, self.accent, Align::Right);
+        x -= canvas.text_width(&rank, 44.0, true);
+        canvas.text("RANK", x - 8.0, 88.0, 16.0, MUTED, Align::Right);
+        x -= canvas.text_width("RANK", 16.0, false) + 32.0;
+        for (label, value) in self.stats.iter().rev() {
+            canvas.bold_text(value, x, 88.0, 32.0, TEXT, Align::Right);
+            x -= canvas.text_width(value, 32.0, true);
+            canvas.text(label, x - 8.0, 88.0, 16.0, MUTED, Align::Right);
+            x -= canvas.text_width(label, 16.0, false) + 32.0;
+        }
+
+        let name = fit_text(&canvas, &self.name, 34.0, true, right - left);
+        canvas.bold_text(&name, left, 150.0, 34.0, TEXT, Align::Left);
+        if !self.subtitle.is_empty() {
+            let label_width = canvas.text_width(&self.progress_label, 16.0, false);
+            let subtitle = fit_text(
+                &canvas,
+                &self.subtitle,
+                18.0,
+                false,
+                right - left - label_width - 16.0,
+            );
+            canvas.text(&subtitle, left, 178.0, 18.0, MUTED, Align::Left);
+        }
+
+        let (bar_y, bar_height) = (196.0, 24.0);
+        let bar_width = right - left;
+        canvas.fill_rounded_rect(left, bar_y, bar_width, bar_height, 12.0, GRID);
+        let filled = bar_width * self.progress.clamp(0.0, 1.0);
+        if filled > 0.0 {
+            canvas.fill_rounded_rect(
+                left,
+                bar_y,
+                filled.max(bar_height),
+                bar_height,
+                12.0,
+                self.accent,
+            );
+        }
+        if !self.progress_label.is_empty() {
+            canvas.text(
+                &self.progress_label,
+                right,
+                178.0,
+                16.0,
+                MUTED,
+                Align::Right,
+            );
+        }
+
+        canvas.encode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tiny_skia::Pixmap;
+
+    use super::*;
+
+    fn render(card: &RankCard) -> Pixmap {
+        let png = card.render().unwrap();
+        Pixmap::decode_png(&png).unwrap()
+    }
+
+    fn card() -> RankCard {
+        RankCard {
+            subtitle: "Member since 2021".to_string(),
+            stats: vec![("LEVEL".to_string(), "12".to_string())],
+            progress: 0.4,
+            progress_label: "400 / 1000".to_string(),
+            ..RankCard::new("Someone", 3)
+        }
+    }
+
+    // A point inside the avatar circle that the placeholder initial doesn't cover.
+    fn avatar_pixel(card: &Pixmap) -> (u8, u8, u8) {
+        let x = (48.0 + AVATAR_RADIUS) as u32;
+        let y = HEIGHT / 2 - AVATAR_RADIUS as u32 + 10;
+        let pixel = card.pixel(x, y).unwrap();
+        (pixel.red(), pixel.green(), pixel.blue())
+    }
+
+    #[test]
+    fn renders_rank_cards() {
+        let card = render(&card());
+        assert_eq!((card.width(), card.height()), (WIDTH, HEIGHT));
+        let [_, r, g, b] = GRID.0.to_be_bytes();
+        assert_eq!(avatar_pixel(&card), (r, g, b));
+    }
+
+    #[test]
+    fn draws_the_avatar() {
+        let avatar = Canvas::new(64, 64, Rgb(0x00FF00)).unwrap();
+        let card = render(&RankCard {
+            avatar: Some(avatar.encode().unwrap()),
+            ..card()
+        });
+
+        assert_eq!((card.width(), card.height()), (WIDTH, HEIGHT));
+        assert_eq!(avatar_pixel(&card), (0, 0xFF, 0));
+    }
+
+    #[test]
+    fn falls_back_for_broken_avatars() {
+        let card = render(&RankCard {
+            name: String::new(),
+            avatar: Some(b"not a png".to_vec()),
+            ..card()
+        });
+
+        assert_eq!((card.width(), card.height()), (WIDTH, HEIGHT));
+        let [_, r, g, b] = GRID.0.to_be_bytes();
+        assert_eq!(avatar_pixel(&card), (r, g, b));
+    }
+
+    #[test]
+    fn renders_extreme_values() {
+        let card = render(&RankCard {
+            name: "A name far too long to fit on the card ".repeat(10),
+            stats: vec![("MESSAGES".to_string(), "1.2M".to_string()); 3],
+            progress: 7.5,
+            ..RankCard::new("", usize::MAX)
+        });
+        assert_eq!((card.width(), card.height()), (WIDTH, HEIGHT));
+    }
+}
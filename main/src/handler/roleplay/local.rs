@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use serenity::async_trait;
+
+use crate::{RoleplayAction, storage};
+
+use super::MediaProvider;
+
+const LOCAL_MEDIA_STORAGE: &str = "roleplay_media";
+
+// Serves media from a static list in `data/roleplay_media.json`, keyed by action name.
+pub struct LocalProvider {
+    media: HashMap<RoleplayAction, Vec<String>>,
+}
+
+impl LocalProvider {
+    pub fn load() -> Self {
+        Self {
+            media: storage::load(LOCAL_MEDIA_STORAGE),
+        }
+    }
+}
+
+#[async_trait]
+impl MediaProvider for LocalProvider {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn fetch(&self, action: RoleplayAction) -> Option<String> {
+        let list = self.media.get(&action)?;
+        if list.is_empty() {
+            return None;
+        }
+        list.get(fastrand::usize(..list.len())).cloned()
+    }
+}
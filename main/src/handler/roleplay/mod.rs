@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{Context, UserId},
+    async_trait,
+};
+
+use crate::{
+    RoleplayAction, RoleplayCounters, RoleplayCountersMap, RoleplayMedia, save_roleplay_counters,
+};
+
+mod local;
+mod nekos;
+
+pub use local::LocalProvider;
+pub use nekos::NekosBestProvider;
+
+#[async_trait]
+pub trait MediaProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn fetch(&self, action: RoleplayAction) -> Option<String>;
+}
+
+// Providers are tried in order, so the local list only serves as a fallback when the API is
+// unreachable.
+pub fn default_providers() -> Vec<Arc<dyn MediaProvider>> {
+    vec![
+        Arc::new(NekosBestProvider::new()),
+        Arc::new(LocalProvider::load()),
+    ]
+}
+
+pub async fn fetch_media(ctx: &Context, action: RoleplayAction) -> Option<String> {
+    let providers = {
+        let data = ctx.data.read().await;
+        data.get::<RoleplayMedia>()
+            .cloned()
+            .expect("Expected RoleplayMedia in TypeMap.")
+    };
+    fetch_from(&providers, action).await
+}
+
+// The first media a provider has for the action, the ones after it aren't asked.
+async fn fetch_from(
+    providers: &[Arc<dyn MediaProvider>],
+    action: RoleplayAction,
+) -> Option<String> {
+    for provider in providers {
+        if let Some(url) = provider.fetch(action).await {
+            return Some(url);
+        }
+    }
+    None
+}
+
+// Increments the counter of `from` towards `to` and returns both directions of the pair.
+pub async fn record_interaction(
+    ctx: &Context,
+    action: RoleplayAction,
+    from: UserId,
+    to: UserId,
+) -> (u64, u64) {
+    let counters = {
+        let data = ctx.data.read().await;
+        data.get::<RoleplayCounters>()
+            .cloned()
+            .expect("Expected RoleplayCounters in TypeMap.")
+    };
+    let counts = count_interaction(&counters, action, from, to);
+    save_roleplay_counters(&counters);
+    counts
+}
+
+fn count_interaction(
+    counters: &RoleplayCountersMap,
+    action: RoleplayAction,
+    from: UserId,
+    to: UserId,
+) -> (u64, u64) {
+    let given = {
+        let mut entry = counters.entry(from).or_default();
+        let count = entry.entry(action).or_default().entry(to).or_default();
+        *count += 1;
+        *count
+    };
+    let received = counters
+        .get(&to)
+        .and_then(|actions| actions.get(&action).and_then(|t| t.get(&from).copied()))
+        .unwrap_or_default();
+    (given, received)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    // Answers with its media when it has some and counts how often it was asked.
+    struct StubProvider {
+        media: Option<&'static str>,
+        calls: AtomicUsize,
+    }
+
+    impl StubProvider {
+        fn new(media: Option<&'static str>) -> Arc<Self> {
+            Arc::new(Self {
+                media,
+                calls: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl MediaProvider for StubProvider {
+        fn name(&self) -> &'static str {
+            "stub"
+        }
+
+        async fn fetch(&self, _: RoleplayAction) -> Option<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.media.map(|m| m.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_next_provider() {
+        let offline = StubProvider::new(None);
+        let local = StubProvider::new(Some("local.gif"));
+        let unused = StubProvider::new(Some("unused.gif"));
+        let providers: Vec<Arc<dyn MediaProvider>> =
+            vec![offline.clone(), local.clone(), unused.clone()];
+
+        assert_eq!(
+            fetch_from(&providers, RoleplayAction::Hug).await.as_deref(),
+            Some("local.gif")
+        );
+        assert_eq!(offline.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(local.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(unused.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn no_media_when_every_provider_fails() {
+        let providers: Vec<Arc<dyn MediaProvider>> =
+            vec![StubProvider::new(None), StubProvider::new(None)];
+        assert_eq!(fetch_from(&providers, RoleplayAction::Pat).await, None);
+        assert_eq!(fetch_from(&[], RoleplayAction::Pat).await, None);
+    }
+
+    #[test]
+    fn counts_both_directions() {
+        let counters = RoleplayCountersMap::new();
+        let (a, b) = (UserId::new(1), UserId::new(2));
+        assert_eq!(
+            count_interaction(&counters, RoleplayAction::Hug, a, b),
+            (1, 0)
+        );
+        assert_eq!(
+            count_interaction(&counters, RoleplayAction::Hug, a, b),
+            (2, 0)
+        );
+        assert_eq!(
+            count_interaction(&counters, RoleplayAction::Hug, b, a),
+            (1, 2)
+        );
+        // Each action is counted on its own.
+        assert_eq!(
+            count_interaction(&counters, RoleplayAction::Pat, b, a),
+            (1, 0)
+        );
+        assert_eq!(
+            count_interaction(&counters, RoleplayAction::Hug, a, b),
+            (3, 1)
+        );
+        assert_eq!(
+            count_interaction(&counters, RoleplayAction::Hug, a, a),
+            (1, 1)
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use serenity::async_trait;
+use utils::error;
+
+use crate::RoleplayAction;
+
+use super::MediaProvider;
+
+const NEKOS_BEST_URL: &str = "https://nekos.best/api/v2";
+
+pub struct NekosBestProvider {
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct NekosResponse {
+    results: Vec<NekosResult>,
+}
+
+#[derive(Deserialize)]
+struct NekosResult {
+    url: String,
+}
+
+impl NekosBestProvider {
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .expect("Failed to build HTTP client");
+        Self { client }
+    }
+}
+
+impl Default for NekosBestProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MediaProvider for NekosBestProvider {
+    fn name(&self) -> &'static str {
+        "nekos.best"
+    }
+
+    async fn fetch(&self, action: RoleplayAction) -> Option<String> {
+        let url = format!("{}/{}", NEKOS_BEST_URL, action.name());
+        let response = match self.client.get(&url).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                error!(
+                    "Failed to fetch {} media from {}: {}",
+                    action.name(),
+                    self.name(),
+                    e
+                );
+                return None;
+            }
+        };
+        match response.error_for_status() {
+            Ok(response) => response
+                .json::<NekosResponse>()
+                .await
+                .ok()?
+                .results
+                .into_iter()
+                .next()
+                .map(|r| r.url),
+            Err(e) => {
+                error!(
+                    "{} returned an error for {}: {}",
+                    self.name(),
+                    action.name(),
+                    e
+                );
+                None
+            }
+        }
+    }
+}
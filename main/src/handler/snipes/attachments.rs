@@ -0,0 +1,240 @@
+use std::{collections::HashSet, fs, time::Duration as StdDuration};
+
+use chrono::{Duration, Utc};
+use serenity::all::{Attachment, Context, CreateAttachment, GuildId, Message, MessageId, UserId};
+use utils::{Data, error, warning};
+
+use crate::{CachedAttachment, CachedAttachments, DEFAULT_SNIPE_TTL, ServerSnipeConfigs, storage};
+
+const ATTACHMENT_CACHE_DIR: &str = "attachments";
+const DOWNLOAD_TIMEOUT: u64 = 10; // seconds
+
+// Downloads the allowed attachments of a deleted message before their URLs expire.
+pub async fn preserve(ctx: &Context, message: &Message, guild_id: &GuildId) {
+    if message.attachments.is_empty() {
+        return;
+    }
+    let (cache, config) = {
+        let data = ctx.data.read().await;
+        let cache = data
+            .get::<CachedAttachments>()
+            .cloned()
+            .expect("Expected CachedAttachments in TypeMap.");
+        (cache, super::guild_config(&data, guild_id).attachments)
+    };
+    if cache.contains_key(&message.id) {
+        return;
+    }
+
+    let allowed = message
+        .attachments
+        .iter()
+        .filter(|a| config.allows(a.size, a.content_type.as_deref()))
+        .collect::<Vec<_>>();
+    if allowed.is_empty() {
+        return;
+    }
+
+    let client = match reqwest::Client::builder()
+        .timeout(StdDuration::from_secs(DOWNLOAD_TIMEOUT))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Failed to build HTTP client: {}", e);
+            return;
+        }
+    };
+    let directory = storage::directory(ATTACHMENT_CACHE_DIR).join(message.id.to_string());
+    if let Err(e) = fs::create_dir_all(&directory) {
+        error!("Failed to create attachment cache directory: {}", e);
+        return;
+    }
+
+    let mut cached = vec![];
+    for attachment in allowed {
+        let Some(bytes) = download(&client, attachment, config.max_size).await else {
+            continue;
+        };
+        let filename = format!("{}_{}", attachment.id, sanitize(&attachment.filename));
+        let path = directory.join(&filename);
+        if let Err(e) = fs::write(&path, bytes) {
+            error!("Failed to cache attachment {}: {}", attachment.id, e);
+            continue;
+        }
+        cached.push(CachedAttachment {
+            guild_id: *guild_id,
+            author_id: message.author.id,
+            filename,
+            content_type: attachment.content_type.clone(),
+            path,
+            cached_at: Utc::now(),
+        });
+    }
+
+    if cached.is_empty() {
+        let _ = fs::remove_dir_all(&directory);
+    } else {
+        cache.insert(message.id, cached);
+    }
+}
+
+// The cached files of a message, ready to be attached again.
+pub async fn uploads(
+    data: &Data,
+    message_id: MessageId,
+) -> Vec<(CachedAttachment, CreateAttachment)> {
+    let cached = {
+        let data = data.read().await;
+        data.get::<CachedAttachments>()
+            .and_then(|cache| cache.get(&message_id).map(|c| c.clone()))
+            .unwrap_or_default()
+    };
+    cached
+        .into_iter()
+        .filter_map(|attachment| match fs::read(&attachment.path) {
+            Ok(bytes) => {
+                let upload = CreateAttachment::bytes(bytes, attachment.filename.clone());
+                Some((attachment, upload))
+            }
+            Err(e) => {
+                warning!(
+                    "Cached attachment {} is missing: {}",
+                    attachment.filename,
+                    e
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+// Cached files live as long as the snipes of their guild, orphaned files are removed too.
+pub async fn sweep(data: &Data) {
+    let (cache, configs) = {
+        let data = data.read().await;
+        (
+            data.get::<CachedAttachments>()
+                .cloned()
+                .expect("Expected CachedAttachments in TypeMap."),
+            data.get::<ServerSnipeConfigs>()
+                .cloned()
+                .expect("Expected ServerSnipeConfigs in TypeMap."),
+        )
+    };
+
+    let now = Utc::now();
+    let mut expired = vec![];
+    cache.retain(|message_id, attachments| {
+        let Some(first) = attachments.first() else {
+            expired.push(*message_id);
+            return false;
+        };
+        let ttl = configs
+            .get(&first.guild_id)
+            .map(|c| c.ttl)
+            .unwrap_or(DEFAULT_SNIPE_TTL);
+        let keep = first.cached_at >= now - Duration::seconds(ttl as i64);
+        if !keep {
+            expired.push(*message_id);
+        }
+        keep
+    });
+    for message_id in expired {
+        remove_files(message_id);
+    }
+
+    let Ok(entries) = fs::read_dir(storage::directory(ATTACHMENT_CACHE_DIR)) else {
+        return;
+    };
+    let known = cache
+        .iter()
+        .map(|entry| entry.key().to_string())
+        .collect::<HashSet<_>>();
+    for entry in entries.flatten() {
+        if !known.contains(entry.file_name().to_string_lossy().as_ref()) {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+pub async fn forget_user(data: &Data, user_id: UserId) {
+    let cache = {
+        let data = data.read().await;
+        data.get::<CachedAttachments>()
+            .cloned()
+            .expect("Expected CachedAttachments in TypeMap.")
+    };
+    let mut removed = vec![];
+    cache.retain(|message_id, attachments| {
+        let keep = attachments.iter().all(|a| a.author_id != user_id);
+        if !keep {
+            removed.push(*message_id);
+        }
+        keep
+    });
+    for message_id in removed {
+        remove_files(message_id);
+    }
+}
+
+async fn download(
+    client: &reqwest::Client,
+    attachment: &Attachment,
+    max_size: u64,
+) -> Option<Vec<u8>> {
+    // The proxy keeps serving files a little longer than the CDN link.
+    for url in [&attachment.url, &attachment.proxy_url] {
+        let response = match client.get(url.as_str()).send().await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(_) => continue,
+            Err(e) => {
+                warning!("Failed to download attachment {}: {}", attachment.id, e);
+                continue;
+            }
+        };
+        if response
+            .content_length()
+            .is_some_and(|length| length > max_size)
+        {
+            return None;
+        }
+        match response.bytes().await {
+            Ok(bytes) if bytes.len() as u64 <= max_size => return Some(bytes.to_vec()),
+            Ok(_) => return None,
+            Err(e) => warning!("Failed to read attachment {}: {}", attachment.id, e),
+        }
+    }
+    None
+}
+
+fn remove_files(message_id: MessageId) {
+    let directory = storage::directory(ATTACHMENT_CACHE_DIR).join(message_id.to_string());
+    if let Err(e) = fs::remove_dir_all(&directory)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        error!(
+            "Failed to remove cached attachments of {}: {}",
+            message_id, e
+        );
+    }
+}
+
+// Discord only resolves `attachment://` names made of safe characters.
+fn sanitize(filename: &str) -> String {
+    let sanitized = filename
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if sanitized.trim_matches(['.', '_']).is_empty() {
+        "attachment".to_string()
+    } else {
+        sanitized
+    }
+}
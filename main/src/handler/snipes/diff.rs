@@ -0,0 +1,89 @@
+use std::cmp::max;
+
+// Keeps the table small enough for messages near the length limit.
+const MAX_DIFF_CELLS: usize = 250_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    Same,
+    Removed,
+    Added,
+}
+
+// Word level diff as markdown, removed words are struck through and added words are bold.
+pub fn word_diff(before: &str, after: &str) -> String {
+    let old = before.split_whitespace().collect::<Vec<_>>();
+    let new = after.split_whitespace().collect::<Vec<_>>();
+
+    let changes = if old.len() * new.len() > MAX_DIFF_CELLS {
+        old.iter()
+            .map(|word| (Change::Removed, *word))
+            .chain(new.iter().map(|word| (Change::Added, *word)))
+            .collect()
+    } else {
+        diff(&old, &new)
+    };
+
+    // Consecutive words with the same change are wrapped together.
+    let mut groups: Vec<(Change, Vec<String>)> = vec![];
+    for (change, word) in changes {
+        match groups.last_mut() {
+            Some((last, words)) if *last == change => words.push(escape(word)),
+            _ => groups.push((change, vec![escape(word)])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(change, words)| {
+            let words = words.join(" ");
+            match change {
+                Change::Same => words,
+                Change::Removed => format!("~~{}~~", words),
+                Change::Added => format!("**{}**", words),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn diff<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Change, &'a str)> {
+    // lengths[i][j] is the longest common subsequence of old[i..] and new[j..].
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                max(lengths[i + 1][j], lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            changes.push((Change::Same, old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            changes.push((Change::Removed, old[i]));
+            i += 1;
+        } else {
+            changes.push((Change::Added, new[j]));
+            j += 1;
+        }
+    }
+    changes
+}
+
+fn escape(word: &str) -> String {
+    let mut escaped = String::with_capacity(word.len());
+    for c in word.chars() {
+        if matches!(c, '\\' | '*' | '_' | '~' | '`' | '|' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -1,82 +1,346 @@
-use serenity::all::{GuildId, Message, Reaction};
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
+use serenity::all::{Cache, ChannelId, Context, GuildId, Message, Reaction, Timestamp, UserId};
 use utils::Data;
 
-use crate::{BlacklistedSnipes, EditSnipes, ReactionSnipes, Snipes};
+use crate::{
+    BlacklistedSnipes, EditSnipe, EditSnipes, MAX_REACTION_EVENTS, MAX_REACTION_HISTORIES,
+    MessageReactionHistory, MessageStore, REACTION_HISTORY_TTL, ReactionEvent, ReactionHistory,
+    ReactionSnipes, Revision, ServerSnipeConfigs, SnipeBuffer, SnipeConfig, SnipeOptOuts, Snipes,
+};
 
-pub async fn delete(data: &Data, message: &Message, guild_id: &GuildId) {
-    let (snipes, blacklist) = {
-        let data = data.read().await;
+pub mod attachments;
+mod diff;
+
+pub use diff::*;
+
+pub async fn delete(ctx: &Context, message: &Message, guild_id: &GuildId) {
+    if is_excluded(ctx, guild_id, message.channel_id, Some(message.author.id)).await {
+        return;
+    }
+    let (snipes, config) = {
+        let data = ctx.data.read().await;
         let snipes = data
             .get::<Snipes>()
             .cloned()
             .expect("Expected Snipes in TypeMap.");
-        let blacklist = data
-            .get::<BlacklistedSnipes>()
-            .cloned()
-            .expect("Expected Blacklisted Snipes in TypeMap.");
-        (snipes, blacklist)
+        (snipes, guild_config(&data, guild_id))
     };
 
-    if let Some(blacklisted) = blacklist.get(guild_id)
-        && blacklisted.contains(&message.channel_id)
-    {
-        return;
-    }
-
     snipes
         .entry(message.channel_id)
-        .or_default()
-        .push(message.clone());
+        .or_insert_with(|| SnipeBuffer::new(*guild_id))
+        .push(message.clone(), &config);
 }
 
-pub async fn edit(data: &Data, message: &Message, guild_id: &GuildId) {
-    let (snipes, blacklist) = {
-        let data = data.read().await;
+// `before` is the previous revision when it was still cached or stored.
+pub async fn edit(ctx: &Context, before: Option<&Message>, message: &Message, guild_id: &GuildId) {
+    if is_excluded(ctx, guild_id, message.channel_id, Some(message.author.id)).await {
+        return;
+    }
+    let (snipes, config) = {
+        let data = ctx.data.read().await;
         let snipes = data
             .get::<EditSnipes>()
             .cloned()
             .expect("Expected Edit Snipes in TypeMap.");
-        let blacklist = data
-            .get::<BlacklistedSnipes>()
-            .cloned()
-            .expect("Expected Blacklisted Snipes in TypeMap.");
-        (snipes, blacklist)
+        (snipes, guild_config(&data, guild_id))
     };
 
-    if let Some(blacklisted) = blacklist.get(guild_id)
-        && blacklisted.contains(&message.channel_id)
+    let mut buffer = snipes
+        .entry(message.channel_id)
+        .or_insert_with(|| SnipeBuffer::new(*guild_id));
+    // Further edits of the same message extend its history and move it back to the top.
+    let mut snipe = buffer
+        .take(|snipe| snipe.message_id == message.id)
+        .unwrap_or_else(|| EditSnipe {
+            message_id: message.id,
+            author: message.author.clone(),
+            revisions: before
+                .map(|before| Revision {
+                    content: before.content.clone(),
+                    at: before.edited_timestamp.unwrap_or(before.timestamp),
+                })
+                .into_iter()
+                .collect(),
+        });
+    if snipe
+        .revisions
+        .last()
+        .is_none_or(|last| last.content != message.content)
     {
-        return;
+        snipe.revisions.push(Revision {
+            content: message.content.clone(),
+            at: message.edited_timestamp.unwrap_or_else(Timestamp::now),
+        });
     }
-
-    snipes
-        .entry(message.channel_id)
-        .or_default()
-        .push(message.clone());
+    buffer.push(snipe, &config);
 }
 
-pub async fn reaction(data: &Data, reaction: &Reaction, guild_id: &GuildId) {
-    let (snipes, blacklist) = {
-        let data = data.read().await;
+pub async fn reaction(ctx: &Context, reaction: &Reaction, guild_id: &GuildId) {
+    if is_excluded(ctx, guild_id, reaction.channel_id, reaction.user_id).await {
+        return;
+    }
+    let (snipes, config) = {
+        let data = ctx.data.read().await;
         let snipes = data
             .get::<ReactionSnipes>()
             .cloned()
             .expect("Expected Snipes in TypeMap.");
-        let blacklist = data
-            .get::<BlacklistedSnipes>()
-            .cloned()
-            .expect("Expected Blacklisted Snipes in TypeMap.");
-        (snipes, blacklist)
+        (snipes, guild_config(&data, guild_id))
     };
 
-    if let Some(blacklisted) = blacklist.get(guild_id)
-        && blacklisted.contains(&reaction.channel_id)
+    snipes
+        .entry(reaction.channel_id)
+        .or_insert_with(|| SnipeBuffer::new(*guild_id))
+        .push(reaction.clone(), &config);
+}
+
+// Records both added and removed reactions for the reaction history timeline.
+pub async fn reaction_history(ctx: &Context, reaction: &Reaction, added: bool) {
+    let (Some(guild_id), Some(user_id)) = (reaction.guild_id, reaction.user_id) else {
+        return;
+    };
+    if user_id == ctx.cache.current_user().id
+        || is_excluded(ctx, &guild_id, reaction.channel_id, Some(user_id)).await
     {
         return;
     }
 
-    snipes
-        .entry(reaction.channel_id)
-        .or_default()
-        .push(reaction.clone());
+    let history = {
+        let data = ctx.data.read().await;
+        data.get::<ReactionHistory>()
+            .cloned()
+            .expect("Expected ReactionHistory in TypeMap.")
+    };
+    let mut entry = history
+        .entry(reaction.message_id)
+        .or_insert_with(|| MessageReactionHistory {
+            guild_id,
+            channel_id: reaction.channel_id,
+            events: vec![],
+        });
+    entry.events.push(ReactionEvent {
+        user_id,
+        emoji: reaction.emoji.clone(),
+        added,
+        at: Utc::now(),
+    });
+    if entry.events.len() > MAX_REACTION_EVENTS {
+        let excess = entry.events.len() - MAX_REACTION_EVENTS;
+        entry.events.drain(..excess);
+    }
+}
+
+// Expires old entries and applies capacity changes, dropping channels left empty.
+pub async fn sweep(data: &Data) {
+    let (snipes, edit_snipes, reaction_snipes, configs, history) = {
+        let data = data.read().await;
+        (
+            data.get::<Snipes>()
+                .cloned()
+                .expect("Expected Snipes in TypeMap."),
+            data.get::<EditSnipes>()
+                .cloned()
+                .expect("Expected Edit Snipes in TypeMap."),
+            data.get::<ReactionSnipes>()
+                .cloned()
+                .expect("Expected Reaction Snipes in TypeMap."),
+            data.get::<ServerSnipeConfigs>()
+                .cloned()
+                .expect("Expected ServerSnipeConfigs in TypeMap."),
+            data.get::<ReactionHistory>()
+                .cloned()
+                .expect("Expected ReactionHistory in TypeMap."),
+        )
+    };
+
+    let now = Utc::now();
+    let config = |guild_id: &GuildId| configs.get(guild_id).map(|c| c.clone()).unwrap_or_default();
+    sweep_buffers(&snipes, config, now);
+    sweep_buffers(&edit_snipes, config, now);
+    sweep_buffers(&reaction_snipes, config, now);
+
+    let oldest = now - Duration::seconds(REACTION_HISTORY_TTL);
+    history.retain(|_, entry| entry.last_activity().is_some_and(|at| at >= oldest));
+    if history.len() > MAX_REACTION_HISTORIES {
+        let mut activity = history
+            .iter()
+            .map(|entry| (entry.last_activity(), *entry.key()))
+            .collect::<Vec<_>>();
+        activity.sort();
+        for (_, message_id) in activity
+            .iter()
+            .take(activity.len() - MAX_REACTION_HISTORIES)
+        {
+            history.remove(message_id);
+        }
+    }
+
+    attachments::sweep(data).await;
+}
+
+fn sweep_buffers<T>(
+    buffers: &DashMap<ChannelId, SnipeBuffer<T>>,
+    config: impl Fn(&GuildId) -> SnipeConfig,
+    now: DateTime<Utc>,
+) {
+    buffers.retain(|_, buffer| {
+        buffer.enforce(&config(&buffer.guild_id), now);
+        !buffer.is_empty()
+    });
+}
+
+fn guild_config(data: &serenity::prelude::TypeMap, guild_id: &GuildId) -> SnipeConfig {
+    data.get::<ServerSnipeConfigs>()
+        .and_then(|configs| configs.get(guild_id).map(|c| c.clone()))
+        .unwrap_or_default()
+}
+
+// Whether the channel, its parent or its category is blacklisted, or the user opted out.
+async fn is_excluded(
+    ctx: &Context,
+    guild_id: &GuildId,
+    channel_id: ChannelId,
+    user_id: Option<UserId>,
+) -> bool {
+    let (blacklist, opt_outs) = {
+        let data = ctx.data.read().await;
+        (
+            data.get::<BlacklistedSnipes>()
+                .cloned()
+                .expect("Expected Blacklisted Snipes in TypeMap."),
+            data.get::<SnipeOptOuts>()
+                .cloned()
+                .expect("Expected SnipeOptOuts in TypeMap."),
+        )
+    };
+
+    if user_id.is_some_and(|user_id| opt_outs.contains_key(&user_id)) {
+        return true;
+    }
+    let Some(blacklisted) = blacklist.get(guild_id) else {
+        return false;
+    };
+    channel_ancestors(&ctx.cache, guild_id, channel_id)
+        .iter()
+        .any(|id| blacklisted.contains(id))
+}
+
+// The channel followed by its parents, e.g. thread, channel and category.
+pub fn channel_ancestors(
+    cache: &Cache,
+    guild_id: &GuildId,
+    channel_id: ChannelId,
+) -> Vec<ChannelId> {
+    let mut ancestors = vec![channel_id];
+    let Some(guild) = cache.guild(guild_id) else {
+        return ancestors;
+    };
+    let mut current = channel_id;
+    while ancestors.len() < 3 {
+        let parent = guild
+            .channels
+            .get(&current)
+            .and_then(|c| c.parent_id)
+            .or_else(|| {
+                guild
+                    .threads
+                    .iter()
+                    .find(|t| t.id == current)
+                    .and_then(|t| t.parent_id)
+            });
+        let Some(parent) = parent else {
+            break;
+        };
+        ancestors.push(parent);
+        current = parent;
+    }
+    ancestors
+}
+
+// Drops everything stored for a user after they opt out.
+pub async fn forget_user(data: &Data, user_id: UserId) {
+    let (snipes, edit_snipes, reaction_snipes, store, history) = {
+        let data = data.read().await;
+        (
+            data.get::<Snipes>()
+                .cloned()
+                .expect("Expected Snipes in TypeMap."),
+            data.get::<EditSnipes>()
+                .cloned()
+                .expect("Expected Edit Snipes in TypeMap."),
+            data.get::<ReactionSnipes>()
+                .cloned()
+                .expect("Expected Reaction Snipes in TypeMap."),
+            data.get::<MessageStore>()
+                .cloned()
+                .expect("Expected MessageStore in TypeMap."),
+            data.get::<ReactionHistory>()
+                .cloned()
+                .expect("Expected ReactionHistory in TypeMap."),
+        )
+    };
+
+    for mut buffer in snipes.iter_mut() {
+        buffer.retain(|message| message.author.id != user_id);
+    }
+    for mut buffer in edit_snipes.iter_mut() {
+        buffer.retain(|snipe| snipe.author.id != user_id);
+    }
+    for mut buffer in reaction_snipes.iter_mut() {
+        buffer.retain(|reaction| reaction.user_id != Some(user_id));
+    }
+    store.retain(|_, stored| stored.author.id != user_id);
+    history.retain(|_, entry| {
+        entry.events.retain(|event| event.user_id != user_id);
+        !entry.events.is_empty()
+    });
+    attachments::forget_user(data, user_id).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sweeps_expired_entries() {
+        let buffers = DashMap::new();
+        let (short, long) = (GuildId::new(1), GuildId::new(2));
+        let config = |guild_id: &GuildId| SnipeConfig {
+            ttl: if *guild_id == short { 60 } else { 600 },
+            ..Default::default()
+        };
+        for (channel, guild_id) in [(1, short), (2, long)] {
+            let mut buffer = SnipeBuffer::new(guild_id);
+            buffer.push(channel, &config(&guild_id));
+            buffers.insert(ChannelId::new(channel), buffer);
+        }
+
+        sweep_buffers(&buffers, config, Utc::now() + Duration::seconds(120));
+        // Channels left empty are dropped.
+        assert!(!buffers.contains_key(&ChannelId::new(1)));
+        assert_eq!(buffers.get(&ChannelId::new(2)).unwrap().len(), 1);
+
+        sweep_buffers(&buffers, config, Utc::now() + Duration::seconds(601));
+        assert!(buffers.is_empty());
+    }
+
+    #[test]
+    fn sweeps_capacity_changes() {
+        let buffers = DashMap::new();
+        let mut buffer = SnipeBuffer::new(GuildId::new(1));
+        for item in 1..=5 {
+            buffer.push(item, &SnipeConfig::default());
+        }
+        buffers.insert(ChannelId::new(1), buffer);
+
+        let config = |_: &GuildId| SnipeConfig {
+            capacity: 2,
+            ..Default::default()
+        };
+        sweep_buffers(&buffers, config, Utc::now());
+        let buffer = buffers.get(&ChannelId::new(1)).unwrap();
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [4, 5]);
+    }
 }
@@ -0,0 +1,233 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::json;
+use tokio::sync::RwLock;
+
+const HELIX_URL: &str = "https://api.twitch.tv/helix";
+const TOKEN_URL: &str = "https://id.twitch.tv/oauth2/token";
+const STREAMS_PER_REQUEST: usize = 100;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TwitchUser {
+    pub id: String,
+    pub login: String,
+    pub display_name: String,
+    #[serde(default)]
+    pub profile_image_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TwitchStream {
+    pub id: String,
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    #[serde(default)]
+    pub game_name: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub viewer_count: u64,
+    pub started_at: DateTime<Utc>,
+    #[serde(default)]
+    pub thumbnail_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventSubSubscription {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub condition: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct HelixResponse<T> {
+    data: Vec<T>,
+    #[serde(default)]
+    pagination: Option<Pagination>,
+}
+
+#[derive(Deserialize)]
+struct Pagination {
+    cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+// Helix client using an app access token from the client credentials flow.
+pub struct TwitchApi {
+    client: reqwest::Client,
+    client_id: String,
+    client_secret: String,
+    token: RwLock<Option<(String, Instant)>>,
+}
+
+impl TwitchApi {
+    pub fn new(client_id: String, client_secret: String) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to build HTTP client");
+        Self {
+            client,
+            client_id,
+            client_secret,
+            token: RwLock::new(None),
+        }
+    }
+
+    async fn token(&self) -> Result<String, String> {
+        if let Some((token, expires)) = self.token.read().await.as_ref()
+            && Instant::now() < *expires
+        {
+            return Ok(token.clone());
+        }
+
+        let response = self
+            .client
+            .post(TOKEN_URL)
+            .form(&[
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+                ("grant_type", "client_credentials"),
+            ])
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Failed to get a Twitch token: {}", e))?
+            .json::<TokenResponse>()
+            .await
+            .map_err(|e| format!("Failed to parse the Twitch token: {}", e))?;
+
+        // Refresh a minute early so requests in flight don't race the expiry.
+        let expires = Instant::now() + Duration::from_secs(response.expires_in.saturating_sub(60));
+        *self.token.write().await = Some((response.access_token.clone(), expires));
+        Ok(response.access_token)
+    }
+
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<serde_json::Value>,
+    ) -> Result<reqwest::Response, String> {
+        for _ in 0..2 {
+            let token = self.token().await?;
+            let mut request = self
+                .client
+                .request(method.clone(), format!("{}{}", HELIX_URL, path))
+                .query(query)
+                .header("Client-Id", &self.client_id)
+                .bearer_auth(token);
+            if let Some(body) = &body {
+                request = request.json(body);
+            }
+            let response = request
+                .send()
+                .await
+                .map_err(|e| format!("Failed to reach Twitch: {}", e))?;
+            if response.status() == StatusCode::UNAUTHORIZED {
+                *self.token.write().await = None;
+                continue;
+            }
+            return Ok(response);
+        }
+        Err("Twitch rejected the app credentials.".to_string())
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<HelixResponse<T>, String> {
+        self.request(Method::GET, path, query, None)
+            .await?
+            .error_for_status()
+            .map_err(|e| format!("Twitch returned an error: {}", e))?
+            .json::<HelixResponse<T>>()
+            .await
+            .map_err(|e| format!("Failed to parse the Twitch response: {}", e))
+    }
+
+    pub async fn user_by_login(&self, login: &str) -> Result<Option<TwitchUser>, String> {
+        let response = self
+            .get::<TwitchUser>("/users", &[("login", login)])
+            .await?;
+        Ok(response.data.into_iter().next())
+    }
+
+    pub async fn streams(&self, user_ids: &[String]) -> Result<Vec<TwitchStream>, String> {
+        let mut streams = vec![];
+        for chunk in user_ids.chunks(STREAMS_PER_REQUEST) {
+            let mut query = vec![("first", "100")];
+            query.extend(chunk.iter().map(|id| ("user_id", id.as_str())));
+            streams.extend(self.get::<TwitchStream>("/streams", &query).await?.data);
+        }
+        Ok(streams)
+    }
+
+    pub async fn create_eventsub(
+        &self,
+        kind: &str,
+        user_id: &str,
+        callback: &str,
+        secret: &str,
+    ) -> Result<(), String> {
+        let body = json!({
+            "type": kind,
+            "version": "1",
+            "condition": { "broadcaster_user_id": user_id },
+            "transport": { "method": "webhook", "callback": callback, "secret": secret },
+        });
+        let response = self
+            .request(Method::POST, "/eventsub/subscriptions", &[], Some(body))
+            .await?;
+        match response.status() {
+            // Twitch answers 409 when the subscription already exists.
+            status if status.is_success() || status == StatusCode::CONFLICT => Ok(()),
+            status => Err(format!("Failed to subscribe to {}: {}", kind, status)),
+        }
+    }
+
+    pub async fn eventsub_subscriptions(&self) -> Result<Vec<EventSubSubscription>, String> {
+        let mut subscriptions = vec![];
+        let mut cursor: Option<String> = None;
+        loop {
+            let query = match &cursor {
+                Some(cursor) => vec![("after", cursor.as_str())],
+                None => vec![],
+            };
+            let response = self
+                .get::<EventSubSubscription>("/eventsub/subscriptions", &query)
+                .await?;
+            subscriptions.extend(response.data);
+            cursor = response.pagination.and_then(|p| p.cursor);
+            if cursor.is_none() {
+                break;
+            }
+        }
+        Ok(subscriptions)
+    }
+
+    pub async fn delete_eventsub(&self, id: &str) -> Result<(), String> {
+        self.request(
+            Method::DELETE,
+            "/eventsub/subscriptions",
+            &[("id", id)],
+            None,
+        )
+        .await?
+        .error_for_status()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to delete subscription {}: {}", id, e))
+    }
+}
@@ -0,0 +1,450 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+};
+
+use axum::{
+    Router,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serenity::async_trait;
+use sha2::Sha256;
+use tokio::sync::{Mutex, mpsc};
+use utils::{error, info, warning};
+
+use super::{StreamEvent, StreamWatcher, TwitchApi};
+
+const EVENTSUB_PATH: &str = "/twitch/eventsub";
+const ONLINE: &str = "stream.online";
+const OFFLINE: &str = "stream.offline";
+const MAX_MESSAGE_AGE: i64 = 60 * 10; // seconds
+const MAX_SEEN_MESSAGES: usize = 500;
+
+const MESSAGE_ID: &str = "Twitch-Eventsub-Message-Id";
+const MESSAGE_TIMESTAMP: &str = "Twitch-Eventsub-Message-Timestamp";
+const MESSAGE_SIGNATURE: &str = "Twitch-Eventsub-Message-Signature";
+const MESSAGE_TYPE: &str = "Twitch-Eventsub-Message-Type";
+
+#[derive(Deserialize)]
+struct Notification {
+    subscription: NotificationSubscription,
+    #[serde(default)]
+    challenge: Option<String>,
+    #[serde(default)]
+    event: Option<NotificationEvent>,
+}
+
+#[derive(Deserialize)]
+struct NotificationSubscription {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct NotificationEvent {
+    broadcaster_user_id: String,
+    broadcaster_user_login: String,
+}
+
+struct EventSubState {
+    secret: String,
+    events: mpsc::Sender<StreamEvent>,
+    // Twitch retries deliveries, so recently handled message IDs are remembered.
+    seen: Mutex<(HashSet<String>, VecDeque<String>)>,
+}
+
+// Receives `stream.online` and `stream.offline` webhooks on `TWITCH_EVENTSUB_PORT`. The callback
+// URL must be public HTTPS and forward to `/twitch/eventsub`.
+pub struct EventSubWatcher {
+    api: Arc<TwitchApi>,
+    callback: String,
+    secret: String,
+    port: u16,
+}
+
+impl EventSubWatcher {
+    pub fn new(api: Arc<TwitchApi>, callback: String, secret: String, port: u16) -> Self {
+        Self {
+            api,
+            callback,
+            secret,
+            port,
+        }
+    }
+}
+
+#[async_trait]
+impl StreamWatcher for EventSubWatcher {
+    fn mode(&self) -> &'static str {
+        "eventsub"
+    }
+
+    async fn watch(&self, user_id: &str, _: &str) -> Result<(), String> {
+        for kind in [ONLINE, OFFLINE] {
+            self.api
+                .create_eventsub(kind, user_id, &self.callback, &self.secret)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn unwatch(&self, user_id: &str) -> Result<(), String> {
+        let subscriptions = self.api.eventsub_subscriptions().await?;
+        for subscription in subscriptions {
+            let broadcaster = subscription
+                .condition
+                .get("broadcaster_user_id")
+                .and_then(|v| v.as_str());
+            if (subscription.kind == ONLINE || subscription.kind == OFFLINE)
+                && broadcaster == Some(user_id)
+            {
+                self.api.delete_eventsub(&subscription.id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn run(&self, events: mpsc::Sender<StreamEvent>) {
+        let state = Arc::new(EventSubState {
+            secret: self.secret.clone(),
+            events,
+            seen: Mutex::new((HashSet::new(), VecDeque::new())),
+        });
+        let app = Router::new()
+            .route(EVENTSUB_PATH, post(handle_webhook))
+            .with_state(state);
+
+        let listener = match tokio::net::TcpListener::bind(("0.0.0.0", self.port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!(
+                    "Failed to bind the EventSub listener on {}: {}",
+                    self.port, e
+                );
+                return;
+            }
+        };
+        info!("Listening for Twitch EventSub on port {}", self.port);
+        if let Err(e) = axum::serve(listener, app).await {
+            error!("EventSub listener stopped: {}", e);
+        }
+    }
+}
+
+async fn handle_webhook(
+    State(state): State<Arc<EventSubState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let (id, timestamp, signature, kind) = (
+        header(MESSAGE_ID),
+        header(MESSAGE_TIMESTAMP),
+        header(MESSAGE_SIGNATURE),
+        header(MESSAGE_TYPE),
+    );
+
+    if !verify_signature(&state.secret, &id, &timestamp, &body, &signature) {
+        warning!("Rejected an EventSub message with an invalid signature.");
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    // Old messages are rejected to prevent replays.
+    let fresh = DateTime::parse_from_rfc3339(&timestamp)
+        .is_ok_and(|t| (Utc::now() - t.with_timezone(&Utc)).num_seconds() < MAX_MESSAGE_AGE);
+    if !fresh {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    let Ok(notification) = serde_json::from_slice::<Notification>(&body) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    match kind.as_str() {
+        "webhook_callback_verification" => match notification.challenge {
+            Some(challenge) => (StatusCode::OK, challenge).into_response(),
+            None => StatusCode::BAD_REQUEST.into_response(),
+        },
+        "revocation" => {
+            warning!(
+                "Twitch revoked the {} subscription: {}",
+                notification.subscription.kind,
+                notification.subscription.status
+            );
+            StatusCode::NO_CONTENT.into_response()
+        }
+        "notification" => {
+            {
+                let mut seen = state.seen.lock().await;
+                if !seen.0.insert(id.clone()) {
+                    return StatusCode::NO_CONTENT.into_response();
+                }
+                seen.1.push_back(id);
+                if seen.1.len() > MAX_SEEN_MESSAGES
+                    && let Some(oldest) = seen.1.pop_front()
+                {
+                    seen.0.remove(&oldest);
+                }
+            }
+
+            let Some(event) = notification.event else {
+                return StatusCode::NO_CONTENT.into_response();
+            };
+            let event = match notification.subscription.kind.as_str() {
+                ONLINE => StreamEvent::Online {
+                    user_id: event.broadcaster_user_id,
+                    login: event.broadcaster_user_login,
+                    stream: None,
+                },
+                OFFLINE => StreamEvent::Offline {
+                    user_id: event.broadcaster_user_id,
+                    login: event.broadcaster_user_login,
+                },
+                _ => return StatusCode::NO_CONTENT.into_response(),
+            };
+            let _ = state.events.send(event).await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        _ => StatusCode::NO_CONTENT.into_response(),
+    }
+}
+
+fn verify_signature(secret: &str, id: &str, timestamp: &str, body: &[u8], signature: &str) -> bool {
+    let Some(expected) = signature
+        .strip_prefix("sha256=")
+        .and_then(|s| hex::decode(s).ok())
+    else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(id.as_bytes());
+    mac.update(timestamp.as_bytes());
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "a very secret secret";
+    const ONLINE_BODY: &str = r#"{"subscription":{"type":"stream.online","status":"enabled"},"event":{"broadcaster_user_id":"1337","broadcaster_user_login":"streamer"}}"#;
+
+    fn sign(secret: &str, id: &str, timestamp: &str, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(id.as_bytes());
+        mac.update(timestamp.as_bytes());
+        mac.update(body);
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    fn state() -> (Arc<EventSubState>, mpsc::Receiver<StreamEvent>) {
+        let (events, receiver) = mpsc::channel(16);
+        let state = EventSubState {
+            secret: SECRET.to_string(),
+            events,
+            seen: Mutex::new((HashSet::new(), VecDeque::new())),
+        };
+        (Arc::new(state), receiver)
+    }
+
+    fn headers(id: &str, timestamp: &str, signature: &str, kind: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(MESSAGE_ID, id.parse().unwrap());
+        headers.insert(MESSAGE_TIMESTAMP, timestamp.parse().unwrap());
+        headers.insert(MESSAGE_SIGNATURE, signature.parse().unwrap());
+        headers.insert(MESSAGE_TYPE, kind.parse().unwrap());
+        headers
+    }
+
+    async fn deliver(
+        state: &Arc<EventSubState>,
+        id: &str,
+        timestamp: &str,
+        body: &str,
+    ) -> StatusCode {
+        let signature = sign(SECRET, id, timestamp, body.as_bytes());
+        let headers = headers(id, timestamp, &signature, "notification");
+        handle_webhook(State(state.clone()), headers, Bytes::from(body.to_string()))
+            .await
+            .status()
+    }
+
+    #[test]
+    fn accepts_valid_signatures() {
+        let signature = sign(SECRET, "id", "2024-01-01T00:00:00Z", b"body");
+        assert!(verify_signature(
+            SECRET,
+            "id",
+            "2024-01-01T00:00:00Z",
+            b"body",
+            &signature
+        ));
+    }
+
+    #[test]
+    fn rejects_tampered_messages() {
+        let signature = sign(SECRET, "id", "2024-01-01T00:00:00Z", b"body");
+        assert!(!verify_signature(
+            SECRET,
+            "id",
+            "2024-01-01T00:00:00Z",
+            b"bodY",
+            &signature
+        ));
+        assert!(!verify_signature(
+            SECRET,
+            "other",
+            "2024-01-01T00:00:00Z",
+            b"body",
+            &signature
+        ));
+        assert!(!verify_signature(
+            SECRET,
+            "id",
+            "2024-01-01T00:00:01Z",
+            b"body",
+            &signature
+        ));
+        assert!(!verify_signature(
+            "another secret",
+            "id",
+            "2024-01-01T00:00:00Z",
+            b"body",
+            &signature
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_signatures() {
+        let signature = sign(SECRET, "id", "2024-01-01T00:00:00Z", b"body");
+        let bare = signature.strip_prefix("sha256=").unwrap();
+        assert!(!verify_signature(
+            SECRET,
+            "id",
+            "2024-01-01T00:00:00Z",
+            b"body",
+            bare
+        ));
+        assert!(!verify_signature(
+            SECRET,
+            "id",
+            "2024-01-01T00:00:00Z",
+            b"body",
+            "sha256=not hex"
+        ));
+        assert!(!verify_signature(
+            SECRET,
+            "id",
+            "2024-01-01T00:00:00Z",
+            b"body",
+            ""
+        ));
+    }
+
+    #[tokio::test]
+    async fn forwards_notifications() {
+        let (state, mut receiver) = state();
+        let status = deliver(&state, "1", &Utc::now().to_rfc3339(), ONLINE_BODY).await;
+
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        match receiver.try_recv() {
+            Ok(StreamEvent::Online {
+                user_id,
+                login,
+                stream,
+            }) => {
+                assert_eq!(user_id, "1337");
+                assert_eq!(login, "streamer");
+                assert!(stream.is_none());
+            }
+            other => panic!("expected an online event, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_signatures() {
+        let (state, mut receiver) = state();
+        let timestamp = Utc::now().to_rfc3339();
+        let signature = sign("another secret", "1", &timestamp, ONLINE_BODY.as_bytes());
+        let headers = headers("1", &timestamp, &signature, "notification");
+        let response = handle_webhook(State(state), headers, Bytes::from(ONLINE_BODY)).await;
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_stale_messages() {
+        let (state, mut receiver) = state();
+        let stale = Utc::now() - chrono::Duration::seconds(MAX_MESSAGE_AGE + 60);
+        let status = deliver(&state, "1", &stale.to_rfc3339(), ONLINE_BODY).await;
+
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn ignores_duplicate_messages() {
+        let (state, mut receiver) = state();
+        let timestamp = Utc::now().to_rfc3339();
+        assert_eq!(
+            deliver(&state, "1", &timestamp, ONLINE_BODY).await,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            deliver(&state, "1", &timestamp, ONLINE_BODY).await,
+            StatusCode::NO_CONTENT
+        );
+
+        assert!(receiver.try_recv().is_ok());
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn forgets_the_oldest_seen_messages() {
+        let (state, mut receiver) = state();
+        let timestamp = Utc::now().to_rfc3339();
+        let body = r#"{"subscription":{"type":"channel.follow"},"event":{"broadcaster_user_id":"1337","broadcaster_user_login":"streamer"}}"#;
+        for id in 0..=MAX_SEEN_MESSAGES {
+            deliver(&state, &id.to_string(), &timestamp, body).await;
+        }
+        let seen = state.seen.lock().await;
+        assert_eq!(seen.1.len(), MAX_SEEN_MESSAGES);
+        assert!(!seen.0.contains("0"));
+        assert!(seen.0.contains("1"));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn answers_verification_challenges() {
+        let (state, _receiver) = state();
+        let timestamp = Utc::now().to_rfc3339();
+        let body = r#"{"subscription":{"type":"stream.online"},"challenge":"pogchamp"}"#;
+        let signature = sign(SECRET, "1", &timestamp, body.as_bytes());
+        let headers = headers("1", &timestamp, &signature, "webhook_callback_verification");
+        let response = handle_webhook(State(state), headers, Bytes::from(body)).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"pogchamp");
+    }
+}
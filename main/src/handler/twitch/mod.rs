@@ -0,0 +1,333 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use serenity::{
+    all::{
+        Cache, Colour, CreateAllowedMentions, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter,
+        CreateMessage, EditMessage, GuildId, Http, Timestamp,
+    },
+    async_trait,
+};
+use tokio::sync::mpsc;
+use utils::{BotStringParser, error, info, warning};
+
+use crate::{
+    DEFAULT_TWITCH_TEMPLATE, LiveMessage, LiveStream, TwitchIntegration, TwitchLiveStreamsMap,
+    TwitchSubscription, TwitchSubscriptionsMap, save_twitch_live,
+};
+
+mod api;
+mod eventsub;
+mod polling;
+
+pub use api::*;
+pub use eventsub::EventSubWatcher;
+pub use polling::PollingWatcher;
+
+const TWITCH_URL: &str = "https://twitch.tv";
+const TWITCH_COLOUR: Colour = Colour::new(0x9146FF);
+const ENDED_COLOUR: Colour = Colour::new(0x747F8D);
+const STREAM_FETCH_ATTEMPTS: usize = 3;
+
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    // `stream` is only known when the watcher got it from Helix; EventSub sends a bare event.
+    Online {
+        user_id: String,
+        login: String,
+        stream: Option<TwitchStream>,
+    },
+    Offline {
+        user_id: String,
+        login: String,
+    },
+}
+
+#[async_trait]
+pub trait StreamWatcher: Send + Sync {
+    fn mode(&self) -> &'static str;
+    async fn watch(&self, user_id: &str, login: &str) -> Result<(), String>;
+    async fn unwatch(&self, user_id: &str) -> Result<(), String>;
+    // Runs for the lifetime of the bot, sending every status change to `events`.
+    async fn run(&self, events: mpsc::Sender<StreamEvent>);
+}
+
+// Builds the integration from `TWITCH_CLIENT_ID` and `TWITCH_CLIENT_SECRET`. `TWITCH_MODE=eventsub`
+// switches from polling to webhooks, which also needs `TWITCH_EVENTSUB_CALLBACK` and
+// `TWITCH_EVENTSUB_SECRET`.
+pub fn from_env() -> Option<TwitchIntegration> {
+    let client_id = std::env::var("TWITCH_CLIENT_ID").ok()?;
+    let client_secret = std::env::var("TWITCH_CLIENT_SECRET").ok()?;
+    let api = Arc::new(TwitchApi::new(client_id, client_secret));
+
+    let mode = std::env::var("TWITCH_MODE").unwrap_or_default();
+    let watcher: Arc<dyn StreamWatcher> = if mode.eq_ignore_ascii_case("eventsub") {
+        let (Ok(callback), Ok(secret)) = (
+            std::env::var("TWITCH_EVENTSUB_CALLBACK"),
+            std::env::var("TWITCH_EVENTSUB_SECRET"),
+        ) else {
+            warning!("TWITCH_MODE is eventsub but the callback or secret is missing.");
+            return None;
+        };
+        let port = std::env::var("TWITCH_EVENTSUB_PORT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(8080);
+        Arc::new(EventSubWatcher::new(api.clone(), callback, secret, port))
+    } else {
+        Arc::new(PollingWatcher::new(api.clone()))
+    };
+
+    Some(TwitchIntegration { api, watcher })
+}
+
+pub fn stream_url(login: &str) -> String {
+    format!("{}/{}", TWITCH_URL, login)
+}
+
+pub async fn handle_event(
+    event: StreamEvent,
+    integration: &TwitchIntegration,
+    subscriptions: &TwitchSubscriptionsMap,
+    live: &TwitchLiveStreamsMap,
+    http: &Http,
+    cache: &Cache,
+) {
+    match event {
+        StreamEvent::Online {
+            user_id,
+            login,
+            stream,
+        } => {
+            let stream = match stream {
+                Some(stream) => stream,
+                None => match fetch_stream(integration, &user_id).await {
+                    Ok(Some(stream)) => stream,
+                    Ok(None) => {
+                        warning!("{} went online but has no stream yet.", login);
+                        return;
+                    }
+                    Err(e) => {
+                        error!("Failed to fetch the stream of {}: {}", login, e);
+                        return;
+                    }
+                },
+            };
+            // A restart or a duplicate notification must not announce the same stream twice.
+            if live.get(&user_id).is_some_and(|l| l.stream_id == stream.id) {
+                return;
+            }
+            announce(&stream, subscriptions, live, http, cache).await;
+        }
+        StreamEvent::Offline { user_id, login } => {
+            let Some((_, stream)) = live.remove(&user_id) else {
+                return;
+            };
+            save_twitch_live(live);
+            end(&stream, http).await;
+            info!("{} went offline", login);
+        }
+    }
+}
+
+// Helix can lag a few seconds behind the `stream.online` notification.
+async fn fetch_stream(
+    integration: &TwitchIntegration,
+    user_id: &str,
+) -> Result<Option<TwitchStream>, String> {
+    for attempt in 0..STREAM_FETCH_ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+        }
+        let streams = integration.api.streams(&[user_id.to_string()]).await?;
+        if let Some(stream) = streams.into_iter().next() {
+            return Ok(Some(stream));
+        }
+    }
+    Ok(None)
+}
+
+async fn announce(
+    stream: &TwitchStream,
+    subscriptions: &TwitchSubscriptionsMap,
+    live: &TwitchLiveStreamsMap,
+    http: &Http,
+    cache: &Cache,
+) {
+    let followers = subscriptions
+        .iter()
+        .flat_map(|entry| {
+            let guild_id = *entry.key();
+            entry
+                .value()
+                .iter()
+                .filter(|s| s.user_id == stream.user_id)
+                .map(|s| (guild_id, s.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut messages = vec![];
+    for (guild_id, subscription) in followers {
+        let Some(content) = render(guild_id, &subscription, stream, cache) else {
+            continue;
+        };
+        let mut mentions = CreateAllowedMentions::new();
+        let content = match subscription.role {
+            Some(role) => {
+                mentions = mentions.roles(vec![role]);
+                format!("<@&{}> {}", role, content)
+            }
+            None => content,
+        };
+        let message = CreateMessage::new()
+            .content(content)
+            .embed(live_embed(stream))
+            .allowed_mentions(mentions);
+        match subscription.channel.send_message(http, message).await {
+            Ok(message) => messages.push(LiveMessage {
+                guild_id,
+                channel_id: subscription.channel,
+                message_id: message.id,
+            }),
+            Err(e) => error!(
+                "Failed to announce {} in {}: {}",
+                stream.user_login, subscription.channel, e
+            ),
+        }
+    }
+
+    info!(
+        "{} went live, announced in {} channels",
+        stream.user_login,
+        messages.len()
+    );
+    live.insert(
+        stream.user_id.clone(),
+        LiveStream {
+            stream_id: stream.id.clone(),
+            display_name: stream.user_name.clone(),
+            login: stream.user_login.clone(),
+            title: stream.title.clone(),
+            game: stream.game_name.clone(),
+            started_at: stream.started_at,
+            messages,
+        },
+    );
+    save_twitch_live(live);
+}
+
+// Edits the announcements so they no longer look live.
+pub async fn end(stream: &LiveStream, http: &Http) {
+    let edit = ended_message(stream);
+    for message in &stream.messages {
+        if let Err(e) = message
+            .channel_id
+            .edit_message(http, message.message_id, edit.clone())
+            .await
+        {
+            error!(
+                "Failed to end the announcement {} in {}: {}",
+                message.message_id, message.channel_id, e
+            );
+        }
+    }
+}
+
+fn ended_message(stream: &LiveStream) -> EditMessage {
+    let duration = Utc::now() - stream.started_at;
+    let embed = CreateEmbed::default()
+        .author(CreateEmbedAuthor::new(&stream.display_name).url(stream_url(&stream.login)))
+        .title(&stream.title)
+        .url(stream_url(&stream.login))
+        .description(format!(
+            "The stream has ended after {}h {}m.",
+            duration.num_hours(),
+            duration.num_minutes() % 60
+        ))
+        .footer(CreateEmbedFooter::new(if stream.game.is_empty() {
+            "Offline".to_string()
+        } else {
+            format!("Offline · {}", stream.game)
+        }))
+        .color(ENDED_COLOUR);
+
+    EditMessage::new()
+        .content(format!("**{}** was live on Twitch.", stream.display_name))
+        .embed(embed)
+}
+
+fn render(
+    guild_id: GuildId,
+    subscription: &TwitchSubscription,
+    stream: &TwitchStream,
+    cache: &Cache,
+) -> Option<String> {
+    let template = subscription
+        .template
+        .clone()
+        .unwrap_or(DEFAULT_TWITCH_TEMPLATE.to_string())
+        .replace("{twitch.name}", &stream.user_name)
+        .replace("{twitch.login}", &stream.user_login)
+        .replace("{twitch.title}", &stream.title)
+        .replace("{twitch.game}", &stream.game_name)
+        .replace("{twitch.url}", &stream_url(&stream.user_login))
+        .replace("{twitch.viewers}", &stream.viewer_count.to_string());
+
+    let guild = cache.guild(guild_id)?.clone();
+    let channel = guild.channels.get(&subscription.channel)?;
+    let member = guild.members.get(&cache.current_user().id)?;
+    Some(BotStringParser::from_cache(cache, &guild, channel, member).render(&template))
+}
+
+fn live_embed(stream: &TwitchStream) -> CreateEmbed {
+    let mut embed = CreateEmbed::default()
+        .author(CreateEmbedAuthor::new(&stream.user_name).url(stream_url(&stream.user_login)))
+        .title(&stream.title)
+        .url(stream_url(&stream.user_login))
+        .color(TWITCH_COLOUR);
+
+    if !stream.game_name.is_empty() {
+        embed = embed.field("Game", &stream.game_name, true);
+    }
+    embed = embed.field("Viewers", stream.viewer_count.to_string(), true);
+    if !stream.thumbnail_url.is_empty() {
+        // Cache-bust so Discord doesn't keep showing the previous stream's preview.
+        let thumbnail = stream
+            .thumbnail_url
+            .replace("{width}", "1280")
+            .replace("{height}", "720");
+        embed = embed.image(format!("{}?t={}", thumbnail, Utc::now().timestamp()));
+    }
+    if let Ok(timestamp) = Timestamp::from_unix_timestamp(stream.started_at.timestamp()) {
+        embed = embed.timestamp(timestamp);
+    }
+    embed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ended_messages_no_longer_look_live() {
+        let stream = LiveStream {
+            stream_id: "a".to_string(),
+            display_name: "Streamer".to_string(),
+            login: "streamer".to_string(),
+            title: "Speedrunning".to_string(),
+            game: "Celeste".to_string(),
+            started_at: Utc::now() - chrono::Duration::minutes(150),
+            messages: vec![],
+        };
+        let edit = serde_json::to_value(ended_message(&stream)).unwrap();
+
+        assert_eq!(edit["content"], "**Streamer** was live on Twitch.");
+        let embed = &edit["embeds"][0];
+        assert_eq!(embed["title"], "Speedrunning");
+        assert_eq!(embed["url"], "https://twitch.tv/streamer");
+        assert_eq!(embed["description"], "The stream has ended after 2h 30m.");
+        assert_eq!(embed["footer"]["text"], "Offline · Celeste");
+        assert_eq!(embed["color"], ENDED_COLOUR.0);
+    }
+}
@@ -0,0 +1,183 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use serenity::async_trait;
+use tokio::sync::{RwLock, mpsc};
+use utils::{error, info};
+
+use super::{StreamEvent, StreamWatcher, TwitchApi, TwitchStream};
+
+const POLL_INTERVAL: u64 = 60; // seconds
+
+struct Watched {
+    login: String,
+    // The ID of the current stream, if the user was live at the last poll.
+    stream_id: Option<String>,
+}
+
+// Asks Helix for the status of every watched user on an interval.
+pub struct PollingWatcher {
+    api: Arc<TwitchApi>,
+    watched: RwLock<HashMap<String, Watched>>,
+}
+
+impl PollingWatcher {
+    pub fn new(api: Arc<TwitchApi>) -> Self {
+        Self {
+            api,
+            watched: RwLock::new(HashMap::new()),
+        }
+    }
+
+    async fn poll(&self, events: &mpsc::Sender<StreamEvent>) {
+        let ids = self
+            .watched
+            .read()
+            .await
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        if ids.is_empty() {
+            return;
+        }
+        let streams = match self.api.streams(&ids).await {
+            Ok(streams) => streams,
+            Err(e) => {
+                error!("Failed to poll Twitch streams: {}", e);
+                return;
+            }
+        };
+
+        let changes = changes(&mut *self.watched.write().await, &streams);
+        for event in changes {
+            if events.send(event).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+// Compares the live streams with the last poll, updating `watched` and returning what changed.
+fn changes(watched: &mut HashMap<String, Watched>, streams: &[TwitchStream]) -> Vec<StreamEvent> {
+    let mut changes = vec![];
+    for (user_id, state) in watched.iter_mut() {
+        match streams.iter().find(|s| &s.user_id == user_id) {
+            Some(stream) if state.stream_id.as_ref() != Some(&stream.id) => {
+                state.stream_id = Some(stream.id.clone());
+                changes.push(StreamEvent::Online {
+                    user_id: user_id.clone(),
+                    login: state.login.clone(),
+                    stream: Some(stream.clone()),
+                });
+            }
+            None if state.stream_id.take().is_some() => {
+                changes.push(StreamEvent::Offline {
+                    user_id: user_id.clone(),
+                    login: state.login.clone(),
+                });
+            }
+            _ => {}
+        }
+    }
+    changes
+}
+
+#[async_trait]
+impl StreamWatcher for PollingWatcher {
+    fn mode(&self) -> &'static str {
+        "polling"
+    }
+
+    async fn watch(&self, user_id: &str, login: &str) -> Result<(), String> {
+        self.watched
+            .write()
+            .await
+            .entry(user_id.to_string())
+            .or_insert(Watched {
+                login: login.to_string(),
+                stream_id: None,
+            });
+        Ok(())
+    }
+
+    async fn unwatch(&self, user_id: &str) -> Result<(), String> {
+        self.watched.write().await.remove(user_id);
+        Ok(())
+    }
+
+    async fn run(&self, events: mpsc::Sender<StreamEvent>) {
+        info!("Started Twitch polling.");
+        loop {
+            self.poll(&events).await;
+            tokio::time::sleep(Duration::from_secs(POLL_INTERVAL)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn stream(id: &str, user_id: &str) -> TwitchStream {
+        TwitchStream {
+            id: id.to_string(),
+            user_id: user_id.to_string(),
+            user_login: "streamer".to_string(),
+            user_name: "Streamer".to_string(),
+            game_name: String::new(),
+            title: String::new(),
+            viewer_count: 0,
+            started_at: Utc::now(),
+            thumbnail_url: String::new(),
+        }
+    }
+
+    fn watched() -> HashMap<String, Watched> {
+        let watched = Watched {
+            login: "streamer".to_string(),
+            stream_id: None,
+        };
+        HashMap::from([("1337".to_string(), watched)])
+    }
+
+    #[test]
+    fn goes_online_and_offline() {
+        let mut watched = watched();
+
+        let online = changes(&mut watched, &[stream("a", "1337")]);
+        assert!(matches!(
+            online.as_slice(),
+            [StreamEvent::Online { user_id, stream: Some(s), .. }] if user_id == "1337" && s.id == "a"
+        ));
+        assert_eq!(watched["1337"].stream_id.as_deref(), Some("a"));
+
+        let offline = changes(&mut watched, &[]);
+        assert!(matches!(
+            offline.as_slice(),
+            [StreamEvent::Offline { user_id, login }] if user_id == "1337" && login == "streamer"
+        ));
+        assert!(watched["1337"].stream_id.is_none());
+    }
+
+    #[test]
+    fn reports_each_change_once() {
+        let mut watched = watched();
+        assert!(changes(&mut watched, &[]).is_empty());
+
+        assert_eq!(changes(&mut watched, &[stream("a", "1337")]).len(), 1);
+        assert!(changes(&mut watched, &[stream("a", "1337")]).is_empty());
+        // A new stream ID means the streamer restarted between polls.
+        assert_eq!(changes(&mut watched, &[stream("b", "1337")]).len(), 1);
+
+        assert_eq!(changes(&mut watched, &[]).len(), 1);
+        assert!(changes(&mut watched, &[]).is_empty());
+    }
+
+    #[test]
+    fn ignores_unwatched_streams() {
+        let mut watched = watched();
+        assert!(changes(&mut watched, &[stream("a", "42")]).is_empty());
+        assert!(watched["1337"].stream_id.is_none());
+    }
+}
@@ -0,0 +1,124 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    Router,
+    extract::{Path, RawQuery, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+};
+
+use crate::YoutubeVideo;
+
+pub const CHANNEL_ID: &str = "UCabcdefghijklmnopqrstuv";
+
+// A local stand-in for YouTube that `YoutubeHttpProvider` can be pointed at. Feeds are keyed by
+// channel ID, video IDs starting with `short` are shorts and ones starting with `premiere` are
+// upcoming premieres.
+pub async fn serve(feeds: HashMap<String, String>) -> String {
+    let app = Router::new()
+        .route("/feeds/videos.xml", get(feed_xml))
+        .route("/shorts/{id}", get(short))
+        .route("/watch", get(watch))
+        .with_state(Arc::new(feeds));
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+        .await
+        .expect("Failed to bind the fixture server");
+    let address = listener
+        .local_addr()
+        .expect("Failed to get the fixture server address");
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    format!("http://{}", address)
+}
+
+async fn feed_xml(
+    State(feeds): State<Arc<HashMap<String, String>>>,
+    RawQuery(query): RawQuery,
+) -> Response {
+    let channel_id = query
+        .as_deref()
+        .and_then(|q| q.strip_prefix("channel_id="))
+        .unwrap_or_default();
+    match feeds.get(channel_id) {
+        Some(feed) => (
+            [(header::CONTENT_TYPE, "application/atom+xml")],
+            feed.clone(),
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+// Like YouTube, only shorts are served here and everything else is redirected to `/watch`.
+async fn short(Path(id): Path<String>) -> Response {
+    if id.starts_with("short") {
+        return "short".into_response();
+    }
+    Redirect::to(&format!("/watch?v={}", id)).into_response()
+}
+
+async fn watch(RawQuery(query): RawQuery) -> String {
+    let id = query
+        .as_deref()
+        .and_then(|q| q.strip_prefix("v="))
+        .unwrap_or_default();
+    format!("{{\"isUpcoming\":{}}}", id.starts_with("premiere"))
+}
+
+// An Atom feed in YouTube's format, the entries given newest first.
+pub fn feed(channel_name: &str, entries: &[String]) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns:yt="http://www.youtube.com/xml/schemas/2015" xmlns:media="http://search.yahoo.com/mrss/" xmlns="http://www.w3.org/2005/Atom">
+ <link rel="self" href="http://www.youtube.com/feeds/videos.xml?channel_id={channel_id}"/>
+ <id>yt:channel:{channel_id}</id>
+ <yt:channelId>{channel_id}</yt:channelId>
+ <title>{channel_name}</title>
+ <link rel="alternate" href="https://www.youtube.com/channel/{channel_id}"/>
+ <author>
+  <name>{channel_name}</name>
+  <uri>https://www.youtube.com/channel/{channel_id}</uri>
+ </author>
+ <published>2015-01-01T00:00:00+00:00</published>
+{entries}
+</feed>"#,
+        channel_id = CHANNEL_ID,
+        channel_name = channel_name,
+        entries = entries.join("\n"),
+    )
+}
+
+// An entry of a feed, `title` must already be escaped.
+pub fn entry(id: &str, title: &str, published: &str) -> String {
+    format!(
+        r#" <entry>
+  <id>yt:video:{id}</id>
+  <yt:videoId>{id}</yt:videoId>
+  <yt:channelId>{channel_id}</yt:channelId>
+  <title>{title}</title>
+  <link rel="alternate" href="https://www.youtube.com/watch?v={id}"/>
+  <author>
+   <name>Channel</name>
+   <uri>https://www.youtube.com/channel/{channel_id}</uri>
+  </author>
+  <published>{published}</published>
+  <updated>{published}</updated>
+  <media:group>
+   <media:title>{title}</media:title>
+   <media:content url="https://www.youtube.com/v/{id}?version=3" type="application/x-shockwave-flash" width="640" height="390"/>
+   <media:thumbnail url="https://i1.ytimg.com/vi/{id}/hqdefault.jpg" width="480" height="360"/>
+   <media:description>The description of {id}.</media:description>
+  </media:group>
+ </entry>"#,
+        id = id,
+        title = title,
+        published = published,
+        channel_id = CHANNEL_ID,
+    )
+}
+
+pub fn parse(entries: &[String]) -> Vec<YoutubeVideo> {
+    let (_, videos) =
+        super::parse_feed(&feed("Channel", entries)).expect("Fixture feed should parse");
+    videos
+}
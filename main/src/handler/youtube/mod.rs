@@ -0,0 +1,352 @@
+#[cfg(test)]
+pub mod fixtures;
+
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use quick_xml::{Reader, events::Event};
+use regex::Regex;
+use reqwest::{StatusCode, redirect::Policy};
+use serenity::{
+    all::{Colour, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, Timestamp},
+    async_trait,
+};
+
+use crate::{VideoKind, YoutubeVideo};
+
+const YOUTUBE_URL: &str = "https://www.youtube.com";
+const YOUTUBE_URL_VAR: &str = "YOUTUBE_URL";
+const YOUTUBE_COLOUR: Colour = Colour::new(0xFF0000);
+
+#[async_trait]
+pub trait YoutubeProvider: Send + Sync {
+    // The channel name and its latest videos, newest first.
+    async fn fetch_feed(&self, channel_id: &str) -> Result<(String, Vec<YoutubeVideo>), String>;
+    // Turns a channel ID, a channel URL or an @handle into a channel ID.
+    async fn resolve_channel(&self, input: &str) -> Result<String, String>;
+    async fn classify(&self, video: &YoutubeVideo) -> VideoKind;
+}
+
+// Reads the public Atom feeds. The base URL can be overridden with `YOUTUBE_URL`, e.g. to point
+// the bot at a local fixture server.
+pub struct YoutubeHttpProvider {
+    client: reqwest::Client,
+    // Doesn't follow redirects, `/shorts/<id>` redirects to `/watch` for regular videos.
+    no_redirect: reqwest::Client,
+    base_url: String,
+}
+
+impl YoutubeHttpProvider {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let build = |policy| {
+            reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .redirect(policy)
+                .build()
+                .expect("Failed to build HTTP client")
+        };
+        Self {
+            client: build(Policy::default()),
+            no_redirect: build(Policy::none()),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(std::env::var(YOUTUBE_URL_VAR).unwrap_or(YOUTUBE_URL.to_string()))
+    }
+
+    async fn get_text(&self, url: &str) -> Result<String, String> {
+        let response = self
+            .client
+            .get(url)
+            // Skips the cookie consent page served to EU addresses.
+            .header("Cookie", "CONSENT=YES+")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach YouTube: {}", e))?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Err("That YouTube channel does not exist.".to_string()),
+            status if !status.is_success() => Err(format!("YouTube returned {}", status)),
+            _ => response
+                .text()
+                .await
+                .map_err(|e| format!("Failed to read the YouTube response: {}", e)),
+        }
+    }
+}
+
+#[async_trait]
+impl YoutubeProvider for YoutubeHttpProvider {
+    async fn fetch_feed(&self, channel_id: &str) -> Result<(String, Vec<YoutubeVideo>), String> {
+        let url = format!(
+            "{}/feeds/videos.xml?channel_id={}",
+            self.base_url, channel_id
+        );
+        let body = self.get_text(&url).await?;
+        parse_feed(&body)
+    }
+
+    async fn resolve_channel(&self, input: &str) -> Result<String, String> {
+        let input = input.trim().trim_end_matches('/');
+        if let Some(id) = channel_id_in(input) {
+            return Ok(id);
+        }
+        let handle = input
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches('@');
+        if handle.is_empty()
+            || !handle
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_-.".contains(c))
+        {
+            return Err(format!("`{}` is not a YouTube channel.", input));
+        }
+        let page = self
+            .get_text(&format!("{}/@{}", self.base_url, handle))
+            .await?;
+        // The canonical link is the most stable place the ID appears on the page.
+        let canonical = Regex::new(r#"<link rel="canonical" href="[^"]*/channel/(UC[\w-]{22})""#)
+            .expect("Invalid canonical regex");
+        canonical
+            .captures(&page)
+            .map(|c| c[1].to_string())
+            .or_else(|| channel_id_in(&page))
+            .ok_or(format!("Couldn't find the YouTube channel @{}.", handle))
+    }
+
+    async fn classify(&self, video: &YoutubeVideo) -> VideoKind {
+        let shorts = format!("{}/shorts/{}", self.base_url, video.id);
+        if let Ok(response) = self.no_redirect.head(&shorts).send().await
+            && response.status() == StatusCode::OK
+        {
+            return VideoKind::Short;
+        }
+        let watch = format!("{}/watch?v={}", self.base_url, video.id);
+        match self.get_text(&watch).await {
+            Ok(page) if page.contains("\"isUpcoming\":true") => VideoKind::Premiere,
+            _ => VideoKind::Upload,
+        }
+    }
+}
+
+pub fn default_provider() -> Arc<dyn YoutubeProvider> {
+    Arc::new(YoutubeHttpProvider::from_env())
+}
+
+fn channel_id_in(text: &str) -> Option<String> {
+    let regex = Regex::new(r"(?:^|/channel/|\x22channelId\x22:\x22)(UC[\w-]{22})")
+        .expect("Invalid channel regex");
+    regex.captures(text).map(|c| c[1].to_string())
+}
+
+pub fn parse_feed(xml: &str) -> Result<(String, Vec<YoutubeVideo>), String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut channel_name = String::new();
+    let mut videos = vec![];
+    let mut entry: Option<YoutubeVideo> = None;
+    let mut path: Vec<String> = vec![];
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if name == "entry" {
+                    entry = Some(YoutubeVideo {
+                        id: String::new(),
+                        title: String::new(),
+                        channel_name: channel_name.clone(),
+                        url: String::new(),
+                        thumbnail: None,
+                        description: String::new(),
+                        published: Utc::now(),
+                    });
+                }
+                path.push(name);
+            }
+            Ok(Event::Empty(e)) => {
+                let Some(video) = entry.as_mut() else {
+                    continue;
+                };
+                let attribute = |key: &str| {
+                    e.try_get_attribute(key)
+                        .ok()
+                        .flatten()
+                        .and_then(|a| a.unescape_value().ok().map(|v| v.to_string()))
+                };
+                match e.name().as_ref() {
+                    b"link" if video.url.is_empty() => {
+                        video.url = attribute("href").unwrap_or_default()
+                    }
+                    b"media:thumbnail" => video.thumbnail = attribute("url"),
+                    _ => {}
+                }
+            }
+            Ok(Event::Text(e)) => {
+                let text = e.unescape().map(|t| t.to_string()).unwrap_or_default();
+                let tags = path.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+                match (entry.as_mut(), tags.as_slice()) {
+                    (None, [.., "author", "name"]) if channel_name.is_empty() => {
+                        channel_name = text
+                    }
+                    (Some(video), [.., "entry", "yt:videoId"]) => video.id = text,
+                    (Some(video), [.., "entry", "title"]) => video.title = text,
+                    (Some(video), [.., "author", "name"]) => video.channel_name = text,
+                    (Some(video), [.., "entry", "published"]) => {
+                        if let Ok(published) = DateTime::parse_from_rfc3339(&text) {
+                            video.published = published.with_timezone(&Utc);
+                        }
+                    }
+                    (Some(video), [.., "media:group", "media:description"]) => {
+                        video.description = text
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::End(e)) => {
+                path.pop();
+                if e.name().as_ref() == b"entry"
+                    && let Some(video) = entry.take()
+                    && !video.id.is_empty()
+                {
+                    videos.push(video);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(format!("Failed to parse the YouTube feed: {}", e)),
+            _ => {}
+        }
+    }
+
+    videos.sort_by_key(|v| std::cmp::Reverse(v.published));
+    Ok((channel_name, videos))
+}
+
+pub fn video_embed(video: &YoutubeVideo, kind: VideoKind) -> CreateEmbed {
+    let mut embed = CreateEmbed::default()
+        .author(CreateEmbedAuthor::new(&video.channel_name))
+        .title(&video.title)
+        .url(&video.url)
+        .color(YOUTUBE_COLOUR)
+        .footer(CreateEmbedFooter::new(match kind {
+            VideoKind::Upload => "New video",
+            VideoKind::Short => "New short",
+            VideoKind::Premiere => "Premiere",
+        }));
+
+    if !video.description.is_empty() {
+        let description = video.description.chars().take(300).collect::<String>();
+        embed = embed.description(if description.len() < video.description.len() {
+            format!("{}…", description)
+        } else {
+            description
+        });
+    }
+    if let Some(thumbnail) = &video.thumbnail {
+        embed = embed.image(thumbnail);
+    }
+    if let Ok(timestamp) = Timestamp::from_unix_timestamp(video.published.timestamp()) {
+        embed = embed.timestamp(timestamp);
+    }
+    embed
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn entries() -> Vec<String> {
+        vec![
+            fixtures::entry("older", "The first video", "2024-01-01T12:00:00+00:00"),
+            fixtures::entry("short1", "A &amp; B", "2024-01-03T12:00:00+00:00"),
+            fixtures::entry("premiere1", "Soon", "2024-01-02T12:00:00+00:00"),
+        ]
+    }
+
+    async fn provider(feeds: Vec<(&str, String)>) -> YoutubeHttpProvider {
+        let feeds = feeds
+            .into_iter()
+            .map(|(channel_id, feed)| (channel_id.to_string(), feed))
+            .collect::<HashMap<_, _>>();
+        YoutubeHttpProvider::new(fixtures::serve(feeds).await)
+    }
+
+    #[test]
+    fn parses_atom_feeds() {
+        let (name, videos) = parse_feed(&fixtures::feed("Channel &amp; Co", &entries())).unwrap();
+
+        assert_eq!(name, "Channel & Co");
+        assert_eq!(
+            videos.iter().map(|v| v.id.as_str()).collect::<Vec<_>>(),
+            ["short1", "premiere1", "older"]
+        );
+        let video = &videos[0];
+        assert_eq!(video.title, "A & B");
+        assert_eq!(video.channel_name, "Channel");
+        assert_eq!(video.url, "https://www.youtube.com/watch?v=short1");
+        assert_eq!(
+            video.thumbnail.as_deref(),
+            Some("https://i1.ytimg.com/vi/short1/hqdefault.jpg")
+        );
+        assert_eq!(video.description, "The description of short1.");
+        assert_eq!(video.published.to_rfc3339(), "2024-01-03T12:00:00+00:00");
+    }
+
+    #[test]
+    fn skips_entries_without_an_id() {
+        let entry = fixtures::entry("", "No ID", "2024-01-01T12:00:00+00:00");
+        let (_, videos) = parse_feed(&fixtures::feed("Channel", &[entry])).unwrap();
+        assert!(videos.is_empty());
+    }
+
+    #[test]
+    fn rejects_broken_feeds() {
+        assert!(parse_feed("<feed><entry></feed>").is_err());
+    }
+
+    #[tokio::test]
+    async fn fetches_feeds() {
+        let provider = provider(vec![(
+            fixtures::CHANNEL_ID,
+            fixtures::feed("Channel", &entries()),
+        )])
+        .await;
+
+        let (name, videos) = provider.fetch_feed(fixtures::CHANNEL_ID).await.unwrap();
+        assert_eq!(name, "Channel");
+        assert_eq!(videos.len(), 3);
+        let missing = provider.fetch_feed("UCnothing").await.unwrap_err();
+        assert_eq!(missing, "That YouTube channel does not exist.");
+    }
+
+    #[tokio::test]
+    async fn classifies_shorts_and_premieres() {
+        let provider = provider(vec![]).await;
+        let videos = fixtures::parse(&entries());
+        let kind = |id: &str| {
+            let video = videos.iter().find(|v| v.id == id).unwrap();
+            provider.classify(video)
+        };
+
+        assert_eq!(kind("short1").await, VideoKind::Short);
+        assert_eq!(kind("premiere1").await, VideoKind::Premiere);
+        assert_eq!(kind("older").await, VideoKind::Upload);
+    }
+
+    #[test]
+    fn finds_channel_ids() {
+        let id = fixtures::CHANNEL_ID;
+        assert_eq!(channel_id_in(id).as_deref(), Some(id));
+        let url = format!("https://www.youtube.com/channel/{}", id);
+        assert_eq!(channel_id_in(&url).as_deref(), Some(id));
+        let page = format!("{{\"channelId\":\"{}\"}}", id);
+        assert_eq!(channel_id_in(&page).as_deref(), Some(id));
+        assert_eq!(channel_id_in("https://www.youtube.com/@handle"), None);
+    }
+}
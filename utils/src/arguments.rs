@@ -0,0 +1,965 @@
+use std::collections::HashMap;
+
+use chrono::Duration;
+use serenity::all::{
+    AttachmentId, ChannelId, ChannelType, CommandDataOptionValue, CommandOptionType,
+    CreateCommandOption, RoleId, UserId,
+};
+
+use crate::{CommandArguments, CommandResponse, LegacyOption, truncate};
+
+// Words from the message are echoed back in errors, cut so a long one can't fill the reply.
+const MAX_ECHOED_LENGTH: usize = 100;
+
+#[derive(Debug, Clone)]
+pub enum ArgumentKind {
+    String { max_length: Option<u16> },
+    Integer { min: Option<i64>, max: Option<i64> },
+    Boolean,
+    User,
+    Channel(Vec<ChannelType>),
+    Role,
+    // Sent as a string like `1h30m` in slash commands.
+    Duration { min: Option<i64>, max: Option<i64> },
+    Choice(Vec<&'static str>),
+    // Only sent by slash commands, legacy messages carry their attachments themselves.
+    Attachment,
+}
+
+#[derive(Debug, Clone)]
+pub struct Argument {
+    name: &'static str,
+    description: &'static str,
+    kind: ArgumentKind,
+    required: bool,
+    autocomplete: bool,
+    rest: bool,
+    named: bool,
+}
+
+impl Argument {
+    pub fn new(name: &'static str, description: &'static str, kind: ArgumentKind) -> Self {
+        Self {
+            name,
+            description,
+            kind,
+            required: false,
+            autocomplete: false,
+            rest: false,
+            named: false,
+        }
+    }
+
+    pub fn string(name: &'static str, description: &'static str) -> Self {
+        Self::new(name, description, ArgumentKind::String { max_length: None })
+    }
+
+    pub fn integer(name: &'static str, description: &'static str) -> Self {
+        Self::new(
+            name,
+            description,
+            ArgumentKind::Integer {
+                min: None,
+                max: None,
+            },
+        )
+    }
+
+    pub fn boolean(name: &'static str, description: &'static str) -> Self {
+        Self::new(name, description, ArgumentKind::Boolean)
+    }
+
+    pub fn user(name: &'static str, description: &'static str) -> Self {
+        Self::new(name, description, ArgumentKind::User)
+    }
+
+    pub fn channel(name: &'static str, description: &'static str) -> Self {
+        Self::new(name, description, ArgumentKind::Channel(vec![]))
+    }
+
+    pub fn role(name: &'static str, description: &'static str) -> Self {
+        Self::new(name, description, ArgumentKind::Role)
+    }
+
+    pub fn duration(name: &'static str, description: &'static str) -> Self {
+        Self::new(
+            name,
+            description,
+            ArgumentKind::Duration {
+                min: None,
+                max: None,
+            },
+        )
+    }
+
+    pub fn choice(
+        name: &'static str,
+        description: &'static str,
+        choices: Vec<&'static str>,
+    ) -> Self {
+        Self::new(name, description, ArgumentKind::Choice(choices))
+    }
+
+    pub fn attachment(name: &'static str, description: &'static str) -> Self {
+        Self::new(name, description, ArgumentKind::Attachment)
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn autocomplete(mut self) -> Self {
+        self.autocomplete = true;
+        self
+    }
+
+    // Takes every remaining word of a legacy message, only valid for the last string argument.
+    pub fn rest(mut self) -> Self {
+        self.rest = true;
+        self
+    }
+
+    // Only filled by `name:value` in legacy messages, so it can't take a word meant for a later
+    // argument.
+    pub fn named(mut self) -> Self {
+        self.named = true;
+        self
+    }
+
+    // Bounds integers, or durations in seconds.
+    pub fn range(mut self, min: i64, max: i64) -> Self {
+        match &mut self.kind {
+            ArgumentKind::Integer { min: lo, max: hi }
+            | ArgumentKind::Duration { min: lo, max: hi } => {
+                *lo = Some(min);
+                *hi = Some(max);
+            }
+            _ => {}
+        }
+        self
+    }
+
+    pub fn max_length(mut self, length: u16) -> Self {
+        if let ArgumentKind::String { max_length } = &mut self.kind {
+            *max_length = Some(length);
+        }
+        self
+    }
+
+    pub fn channel_types(mut self, types: Vec<ChannelType>) -> Self {
+        if let ArgumentKind::Channel(channel_types) = &mut self.kind {
+            *channel_types = types;
+        }
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn option(&self) -> CreateCommandOption {
+        let kind = match self.kind {
+            ArgumentKind::String { .. } | ArgumentKind::Duration { .. } => {
+                CommandOptionType::String
+            }
+            ArgumentKind::Choice(_) => CommandOptionType::String,
+            ArgumentKind::Integer { .. } => CommandOptionType::Integer,
+            ArgumentKind::Boolean => CommandOptionType::Boolean,
+            ArgumentKind::User => CommandOptionType::User,
+            ArgumentKind::Channel(_) => CommandOptionType::Channel,
+            ArgumentKind::Role => CommandOptionType::Role,
+            ArgumentKind::Attachment => CommandOptionType::Attachment,
+        };
+        let mut option = CreateCommandOption::new(kind, self.name, self.description)
+            .required(self.required)
+            .set_autocomplete(self.autocomplete);
+        match &self.kind {
+            ArgumentKind::String {
+                max_length: Some(length),
+            } => option = option.max_length(*length),
+            ArgumentKind::Integer { min, max } => {
+                // Negative bounds don't fit the unsigned setters, Discord takes them as numbers.
+                match min {
+                    Some(min) if *min < 0 => option = option.min_number_value(*min as f64),
+                    Some(min) => option = option.min_int_value(*min as u64),
+                    None => {}
+                }
+                match max {
+                    Some(max) if *max < 0 => option = option.max_number_value(*max as f64),
+                    Some(max) => option = option.max_int_value(*max as u64),
+                    None => {}
+                }
+            }
+            ArgumentKind::Channel(types) if !types.is_empty() => {
+                option = option.channel_types(types.clone())
+            }
+            ArgumentKind::Choice(choices) => {
+                for choice in choices {
+                    option = option.add_string_choice(*choice, *choice);
+                }
+            }
+            _ => {}
+        }
+        option
+    }
+
+    fn usage(&self) -> String {
+        let name = match &self.kind {
+            ArgumentKind::Choice(choices) => choices.join("|"),
+            _ if self.rest => format!("{}...", self.name),
+            _ if self.named => format!("{}:…", self.name),
+            _ => self.name.to_string(),
+        };
+        if self.required {
+            format!("<{}>", name)
+        } else {
+            format!("[{}]", name)
+        }
+    }
+
+    // Checks the bounds of a value that was already converted to the right kind.
+    fn validate(&self, value: &ArgumentValue) -> Result<(), String> {
+        match (&self.kind, value) {
+            (ArgumentKind::Integer { min, max }, ArgumentValue::Integer(n))
+                if min.is_some_and(|min| *n < min) || max.is_some_and(|max| *n > max) =>
+            {
+                Err(format!(
+                    "`{}` must be between {} and {}.",
+                    self.name,
+                    min.map(|m| m.to_string()).unwrap_or("-∞".to_string()),
+                    max.map(|m| m.to_string()).unwrap_or("∞".to_string())
+                ))
+            }
+            (ArgumentKind::Duration { min, max }, ArgumentValue::Duration(duration))
+                if min.is_some_and(|min| duration.num_seconds() < min)
+                    || max.is_some_and(|max| duration.num_seconds() > max) =>
+            {
+                Err(format!(
+                    "`{}` must be between {} and {}.",
+                    self.name,
+                    LegacyOption::time_str(&Duration::seconds(min.unwrap_or_default())),
+                    max.map(|m| LegacyOption::time_str(&Duration::seconds(m)))
+                        .unwrap_or("forever".to_string())
+                ))
+            }
+            (
+                ArgumentKind::String {
+                    max_length: Some(max),
+                },
+                ArgumentValue::String(text),
+            ) if text.chars().count() > *max as usize => Err(format!(
+                "`{}` can be at most {} characters long.",
+                self.name, max
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    // Converts a single legacy token, `None` when it doesn't fit this argument.
+    fn convert(&self, token: &Token) -> Option<ArgumentValue> {
+        let option = match token {
+            Token::Option(option) => Some(option.as_ref()),
+            Token::Word(_) => None,
+        };
+        match (&self.kind, token, option) {
+            (ArgumentKind::String { .. }, token, _) => {
+                Some(ArgumentValue::String(token.to_string()))
+            }
+            (ArgumentKind::Integer { .. }, _, Some(LegacyOption::Integer(n))) => {
+                Some(ArgumentValue::Integer(*n))
+            }
+            (ArgumentKind::Integer { .. }, Token::Word(word), _) => {
+                word.parse().ok().map(ArgumentValue::Integer)
+            }
+            (ArgumentKind::Boolean, _, Some(LegacyOption::Boolean(b))) => {
+                Some(ArgumentValue::Boolean(*b))
+            }
+            (ArgumentKind::Boolean, Token::Word(word), _) => {
+                parse_bool(word).map(ArgumentValue::Boolean)
+            }
+            (ArgumentKind::User, _, Some(LegacyOption::Member(member))) => {
+                Some(ArgumentValue::User(member.user.id))
+            }
+            // Raw IDs are parsed as integers when they come before any text.
+            (ArgumentKind::User, _, Some(LegacyOption::Integer(id))) if *id > 0 => {
+                Some(ArgumentValue::User(UserId::new(*id as u64)))
+            }
+            (ArgumentKind::User, Token::Word(word), _) => {
+                mention_id(word, &["<@!", "<@"]).map(|id| ArgumentValue::User(UserId::new(id)))
+            }
+            (ArgumentKind::Channel(_), _, Some(LegacyOption::Channel(channel))) => {
+                Some(ArgumentValue::Channel(channel.id))
+            }
+            (ArgumentKind::Channel(_), _, Some(LegacyOption::Integer(id))) if *id > 0 => {
+                Some(ArgumentValue::Channel(ChannelId::new(*id as u64)))
+            }
+            (ArgumentKind::Channel(_), Token::Word(word), _) => {
+                mention_id(word, &["<#"]).map(|id| ArgumentValue::Channel(ChannelId::new(id)))
+            }
+            (ArgumentKind::Role, _, Some(LegacyOption::Role(role))) => {
+                Some(ArgumentValue::Role(role.id))
+            }
+            (ArgumentKind::Role, _, Some(LegacyOption::Integer(id))) if *id > 0 => {
+                Some(ArgumentValue::Role(RoleId::new(*id as u64)))
+            }
+            (ArgumentKind::Role, Token::Word(word), _) => {
+                mention_id(word, &["<@&"]).map(|id| ArgumentValue::Role(RoleId::new(id)))
+            }
+            (ArgumentKind::Duration { .. }, _, Some(LegacyOption::Time(duration))) => {
+                Some(ArgumentValue::Duration(*duration))
+            }
+            (ArgumentKind::Duration { .. }, _, Some(LegacyOption::Integer(seconds))) => {
+                Some(ArgumentValue::Duration(Duration::seconds(*seconds)))
+            }
+            (ArgumentKind::Duration { .. }, Token::Word(word), _) => {
+                LegacyOption::parse_time(word).map(ArgumentValue::Duration)
+            }
+            (ArgumentKind::Choice(choices), token, _) => {
+                let word = token.to_string();
+                choices
+                    .iter()
+                    .find(|choice| choice.eq_ignore_ascii_case(&word))
+                    .map(|choice| ArgumentValue::String(choice.to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    fn slash_value(&self, value: &CommandDataOptionValue) -> Option<ArgumentValue> {
+        match (&self.kind, value) {
+            (ArgumentKind::Duration { .. }, CommandDataOptionValue::String(text)) => {
+                LegacyOption::parse_time(text.trim()).map(ArgumentValue::Duration)
+            }
+            (_, CommandDataOptionValue::String(text)) => Some(ArgumentValue::String(text.clone())),
+            (_, CommandDataOptionValue::Integer(n)) => Some(ArgumentValue::Integer(*n)),
+            (_, CommandDataOptionValue::Boolean(b)) => Some(ArgumentValue::Boolean(*b)),
+            (_, CommandDataOptionValue::User(id)) => Some(ArgumentValue::User(*id)),
+            (_, CommandDataOptionValue::Channel(id)) => Some(ArgumentValue::Channel(*id)),
+            (_, CommandDataOptionValue::Role(id)) => Some(ArgumentValue::Role(*id)),
+            (_, CommandDataOptionValue::Attachment(id)) => Some(ArgumentValue::Attachment(*id)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Subcommand {
+    name: &'static str,
+    description: &'static str,
+    arguments: Vec<Argument>,
+}
+
+impl Subcommand {
+    pub fn new(name: &'static str, description: &'static str) -> Self {
+        Self {
+            name,
+            description,
+            arguments: vec![],
+        }
+    }
+
+    pub fn argument(mut self, argument: Argument) -> Self {
+        self.arguments.push(argument);
+        self
+    }
+}
+
+// The arguments of a command, either a flat list or one list per subcommand.
+#[derive(Debug, Clone, Default)]
+pub struct ArgumentSchema {
+    arguments: Vec<Argument>,
+    subcommands: Vec<Subcommand>,
+    fallback: Option<&'static str>,
+}
+
+impl ArgumentSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn argument(mut self, argument: Argument) -> Self {
+        self.arguments.push(argument);
+        self
+    }
+
+    pub fn subcommand(mut self, subcommand: Subcommand) -> Self {
+        self.subcommands.push(subcommand);
+        self
+    }
+
+    // Legacy messages that don't start with a subcommand name are parsed as this one.
+    pub fn fallback(mut self, subcommand: &'static str) -> Self {
+        self.fallback = Some(subcommand);
+        self
+    }
+
+    pub fn options(&self) -> Vec<CreateCommandOption> {
+        if self.subcommands.is_empty() {
+            return self.arguments.iter().map(Argument::option).collect();
+        }
+        self.subcommands
+            .iter()
+            .map(|subcommand| {
+                subcommand.arguments.iter().fold(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        subcommand.name,
+                        subcommand.description,
+                    ),
+                    |option, argument| option.add_sub_option(argument.option()),
+                )
+            })
+            .collect()
+    }
+
+    pub fn usage(&self, command: &str) -> String {
+        let line = |name: String, arguments: &[Argument]| {
+            std::iter::once(name)
+                .chain(arguments.iter().map(Argument::usage))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        if self.subcommands.is_empty() {
+            return format!("`{}`", line(command.to_string(), &self.arguments));
+        }
+        self.subcommands
+            .iter()
+            .map(|subcommand| {
+                format!(
+                    "`{}`",
+                    line(
+                        format!("{} {}", command, subcommand.name),
+                        &subcommand.arguments
+                    )
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn parse(
+        &self,
+        command: &str,
+        args: &CommandArguments,
+    ) -> Result<ParsedArguments, ArgumentError> {
+        let result = match args {
+            CommandArguments::Slash(options, _) => self.parse_slash(options.as_ref()),
+            CommandArguments::Legacy(options, _) => {
+                let tokens = options.iter().flatten().flat_map(Token::split).collect();
+                self.parse_legacy(tokens)
+            }
+        };
+        result.map_err(|message| ArgumentError {
+            message,
+            usage: self.usage(command),
+        })
+    }
+
+    fn parse_slash(
+        &self,
+        options: Option<&HashMap<String, CommandDataOptionValue>>,
+    ) -> Result<ParsedArguments, String> {
+        let empty = HashMap::new();
+        let options = options.unwrap_or(&empty);
+        let (subcommand, arguments, values) = if self.subcommands.is_empty() {
+            (None, &self.arguments, options.clone())
+        } else {
+            let Some((name, CommandDataOptionValue::SubCommand(sub_options))) = options
+                .iter()
+                .find(|(_, value)| matches!(value, CommandDataOptionValue::SubCommand(_)))
+            else {
+                return Err("Choose a subcommand.".to_string());
+            };
+            let Some(subcommand) = self.subcommands.iter().find(|s| s.name == name) else {
+                return Err(format!("Unknown subcommand `{}`.", name));
+            };
+            let values = sub_options
+                .iter()
+                .map(|option| (option.name.clone(), option.value.clone()))
+                .collect::<HashMap<_, _>>();
+            (Some(subcommand.name), &subcommand.arguments, values)
+        };
+
+        let mut parsed = ParsedArguments {
+            subcommand,
+            values: HashMap::new(),
+        };
+        for argument in arguments {
+            match values.get(argument.name) {
+                Some(value) => {
+                    let Some(value) = argument.slash_value(value) else {
+                        return Err(format!("`{}` is not valid.", argument.name));
+                    };
+                    argument.validate(&value)?;
+                    parsed.values.insert(argument.name, value);
+                }
+                None if argument.required => {
+                    return Err(format!("`{}` is required.", argument.name));
+                }
+                None => {}
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn parse_legacy(&self, mut tokens: Vec<Token>) -> Result<ParsedArguments, String> {
+        let (subcommand, arguments) = if self.subcommands.is_empty() {
+            (None, &self.arguments)
+        } else {
+            let first = tokens.first().map(|t| t.to_string().to_lowercase());
+            let named = first
+                .as_deref()
+                .and_then(|first| self.subcommands.iter().find(|s| s.name == first));
+            let subcommand = match named {
+                Some(subcommand) => {
+                    tokens.remove(0);
+                    Some(subcommand)
+                }
+                None if tokens.is_empty() => None,
+                None => {
+                    let fallback = self
+                        .fallback
+                        .and_then(|name| self.subcommands.iter().find(|s| s.name == name));
+                    match fallback {
+                        Some(subcommand) => Some(subcommand),
+                        None => {
+                            return Err(format!(
+                                "Unknown subcommand `{}`.",
+                                echo(first.unwrap_or_default())
+                            ));
+                        }
+                    }
+                }
+            };
+            // Without a subcommand there is nothing to parse, the command picks its default.
+            let Some(subcommand) = subcommand else {
+                return Ok(ParsedArguments::default());
+            };
+            (Some(subcommand.name), &subcommand.arguments)
+        };
+
+        let mut parsed = ParsedArguments {
+            subcommand,
+            values: HashMap::new(),
+        };
+
+        // Named arguments like `limit:5` can appear anywhere.
+        let mut positional = vec![];
+        for token in tokens {
+            let named = match &token {
+                Token::Word(word) => word.split_once([':', '=']).and_then(|(name, value)| {
+                    arguments
+                        .iter()
+                        .find(|a| a.name.eq_ignore_ascii_case(name) && !value.is_empty())
+                        .map(|argument| (argument, value.to_string()))
+                }),
+                _ => None,
+            };
+            match named {
+                Some((argument, value)) => {
+                    let Some(value) = argument.convert(&Token::Word(value)) else {
+                        return Err(format!("`{}` is not valid.", argument.name));
+                    };
+                    argument.validate(&value)?;
+                    parsed.values.insert(argument.name, value);
+                }
+                None => positional.push(token),
+            }
+        }
+
+        let mut positional = positional.into_iter().peekable();
+        for argument in arguments {
+            if parsed.values.contains_key(argument.name) || argument.named {
+                continue;
+            }
+            let value = if argument.rest {
+                let rest = positional
+                    .by_ref()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<_>>();
+                (!rest.is_empty()).then(|| ArgumentValue::String(rest.join(" ")))
+            } else {
+                match positional.peek().and_then(|token| argument.convert(token)) {
+                    Some(value) => {
+                        positional.next();
+                        Some(value)
+                    }
+                    // Optional arguments are skipped so the word can fill the next one.
+                    None if !argument.required => None,
+                    None => match positional.peek() {
+                        Some(token) => {
+                            return Err(format!(
+                                "`{}` is not a valid `{}`.",
+                                echo(token),
+                                argument.name
+                            ));
+                        }
+                        None => return Err(format!("`{}` is required.", argument.name)),
+                    },
+                }
+            };
+            if let Some(value) = value {
+                argument.validate(&value)?;
+                parsed.values.insert(argument.name, value);
+            }
+        }
+
+        if let Some(token) = positional.next() {
+            return Err(format!("Unexpected argument `{}`.", echo(token)));
+        }
+        Ok(parsed)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ArgumentValue {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    User(UserId),
+    Channel(ChannelId),
+    Role(RoleId),
+    Duration(Duration),
+    Attachment(AttachmentId),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParsedArguments {
+    subcommand: Option<&'static str>,
+    values: HashMap<&'static str, ArgumentValue>,
+}
+
+impl ParsedArguments {
+    pub fn subcommand(&self) -> Option<&'static str> {
+        self.subcommand
+    }
+
+    pub fn get<T: FromArgument>(&self, name: &str) -> Option<T> {
+        self.values.get(name).and_then(T::from_argument)
+    }
+}
+
+pub trait FromArgument: Sized {
+    fn from_argument(value: &ArgumentValue) -> Option<Self>;
+}
+
+impl FromArgument for String {
+    fn from_argument(value: &ArgumentValue) -> Option<Self> {
+        match value {
+            ArgumentValue::String(text) => Some(text.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromArgument for i64 {
+    fn from_argument(value: &ArgumentValue) -> Option<Self> {
+        match value {
+            ArgumentValue::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+impl FromArgument for bool {
+    fn from_argument(value: &ArgumentValue) -> Option<Self> {
+        match value {
+            ArgumentValue::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl FromArgument for UserId {
+    fn from_argument(value: &ArgumentValue) -> Option<Self> {
+        match value {
+            ArgumentValue::User(id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+impl FromArgument for ChannelId {
+    fn from_argument(value: &ArgumentValue) -> Option<Self> {
+        match value {
+            ArgumentValue::Channel(id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+impl FromArgument for RoleId {
+    fn from_argument(value: &ArgumentValue) -> Option<Self> {
+        match value {
+            ArgumentValue::Role(id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+impl FromArgument for Duration {
+    fn from_argument(value: &ArgumentValue) -> Option<Self> {
+        match value {
+            ArgumentValue::Duration(duration) => Some(*duration),
+            _ => None,
+        }
+    }
+}
+
+impl FromArgument for AttachmentId {
+    fn from_argument(value: &ArgumentValue) -> Option<Self> {
+        match value {
+            ArgumentValue::Attachment(id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+// A typed argument struct, declared once for both slash and legacy commands.
+pub trait Arguments: Sized {
+    fn schema() -> ArgumentSchema;
+
+    fn from_parsed(parsed: ParsedArguments) -> Result<Self, String>;
+
+    fn options() -> Vec<CreateCommandOption> {
+        Self::schema().options()
+    }
+
+    fn parse(command: &str, args: &CommandArguments) -> Result<Self, ArgumentError> {
+        let schema = Self::schema();
+        let parsed = schema.parse(command, args)?;
+        Self::from_parsed(parsed).map_err(|message| ArgumentError {
+            message,
+            usage: schema.usage(command),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArgumentError {
+    pub message: String,
+    pub usage: String,
+}
+
+impl ArgumentError {
+    pub fn response(&self) -> CommandResponse {
+        CommandResponse::new_content(format!("{}\nUsage: {}", self.message, self.usage)).reply()
+    }
+}
+
+// Typed legacy options stay whole, text is split back into words.
+#[derive(Debug, Clone)]
+enum Token {
+    Option(Box<LegacyOption>),
+    Word(String),
+}
+
+impl Token {
+    fn split(option: &LegacyOption) -> Vec<Self> {
+        match option {
+            LegacyOption::Text(text) => text
+                .split_whitespace()
+                .map(|word| Token::Word(word.to_string()))
+                .collect(),
+            option => vec![Token::Option(Box::new(option.clone()))],
+        }
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // Durations are shown the way they were typed, not as `1 hour 30 minutes`.
+            Token::Option(option) if let LegacyOption::Time(duration) = option.as_ref() => {
+                write!(f, "{}s", duration.num_seconds())
+            }
+            Token::Option(option) => write!(f, "{}", option),
+            Token::Word(word) => write!(f, "{}", word),
+        }
+    }
+}
+
+fn echo(token: impl std::fmt::Display) -> String {
+    truncate(&token.to_string(), MAX_ECHOED_LENGTH)
+}
+
+fn parse_bool(word: &str) -> Option<bool> {
+    match word.to_lowercase().as_str() {
+        "true" | "yes" | "on" | "enable" | "enabled" => Some(true),
+        "false" | "no" | "off" | "disable" | "disabled" => Some(false),
+        _ => None,
+    }
+}
+
+fn mention_id(word: &str, prefixes: &[&str]) -> Option<u64> {
+    let id = prefixes
+        .iter()
+        .find_map(|prefix| word.strip_prefix(prefix).and_then(|w| w.strip_suffix('>')))
+        .unwrap_or(word);
+    id.parse::<u64>().ok().filter(|id| *id != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::all::Message;
+
+    use super::*;
+
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .subcommand(
+                Subcommand::new("add", "Add a note")
+                    .argument(Argument::user("user", "The user").required())
+                    .argument(Argument::integer("limit", "The limit").range(-5, 10))
+                    .argument(
+                        Argument::string("text", "The text")
+                            .required()
+                            .rest()
+                            .max_length(20),
+                    ),
+            )
+            .subcommand(
+                Subcommand::new("list", "List the notes").argument(Argument::choice(
+                    "order",
+                    "The order",
+                    vec!["new", "old"],
+                )),
+            )
+            .fallback("add")
+    }
+
+    fn legacy(content: &str) -> Result<ParsedArguments, ArgumentError> {
+        let message = Message::default();
+        let options = LegacyOption::parse(content, &None);
+        schema().parse("notes", &CommandArguments::Legacy(Some(options), &message))
+    }
+
+    #[test]
+    fn parses_positional_and_named_arguments() {
+        let parsed = legacy("add <@42> 3 hello there").unwrap();
+        assert_eq!(parsed.subcommand(), Some("add"));
+        assert_eq!(parsed.get::<UserId>("user"), Some(UserId::new(42)));
+        assert_eq!(parsed.get::<i64>("limit"), Some(3));
+        assert_eq!(parsed.get::<String>("text").as_deref(), Some("hello there"));
+
+        let parsed = legacy("add limit:-2 <@42> hi").unwrap();
+        assert_eq!(parsed.get::<i64>("limit"), Some(-2));
+        assert_eq!(parsed.get::<String>("text").as_deref(), Some("hi"));
+    }
+
+    #[test]
+    fn optional_arguments_can_be_skipped() {
+        let parsed = legacy("add 42 hello").unwrap();
+        assert_eq!(parsed.get::<i64>("limit"), None);
+        assert_eq!(parsed.get::<String>("text").as_deref(), Some("hello"));
+    }
+
+    #[test]
+    fn named_arguments_are_not_positional() {
+        let schema = ArgumentSchema::new()
+            .argument(Argument::string("tags", "The tags").named())
+            .argument(Argument::string("text", "The text").rest());
+        let message = Message::default();
+        let parse = |content: &str| {
+            let options = LegacyOption::parse(content, &None);
+            schema
+                .parse("note", &CommandArguments::Legacy(Some(options), &message))
+                .unwrap()
+        };
+
+        let parsed = parse("hello there");
+        assert_eq!(parsed.get::<String>("tags"), None);
+        assert_eq!(parsed.get::<String>("text").as_deref(), Some("hello there"));
+        let parsed = parse("tags=a,b hello");
+        assert_eq!(parsed.get::<String>("tags").as_deref(), Some("a,b"));
+        assert_eq!(parsed.get::<String>("text").as_deref(), Some("hello"));
+        assert_eq!(schema.usage("note"), "`note [tags:…] [text...]`");
+    }
+
+    #[test]
+    fn uses_the_fallback_subcommand() {
+        let parsed = legacy("42 hello").unwrap();
+        assert_eq!(parsed.subcommand(), Some("add"));
+        assert_eq!(parsed.get::<UserId>("user"), Some(UserId::new(42)));
+        assert_eq!(legacy("").unwrap().subcommand(), None);
+    }
+
+    #[test]
+    fn matches_choices_ignoring_case() {
+        let parsed = legacy("list OLD").unwrap();
+        assert_eq!(parsed.subcommand(), Some("list"));
+        assert_eq!(parsed.get::<String>("order").as_deref(), Some("old"));
+        let error = legacy("list newest").unwrap_err();
+        assert_eq!(error.message, "Unexpected argument `newest`.");
+    }
+
+    #[test]
+    fn validates_bounds() {
+        let error = legacy("add 42 11 hello").unwrap_err();
+        assert_eq!(error.message, "`limit` must be between -5 and 10.");
+        let error = legacy("add 42 a very long note that goes on").unwrap_err();
+        assert_eq!(error.message, "`text` can be at most 20 characters long.");
+    }
+
+    #[test]
+    fn reports_missing_and_invalid_arguments() {
+        let error = legacy("add").unwrap_err();
+        assert_eq!(error.message, "`user` is required.");
+        assert_eq!(
+            error.usage,
+            "`notes add <user> [limit] <text...>`, `notes list [new|old]`"
+        );
+        let error = legacy("add someone hello").unwrap_err();
+        assert_eq!(error.message, "`someone` is not a valid `user`.");
+    }
+
+    #[test]
+    fn echoed_words_are_truncated() {
+        let word = "x".repeat(500);
+        let error = legacy(&format!("add {}", word)).unwrap_err();
+        assert!(error.message.chars().count() < 150);
+        assert!(error.message.contains('…'));
+    }
+
+    #[test]
+    fn parses_slash_options() {
+        let schema = ArgumentSchema::new()
+            .argument(Argument::duration("time", "How long").range(60, 3600))
+            .argument(Argument::integer("count", "How many").required());
+        let options = HashMap::from([
+            (
+                "time".to_string(),
+                CommandDataOptionValue::String("5m".into()),
+            ),
+            ("count".to_string(), CommandDataOptionValue::Integer(2)),
+        ]);
+        let parsed = schema.parse_slash(Some(&options)).unwrap();
+        assert_eq!(parsed.get::<Duration>("time"), Some(Duration::minutes(5)));
+        assert_eq!(parsed.get::<i64>("count"), Some(2));
+
+        let options = HashMap::from([
+            (
+                "time".to_string(),
+                CommandDataOptionValue::String("2h".into()),
+            ),
+            ("count".to_string(), CommandDataOptionValue::Integer(2)),
+        ]);
+        let error = schema.parse_slash(Some(&options)).unwrap_err();
+        assert_eq!(error, "`time` must be between 1 minute and 1 hour.");
+        assert_eq!(
+            schema.parse_slash(None).unwrap_err(),
+            "`count` is required."
+        );
+    }
+
+    #[test]
+    fn keeps_the_sign_of_integer_bounds() {
+        let option = Argument::integer("offset", "The offset")
+            .range(-5, 10)
+            .option();
+        let option = serenity::json::to_value(option).unwrap();
+        assert_eq!(option["min_value"].as_f64(), Some(-5.0));
+        assert_eq!(option["max_value"].as_f64(), Some(10.0));
+    }
+}
@@ -6,13 +6,16 @@ use serenity::{
         AutocompleteOption, ChannelId, CommandDataOptionValue, CommandInteraction, CommandType,
         Context, CreateActionRow, CreateAttachment, CreateCommandOption, CreateEmbed,
         CreateInteractionResponseMessage, CreateMessage, CreatePoll, Guild, GuildChannel, Member,
-        Message, Role, RoleId, User, UserId, create_poll::Ready,
+        Message, ResolvedTarget, Role, RoleId, User, UserId, create_poll::Ready,
     },
     async_trait,
     json::Value,
 };
 
-use crate::{BotPermission, error};
+use crate::{
+    BotPermission, Cooldown, SubcommandNode, SubcommandRouter, error,
+    subcommands::{option_usage, subcommand_usage},
+};
 
 #[async_trait]
 pub trait CommandTrait: Send + Sync {
@@ -42,6 +45,9 @@ pub trait CommandTrait: Send + Sync {
     fn supports_autocomplete(&self) -> bool {
         false
     }
+    fn cooldown(&self) -> Option<Cooldown> {
+        None
+    }
 }
 
 pub struct ICommand {
@@ -50,6 +56,10 @@ pub struct ICommand {
     options: Vec<CreateCommandOption>,
     permissions: Vec<BotPermission>,
     kind: CommandType,
+    subcommands: Vec<SubcommandNode>,
+    fallback: Option<String>,
+    default: Option<String>,
+    aliases: Vec<String>,
 }
 
 impl ICommand {
@@ -77,10 +87,15 @@ impl ICommand {
             error!("Command name cannot be empty");
             process::exit(1);
         }
-        if description.is_empty() {
+        // Context menu commands are the only ones without a description.
+        if description.is_empty() && kind == CommandType::ChatInput {
             error!("Command description cannot be empty");
             process::exit(1);
         }
+        if !options.is_empty() && kind != CommandType::ChatInput {
+            error!("Context menu command {} cannot have options", name);
+            process::exit(1);
+        }
 
         Self {
             name,
@@ -88,9 +103,59 @@ impl ICommand {
             options,
             permissions,
             kind,
+            subcommands: Vec::new(),
+            fallback: None,
+            default: None,
+            aliases: Vec::new(),
         }
     }
 
+    pub fn subcommand(mut self, node: SubcommandNode) -> Self {
+        validate_subcommand(&self.name, &node, true);
+        if self.subcommands.len() >= 25 {
+            error!("Command {} has too many subcommands", self.name);
+            process::exit(1);
+        }
+        self.subcommands.push(node);
+        self
+    }
+
+    // Other names the command can be used with in legacy messages, matched ignoring case.
+    pub fn aliases(mut self, aliases: &[&str]) -> Self {
+        for alias in aliases {
+            if alias.is_empty() || alias.chars().any(char::is_whitespace) {
+                error!("Command {} has an invalid alias: '{}'", self.name, alias);
+                process::exit(1);
+            }
+            self.aliases.push(alias.to_lowercase());
+        }
+        self
+    }
+
+    // Legacy messages that don't start with a subcommand name go to this one.
+    pub fn fallback(mut self, subcommand: impl Into<String>) -> Self {
+        self.fallback = Some(subcommand.into());
+        self
+    }
+
+    // Legacy messages without any words go to this one instead of listing the subcommands.
+    pub fn default(mut self, subcommand: impl Into<String>) -> Self {
+        self.default = Some(subcommand.into());
+        self
+    }
+
+    // Pairs the command with a router that dispatches to the handlers of its subcommands.
+    pub fn routed(self) -> CommandTemplate {
+        let router = SubcommandRouter::new(
+            self.name.clone(),
+            self.description.clone(),
+            self.subcommands.clone(),
+            self.fallback.clone(),
+            self.default.clone(),
+        );
+        (self, Arc::new(router))
+    }
+
     pub fn get_name(&self) -> String {
         self.name.clone()
     }
@@ -104,26 +169,89 @@ impl ICommand {
     }
 
     pub fn get_command(&self) -> serenity::all::CreateCommand {
-        if self.options.is_empty() {
-            serenity::all::CreateCommand::new(self.name.clone())
-                .description(self.description.clone())
-                .kind(self.kind)
-                .nsfw(false)
-        } else {
-            let mut command = serenity::all::CreateCommand::new(self.name.clone())
-                .description(self.description.clone())
-                .kind(self.kind)
-                .nsfw(false);
-            for option in &self.options {
-                command = command.add_option(option.clone().to_owned());
-            }
-            command
+        let mut command = serenity::all::CreateCommand::new(self.name.clone())
+            .kind(self.kind)
+            .nsfw(false);
+        if !self.description.is_empty() {
+            command = command.description(self.description.clone());
         }
+        for option in &self.options {
+            command = command.add_option(option.clone().to_owned());
+        }
+        for subcommand in &self.subcommands {
+            command = command.add_option(subcommand.option());
+        }
+        command
+    }
+
+    pub fn get_aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    pub fn get_subcommands(&self) -> &[SubcommandNode] {
+        &self.subcommands
     }
 
     pub fn get_permissions(&self) -> Vec<BotPermission> {
         self.permissions.clone()
     }
+
+    pub fn get_kind(&self) -> CommandType {
+        self.kind
+    }
+
+    // `name sub <required> [optional]` and the description of every way to use the command with
+    // the given syntax. Whether the command itself supports it is up to its handler.
+    pub fn usage(&self, legacy: bool) -> Vec<(String, String)> {
+        if self.subcommands.is_empty() {
+            let usage = std::iter::once(self.name.clone())
+                .chain(self.options.iter().filter_map(option_usage))
+                .collect::<Vec<_>>()
+                .join(" ");
+            return vec![(usage, self.description.clone())];
+        }
+        subcommand_usage(&self.name, &self.subcommands, &[], legacy)
+    }
+}
+
+fn validate_subcommand(command: &str, node: &SubcommandNode, top_level: bool) {
+    let name = node.name();
+    if name.is_empty()
+        || name.chars().count() > 32
+        || name
+            .chars()
+            .any(|c| !(c == '-' || c == '_' || (c.is_alphanumeric() && !c.is_uppercase())))
+    {
+        error!(
+            "Command {} has an invalid subcommand name: {}",
+            command, name
+        );
+        process::exit(1);
+    }
+    if node.description().is_empty() || node.description().chars().count() > 100 {
+        error!(
+            "Subcommand {} of {} needs a description of 1 to 100 characters",
+            name, command
+        );
+        process::exit(1);
+    }
+    if let SubcommandNode::Group { children, .. } = node {
+        // Discord only allows subcommands inside a group.
+        if !top_level || children.len() > 25 {
+            error!(
+                "Command {} has an invalid subcommand group: {}",
+                command, name
+            );
+            process::exit(1);
+        }
+        for child in children {
+            if matches!(child, SubcommandNode::Group { .. }) {
+                error!("Subcommand groups of {} can't be nested", command);
+                process::exit(1);
+            }
+            validate_subcommand(command, child, false);
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -134,6 +262,16 @@ pub enum CommandArguments<'a> {
     ),
     Legacy(Option<Vec<LegacyOption>>, &'a Message),
 }
+
+impl<'a> CommandArguments<'a> {
+    // The user or message a context menu command was used on.
+    pub fn target(&self) -> Option<ResolvedTarget<'a>> {
+        match self {
+            Self::Slash(_, interaction) => interaction.data.target(),
+            Self::Legacy(..) => None,
+        }
+    }
+}
 #[derive(Debug, Clone, Default)]
 pub struct CommandResponse {
     content: Option<String>,
@@ -266,7 +404,7 @@ impl CommandResponse {
     }
 
     pub fn to_interaction_msg(&self) -> CreateInteractionResponseMessage {
-        let mut msg = CreateInteractionResponseMessage::new();
+        let mut msg = CreateInteractionResponseMessage::new().ephemeral(self.ephemeral);
         if let Some(ref content) = self.content {
             msg = msg.content(content);
         }
@@ -377,7 +515,7 @@ impl LegacyOption {
         options
     }
 
-    fn parse_time(arg: &str) -> Option<Duration> {
+    pub(crate) fn parse_time(arg: &str) -> Option<Duration> {
         // This function parses a time string like "1h30m" into a Duration object.
         let mut total_seconds = 0;
         let mut current_number = String::new();
@@ -484,3 +622,71 @@ impl Display for LegacyOption {
 
 pub type CommandTemplate = (ICommand, Arc<dyn CommandTrait>);
 pub type AutocompleteResponse = HashMap<String, Value>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Vec<LegacyOption> {
+        LegacyOption::parse(content, &None)
+    }
+
+    #[test]
+    fn parses_values_before_text() {
+        let options = parse("yes 12 1h30m hello there");
+        assert_eq!(options.len(), 4);
+        assert!(matches!(options[0], LegacyOption::Boolean(true)));
+        assert!(matches!(options[1], LegacyOption::Integer(12)));
+        assert!(matches!(options[2], LegacyOption::Time(t) if t == Duration::seconds(5400)));
+        assert!(matches!(&options[3], LegacyOption::Text(t) if t == "hello there"));
+    }
+
+    #[test]
+    fn everything_after_text_is_text() {
+        let options = parse("note no 5  10m");
+        assert_eq!(options.len(), 1);
+        assert!(matches!(&options[0], LegacyOption::Text(t) if t == "note no 5 10m"));
+    }
+
+    #[test]
+    fn negative_numbers_are_integers() {
+        let options = parse("-3 false");
+        assert!(matches!(options[0], LegacyOption::Integer(-3)));
+        assert!(matches!(options[1], LegacyOption::Boolean(false)));
+    }
+
+    #[test]
+    fn mentions_need_a_server() {
+        assert!(parse("<@123>").is_empty());
+        assert!(parse("<#123> <@&456>").is_empty());
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(LegacyOption::parse_time("2w"), Some(Duration::weeks(2)));
+        assert_eq!(
+            LegacyOption::parse_time("1d2h3m4s"),
+            Some(Duration::seconds(86400 + 7200 + 180 + 4))
+        );
+        assert_eq!(
+            LegacyOption::parse_time("1m30"),
+            Some(Duration::seconds(90))
+        );
+        assert_eq!(LegacyOption::parse_time("0s"), None);
+        assert_eq!(LegacyOption::parse_time("5x"), None);
+        assert_eq!(LegacyOption::parse_time("h"), None);
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(LegacyOption::time_str(&Duration::seconds(0)), "0s");
+        assert_eq!(
+            LegacyOption::time_str(&Duration::seconds(8 * 86400 + 3600 + 61)),
+            "1 week 1 day 1 hour 1 minute 1 second"
+        );
+        assert_eq!(
+            LegacyOption::time_str(&Duration::seconds(2 * 3600 + 120)),
+            "2 hours 2 minutes"
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use chrono::Duration;
+
+// Who shares a cooldown. With `User` everyone has their own, with `Channel` or `Guild` one use
+// puts the command on cooldown for everyone there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CooldownBucket {
+    User,
+    Channel,
+    Guild,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Cooldown {
+    pub bucket: CooldownBucket,
+    pub duration: Duration,
+}
+
+impl Cooldown {
+    pub fn new(bucket: CooldownBucket, seconds: i64) -> Self {
+        Self {
+            bucket,
+            duration: Duration::seconds(seconds),
+        }
+    }
+}
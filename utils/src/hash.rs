@@ -39,6 +39,15 @@ impl<V> UserConfigHash<V> {
             .or(self.0.get_mut(&UserGlobalType::User(*user_id)))
     }
 
+    // Only the value stored under this key, without falling back to the user's global one.
+    pub fn get_exact<'a>(&'a self, key: &UserGlobalType) -> Option<Ref<'a, UserGlobalType, V>> {
+        self.0.get(key)
+    }
+
+    pub fn remove_exact(&self, key: &UserGlobalType) -> Option<(UserGlobalType, V)> {
+        self.0.remove(key)
+    }
+
     pub fn insert(&self, key: UserGlobalType, value: V) {
         self.0.insert(key, value);
     }
@@ -49,6 +58,10 @@ impl<V> UserConfigHash<V> {
             .or(self.0.remove(&UserGlobalType::User(*user_id)))
     }
 
+    pub fn iter(&self) -> dashmap::iter::Iter<'_, UserGlobalType, V> {
+        self.0.iter()
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
@@ -1,21 +1,28 @@
+mod arguments;
 mod commands;
+mod cooldowns;
 mod hash;
 mod logging;
 mod pagination;
 mod parser;
 mod permissions;
+mod subcommands;
 
 use std::sync::Arc;
 
 use serenity::{all::UserId, prelude::TypeMap};
 use tokio::sync::RwLock;
 
+pub use arguments::*;
 pub use commands::*;
+pub use cooldowns::*;
 pub use hash::*;
 pub use logging::*;
+pub use macros::command;
 pub use pagination::*;
 pub use parser::*;
 pub use permissions::*;
+pub use subcommands::*;
 
 pub type InteractionCommandResult = Result<serenity::builder::CreateInteractionResponse, String>;
 pub type MessageResponseResult = Result<serenity::builder::CreateMessage, String>;
@@ -32,6 +39,16 @@ pub fn position_suffix(position: usize) -> String {
     format!("{}{}", position, suffix)
 }
 
+// Cuts text to at most `max` characters, ending with an ellipsis when something was cut.
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated = text.chars().take(max.saturating_sub(1)).collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
 pub fn parse_button_id(custom_id: &str) -> Option<(&str, u64, UserId, PaginationAction)> {
     let parts: Vec<&str> = custom_id.split('|').collect();
     if parts.len() != 4 {
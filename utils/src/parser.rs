@@ -2,14 +2,14 @@ use std::collections::HashMap;
 
 use regex::Regex;
 use serenity::all::{
-    AfkTimeout, ChannelType, Context, FormattedTimestamp, FormattedTimestampStyle, Guild,
+    AfkTimeout, Cache, ChannelType, Context, FormattedTimestamp, FormattedTimestampStyle, Guild,
     GuildChannel, Member, PremiumTier, Role, Timestamp,
 };
 
 const NOT_AVAILABLE: &str = "`n/a`";
 #[derive(Debug, Clone)]
 pub struct BotStringParser<'a> {
-    ctx: &'a Context,
+    discord_cache: &'a Cache,
     guild: &'a Guild,
     channel: &'a GuildChannel,
     member: &'a Member,
@@ -27,9 +27,18 @@ impl<'a> BotStringParser<'a> {
         guild: &'a Guild,
         channel: &'a GuildChannel,
         member: &'a Member,
+    ) -> Self {
+        Self::from_cache(&ctx.cache, guild, channel, member)
+    }
+
+    pub fn from_cache(
+        cache: &'a Cache,
+        guild: &'a Guild,
+        channel: &'a GuildChannel,
+        member: &'a Member,
     ) -> Self {
         Self {
-            ctx,
+            discord_cache: cache,
             member,
             channel,
             guild,
@@ -197,7 +206,7 @@ impl<'a> BotStringParser<'a> {
             "id" => Some(guild.id.to_string()),
             "name" => Some(guild.name.clone()),
             "count" => Some(guild.member_count.to_string()),
-            "shard" => Some(guild.shard_id(self.ctx).to_string()),
+            "shard" => Some(guild.shard_id(self.discord_cache).to_string()),
             "owner_id" => Some(guild.owner_id.to_string()),
             "created_at" => Some(
                 FormattedTimestamp::new(
@@ -37,6 +37,30 @@ pub enum BotPermission {
     MentionEveryone,
 }
 
+impl BotPermission {
+    // The server permissions a member needs for it. There's no bot owner setting, so bot masters
+    // are the server's administrators.
+    pub fn permissions(&self) -> Permissions {
+        match self {
+            Self::BotMaster | Self::Administrator => Permissions::ADMINISTRATOR,
+            Self::BanMembers => Permissions::BAN_MEMBERS,
+            Self::KickMembers => Permissions::KICK_MEMBERS,
+            Self::MuteMembers => Permissions::MUTE_MEMBERS,
+            Self::DeafenMembers => Permissions::DEAFEN_MEMBERS,
+            Self::MoveMembers => Permissions::MOVE_MEMBERS,
+            Self::ManageGuild => Permissions::MANAGE_GUILD,
+            Self::ManageChannels => Permissions::MANAGE_CHANNELS,
+            Self::ManageRoles => Permissions::MANAGE_ROLES,
+            Self::ManageMessages => Permissions::MANAGE_MESSAGES,
+            Self::ManageWebhooks => Permissions::MANAGE_WEBHOOKS,
+            Self::ManageGuildExpressions => Permissions::MANAGE_GUILD_EXPRESSIONS,
+            Self::ManageEvents => Permissions::MANAGE_EVENTS,
+            Self::ManageNicknames => Permissions::MANAGE_NICKNAMES,
+            Self::MentionEveryone => Permissions::MENTION_EVERYONE,
+        }
+    }
+}
+
 pub const PERMISSION_PRIORITY: [Permissions; 32] = [
     // 🔒 Superuser
     Permissions::ADMINISTRATOR,
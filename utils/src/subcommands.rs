@@ -0,0 +1,490 @@
+use std::{collections::HashMap, sync::Arc};
+
+use serenity::{
+    all::{
+        AutocompleteOption, CommandDataOption, CommandDataOptionValue, CommandInteraction,
+        CommandOptionType, Context, CreateCommandOption, Guild, GuildChannel,
+    },
+    async_trait,
+};
+
+use crate::{
+    AutocompleteResponse, CommandArguments, CommandResponse, CommandTrait, LegacyOption, UserType,
+};
+
+#[derive(Clone)]
+pub enum SubcommandNode {
+    Command {
+        name: String,
+        description: String,
+        options: Vec<CreateCommandOption>,
+        handler: Arc<dyn CommandTrait>,
+    },
+    Group {
+        name: String,
+        description: String,
+        children: Vec<SubcommandNode>,
+    },
+}
+
+impl SubcommandNode {
+    pub fn command(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        options: Vec<CreateCommandOption>,
+        handler: Arc<dyn CommandTrait>,
+    ) -> Self {
+        Self::Command {
+            name: name.into(),
+            description: description.into(),
+            options,
+            handler,
+        }
+    }
+
+    pub fn group(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        children: Vec<SubcommandNode>,
+    ) -> Self {
+        Self::Group {
+            name: name.into(),
+            description: description.into(),
+            children,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Command { name, .. } | Self::Group { name, .. } => name,
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        match self {
+            Self::Command { description, .. } | Self::Group { description, .. } => description,
+        }
+    }
+
+    pub fn option(&self) -> CreateCommandOption {
+        match self {
+            Self::Command {
+                name,
+                description,
+                options,
+                ..
+            } => options.iter().fold(
+                CreateCommandOption::new(CommandOptionType::SubCommand, name, description),
+                |option, sub_option| option.add_sub_option(sub_option.clone()),
+            ),
+            Self::Group {
+                name,
+                description,
+                children,
+            } => children.iter().fold(
+                CreateCommandOption::new(CommandOptionType::SubCommandGroup, name, description),
+                |option, child| option.add_sub_option(child.option()),
+            ),
+        }
+    }
+
+    // Every handler below this node with its full path, e.g. `["role", "add"]`.
+    fn leaves(&self) -> Vec<(Vec<&str>, &SubcommandNode)> {
+        match self {
+            Self::Command { name, .. } => vec![(vec![name.as_str()], self)],
+            Self::Group { name, children, .. } => children
+                .iter()
+                .flat_map(|child| child.leaves())
+                .map(|(mut path, leaf)| {
+                    path.insert(0, name.as_str());
+                    (path, leaf)
+                })
+                .collect(),
+        }
+    }
+
+    fn handler(&self) -> Option<&Arc<dyn CommandTrait>> {
+        match self {
+            Self::Command { handler, .. } => Some(handler),
+            Self::Group { .. } => None,
+        }
+    }
+
+    fn find(nodes: &[SubcommandNode], name: &str) -> Option<SubcommandNode> {
+        nodes
+            .iter()
+            .find(|node| node.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+
+// Dispatches a command with subcommands to the handler of the chosen subcommand, for slash
+// commands as well as legacy messages like `prefix set !`.
+pub struct SubcommandRouter {
+    name: String,
+    description: String,
+    subcommands: Vec<SubcommandNode>,
+    fallback: Option<String>,
+    default: Option<String>,
+}
+
+impl SubcommandRouter {
+    pub fn new(
+        name: String,
+        description: String,
+        subcommands: Vec<SubcommandNode>,
+        fallback: Option<String>,
+        default: Option<String>,
+    ) -> Self {
+        Self {
+            name,
+            description,
+            subcommands,
+            fallback,
+            default,
+        }
+    }
+
+    fn leaves(&self) -> impl Iterator<Item = (Vec<&str>, &SubcommandNode)> {
+        self.subcommands.iter().flat_map(|node| node.leaves())
+    }
+
+    // Lists the subcommands below a group, or the whole command when `path` is empty.
+    pub fn usage(&self, path: &[&str], legacy: bool) -> CommandResponse {
+        let lines = subcommand_usage(&self.name, &self.subcommands, path, legacy)
+            .into_iter()
+            .map(|(usage, description)| {
+                format!(
+                    "`{}{}` — {}",
+                    if legacy { "" } else { "/" },
+                    usage,
+                    description
+                )
+            })
+            .collect::<Vec<_>>();
+        let description = path
+            .first()
+            .and_then(|group| SubcommandNode::find(&self.subcommands, group))
+            .map(|group| group.description().to_string())
+            .unwrap_or(self.description.clone());
+        CommandResponse::new_content(format!(
+            "**{}** — {}\n{}",
+            std::iter::once(self.name.as_str())
+                .chain(path.iter().copied())
+                .collect::<Vec<_>>()
+                .join(" "),
+            description,
+            lines.join("\n")
+        ))
+        .reply()
+    }
+
+    // Walks the legacy words until a handler is found, the words left over are its arguments.
+    fn route_legacy(&self, content: &str) -> Result<(SubcommandNode, String), Vec<String>> {
+        let mut nodes = self.subcommands.clone();
+        let mut path = vec![];
+        let mut rest = content.trim();
+        loop {
+            let (word, remaining) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            let node = match SubcommandNode::find(&nodes, word) {
+                Some(node) if !word.is_empty() => node,
+                _ if path.is_empty() && word.is_empty() => {
+                    match self
+                        .default
+                        .as_ref()
+                        .and_then(|d| SubcommandNode::find(&nodes, d))
+                    {
+                        Some(node) => return Ok((node, String::new())),
+                        None => return Err(path),
+                    }
+                }
+                // Unknown words at the top level go to the fallback, with the word kept.
+                _ if path.is_empty() && !word.is_empty() => {
+                    match self
+                        .fallback
+                        .as_ref()
+                        .and_then(|f| SubcommandNode::find(&nodes, f))
+                    {
+                        Some(node) => return Ok((node, rest.to_string())),
+                        None => return Err(path),
+                    }
+                }
+                _ => return Err(path),
+            };
+            path.push(node.name().to_string());
+            rest = remaining.trim();
+            match node {
+                SubcommandNode::Group { children, .. } => nodes = children,
+                leaf => return Ok((leaf, rest.to_string())),
+            }
+        }
+    }
+
+    // Walks the slash options until a handler is found, returning it with its own options.
+    fn route_slash(
+        &self,
+        options: &[(String, CommandDataOptionValue)],
+    ) -> Option<(SubcommandNode, Vec<CommandDataOption>)> {
+        let mut nodes = self.subcommands.clone();
+        let mut current = options.to_vec();
+        loop {
+            let (name, value) = current.iter().find(|(_, value)| {
+                matches!(
+                    value,
+                    CommandDataOptionValue::SubCommand(_)
+                        | CommandDataOptionValue::SubCommandGroup(_)
+                )
+            })?;
+            let node = SubcommandNode::find(&nodes, name)?;
+            match (node, value) {
+                (
+                    SubcommandNode::Group { children, .. },
+                    CommandDataOptionValue::SubCommandGroup(sub),
+                ) => {
+                    nodes = children;
+                    current = sub
+                        .iter()
+                        .map(|o| (o.name.clone(), o.value.clone()))
+                        .collect();
+                }
+                (
+                    leaf @ SubcommandNode::Command { .. },
+                    CommandDataOptionValue::SubCommand(sub),
+                ) => {
+                    return Some((leaf, sub.clone()));
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CommandTrait for SubcommandRouter {
+    async fn execute<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        args: CommandArguments<'a>,
+    ) -> Result<Option<CommandResponse>, String> {
+        match args {
+            CommandArguments::Slash(options, interaction) => {
+                let options = options
+                    .map(|o| o.into_iter().collect::<Vec<_>>())
+                    .unwrap_or_default();
+                let Some((leaf, sub_options)) = self.route_slash(&options) else {
+                    return Ok(Some(self.usage(&[], false)));
+                };
+                let Some(handler) = leaf.handler().filter(|h| h.is_slash()) else {
+                    return Ok(Some(self.usage(&[], false)));
+                };
+                let sub_options = (!sub_options.is_empty()).then(|| {
+                    sub_options
+                        .into_iter()
+                        .map(|o| (o.name, o.value))
+                        .collect::<HashMap<_, _>>()
+                });
+                handler
+                    .execute(
+                        ctx,
+                        user,
+                        location,
+                        CommandArguments::Slash(sub_options, interaction),
+                    )
+                    .await
+            }
+            CommandArguments::Legacy(options, message) => {
+                let content = options
+                    .iter()
+                    .flatten()
+                    .map(raw_option)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let (leaf, rest) = match self.route_legacy(&content) {
+                    Ok(route) => route,
+                    Err(path) => {
+                        let path = path.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+                        return Ok(Some(self.usage(&path, true)));
+                    }
+                };
+                let Some(handler) = leaf.handler().filter(|h| h.is_legacy()) else {
+                    return Ok(Some(self.usage(&[], true)));
+                };
+                // Re-parsed so typed values like numbers and mentions follow the subcommand.
+                let options = LegacyOption::parse(&rest, &location);
+                let options = (!options.is_empty()).then_some(options);
+                handler
+                    .execute(
+                        ctx,
+                        user,
+                        location,
+                        CommandArguments::Legacy(options, message),
+                    )
+                    .await
+            }
+        }
+    }
+    async fn autocomplete<'a>(
+        &self,
+        ctx: &'a Context,
+        user: UserType,
+        location: Option<(Guild, GuildChannel)>,
+        focused: AutocompleteOption<'a>,
+        interaction: &'a CommandInteraction,
+    ) -> Option<AutocompleteResponse> {
+        let options = interaction
+            .data
+            .options
+            .iter()
+            .map(|o| (o.name.clone(), o.value.clone()))
+            .collect::<Vec<_>>();
+        let (leaf, _) = self.route_slash(&options)?;
+        let handler = leaf.handler().filter(|h| h.supports_autocomplete())?;
+        handler
+            .autocomplete(ctx, user, location, focused, interaction)
+            .await
+    }
+    fn is_legacy(&self) -> bool {
+        self.leaves()
+            .any(|(_, leaf)| leaf.handler().is_some_and(|h| h.is_legacy()))
+    }
+    fn is_slash(&self) -> bool {
+        self.leaves()
+            .any(|(_, leaf)| leaf.handler().is_some_and(|h| h.is_slash()))
+    }
+    fn supports_autocomplete(&self) -> bool {
+        self.leaves()
+            .any(|(_, leaf)| leaf.handler().is_some_and(|h| h.supports_autocomplete()))
+    }
+}
+
+// The option as it would have been typed, durations aren't shown as `1 hour 30 minutes`.
+fn raw_option(option: &LegacyOption) -> String {
+    match option {
+        LegacyOption::Time(duration) => format!("{}s", duration.num_seconds()),
+        option => option.to_string(),
+    }
+}
+
+// `command sub <required> [optional]` and the description of every handler below `path` that can
+// be used with the given syntax.
+pub(crate) fn subcommand_usage(
+    command: &str,
+    subcommands: &[SubcommandNode],
+    path: &[&str],
+    legacy: bool,
+) -> Vec<(String, String)> {
+    subcommands
+        .iter()
+        .flat_map(|node| node.leaves())
+        .filter(|(leaf_path, _)| leaf_path.starts_with(path))
+        .filter(|(_, leaf)| {
+            leaf.handler()
+                .is_some_and(|h| if legacy { h.is_legacy() } else { h.is_slash() })
+        })
+        .map(|(leaf_path, leaf)| {
+            let mut usage = vec![command.to_string()];
+            usage.extend(leaf_path.iter().map(|s| s.to_string()));
+            if let SubcommandNode::Command { options, .. } = leaf {
+                usage.extend(options.iter().filter_map(option_usage));
+            }
+            (usage.join(" "), leaf.description().to_string())
+        })
+        .collect()
+}
+
+// `<name>` for required options and `[name]` for optional ones.
+pub(crate) fn option_usage(option: &CreateCommandOption) -> Option<String> {
+    let value = serenity::json::to_value(option).ok()?;
+    let name = value.get("name")?.as_str()?;
+    let required = value
+        .get("required")
+        .and_then(|r| r.as_bool())
+        .unwrap_or(false);
+    Some(if required {
+        format!("<{}>", name)
+    } else {
+        format!("[{}]", name)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Noop;
+
+    #[async_trait]
+    impl CommandTrait for Noop {
+        async fn execute<'a>(
+            &self,
+            _: &'a Context,
+            _: UserType,
+            _: Option<(Guild, GuildChannel)>,
+            _: CommandArguments<'a>,
+        ) -> Result<Option<CommandResponse>, String> {
+            Ok(None)
+        }
+    }
+
+    fn router(fallback: Option<&str>, default: Option<&str>) -> SubcommandRouter {
+        let leaf = |name: &str| SubcommandNode::command(name, name, vec![], Arc::new(Noop));
+        SubcommandRouter::new(
+            "birthday".to_string(),
+            "Birthdays".to_string(),
+            vec![
+                leaf("set"),
+                leaf("view"),
+                SubcommandNode::group("role", "Roles", vec![leaf("add")]),
+            ],
+            fallback.map(|f| f.to_string()),
+            default.map(|d| d.to_string()),
+        )
+    }
+
+    fn route(router: &SubcommandRouter, content: &str) -> Result<(String, String), Vec<String>> {
+        router
+            .route_legacy(content)
+            .map(|(node, rest)| (node.name().to_string(), rest))
+    }
+
+    #[test]
+    fn routes_words_to_subcommands() {
+        let router = router(None, None);
+        assert_eq!(
+            route(&router, "SET 01/02 Europe/Paris"),
+            Ok(("set".to_string(), "01/02 Europe/Paris".to_string()))
+        );
+        assert_eq!(
+            route(&router, "role add <@&1>"),
+            Ok(("add".to_string(), "<@&1>".to_string()))
+        );
+        assert_eq!(route(&router, "role"), Err(vec!["role".to_string()]));
+        assert_eq!(route(&router, "role nope"), Err(vec!["role".to_string()]));
+    }
+
+    #[test]
+    fn bare_messages_use_the_default() {
+        assert_eq!(route(&router(None, None), ""), Err(vec![]));
+        assert_eq!(
+            route(&router(None, Some("view")), "  "),
+            Ok(("view".to_string(), String::new()))
+        );
+        // The default doesn't catch unknown words.
+        assert_eq!(route(&router(None, Some("view")), "<@1>"), Err(vec![]));
+    }
+
+    #[test]
+    fn unknown_words_use_the_fallback() {
+        let router = router(Some("view"), Some("view"));
+        assert_eq!(
+            route(&router, "<@1>"),
+            Ok(("view".to_string(), "<@1>".to_string()))
+        );
+        assert_eq!(
+            route(&router, "set"),
+            Ok(("set".to_string(), String::new()))
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use chrono::Duration;
+use serenity::all::{CommandType, Context, Guild, GuildChannel};
+use utils::{
+    Argument, ArgumentSchema, Arguments, BotPermission, CommandArguments, CommandResponse,
+    CommandTrait, CooldownBucket, ParsedArguments, UserType,
+};
+
+// Declares a command whose handler does nothing, with the given macro attributes.
+macro_rules! command {
+    ($module:ident, [$($attributes:tt)*] $(, $item:item)*) => {
+        mod $module {
+            use super::*;
+
+            pub struct Command;
+
+            #[utils::command($($attributes)*)]
+            #[serenity::async_trait]
+            impl CommandTrait for Command {
+                async fn execute<'a>(
+                    &self,
+                    _: &'a Context,
+                    _: UserType,
+                    _: Option<(Guild, GuildChannel)>,
+                    _: CommandArguments<'a>,
+                ) -> Result<Option<CommandResponse>, String> {
+                    Ok(None)
+                }
+                $($item)*
+            }
+        }
+    };
+}
+
+command!(
+    ping,
+    [
+        name = "ping",
+        description = "Check the latency.",
+        aliases = ["p"],
+        cooldown = 5,
+        slash,
+        legacy
+    ]
+);
+command!(
+    config,
+    [
+        name = "config",
+        description = "Configure the server.",
+        permissions = [ManageGuild, ManageRoles],
+        cooldown = 3,
+        bucket = Channel,
+        slash
+    ]
+);
+command!(
+    search,
+    [
+        name = "search",
+        description = "Search for something.",
+        arguments = SearchArguments,
+        slash,
+        legacy,
+        autocomplete
+    ]
+);
+command!(
+    quiet,
+    [
+        name = "quiet",
+        description = "Only a slash command for now.",
+        slash,
+        legacy
+    ],
+    fn is_legacy(&self) -> bool {
+        false
+    }
+);
+command!(avatar, [name = "View avatar", kind = User]);
+command!(
+    list,
+    [name = "list", description = "List the entries.", subcommand]
+);
+
+struct SearchArguments;
+
+impl Arguments for SearchArguments {
+    fn schema() -> ArgumentSchema {
+        ArgumentSchema::new()
+            .argument(Argument::string("query", "What to search for").required())
+            .argument(Argument::integer("limit", "How many results"))
+    }
+
+    fn from_parsed(_: ParsedArguments) -> Result<Self, String> {
+        Ok(Self)
+    }
+}
+
+#[test]
+fn sets_the_flags() {
+    assert!(ping::Command.is_slash());
+    assert!(ping::Command.is_legacy());
+    assert!(!ping::Command.supports_autocomplete());
+    assert!(config::Command.is_slash());
+    assert!(!config::Command.is_legacy());
+    assert!(search::Command.supports_autocomplete());
+}
+
+#[test]
+fn keeps_flags_defined_by_hand() {
+    assert!(quiet::Command.is_slash());
+    assert!(!quiet::Command.is_legacy());
+}
+
+#[test]
+fn generates_the_command() {
+    let (command, handler) = ping::command();
+    assert_eq!(ping::Command::NAME, "ping");
+    assert_eq!(ping::Command::DESCRIPTION, "Check the latency.");
+    assert_eq!(command.get_name(), "ping");
+    assert_eq!(command.get_description(), "Check the latency.");
+    assert_eq!(command.get_kind(), CommandType::ChatInput);
+    assert_eq!(command.get_aliases(), ["p"]);
+    assert!(command.get_permissions().is_empty());
+    assert!(handler.is_legacy());
+}
+
+#[test]
+fn sets_permissions_and_cooldowns() {
+    let (command, handler) = config::command();
+    assert!(matches!(
+        command.get_permissions()[..],
+        [BotPermission::ManageGuild, BotPermission::ManageRoles]
+    ));
+    let cooldown = handler.cooldown().unwrap();
+    assert_eq!(cooldown.bucket, CooldownBucket::Channel);
+    assert_eq!(cooldown.duration, Duration::seconds(3));
+
+    let cooldown = ping::Command.cooldown().unwrap();
+    assert_eq!(cooldown.bucket, CooldownBucket::User);
+    assert_eq!(cooldown.duration, Duration::seconds(5));
+    assert!(search::Command.cooldown().is_none());
+}
+
+#[test]
+fn uses_the_options_of_the_arguments() {
+    let (command, _) = search::command();
+    let command = serenity::json::to_value(command.get_command()).unwrap();
+    let options = command["options"].as_array().unwrap();
+    assert_eq!(options.len(), 2);
+    assert_eq!(options[0]["name"], "query");
+    assert_eq!(options[0]["required"], true);
+    assert_eq!(options[1]["name"], "limit");
+}
+
+#[test]
+fn context_menus_are_application_commands() {
+    let (command, handler) = avatar::command();
+    assert_eq!(command.get_kind(), CommandType::User);
+    assert_eq!(command.get_description(), "");
+    assert!(handler.is_slash());
+    assert!(!handler.is_legacy());
+}
+
+#[test]
+fn subcommands_become_nodes() {
+    let node = list::Command::node();
+    assert_eq!(node.name(), "list");
+    assert_eq!(node.description(), "List the entries.");
+}
+
+#[test]
+fn rejects_invalid_attributes() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
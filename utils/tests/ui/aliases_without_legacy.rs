@@ -0,0 +1,6 @@
+struct Command;
+
+#[utils::command(name = "ping", description = "Check the latency.", aliases = ["p"], slash)]
+impl Command {}
+
+fn main() {}
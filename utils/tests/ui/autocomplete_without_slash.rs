@@ -0,0 +1,6 @@
+struct Command;
+
+#[utils::command(name = "search", description = "Search for something.", legacy, autocomplete)]
+impl Command {}
+
+fn main() {}
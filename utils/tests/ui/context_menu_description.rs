@@ -0,0 +1,6 @@
+struct Command;
+
+#[utils::command(name = "View avatar", description = "View the avatar.", kind = User)]
+impl Command {}
+
+fn main() {}
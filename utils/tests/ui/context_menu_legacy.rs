@@ -0,0 +1,6 @@
+struct Command;
+
+#[utils::command(name = "Report message", kind = Message, legacy)]
+impl Command {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+struct Command;
+
+#[utils::command(name = "Ping pong", description = "Check the latency.", slash)]
+impl Command {}
+
+fn main() {}
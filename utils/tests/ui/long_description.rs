@@ -0,0 +1,6 @@
+struct Command;
+
+#[utils::command(name = "ping", description = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa", slash)]
+impl Command {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+struct Command;
+
+#[utils::command(name = "list", description = "List the entries.", permissions = [ManageGuild], subcommand)]
+impl Command {}
+
+fn main() {}
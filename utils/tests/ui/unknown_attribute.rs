@@ -0,0 +1,6 @@
+struct Command;
+
+#[utils::command(name = "ping", description = "Check the latency.", hidden, slash)]
+impl Command {}
+
+fn main() {}
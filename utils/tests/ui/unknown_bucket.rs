@@ -0,0 +1,6 @@
+struct Command;
+
+#[utils::command(name = "ping", description = "Check the latency.", cooldown = 5, bucket = Server, slash)]
+impl Command {}
+
+fn main() {}